@@ -0,0 +1,202 @@
+//! Kalibr camchain YAML, as written by `kalibr_calibrate_cameras`.
+//!
+//! A camchain contains one entry (`cam0`, `cam1`, ...) for every camera in the rig.
+//! The camera models "pinhole", "omni", and "ds" and the distortion models "none", "radtan",
+//! and "equidistant" are supported. The extrinsics between cameras (`T_cn_cnm1`) are ignored.
+
+use crate::{custom_error, CameraCalibration, CameraProjection, Distortion, Error};
+use cv_core::nalgebra::{Point2, Vector2};
+use cv_pinhole::CameraIntrinsics;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize)]
+struct KalibrCamera {
+    camera_model: String,
+    intrinsics: Vec<f64>,
+    distortion_model: String,
+    distortion_coeffs: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolution: Option<[usize; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rostopic: Option<String>,
+}
+
+impl KalibrCamera {
+    fn calibration(&self) -> Result<CameraCalibration, Error> {
+        let expected_intrinsics = match self.camera_model.as_str() {
+            "pinhole" => 4,
+            "omni" => 5,
+            "ds" => 6,
+            model => {
+                return Err(custom_error(format_args!(
+                    "unsupported camera model: {:?}",
+                    model
+                )))
+            }
+        };
+        if self.intrinsics.len() != expected_intrinsics {
+            return Err(custom_error(format_args!(
+                "camera model {:?} must have {} intrinsics",
+                self.camera_model, expected_intrinsics
+            )));
+        }
+        // The focals and principal point are always the last four intrinsics.
+        let (parameters, pinhole) = self.intrinsics.split_at(expected_intrinsics - 4);
+        let projection = match *parameters {
+            [] => CameraProjection::Pinhole,
+            [xi] => CameraProjection::Unified { xi },
+            [xi, alpha] => CameraProjection::DoubleSphere { xi, alpha },
+            _ => unreachable!(),
+        };
+        let intrinsics = CameraIntrinsics::identity()
+            .focals(Vector2::new(pinhole[0], pinhole[1]))
+            .principal_point(Point2::new(pinhole[2], pinhole[3]));
+
+        let coefficients = &self.distortion_coeffs;
+        let distortion = match self.distortion_model.as_str() {
+            "none" => Distortion::None,
+            "radtan" if coefficients.len() == 4 => {
+                Distortion::RadialTangential(coefficients.clone())
+            }
+            "equidistant" if coefficients.len() == 4 => Distortion::Equidistant([
+                coefficients[0],
+                coefficients[1],
+                coefficients[2],
+                coefficients[3],
+            ]),
+            "radtan" | "equidistant" => {
+                return Err(custom_error(format_args!(
+                    "distortion model {:?} must have 4 coefficients",
+                    self.distortion_model
+                )))
+            }
+            model => {
+                return Err(custom_error(format_args!(
+                    "unsupported distortion model: {:?}",
+                    model
+                )))
+            }
+        };
+
+        let calibration = CameraCalibration::new(intrinsics, projection, distortion);
+        Ok(match self.resolution {
+            Some([width, height]) => calibration.resolution(Vector2::new(width, height)),
+            None => calibration,
+        })
+    }
+
+    fn from_calibration(calibration: &CameraCalibration) -> Result<Self, Error> {
+        let intrinsics = &calibration.intrinsics;
+        if intrinsics.skew != 0.0 {
+            return Err(custom_error(
+                "Kalibr calibration files cannot store camera skew",
+            ));
+        }
+        let (camera_model, mut parameters) = match calibration.projection {
+            CameraProjection::Pinhole => ("pinhole", vec![]),
+            CameraProjection::Unified { xi } => ("omni", vec![xi]),
+            CameraProjection::DoubleSphere { xi, alpha } => ("ds", vec![xi, alpha]),
+        };
+        parameters.extend_from_slice(&[
+            intrinsics.focals.x,
+            intrinsics.focals.y,
+            intrinsics.principal_point.x,
+            intrinsics.principal_point.y,
+        ]);
+        let (distortion_model, distortion_coeffs) = match &calibration.distortion {
+            Distortion::None => ("none", vec![]),
+            Distortion::RadialTangential(coefficients) => {
+                // Kalibr only supports `[k1, k2, r1, r2]`, so `k3` and beyond must be zero.
+                if coefficients.len() < 4 || coefficients[4..].iter().any(|&c| c != 0.0) {
+                    return Err(custom_error(
+                        "Kalibr radtan distortion only supports the coefficients [k1, k2, p1, p2]",
+                    ));
+                }
+                ("radtan", coefficients[..4].to_vec())
+            }
+            Distortion::Equidistant(coefficients) => ("equidistant", coefficients.to_vec()),
+        };
+        Ok(Self {
+            camera_model: camera_model.to_owned(),
+            intrinsics: parameters,
+            distortion_model: distortion_model.to_owned(),
+            distortion_coeffs,
+            resolution: calibration.resolution.map(|r| [r.x, r.y]),
+            rostopic: None,
+        })
+    }
+}
+
+/// Reads the [`CameraCalibration`] of every camera from the contents of a Kalibr camchain YAML file.
+///
+/// The calibrations are returned in the order of the camera indices (`cam0`, `cam1`, ...).
+///
+/// ```
+/// use cv_camera_files::{kalibr, CameraProjection};
+/// let file = "
+/// cam0:
+///   camera_model: ds
+///   distortion_coeffs: []
+///   distortion_model: none
+///   intrinsics: [-0.2, 0.6, 300.0, 310.0, 320.0, 240.0]
+///   resolution: [640, 480]
+///   rostopic: /cam0/image_raw
+/// cam1:
+///   T_cn_cnm1:
+///   - [1.0, 0.0, 0.0, 0.1]
+///   - [0.0, 1.0, 0.0, 0.0]
+///   - [0.0, 0.0, 1.0, 0.0]
+///   - [0.0, 0.0, 0.0, 1.0]
+///   camera_model: pinhole
+///   distortion_coeffs: [-0.28, 0.07, 0.0002, 0.00002]
+///   distortion_model: radtan
+///   intrinsics: [458.6, 457.3, 367.2, 248.4]
+///   resolution: [752, 480]
+///   rostopic: /cam1/image_raw
+/// ";
+/// let calibrations = kalibr::read(file).unwrap();
+/// assert_eq!(calibrations[0].projection, CameraProjection::DoubleSphere { xi: -0.2, alpha: 0.6 });
+/// assert!(calibrations[0].double_sphere().is_some());
+/// assert_eq!(calibrations[1].intrinsics.focals.x, 458.6);
+///
+/// // Writing the calibrations and reading them back preserves them.
+/// let written = kalibr::write(&calibrations).unwrap();
+/// assert_eq!(kalibr::read(&written).unwrap(), calibrations);
+/// ```
+pub fn read(file: &str) -> Result<Vec<CameraCalibration>, Error> {
+    let cameras: HashMap<String, KalibrCamera> = serde_yaml::from_str(file)?;
+    let mut cameras = cameras
+        .into_iter()
+        .map(|(name, camera)| {
+            let index = name
+                .strip_prefix("cam")
+                .and_then(|index| index.parse::<usize>().ok())
+                .ok_or_else(|| custom_error(format_args!("unexpected camera name: {:?}", name)))?;
+            Ok((index, camera.calibration()?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    cameras.sort_by_key(|&(index, _)| index);
+    Ok(cameras
+        .into_iter()
+        .map(|(_, calibration)| calibration)
+        .collect())
+}
+
+/// Writes the [`CameraCalibration`] of every camera as a Kalibr camchain YAML file.
+///
+/// The cameras are named `cam0`, `cam1`, ... in the order they are given.
+/// This fails if a camera has skew or has radial-tangential distortion beyond `[k1, k2, p1, p2]`,
+/// since Kalibr cannot represent either.
+pub fn write(calibrations: &[CameraCalibration]) -> Result<String, Error> {
+    let mut file = serde_yaml::Mapping::new();
+    for (index, calibration) in calibrations.iter().enumerate() {
+        let mut camera = KalibrCamera::from_calibration(calibration)?;
+        camera.rostopic = Some(format!("/cam{}/image_raw", index));
+        file.insert(
+            format!("cam{}", index).into(),
+            serde_yaml::to_value(camera)?,
+        );
+    }
+    serde_yaml::to_string(&file)
+}
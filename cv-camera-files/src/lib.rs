@@ -0,0 +1,339 @@
+//! This crate reads and writes camera calibration files produced by common calibration tools.
+//!
+//! The following formats are supported:
+//!
+//! * [`opencv`] - OpenCV `FileStorage` YAML with `camera_matrix` and `distortion_coefficients`
+//! * [`ros`] - ROS `camera_info` YAML
+//! * [`kalibr`] - Kalibr camchain YAML
+//!
+//! Every format is read into a [`CameraCalibration`], which describes the camera independently of the file format.
+//! A [`CameraCalibration`] can be converted into (and created from) the camera models in `cv-pinhole`
+//! and `cv-omnidirectional`.
+//!
+//! ```
+//! use cv_camera_files::{opencv, ros};
+//! let file = "%YAML:1.0
+//! ---
+//! image_width: 640
+//! image_height: 480
+//! camera_matrix: !!opencv-matrix
+//!    rows: 3
+//!    cols: 3
+//!    dt: d
+//!    data: [ 500., 0., 320., 0., 505., 240., 0., 0., 1. ]
+//! distortion_coefficients: !!opencv-matrix
+//!    rows: 1
+//!    cols: 5
+//!    dt: d
+//!    data: [ -0.2, 0., 0., 0., 0. ]
+//! ";
+//! let calibration = opencv::read(file).unwrap();
+//! let intrinsics = calibration.k1_distortion().unwrap();
+//! assert_eq!(intrinsics.simple_intrinsics.focals.y, 505.0);
+//! assert_eq!(intrinsics.k1, -0.2);
+//!
+//! // Convert it to a ROS camera_info file.
+//! let ros_file = ros::write(&calibration, "camera").unwrap();
+//! assert_eq!(ros::read(&ros_file).unwrap(), calibration);
+//! ```
+
+pub mod kalibr;
+pub mod opencv;
+pub mod ros;
+
+use cv_core::nalgebra::{Matrix3, Point2, Vector2};
+use cv_omnidirectional::{DoubleSphereIntrinsics, UnifiedIntrinsics};
+use cv_pinhole::{CameraIntrinsics, CameraIntrinsicsK1Distortion};
+use serde::{Deserialize, Serialize};
+
+/// The error produced when a calibration file cannot be read or written.
+pub type Error = serde_yaml::Error;
+
+/// The projection model of a calibrated camera (before distortion is applied).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum CameraProjection {
+    /// A pinhole camera.
+    Pinhole,
+    /// The unified camera model (see [`UnifiedIntrinsics`]).
+    Unified { xi: f64 },
+    /// The double sphere camera model (see [`DoubleSphereIntrinsics`]).
+    DoubleSphere { xi: f64, alpha: f64 },
+}
+
+/// The lens distortion of a calibrated camera.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Distortion {
+    /// No distortion.
+    None,
+    /// The radial-tangential distortion model used by OpenCV (called "plumb_bob" in ROS and "radtan" in Kalibr).
+    ///
+    /// The coefficients are in the OpenCV order `[k1, k2, p1, p2, k3, k4, k5, k6]`, and may be truncated
+    /// after `p2`, `k3`, or `k6` (which is the "rational_polynomial" model in ROS).
+    RadialTangential(Vec<f64>),
+    /// The equidistant fisheye distortion model used by `cv::fisheye` in OpenCV with coefficients `[k1, k2, k3, k4]`.
+    Equidistant([f64; 4]),
+}
+
+impl Distortion {
+    /// Checks if the distortion has no effect on the image.
+    pub fn is_none(&self) -> bool {
+        match self {
+            Self::None => true,
+            Self::RadialTangential(coefficients) => coefficients.iter().all(|&c| c == 0.0),
+            Self::Equidistant(coefficients) => coefficients.iter().all(|&c| c == 0.0),
+        }
+    }
+}
+
+/// The calibration of a single camera, independent of the file format it came from.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct CameraCalibration {
+    /// The width and height of the image in pixels, if it is known.
+    pub resolution: Option<Vector2<usize>>,
+    /// The focals, principal point, and skew of the camera.
+    pub intrinsics: CameraIntrinsics,
+    pub projection: CameraProjection,
+    pub distortion: Distortion,
+}
+
+impl CameraCalibration {
+    /// Creates a calibration from camera intrinsics with the given projection and distortion.
+    pub fn new(
+        intrinsics: CameraIntrinsics,
+        projection: CameraProjection,
+        distortion: Distortion,
+    ) -> Self {
+        Self {
+            resolution: None,
+            intrinsics,
+            projection,
+            distortion,
+        }
+    }
+
+    /// Sets the resolution of the image in pixels.
+    pub fn resolution(self, resolution: Vector2<usize>) -> Self {
+        Self {
+            resolution: Some(resolution),
+            ..self
+        }
+    }
+
+    /// Retrieves the [`CameraIntrinsics`] if this is a pinhole camera without distortion.
+    pub fn pinhole(&self) -> Option<CameraIntrinsics> {
+        if self.projection == CameraProjection::Pinhole && self.distortion.is_none() {
+            Some(self.intrinsics)
+        } else {
+            None
+        }
+    }
+
+    /// Retrieves the [`CameraIntrinsicsK1Distortion`] if this is a pinhole camera with at most radial distortion.
+    ///
+    /// [`CameraIntrinsicsK1Distortion`] uses a division model for distortion, while the files
+    /// use the polynomial model from OpenCV. The two models agree to first order in `k1`,
+    /// so `k1` is used directly. Returns `None` if any other coefficient is nonzero, unless
+    /// the coefficients are exactly the series expansion written by `From<CameraIntrinsicsK1Distortion>`.
+    ///
+    /// ```
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_camera_files::{CameraCalibration, CameraProjection, Distortion};
+    /// let calibration = |coefficients| {
+    ///     CameraCalibration::new(
+    ///         CameraIntrinsics::identity(),
+    ///         CameraProjection::Pinhole,
+    ///         Distortion::RadialTangential(coefficients),
+    ///     )
+    /// };
+    /// assert!(calibration(vec![-0.2, 0.0, 0.0, 0.0, 0.0]).k1_distortion().is_some());
+    /// assert!(calibration(vec![-0.2, 0.05, 0.0, 0.0, 0.0]).k1_distortion().is_none());
+    /// assert!(calibration(vec![-0.2, 0.0, 0.0, 0.0, 0.01]).k1_distortion().is_none());
+    /// assert!(calibration(vec![-0.2, 0.0, 0.001, 0.0]).k1_distortion().is_none());
+    /// ```
+    pub fn k1_distortion(&self) -> Option<CameraIntrinsicsK1Distortion> {
+        if self.projection != CameraProjection::Pinhole {
+            return None;
+        }
+        let k1 = match &self.distortion {
+            Distortion::None => 0.0,
+            Distortion::RadialTangential(coefficients) => {
+                let k1 = coefficients.first().copied().unwrap_or(0.0);
+                let expansion = division_model_expansion(k1);
+                let only_k1 = coefficients.iter().skip(1).all(|&c| c == 0.0);
+                let is_expansion = coefficients
+                    .iter()
+                    .enumerate()
+                    .all(|(i, &c)| c == expansion.get(i).copied().unwrap_or(0.0));
+                if !only_k1 && !is_expansion {
+                    return None;
+                }
+                k1
+            }
+            Distortion::Equidistant(_) => return None,
+        };
+        Some(CameraIntrinsicsK1Distortion::new(self.intrinsics, k1))
+    }
+
+    /// Retrieves the [`UnifiedIntrinsics`] if this is a unified camera without distortion.
+    pub fn unified(&self) -> Option<UnifiedIntrinsics> {
+        match self.projection {
+            CameraProjection::Unified { xi } if self.distortion.is_none() => {
+                Some(UnifiedIntrinsics::new(self.intrinsics, xi))
+            }
+            _ => None,
+        }
+    }
+
+    /// Retrieves the [`DoubleSphereIntrinsics`] if this is a double sphere camera without distortion.
+    pub fn double_sphere(&self) -> Option<DoubleSphereIntrinsics> {
+        match self.projection {
+            CameraProjection::DoubleSphere { xi, alpha } if self.distortion.is_none() => {
+                Some(DoubleSphereIntrinsics::new(self.intrinsics, xi, alpha))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<CameraIntrinsics> for CameraCalibration {
+    fn from(intrinsics: CameraIntrinsics) -> Self {
+        Self::new(intrinsics, CameraProjection::Pinhole, Distortion::None)
+    }
+}
+
+impl From<CameraIntrinsicsK1Distortion> for CameraCalibration {
+    /// The division model of [`CameraIntrinsicsK1Distortion`] is stored as the polynomial
+    /// radial distortion `[k1, 2 * k1^2, 0, 0, 5 * k1^3]`, which is its series expansion to third order.
+    ///
+    /// ```
+    /// use cv_core::{CameraModel, KeyPoint};
+    /// use cv_core::nalgebra::{Point2, Vector2};
+    /// use cv_pinhole::{CameraIntrinsics, CameraIntrinsicsK1Distortion};
+    /// use cv_camera_files::{CameraCalibration, Distortion};
+    /// let intrinsics = CameraIntrinsicsK1Distortion::new(
+    ///     CameraIntrinsics::identity().focal(800.0).principal_point(Point2::new(500.0, 400.0)),
+    ///     -0.1,
+    /// );
+    /// let calibration = CameraCalibration::from(intrinsics);
+    /// let coefficients = match &calibration.distortion {
+    ///     Distortion::RadialTangential(coefficients) => coefficients.clone(),
+    ///     _ => unreachable!(),
+    /// };
+    /// // Apply the polynomial model from OpenCV to an undistorted point.
+    /// let u = intrinsics.calibrate(KeyPoint(Point2::new(700.0, 550.0))).0.coords;
+    /// let r2 = u.norm_squared();
+    /// let scale = 1.0 + coefficients[0] * r2 + coefficients[1] * r2 * r2 + coefficients[4] * r2 * r2 * r2;
+    /// let distorted = intrinsics.simple_intrinsics.calibrate(KeyPoint(Point2::new(700.0, 550.0))).0.coords;
+    /// assert!((u * scale - distorted).norm() < 1e-4);
+    /// assert_eq!(calibration.k1_distortion(), Some(intrinsics));
+    /// ```
+    fn from(intrinsics: CameraIntrinsicsK1Distortion) -> Self {
+        let k1 = intrinsics.k1;
+        let distortion = if k1 == 0.0 {
+            Distortion::None
+        } else {
+            Distortion::RadialTangential(division_model_expansion(k1).to_vec())
+        };
+        Self::new(
+            intrinsics.simple_intrinsics,
+            CameraProjection::Pinhole,
+            distortion,
+        )
+    }
+}
+
+impl From<UnifiedIntrinsics> for CameraCalibration {
+    fn from(intrinsics: UnifiedIntrinsics) -> Self {
+        Self::new(
+            intrinsics.simple_intrinsics,
+            CameraProjection::Unified { xi: intrinsics.xi },
+            Distortion::None,
+        )
+    }
+}
+
+impl From<DoubleSphereIntrinsics> for CameraCalibration {
+    fn from(intrinsics: DoubleSphereIntrinsics) -> Self {
+        Self::new(
+            intrinsics.simple_intrinsics,
+            CameraProjection::DoubleSphere {
+                xi: intrinsics.xi,
+                alpha: intrinsics.alpha,
+            },
+            Distortion::None,
+        )
+    }
+}
+
+/// A row-major matrix as it appears in OpenCV and ROS files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileMatrix {
+    rows: usize,
+    cols: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dt: Option<String>,
+    data: Vec<f64>,
+}
+
+impl FileMatrix {
+    fn new(rows: usize, cols: usize, data: Vec<f64>) -> Self {
+        Self {
+            rows,
+            cols,
+            dt: None,
+            data,
+        }
+    }
+
+    fn intrinsics(&self) -> Result<CameraIntrinsics, Error> {
+        if self.rows != 3 || self.cols != 3 || self.data.len() != 9 {
+            return Err(custom_error("camera matrix must be 3x3"));
+        }
+        let d = &self.data;
+        Ok(CameraIntrinsics {
+            focals: Vector2::new(d[0], d[4]),
+            principal_point: Point2::new(d[2], d[5]),
+            skew: d[1],
+        })
+    }
+
+    fn from_intrinsics(intrinsics: &CameraIntrinsics) -> Self {
+        Self::new(3, 3, matrix_data(&intrinsics.matrix()))
+    }
+}
+
+/// Converts a matrix into its row-major data.
+fn matrix_data(matrix: &Matrix3<f64>) -> Vec<f64> {
+    matrix.transpose().iter().copied().collect()
+}
+
+fn custom_error(message: impl core::fmt::Display) -> Error {
+    <Error as serde::de::Error>::custom(message)
+}
+
+/// Extracts the coefficients of the distortion for the formats which only support pinhole cameras.
+/// The polynomial radial distortion `[k1, k2, p1, p2, k3]` which approximates the division model of
+/// [`CameraIntrinsicsK1Distortion`] to third order.
+fn division_model_expansion(k1: f64) -> [f64; 5] {
+    [k1, 2.0 * k1 * k1, 0.0, 0.0, 5.0 * k1 * k1 * k1]
+}
+
+fn pinhole_distortion(calibration: &CameraCalibration) -> Result<&Distortion, Error> {
+    if calibration.projection != CameraProjection::Pinhole {
+        return Err(custom_error(
+            "only pinhole cameras can be stored in this format",
+        ));
+    }
+    Ok(&calibration.distortion)
+}
+
+/// Checks that the number of radial-tangential coefficients is one that OpenCV understands.
+fn check_radial_tangential(coefficients: &[f64]) -> Result<(), Error> {
+    match coefficients.len() {
+        4 | 5 | 8 => Ok(()),
+        n => Err(custom_error(format_args!(
+            "expected 4, 5, or 8 radial-tangential distortion coefficients, but found {}",
+            n
+        ))),
+    }
+}
@@ -0,0 +1,122 @@
+//! OpenCV `FileStorage` YAML, as written by `cv::FileStorage` after `cv::calibrateCamera`.
+//!
+//! The file contains `camera_matrix`, `distortion_coefficients` and optionally `image_width` and `image_height`.
+//! Only pinhole cameras with radial-tangential distortion can be stored in this format.
+
+use crate::{
+    check_radial_tangential, custom_error, pinhole_distortion, CameraCalibration, CameraProjection,
+    Distortion, Error, FileMatrix,
+};
+use cv_core::nalgebra::Vector2;
+use serde::Deserialize;
+use std::fmt::Write;
+
+#[derive(Deserialize)]
+struct OpenCvFile {
+    image_width: Option<usize>,
+    image_height: Option<usize>,
+    camera_matrix: FileMatrix,
+    #[serde(alias = "dist_coeffs", alias = "distortion_coeffs")]
+    distortion_coefficients: Option<FileMatrix>,
+}
+
+/// Reads a [`CameraCalibration`] from the contents of an OpenCV `FileStorage` YAML file.
+///
+/// The `%YAML:1.0` directive and the `!!opencv-matrix` tags that OpenCV writes are accepted.
+pub fn read(file: &str) -> Result<CameraCalibration, Error> {
+    // OpenCV writes a non-standard YAML directive, so directives are removed before parsing.
+    let file = file
+        .lines()
+        .filter(|line| !line.starts_with('%'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("!!opencv-matrix", "");
+    let file: OpenCvFile = serde_yaml::from_str(&file)?;
+    let distortion = match file.distortion_coefficients {
+        Some(coefficients) if !coefficients.data.is_empty() => {
+            check_radial_tangential(&coefficients.data)?;
+            Distortion::RadialTangential(coefficients.data)
+        }
+        _ => Distortion::None,
+    };
+    let calibration = CameraCalibration::new(
+        file.camera_matrix.intrinsics()?,
+        CameraProjection::Pinhole,
+        distortion,
+    );
+    Ok(match (file.image_width, file.image_height) {
+        (Some(width), Some(height)) => calibration.resolution(Vector2::new(width, height)),
+        _ => calibration,
+    })
+}
+
+/// Writes a [`CameraCalibration`] as an OpenCV `FileStorage` YAML file.
+///
+/// This fails if the camera is not a pinhole camera or if it has equidistant distortion.
+///
+/// ```
+/// use cv_core::nalgebra::{Point2, Vector2};
+/// use cv_pinhole::CameraIntrinsics;
+/// use cv_camera_files::{opencv, CameraCalibration, CameraProjection, Distortion};
+/// let calibration = CameraCalibration::new(
+///     CameraIntrinsics::identity()
+///         .focals(Vector2::new(984.2439, 980.8141))
+///         .principal_point(Point2::new(690.0, 233.1966)),
+///     CameraProjection::Pinhole,
+///     Distortion::RadialTangential(vec![-0.3728755, 0.2037299, 0.002219027, 0.001383707, -0.07233722]),
+/// )
+/// .resolution(Vector2::new(1392, 512));
+/// let file = opencv::write(&calibration).unwrap();
+/// assert!(file.starts_with("%YAML:1.0"));
+/// assert_eq!(opencv::read(&file).unwrap(), calibration);
+///
+/// let calibration = CameraCalibration::from(calibration.intrinsics);
+/// assert_eq!(opencv::read(&opencv::write(&calibration).unwrap()).unwrap(), calibration);
+/// ```
+pub fn write(calibration: &CameraCalibration) -> Result<String, Error> {
+    let coefficients = match pinhole_distortion(calibration)? {
+        // The coefficients are left out so that the distortion is read back as `Distortion::None`.
+        Distortion::None => vec![],
+        Distortion::RadialTangential(coefficients) => {
+            check_radial_tangential(coefficients)?;
+            coefficients.clone()
+        }
+        Distortion::Equidistant(_) => {
+            return Err(custom_error(
+                "equidistant distortion cannot be stored in an OpenCV calibration file",
+            ))
+        }
+    };
+    let mut file = String::from("%YAML:1.0\n---\n");
+    if let Some(resolution) = calibration.resolution {
+        writeln!(file, "image_width: {}", resolution.x).unwrap();
+        writeln!(file, "image_height: {}", resolution.y).unwrap();
+    }
+    write_matrix(
+        &mut file,
+        "camera_matrix",
+        &FileMatrix::from_intrinsics(&calibration.intrinsics),
+    );
+    if !coefficients.is_empty() {
+        write_matrix(
+            &mut file,
+            "distortion_coefficients",
+            &FileMatrix::new(1, coefficients.len(), coefficients),
+        );
+    }
+    Ok(file)
+}
+
+fn write_matrix(file: &mut String, name: &str, matrix: &FileMatrix) {
+    let data = matrix
+        .data
+        .iter()
+        .map(|v| format!("{:?}", v))
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(file, "{}: !!opencv-matrix", name).unwrap();
+    writeln!(file, "   rows: {}", matrix.rows).unwrap();
+    writeln!(file, "   cols: {}", matrix.cols).unwrap();
+    writeln!(file, "   dt: d").unwrap();
+    writeln!(file, "   data: [ {} ]", data).unwrap();
+}
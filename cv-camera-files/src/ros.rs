@@ -0,0 +1,129 @@
+//! ROS `camera_info` YAML, as written by `camera_calibration` and read by `camera_info_manager`.
+//!
+//! The distortion models "plumb_bob", "rational_polynomial", and "equidistant" are supported.
+//! Only pinhole cameras can be stored in this format. The rectification and projection matrices
+//! are written for a monocular camera and ignored when reading.
+
+use crate::{
+    check_radial_tangential, custom_error, matrix_data, pinhole_distortion, CameraCalibration,
+    CameraProjection, Distortion, Error, FileMatrix,
+};
+use cv_core::nalgebra::{Matrix3, Vector2};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct CameraInfoFile {
+    image_width: usize,
+    image_height: usize,
+    #[serde(default)]
+    camera_name: String,
+    camera_matrix: FileMatrix,
+    #[serde(default)]
+    distortion_model: String,
+    distortion_coefficients: FileMatrix,
+    #[serde(skip_deserializing)]
+    rectification_matrix: Option<FileMatrix>,
+    #[serde(skip_deserializing)]
+    projection_matrix: Option<FileMatrix>,
+}
+
+/// Reads a [`CameraCalibration`] from the contents of a ROS `camera_info` YAML file.
+pub fn read(file: &str) -> Result<CameraCalibration, Error> {
+    let file: CameraInfoFile = serde_yaml::from_str(file)?;
+    let coefficients = file.distortion_coefficients.data;
+    let distortion = if coefficients.is_empty() {
+        Distortion::None
+    } else {
+        match file.distortion_model.as_str() {
+            "plumb_bob" | "rational_polynomial" => {
+                check_radial_tangential(&coefficients)?;
+                Distortion::RadialTangential(coefficients)
+            }
+            "equidistant" => {
+                if coefficients.len() != 4 {
+                    return Err(custom_error(
+                        "equidistant distortion must have 4 coefficients",
+                    ));
+                }
+                Distortion::Equidistant([
+                    coefficients[0],
+                    coefficients[1],
+                    coefficients[2],
+                    coefficients[3],
+                ])
+            }
+            model => {
+                return Err(custom_error(format_args!(
+                    "unsupported distortion model: {:?}",
+                    model
+                )))
+            }
+        }
+    };
+    Ok(CameraCalibration::new(
+        file.camera_matrix.intrinsics()?,
+        CameraProjection::Pinhole,
+        distortion,
+    )
+    .resolution(Vector2::new(file.image_width, file.image_height)))
+}
+
+/// Writes a [`CameraCalibration`] as a ROS `camera_info` YAML file for the camera named `camera_name`.
+///
+/// This fails if the camera is not a pinhole camera or if the resolution is unknown, since ROS requires it.
+///
+/// ```
+/// use cv_core::nalgebra::{Point2, Vector2};
+/// use cv_pinhole::CameraIntrinsics;
+/// use cv_camera_files::{ros, CameraCalibration, CameraProjection, Distortion};
+/// let calibration = CameraCalibration::new(
+///     CameraIntrinsics::identity()
+///         .focals(Vector2::new(460.0, 458.0))
+///         .principal_point(Point2::new(367.0, 248.0)),
+///     CameraProjection::Pinhole,
+///     Distortion::Equidistant([0.1, -0.02, 0.003, -0.0004]),
+/// )
+/// .resolution(Vector2::new(752, 480));
+/// let file = ros::write(&calibration, "cam0").unwrap();
+/// assert!(file.contains("distortion_model: equidistant"));
+/// assert_eq!(ros::read(&file).unwrap(), calibration);
+///
+/// let calibration = CameraCalibration::from(calibration.intrinsics).resolution(Vector2::new(752, 480));
+/// assert_eq!(ros::read(&ros::write(&calibration, "cam0").unwrap()).unwrap(), calibration);
+/// ```
+pub fn write(calibration: &CameraCalibration, camera_name: &str) -> Result<String, Error> {
+    let (distortion_model, coefficients) = match pinhole_distortion(calibration)? {
+        // ROS treats empty coefficients as no distortion, so it is read back as `Distortion::None`.
+        Distortion::None => ("plumb_bob", vec![]),
+        Distortion::RadialTangential(coefficients) => {
+            check_radial_tangential(coefficients)?;
+            let model = if coefficients.len() == 8 {
+                "rational_polynomial"
+            } else {
+                "plumb_bob"
+            };
+            (model, coefficients.clone())
+        }
+        Distortion::Equidistant(coefficients) => ("equidistant", coefficients.to_vec()),
+    };
+    let resolution = calibration
+        .resolution
+        .ok_or_else(|| custom_error("ROS camera_info files require the image resolution"))?;
+    let camera_matrix = calibration.intrinsics.matrix();
+    let mut projection_matrix = matrix_data(&camera_matrix);
+    // The projection matrix is the camera matrix with an additional zero column for a monocular camera.
+    for row in (1..=3).rev() {
+        projection_matrix.insert(row * 3, 0.0);
+    }
+    let file = CameraInfoFile {
+        image_width: resolution.x,
+        image_height: resolution.y,
+        camera_name: camera_name.to_owned(),
+        camera_matrix: FileMatrix::new(3, 3, matrix_data(&camera_matrix)),
+        distortion_model: distortion_model.to_owned(),
+        distortion_coefficients: FileMatrix::new(1, coefficients.len(), coefficients),
+        rectification_matrix: Some(FileMatrix::new(3, 3, matrix_data(&Matrix3::identity()))),
+        projection_matrix: Some(FileMatrix::new(3, 4, projection_matrix)),
+    };
+    serde_yaml::to_string(&file)
+}
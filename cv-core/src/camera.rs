@@ -1,5 +1,8 @@
-use crate::{ImagePoint, KeyPoint};
-use nalgebra::{Unit, Vector3};
+use crate::{CameraPoint, ImagePoint, KeyPoint};
+use nalgebra::{
+    allocator::Allocator, DefaultAllocator, DimName, Matrix2x4, MatrixMN, Unit, Vector3, VectorN,
+    U2,
+};
 
 /// Describes the direction that the projection onto the camera's optical center
 /// came from. It is implemented on projection items from different camera models.
@@ -69,3 +72,49 @@ pub trait CameraModel {
     /// Extracts the pixel location in the image from the projection.
     fn uncalibrate(&self, projection: Self::Projection) -> KeyPoint;
 }
+
+/// A [`CameraModel`] which can compute the analytic Jacobians of [`CameraModel::uncalibrate`].
+///
+/// This allows optimizers to refine the camera intrinsics (and points seen by the camera)
+/// with gradient-based methods like Levenberg-Marquardt.
+///
+/// The intrinsic parameters are exposed as a fixed-size vector so that the Jacobian with respect to them
+/// has a known layout. The order of the parameters is documented by each implementor.
+pub trait CameraModelJacobians: CameraModel {
+    /// The number of intrinsic parameters of the camera model.
+    type Parameters: DimName;
+
+    /// Retrieve the intrinsic parameters of the camera model as a vector.
+    fn parameters(&self) -> VectorN<f64, Self::Parameters>
+    where
+        DefaultAllocator: Allocator<f64, Self::Parameters>;
+
+    /// Create the camera model from a vector of intrinsic parameters in the same order as [`CameraModelJacobians::parameters`].
+    fn from_parameters(parameters: &VectorN<f64, Self::Parameters>) -> Self
+    where
+        DefaultAllocator: Allocator<f64, Self::Parameters>;
+
+    /// Projects the point into the image, while also retrieving both Jacobians.
+    ///
+    /// The following things are returned in this order:
+    ///
+    /// * The pixel location of the point in the image
+    /// * The Jacobian of the pixel location in respect to the homogeneous [`CameraPoint`]
+    /// * The Jacobian of the pixel location in respect to the intrinsic parameters
+    ///
+    /// The Jacobian in respect to the point uses the homogeneous coordinates so that it can be chained
+    /// with [`Pose::transform_jacobians`](crate::Pose::transform_jacobians).
+    ///
+    /// Returns `None` if the point cannot be projected into the image by this camera model.
+    #[allow(clippy::type_complexity)]
+    fn uncalibrate_jacobians(
+        &self,
+        point: CameraPoint,
+    ) -> Option<(
+        KeyPoint,
+        Matrix2x4<f64>,
+        MatrixMN<f64, U2, Self::Parameters>,
+    )>
+    where
+        DefaultAllocator: Allocator<f64, U2, Self::Parameters>;
+}
@@ -1,5 +1,5 @@
 use derive_more::{AsMut, AsRef, Deref, DerefMut, From, Into};
-use nalgebra::Point2;
+use nalgebra::{Matrix3, Point2, Vector3};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
@@ -26,3 +26,54 @@ impl ImagePoint for KeyPoint {
         self.0
     }
 }
+
+/// Computes the similarity transform that moves the centroid of the points to the origin
+/// and makes their average distance from the origin `sqrt(2)`.
+///
+/// This is the normalization by Richard Hartley which conditions the direct linear transforms
+/// used to estimate homographies and fundamental matrices.
+/// Returns `None` if there are no points or they are all the same.
+///
+/// ```
+/// use cv_core::nalgebra::Point2;
+/// use cv_core::normalizing_transform;
+/// let points = [Point2::new(10.0, 20.0), Point2::new(14.0, 20.0), Point2::new(12.0, 26.0)];
+/// let transform = normalizing_transform(points.iter().copied()).unwrap();
+/// let normalized: Vec<Point2<f64>> = points
+///     .iter()
+///     .map(|p| Point2::from_homogeneous(transform * p.to_homogeneous()).unwrap())
+///     .collect();
+/// let centroid = normalized.iter().map(|p| p.coords).sum::<cv_core::nalgebra::Vector2<f64>>() / 3.0;
+/// let mean_distance = normalized.iter().map(|p| p.coords.norm()).sum::<f64>() / 3.0;
+/// assert!(centroid.norm() < 1e-12);
+/// assert!((mean_distance - 2.0f64.sqrt()).abs() < 1e-12);
+/// ```
+pub fn normalizing_transform(
+    points: impl Iterator<Item = Point2<f64>> + Clone,
+) -> Option<Matrix3<f64>> {
+    let (sum, count) = points
+        .clone()
+        .fold((Vector3::zeros(), 0), |(sum, count), p| {
+            (sum + p.coords.push(1.0), count + 1)
+        });
+    if count == 0 {
+        return None;
+    }
+    let centroid = Point2::from(sum.xy() / count as f64);
+    let mean_distance = points.map(|p| (p - centroid).norm()).sum::<f64>() / count as f64;
+    if mean_distance <= 0.0 {
+        return None;
+    }
+    let scale = core::f64::consts::SQRT_2 / mean_distance;
+    Some(Matrix3::new(
+        scale,
+        0.0,
+        -scale * centroid.x,
+        0.0,
+        scale,
+        -scale * centroid.y,
+        0.0,
+        0.0,
+        1.0,
+    ))
+}
@@ -69,8 +69,13 @@ mod camera;
 mod keypoint;
 mod matches;
 mod point;
+mod polynomial;
 mod pose;
+mod rig;
+mod se3;
+mod sim3;
 mod so3;
+mod trajectory;
 mod triangulation;
 
 pub use camera::*;
@@ -78,7 +83,12 @@ pub use keypoint::*;
 pub use matches::*;
 pub use nalgebra;
 pub use point::*;
+pub use polynomial::*;
 pub use pose::*;
+pub use rig::*;
 pub use sample_consensus;
+pub use se3::*;
+pub use sim3::*;
 pub use so3::*;
+pub use trajectory::*;
 pub use triangulation::*;
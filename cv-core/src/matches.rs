@@ -1,4 +1,6 @@
-use crate::WorldPoint;
+use crate::{Projective, WorldPoint};
+use nalgebra::Similarity3;
+use sample_consensus::Model;
 
 /// Normalized keypoint match
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -7,3 +9,20 @@ pub struct FeatureMatch<P>(pub P, pub P);
 /// Normalized keypoint to world point match
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct FeatureWorldMatch<P>(pub P, pub WorldPoint);
+
+/// World point to world point match, such as between the same points in two reconstructions
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct WorldMatch(pub WorldPoint, pub WorldPoint);
+
+/// The residual is the distance between the second point and the first point after it is transformed.
+///
+/// If either point is at infinity, the residual is infinite.
+impl Model<WorldMatch> for Similarity3<f64> {
+    fn residual(&self, data: &WorldMatch) -> f64 {
+        let WorldMatch(a, b) = data;
+        match (a.point(), b.point()) {
+            (Some(a), Some(b)) => (self * a - b).norm(),
+            _ => f64::INFINITY,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use arrayvec::ArrayVec;
+use nalgebra::{
+    allocator::Allocator, Complex, DefaultAllocator, DimDiff, DimName, DimSub, MatrixN, U1, U2, U3,
+    U4, U5, U6, U7, U8,
+};
+use num_traits::Float;
+
+/// The highest degree of polynomial that [`real_polynomial_roots`] can solve.
+pub const MAX_POLYNOMIAL_DEGREE: usize = 8;
+
+/// Coefficients smaller than this relative to the largest coefficient are considered zero.
+const NEGLIGIBLE_COEFFICIENT: f64 = 1e-14;
+/// Eigenvalues with an imaginary part smaller than this relative to their real part are considered real.
+///
+/// A root with multiplicity two is split by rounding into a pair with an imaginary part on the order of
+/// the square root of the machine epsilon, so this must be well above that.
+const REAL_TOLERANCE: f64 = 1e-6;
+/// The number of Newton iterations used to polish each root.
+const POLISH_ITERATIONS: usize = 4;
+
+/// Finds the real roots of a polynomial from the eigenvalues of its companion matrix.
+///
+/// The coefficients start from the constant term, so `[c0, c1, c2]` is `c0 + c1 * x + c2 * x^2`.
+/// Leading coefficients which are negligible are dropped, which removes the roots at infinity.
+/// Unlike searching for sign changes, this finds roots with even multiplicity and roots that are close together.
+/// A root with multiplicity is returned once for each eigenvalue it produces.
+///
+/// Panics if the degree is above [`MAX_POLYNOMIAL_DEGREE`].
+///
+/// ```
+/// use cv_core::real_polynomial_roots;
+/// // (x - 1)^2 * (x + 2) * (x^2 + 1) has a double root which doesn't change sign.
+/// let mut roots = real_polynomial_roots(&[2.0, -3.0, 2.0, -2.0, 0.0, 1.0]);
+/// roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+/// assert_eq!(roots.len(), 3);
+/// assert!((roots[0] + 2.0).abs() < 1e-9);
+/// assert!((roots[1] - 1.0).abs() < 1e-6 && (roots[2] - 1.0).abs() < 1e-6);
+/// ```
+pub fn real_polynomial_roots(coefficients: &[f64]) -> ArrayVec<[f64; MAX_POLYNOMIAL_DEGREE]> {
+    let scale = coefficients
+        .iter()
+        .fold(0.0, |scale: f64, &c| scale.max(Float::abs(c)));
+    let degree = match coefficients
+        .iter()
+        .rposition(|&c| Float::abs(c) > NEGLIGIBLE_COEFFICIENT * scale)
+    {
+        Some(degree) => degree,
+        None => return ArrayVec::new(),
+    };
+    assert!(
+        degree <= MAX_POLYNOMIAL_DEGREE,
+        "polynomials of degree {} are not supported",
+        degree
+    );
+    let coefficients = &coefficients[..=degree];
+    let eigenvalues = match degree {
+        0 => return ArrayVec::new(),
+        1 => [Complex::new(-coefficients[0] / coefficients[1], 0.0)]
+            .iter()
+            .copied()
+            .collect(),
+        2 => companion_eigenvalues::<U2>(coefficients),
+        3 => companion_eigenvalues::<U3>(coefficients),
+        4 => companion_eigenvalues::<U4>(coefficients),
+        5 => companion_eigenvalues::<U5>(coefficients),
+        6 => companion_eigenvalues::<U6>(coefficients),
+        7 => companion_eigenvalues::<U7>(coefficients),
+        _ => companion_eigenvalues::<U8>(coefficients),
+    };
+    eigenvalues
+        .into_iter()
+        .filter(|root| Float::abs(root.im) <= REAL_TOLERANCE * Float::abs(root.re).max(1.0))
+        .map(|root| polish(coefficients, root.re))
+        .collect()
+}
+
+/// Computes the eigenvalues of the companion matrix of the polynomial, which are its roots.
+fn companion_eigenvalues<D>(coefficients: &[f64]) -> ArrayVec<[Complex<f64>; MAX_POLYNOMIAL_DEGREE]>
+where
+    D: DimName + DimSub<U1>,
+    DefaultAllocator: Allocator<f64, D, D>
+        + Allocator<f64, D>
+        + Allocator<f64, D, DimDiff<D, U1>>
+        + Allocator<f64, DimDiff<D, U1>>
+        + Allocator<Complex<f64>, D>,
+{
+    let n = D::dim();
+    let mut companion = MatrixN::<f64, D>::zeros();
+    for i in 1..n {
+        companion[(i, i - 1)] = 1.0;
+    }
+    for i in 0..n {
+        companion[(i, n - 1)] = -coefficients[i] / coefficients[n];
+    }
+    companion.complex_eigenvalues().iter().copied().collect()
+}
+
+/// Refines a root with Newton's method, keeping each step only if it gets closer to zero.
+fn polish(coefficients: &[f64], mut root: f64) -> f64 {
+    let evaluate = |x: f64| {
+        coefficients
+            .iter()
+            .rev()
+            .fold((0.0, 0.0), |(value, derivative), &c| {
+                (value * x + c, derivative * x + value)
+            })
+    };
+    let (mut value, mut derivative) = evaluate(root);
+    for _ in 0..POLISH_ITERATIONS {
+        if derivative == 0.0 {
+            break;
+        }
+        let candidate = root - value / derivative;
+        let (candidate_value, candidate_derivative) = evaluate(candidate);
+        if Float::abs(candidate_value) >= Float::abs(value) {
+            break;
+        }
+        root = candidate;
+        value = candidate_value;
+        derivative = candidate_derivative;
+    }
+    root
+}
+
+/// Evaluates a polynomial whose coefficients start from the constant term.
+///
+/// ```
+/// use cv_core::evaluate_polynomial;
+/// assert_eq!(evaluate_polynomial(&[1.0, -2.0, 3.0], 2.0), 9.0);
+/// ```
+pub fn evaluate_polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0.0, |value, &c| value * x + c)
+}
+
+/// Adds `factor` times the product of the polynomials `a` and `b` to `output`.
+///
+/// All of the polynomials start from the constant term. Terms of the product beyond the end of `output`
+/// must be zero, which allows polynomials of different degrees to be stored in arrays of the same size.
+///
+/// ```
+/// use cv_core::add_polynomial_product;
+/// // (1 + x) * (1 - x) = 1 - x^2
+/// let mut product = [0.0; 3];
+/// add_polynomial_product(&mut product, 1.0, &[1.0, 1.0, 0.0], &[1.0, -1.0, 0.0]);
+/// assert_eq!(product, [1.0, 0.0, -1.0]);
+/// ```
+pub fn add_polynomial_product(output: &mut [f64], factor: f64, a: &[f64], b: &[f64]) {
+    for (i, &a) in a.iter().enumerate() {
+        for (j, &b) in b.iter().enumerate() {
+            match output.get_mut(i + j) {
+                Some(output) => *output += factor * a * b,
+                None => debug_assert!(
+                    a * b == 0.0,
+                    "the product doesn't fit in the output polynomial"
+                ),
+            }
+        }
+    }
+}
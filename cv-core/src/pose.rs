@@ -1,8 +1,12 @@
-use crate::{Bearing, CameraPoint, FeatureWorldMatch, Projective, Skew3, WorldPoint};
+use crate::{
+    Bearing, CameraPoint, FeatureMatch, FeatureWorldMatch, Projective, Se3, Skew3, WorldPoint,
+};
 use derive_more::{AsMut, AsRef, From, Into};
 use nalgebra::{
-    IsometryMatrix3, Matrix4, Matrix4x6, Matrix6x4, Rotation3, Vector3, Vector4, Vector6,
+    IsometryMatrix3, Matrix3, Matrix4, Matrix4x6, Matrix6, Matrix6x4, Rotation3, Vector3, Vector4,
+    Vector6, U3,
 };
+use num_traits::Float;
 use sample_consensus::Model;
 
 #[cfg(feature = "serde-serialize")]
@@ -49,6 +53,9 @@ pub trait Pose: From<IsometryMatrix3<f64>> + Clone + Copy {
     }
 
     /// Retrieve the se(3) representation of the pose.
+    ///
+    /// This concatenates the translation with the so(3) rotation, so it is not the log map of SE(3).
+    /// Use [`Pose::log`] for the log map.
     fn se3(self) -> Vector6<f64> {
         let isometry = self.isometry();
         let t = isometry.translation.vector;
@@ -57,12 +64,90 @@ pub trait Pose: From<IsometryMatrix3<f64>> + Clone + Copy {
     }
 
     /// Set the se(3) representation of the pose.
+    ///
+    /// This is the inverse of [`Pose::se3`], so it is not the exponential map of SE(3).
+    /// Use [`Pose::exp`] for the exponential map.
     fn from_se3(se3: Vector6<f64>) -> Self {
         let translation = se3.xyz();
         let rotation = Skew3(Vector3::new(se3[3], se3[4], se3[5])).into();
         Self::from_parts(translation, rotation)
     }
 
+    /// Retrieve the se(3) tangent of the pose with the log map of SE(3).
+    fn log(self) -> Se3 {
+        self.isometry().into()
+    }
+
+    /// Create the pose from an se(3) tangent with the exponential map of SE(3).
+    fn exp(tangent: Se3) -> Self {
+        IsometryMatrix3::from(tangent).into()
+    }
+
+    /// Retrieve the adjoint of the pose, which transforms a tangent on its right side to its left side.
+    ///
+    /// See [`Se3::adjoint`].
+    fn adjoint(self) -> Matrix6<f64> {
+        Se3::adjoint(self.isometry())
+    }
+
+    /// Transform the given point to an output point, while also retrieving the Jacobian of the output
+    /// in respect to a perturbation `exp(dx) * pose` on the left side of the pose.
+    ///
+    /// The following things are returned in this order:
+    ///
+    /// * The output point of the transformation
+    /// * The Jacobian of the output in respect to the se(3) perturbation (with translation components before so(3) components)
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{Point3, Rotation3, Vector3, Vector6};
+    /// use cv_core::{CameraPoint, CameraToCamera, Pose, Projective, Se3};
+    /// let pose = CameraToCamera::from_parts(Vector3::new(0.1, -0.2, 0.3), Rotation3::from_euler_angles(0.1, 0.2, 0.3));
+    /// let point = CameraPoint::from_point(Point3::new(0.5, -1.0, 2.0));
+    /// let (output, jacobian) = pose.transform_jacobian_left(point);
+    /// let delta = Vector6::new(1e-7, -2e-7, 1e-7, 3e-7, -1e-7, 2e-7);
+    /// let perturbed = CameraToCamera::exp(Se3(delta)).isometry() * pose.isometry();
+    /// let expected = CameraToCamera::from(perturbed).transform(point).0 - output.0;
+    /// assert!((jacobian * delta - expected).norm() < 1e-12);
+    /// ```
+    fn transform_jacobian_left(
+        self,
+        input: Self::InputPoint,
+    ) -> (Self::OutputPoint, Matrix4x6<f64>) {
+        let output = pose_output(self, input);
+        let mut jacobian = Matrix4x6::zeros();
+        jacobian
+            .fixed_slice_mut::<U3, U3>(0, 0)
+            .copy_from(&(Matrix3::identity() * output.w));
+        jacobian
+            .fixed_slice_mut::<U3, U3>(0, 3)
+            .copy_from(&-output.xyz().cross_matrix());
+        (output.into(), jacobian)
+    }
+
+    /// Transform the given point to an output point, while also retrieving the Jacobian of the output
+    /// in respect to a perturbation `pose * exp(dx)` on the right side of the pose.
+    ///
+    /// The following things are returned in this order:
+    ///
+    /// * The output point of the transformation
+    /// * The Jacobian of the output in respect to the se(3) perturbation (with translation components before so(3) components)
+    fn transform_jacobian_right(
+        self,
+        input: Self::InputPoint,
+    ) -> (Self::OutputPoint, Matrix4x6<f64>) {
+        let output = pose_output(self, input);
+        let input = input.homogeneous();
+        let rotation = *self.isometry().rotation.matrix();
+        let mut jacobian = Matrix4x6::zeros();
+        jacobian
+            .fixed_slice_mut::<U3, U3>(0, 0)
+            .copy_from(&(rotation * input.w));
+        jacobian
+            .fixed_slice_mut::<U3, U3>(0, 3)
+            .copy_from(&(-rotation * input.xyz().cross_matrix()));
+        (output.into(), jacobian)
+    }
+
     /// Transform the given point to an output point, while also retrieving both Jacobians.
     ///
     /// The following things are returned in this order:
@@ -246,3 +331,30 @@ impl Pose for CameraToCamera {
         self.into()
     }
 }
+
+/// The residual is the epipolar constraint between the bearings `a` and `b` of a match,
+/// which is `abs(dot(b, cross(t, R * a)))` for the rotation `R` and normalized translation `t`.
+/// This is the sine of the angle between `b` and the epipolar plane, times the sine of
+/// the angle between `t` and `R * a`.
+///
+/// If the pose has no translation, the epipolar constraint is undefined, so the residual is
+/// the cosine distance between `b` and `R * a` instead.
+impl<P> Model<FeatureMatch<P>> for CameraToCamera
+where
+    P: Bearing,
+{
+    fn residual(&self, data: &FeatureMatch<P>) -> f64 {
+        let CameraToCamera(iso) = *self;
+        let FeatureMatch(a, b) = data;
+
+        let rotated = iso.rotation * a.bearing();
+        let b = b.bearing();
+        let translation = iso.translation.vector;
+        let norm = translation.norm();
+        if norm == 0.0 {
+            1.0 - b.dot(&rotated)
+        } else {
+            Float::abs(b.dot(&(translation / norm).cross(&rotated)))
+        }
+    }
+}
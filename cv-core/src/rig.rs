@@ -0,0 +1,141 @@
+use crate::{Bearing, CameraToCamera, FeatureMatch, FeatureWorldMatch, Pose, WorldToCamera};
+use nalgebra::{Point3, Unit, Vector3};
+use num_traits::Float;
+use sample_consensus::Model;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// A line in 3d space in [Plücker coordinates](https://en.wikipedia.org/wiki/Pl%C3%BCcker_coordinates).
+///
+/// Every point `x` on the line satisfies `cross(x, direction) = moment`. This is how the observations
+/// of a generalized camera are represented, since the rays of a generalized camera don't share an optical center.
+/// A ray that passes through the origin, such as a [`Bearing`] of a central camera, has a moment of zero.
+///
+/// Only the line is stored, not the point the ray starts at, so the optical center of the camera that made
+/// an observation is lost when it is converted into a `PluckerRay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct PluckerRay {
+    /// The direction of the ray.
+    pub direction: Unit<Vector3<f64>>,
+    /// The moment of the ray about the origin.
+    pub moment: Vector3<f64>,
+}
+
+impl PluckerRay {
+    /// Creates the ray that leaves `origin` in the given `direction`.
+    pub fn new(origin: Point3<f64>, direction: Unit<Vector3<f64>>) -> Self {
+        Self {
+            direction,
+            moment: origin.coords.cross(&direction),
+        }
+    }
+
+    /// Creates the ray that leaves the origin along the bearing.
+    pub fn from_bearing(bearing: impl Bearing) -> Self {
+        Self {
+            direction: bearing.bearing(),
+            moment: Vector3::zeros(),
+        }
+    }
+
+    /// Transforms the ray with a pose, such as a [`CameraToCamera`] into the frame of a [`CameraRig`].
+    pub fn transform(self, pose: impl Pose) -> Self {
+        let isometry = pose.isometry();
+        let direction = isometry.rotation * self.direction;
+        Self {
+            direction,
+            moment: isometry.rotation * self.moment + isometry.translation.vector.cross(&direction),
+        }
+    }
+
+    /// Retrieves the point on the line which is closest to the origin.
+    pub fn closest_point_to_origin(&self) -> Point3<f64> {
+        Point3::from(self.direction.cross(&self.moment))
+    }
+
+    /// Computes the distance between a point and the line.
+    pub fn distance(&self, point: Point3<f64>) -> f64 {
+        (point.coords.cross(&self.direction) - self.moment).norm()
+    }
+}
+
+/// A rigid rig of cameras, such as the cameras mounted on a vehicle, which moves as one body.
+///
+/// Each camera of the rig is described by the [`CameraToCamera`] pose that transforms points from the camera
+/// into the frame of the rig. The rig is then a generalized camera whose observations are [`PluckerRay`]s
+/// in the frame of the rig, so that a [`WorldToCamera`] or [`CameraToCamera`] can be estimated for the whole rig
+/// rather than for each camera. The cameras can be stored in anything that can be borrowed as a slice,
+/// such as an array.
+///
+/// ```
+/// use cv_core::nalgebra::{Point3, Rotation3, Vector3};
+/// use cv_core::{CameraRig, CameraToCamera, Pose};
+/// let rig = CameraRig([
+///     CameraToCamera::identity(),
+///     CameraToCamera::from_parts(Vector3::new(1.0, 0.0, 0.0), Rotation3::identity()),
+/// ]);
+/// let ray = rig.ray(1, Vector3::z_axis());
+/// assert!(ray.distance(Point3::new(1.0, 0.0, 5.0)) < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct CameraRig<C>(pub C);
+
+impl<C> CameraRig<C>
+where
+    C: AsRef<[CameraToCamera]>,
+{
+    /// Retrieves the poses which transform points from each camera into the frame of the rig.
+    pub fn cameras(&self) -> &[CameraToCamera] {
+        self.0.as_ref()
+    }
+
+    /// Converts a bearing observed by the given camera into a ray in the frame of the rig.
+    ///
+    /// Panics if the camera is not part of the rig.
+    pub fn ray(&self, camera: usize, bearing: impl Bearing) -> PluckerRay {
+        PluckerRay::from_bearing(bearing).transform(self.cameras()[camera])
+    }
+
+    /// Computes the pose of one camera of the rig from the pose of the rig.
+    ///
+    /// Panics if the camera is not part of the rig.
+    pub fn camera_pose(&self, camera: usize, rig: WorldToCamera) -> WorldToCamera {
+        WorldToCamera(self.cameras()[camera].inverse().isometry() * rig.isometry())
+    }
+}
+
+/// The residual is the generalized epipolar constraint between the rays `a` and `b` of a match,
+/// which is zero when the ray `a` intersects the ray `b` after it is transformed by the pose.
+///
+/// Unlike the residual for central cameras, the translation is not normalized, since the
+/// scale of the translation of a generalized camera is observable.
+impl Model<FeatureMatch<PluckerRay>> for CameraToCamera {
+    fn residual(&self, data: &FeatureMatch<PluckerRay>) -> f64 {
+        let FeatureMatch(a, b) = data;
+        let a = a.transform(*self);
+        Float::abs(b.direction.dot(&a.moment) + b.moment.dot(&a.direction))
+    }
+}
+
+/// The residual is the cosine distance between the ray and the direction from the point
+/// on the ray closest to the origin of the rig towards the world point.
+///
+/// Since a [`PluckerRay`] doesn't store where the ray starts, points on either side of the
+/// closest point are treated equally.
+impl Model<FeatureWorldMatch<PluckerRay>> for WorldToCamera {
+    fn residual(&self, data: &FeatureWorldMatch<PluckerRay>) -> f64 {
+        let WorldToCamera(iso) = *self;
+        let FeatureWorldMatch(ray, world) = data;
+
+        let point = (iso.to_homogeneous() * world.0).xyz();
+        let delta = point - ray.closest_point_to_origin().coords;
+        let norm = delta.norm();
+        if norm == 0.0 {
+            return 0.0;
+        }
+        1.0 - Float::abs(ray.direction.dot(&delta)) / norm
+    }
+}
@@ -0,0 +1,180 @@
+use crate::so3::{jacobian_coefficients, SMALL_ANGLE_SQUARED};
+use crate::Skew3;
+use derive_more::{AsMut, AsRef, Deref, DerefMut, From, Into};
+use nalgebra::{IsometryMatrix3, Matrix3, Matrix4, Matrix6, Rotation3, Vector3, Vector6, U1, U3};
+use num_traits::Float;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Contains a member of the lie algebra se(3), a representation of the tangent space
+/// of 3d rigid motion. This is also known as the lie algebra of the 3d special Euclidean group SE(3).
+///
+/// The first three components are the translation part `rho` and the last three are the so(3) rotation part
+/// as in [`Skew3`], which is the same order as [`Pose::se3`](crate::Pose::se3). Unlike [`Pose::se3`](crate::Pose::se3),
+/// the conversions to and from [`IsometryMatrix3`] are the true exponential and log maps, in which the translation
+/// part is coupled to the rotation. This is what perturbations in Gauss-Newton style optimizers should use.
+///
+/// ```
+/// use cv_core::nalgebra::{IsometryMatrix3, Rotation3, Vector3, Vector6};
+/// use cv_core::Se3;
+/// let isometry = IsometryMatrix3::from_parts(
+///     Vector3::new(0.5, -1.0, 2.0).into(),
+///     Rotation3::from_euler_angles(0.3, -0.2, 0.9),
+/// );
+/// let tangent = Se3::from(isometry);
+/// let round_trip = IsometryMatrix3::from(tangent);
+/// assert!((round_trip.to_homogeneous() - isometry.to_homogeneous()).norm() < 1e-12);
+///
+/// // A small change in the tangent is a small motion on the left, mapped by the left Jacobian.
+/// let delta = Vector6::new(1e-6, -2e-6, 1e-6, 3e-6, -1e-6, 2e-6);
+/// let perturbed = IsometryMatrix3::from(Se3(tangent.0 + delta));
+/// let left = IsometryMatrix3::from(Se3(tangent.left_jacobian() * delta)) * isometry;
+/// assert!((perturbed.to_homogeneous() - left.to_homogeneous()).norm() < 1e-10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, AsMut, AsRef, Deref, DerefMut, From, Into)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Se3(pub Vector6<f64>);
+
+impl Se3 {
+    /// Creates the se(3) element from its translation and rotation parts.
+    pub fn new(rho: Vector3<f64>, skew: Skew3) -> Self {
+        Self(Vector6::new(rho.x, rho.y, rho.z, skew.x, skew.y, skew.z))
+    }
+
+    /// Retrieves the translation part.
+    ///
+    /// This is not the translation of the isometry unless the rotation part is zero.
+    pub fn rho(self) -> Vector3<f64> {
+        self.0.xyz()
+    }
+
+    /// Retrieves the rotation part.
+    pub fn skew(self) -> Skew3 {
+        Skew3(Vector3::new(self.0[3], self.0[4], self.0[5]))
+    }
+
+    /// Converts the Se3 to an isometry with the exponential map.
+    pub fn isometry(self) -> IsometryMatrix3<f64> {
+        self.into()
+    }
+
+    /// This converts a matrix in homogeneous form into a Se3.
+    ///
+    /// Warning: Does no check to ensure the rotation block of the matrix is actually skew-symmetric.
+    pub fn vee(mat: Matrix4<f64>) -> Self {
+        Self::new(
+            Vector3::new(mat.m14, mat.m24, mat.m34),
+            Skew3::vee(mat.fixed_slice::<U3, U3>(0, 0).into_owned()),
+        )
+    }
+
+    /// This converts the Se3 into its homogeneous matrix form.
+    pub fn hat(self) -> Matrix4<f64> {
+        let mut mat = Matrix4::zeros();
+        mat.fixed_slice_mut::<U3, U3>(0, 0)
+            .copy_from(&self.skew().hat());
+        mat.fixed_slice_mut::<U3, U1>(0, 3).copy_from(&self.rho());
+        mat
+    }
+
+    /// Computes the adjoint of this se(3) element, which is the matrix form of the lie bracket `[self, rhs]`.
+    pub fn ad(self) -> Matrix6<f64> {
+        let skew = self.skew().hat();
+        block_upper_triangular(skew, self.rho().cross_matrix(), skew)
+    }
+
+    /// Computes the adjoint of an isometry, which transforms a tangent on its right side to its left side:
+    ///
+    /// `T * exp(x) = exp(Ad(T) * x) * T`
+    pub fn adjoint(isometry: IsometryMatrix3<f64>) -> Matrix6<f64> {
+        let rotation = *isometry.rotation.matrix();
+        let translation = isometry.translation.vector.cross_matrix();
+        block_upper_triangular(rotation, translation * rotation, rotation)
+    }
+
+    /// The left Jacobian of SE(3), which relates a perturbation of this se(3) element to a perturbation
+    /// on the left side of its isometry:
+    ///
+    /// `exp(x + dx) = exp(J_l * dx) * exp(x)`
+    pub fn left_jacobian(self) -> Matrix6<f64> {
+        let jacobian = self.skew().left_jacobian();
+        block_upper_triangular(jacobian, self.q_matrix(), jacobian)
+    }
+
+    /// The right Jacobian of SE(3), which relates a perturbation of this se(3) element to a perturbation
+    /// on the right side of its isometry:
+    ///
+    /// `exp(x + dx) = exp(x) * exp(J_r * dx)`
+    pub fn right_jacobian(self) -> Matrix6<f64> {
+        Self(-self.0).left_jacobian()
+    }
+
+    /// The inverse of [`Se3::left_jacobian`].
+    pub fn left_jacobian_inverse(self) -> Matrix6<f64> {
+        let inverse = self.skew().left_jacobian_inverse();
+        block_upper_triangular(inverse, -inverse * self.q_matrix() * inverse, inverse)
+    }
+
+    /// The inverse of [`Se3::right_jacobian`].
+    pub fn right_jacobian_inverse(self) -> Matrix6<f64> {
+        Self(-self.0).left_jacobian_inverse()
+    }
+
+    /// Computes the coupling block `Q` of the left Jacobian from "State Estimation for Robotics" by Timothy Barfoot.
+    fn q_matrix(self) -> Matrix3<f64> {
+        let rho = self.rho().cross_matrix();
+        let skew = self.skew();
+        let w = skew.hat();
+        let w2 = skew.hat2();
+        let theta2 = skew.norm_squared();
+        let (_, b) = jacobian_coefficients(theta2);
+        let (c, d) = if theta2 < SMALL_ANGLE_SQUARED {
+            (
+                1.0 / 24.0 - theta2 / 720.0 + theta2 * theta2 / 40320.0,
+                1.0 / 120.0 - theta2 / 2520.0 + theta2 * theta2 / 120_960.0,
+            )
+        } else {
+            let theta = Float::sqrt(theta2);
+            let (sin, cos) = Float::sin_cos(theta);
+            (
+                (theta2 + 2.0 * cos - 2.0) / (2.0 * theta2 * theta2),
+                (2.0 * theta - 3.0 * sin + theta * cos) / (2.0 * theta2 * theta2 * theta),
+            )
+        };
+        let wrw = w * rho * w;
+        rho * 0.5
+            + (w * rho + rho * w + wrw) * b
+            + (w2 * rho + rho * w2 - wrw * 3.0) * c
+            + (wrw * w + w * wrw) * d
+    }
+}
+
+/// Assembles the 6x6 matrix `[a, b; 0, c]` from its 3x3 blocks.
+fn block_upper_triangular(a: Matrix3<f64>, b: Matrix3<f64>, c: Matrix3<f64>) -> Matrix6<f64> {
+    let mut mat = Matrix6::zeros();
+    mat.fixed_slice_mut::<U3, U3>(0, 0).copy_from(&a);
+    mat.fixed_slice_mut::<U3, U3>(0, 3).copy_from(&b);
+    mat.fixed_slice_mut::<U3, U3>(3, 3).copy_from(&c);
+    mat
+}
+
+/// This is the exponential map.
+impl From<Se3> for IsometryMatrix3<f64> {
+    fn from(x: Se3) -> Self {
+        let skew = x.skew();
+        let rotation: Rotation3<f64> = skew.into();
+        IsometryMatrix3::from_parts((skew.left_jacobian() * x.rho()).into(), rotation)
+    }
+}
+
+/// This is the log map.
+impl From<IsometryMatrix3<f64>> for Se3 {
+    fn from(isometry: IsometryMatrix3<f64>) -> Self {
+        let skew: Skew3 = isometry.rotation.into();
+        Self::new(
+            skew.left_jacobian_inverse() * isometry.translation.vector,
+            skew,
+        )
+    }
+}
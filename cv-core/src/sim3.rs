@@ -0,0 +1,243 @@
+use crate::{Projective, Skew3, WorldMatch, WorldToCamera};
+use core::ops::Mul;
+use derive_more::{AsMut, AsRef, From, Into};
+use nalgebra::{
+    IsometryMatrix3, Matrix3, Matrix4, Rotation3, Similarity3, SimilarityMatrix3, Translation3,
+    Vector3, VectorN, U7,
+};
+use num_traits::Float;
+use sample_consensus::Model;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Below this magnitude of the tangent, the power series is used to compute the translation map.
+const V_SERIES_THRESHOLD: f64 = 0.5;
+/// The number of terms of the power series, which is enough for double precision below the threshold.
+const V_SERIES_TERMS: usize = 20;
+
+/// Contains a similarity transform, which is a rotation, uniform scale, and translation:
+///
+/// `y = s * R * x + t`
+///
+/// Monocular reconstructions can only be recovered up to scale, and the scale drifts over the course of a
+/// reconstruction. A similarity is the transform between two such reconstructions, or between a reconstruction
+/// and itself when a loop is closed. Unlike the [`Pose`](crate::Pose) types, a similarity doesn't specify which
+/// spaces it transforms between, so it can be applied to any [`Projective`] point.
+///
+/// The lie algebra sim(3) is represented as a 7-vector with the translation components first, then the so(3)
+/// components as in [`Skew3`], and then the logarithm of the scale last, which is the same order as
+/// [`Pose::se3`](crate::Pose::se3) followed by the scale.
+///
+/// ```
+/// use cv_core::nalgebra::{Point3, Rotation3, Vector3};
+/// use cv_core::{Projective, Sim3, WorldPoint};
+/// let a = Sim3::from_parts(Vector3::new(1.0, 2.0, 3.0), Rotation3::from_euler_angles(0.1, 0.2, 0.3), 2.0);
+/// let b = Sim3::from_parts(Vector3::new(-0.5, 0.0, 0.2), Rotation3::from_euler_angles(0.3, 0.0, -0.1), 0.5);
+/// let point = WorldPoint::from_point(Point3::new(0.3, -0.4, 1.5));
+///
+/// // Composition applies the right transform first.
+/// let composed = (a * b).transform(point).point().unwrap();
+/// let sequential = a.transform(b.transform(point)).point().unwrap();
+/// assert!((composed - sequential).norm() < 1e-12);
+///
+/// // The inverse undoes the transform.
+/// let back = a.inverse().transform(a.transform(point)).point().unwrap();
+/// assert!((back - point.point().unwrap()).norm() < 1e-12);
+///
+/// // The exponential map undoes the log map.
+/// let round_trip = Sim3::exp(a.log());
+/// assert!((round_trip.homogeneous() - a.homogeneous()).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, AsMut, AsRef, From, Into)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Sim3(pub SimilarityMatrix3<f64>);
+
+impl Sim3 {
+    /// Creates a similarity with no change in position, orientation, or scale.
+    pub fn identity() -> Self {
+        Self(SimilarityMatrix3::identity())
+    }
+
+    /// Create the similarity from translation, rotation, and scale.
+    ///
+    /// Panics if the scale is zero.
+    pub fn from_parts(translation: Vector3<f64>, rotation: Rotation3<f64>, scale: f64) -> Self {
+        Self(SimilarityMatrix3::from_parts(
+            translation.into(),
+            rotation,
+            scale,
+        ))
+    }
+
+    /// Creates a similarity from an isometry with a scale of one.
+    pub fn from_isometry(isometry: IsometryMatrix3<f64>) -> Self {
+        Self(SimilarityMatrix3::from_isometry(isometry, 1.0))
+    }
+
+    /// Retrieve the rotation.
+    pub fn rotation(self) -> Rotation3<f64> {
+        self.0.isometry.rotation
+    }
+
+    /// Retrieve the translation.
+    pub fn translation(self) -> Vector3<f64> {
+        self.0.isometry.translation.vector
+    }
+
+    /// Retrieve the scale.
+    pub fn scale(self) -> f64 {
+        self.0.scaling()
+    }
+
+    /// Retrieve the homogeneous matrix.
+    pub fn homogeneous(self) -> Matrix4<f64> {
+        self.0.to_homogeneous()
+    }
+
+    /// Takes the inverse of the similarity.
+    pub fn inverse(self) -> Self {
+        Self(self.0.inverse())
+    }
+
+    /// Transforms any point with the similarity.
+    pub fn transform<P: Projective>(self, point: P) -> P {
+        (self.homogeneous() * point.homogeneous()).into()
+    }
+
+    /// Computes the pose of a camera after the world it is in is transformed by this similarity.
+    ///
+    /// The camera moves along with the world, and its [`CameraPoint`](crate::CameraPoint)s are
+    /// scaled by the same factor as the world, so the bearings of every world point are preserved.
+    /// This is how every view of a reconstruction is moved when the reconstruction is transformed.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Vector3};
+    /// use cv_core::{Pose, Projective, Sim3, WorldPoint, WorldToCamera};
+    /// let similarity = Sim3::from_parts(Vector3::new(1.0, 2.0, 3.0), Rotation3::from_euler_angles(0.1, 0.2, 0.3), 2.0);
+    /// let pose = WorldToCamera(IsometryMatrix3::from_parts(
+    ///     Vector3::new(0.2, -0.1, 0.5).into(),
+    ///     Rotation3::from_euler_angles(-0.2, 0.1, 0.0),
+    /// ));
+    /// let point = WorldPoint::from_point(Point3::new(0.3, -0.4, 1.5));
+    /// let before = pose.transform(point).bearing();
+    /// let after = similarity.transform_pose(pose).transform(similarity.transform(point)).bearing();
+    /// assert!((before.into_inner() - after.into_inner()).norm() < 1e-12);
+    /// ```
+    pub fn transform_pose(self, pose: WorldToCamera) -> WorldToCamera {
+        let WorldToCamera(pose) = pose;
+        let rotation = pose.rotation * self.rotation().inverse();
+        let translation = self.scale() * pose.translation.vector - rotation * self.translation();
+        WorldToCamera(IsometryMatrix3::from_parts(translation.into(), rotation))
+    }
+
+    /// Computes the matrix which maps the translation part of the tangent space to the translation.
+    ///
+    /// This is the integral of `exp(u * (sigma * I + hat(omega)))` for `u` from zero to one.
+    fn v_matrix(omega: Vector3<f64>, sigma: f64) -> Matrix3<f64> {
+        let skew = Skew3(omega);
+        let theta2 = omega.norm_squared();
+        let theta = Float::sqrt(theta2);
+        if Float::abs(sigma) + theta < V_SERIES_THRESHOLD {
+            // The closed form loses precision near zero, so sum the power series instead.
+            let generator = Matrix3::identity() * sigma + skew.hat();
+            let mut term = Matrix3::identity();
+            let mut v = term;
+            for k in 1..=V_SERIES_TERMS {
+                term = term * generator / (k + 1) as f64;
+                v += term;
+            }
+            return v;
+        }
+
+        let scale = Float::exp(sigma);
+        let c = if sigma == 0.0 {
+            1.0
+        } else {
+            Float::exp_m1(sigma) / sigma
+        };
+        let (a, b) = if theta2 <= f64::epsilon() {
+            let sigma2 = sigma * sigma;
+            (
+                ((sigma - 1.0) * scale + 1.0) / sigma2,
+                (scale * (0.5 * sigma2 - sigma + 1.0) - 1.0) / (sigma2 * sigma),
+            )
+        } else {
+            let sin = scale * Float::sin(theta);
+            let cos = scale * Float::cos(theta);
+            let denominator = theta2 + sigma * sigma;
+            (
+                (sin * sigma + (1.0 - cos) * theta) / (theta * denominator),
+                (c - ((cos - 1.0) * sigma + sin * theta) / denominator) / theta2,
+            )
+        };
+        skew.hat() * a + skew.hat2() * b + Matrix3::identity() * c
+    }
+
+    /// This is the exponential map from sim(3) into Sim(3).
+    pub fn exp(tangent: VectorN<f64, U7>) -> Self {
+        let rho = Vector3::new(tangent[0], tangent[1], tangent[2]);
+        let omega = Vector3::new(tangent[3], tangent[4], tangent[5]);
+        let sigma = tangent[6];
+        Self::from_parts(
+            Self::v_matrix(omega, sigma) * rho,
+            Skew3(omega).into(),
+            Float::exp(sigma),
+        )
+    }
+
+    /// This is the log map from Sim(3) into sim(3).
+    pub fn log(self) -> VectorN<f64, U7> {
+        let Skew3(omega) = self.rotation().into();
+        let sigma = Float::ln(self.scale());
+        let rho = Self::v_matrix(omega, sigma)
+            .try_inverse()
+            .expect("the translation map of a similarity is always invertible")
+            * self.translation();
+        VectorN::<f64, U7>::from_column_slice(&[
+            rho.x, rho.y, rho.z, omega.x, omega.y, omega.z, sigma,
+        ])
+    }
+}
+
+/// Composes two similarities, where the right-hand side is applied first.
+impl Mul for Sim3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0)
+    }
+}
+
+impl From<Similarity3<f64>> for Sim3 {
+    fn from(similarity: Similarity3<f64>) -> Self {
+        Self::from_parts(
+            similarity.isometry.translation.vector,
+            similarity.isometry.rotation.to_rotation_matrix(),
+            similarity.scaling(),
+        )
+    }
+}
+
+impl From<Sim3> for Similarity3<f64> {
+    fn from(Sim3(similarity): Sim3) -> Self {
+        Similarity3::from_parts(
+            Translation3::from(similarity.isometry.translation.vector),
+            similarity.isometry.rotation.into(),
+            similarity.scaling(),
+        )
+    }
+}
+
+/// The residual is the distance between the second point and the first point after it is transformed.
+///
+/// If either point is at infinity, the residual is infinite.
+impl Model<WorldMatch> for Sim3 {
+    fn residual(&self, data: &WorldMatch) -> f64 {
+        let WorldMatch(a, b) = data;
+        match (a.point(), b.point()) {
+            (Some(a), Some(b)) => (self.0 * a - b).norm(),
+            _ => f64::INFINITY,
+        }
+    }
+}
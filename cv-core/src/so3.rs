@@ -92,6 +92,62 @@ impl Skew3 {
     pub fn jacobian_self(y: Vector3<f64>) -> Matrix3<f64> {
         y.cross_matrix()
     }
+
+    /// The left Jacobian of SO(3), which relates a perturbation of this so(3) element to a perturbation
+    /// on the left side of its rotation:
+    ///
+    /// `exp(w + dw) = exp(J_l * dw) * exp(w)`
+    ///
+    /// This also maps the translation part of se(3) to the translation of the SE(3) exponential map.
+    pub fn left_jacobian(self) -> Matrix3<f64> {
+        let (a, b) = jacobian_coefficients(self.0.norm_squared());
+        Matrix3::identity() + self.hat() * a + self.hat2() * b
+    }
+
+    /// The right Jacobian of SO(3), which relates a perturbation of this so(3) element to a perturbation
+    /// on the right side of its rotation:
+    ///
+    /// `exp(w + dw) = exp(w) * exp(J_r * dw)`
+    pub fn right_jacobian(self) -> Matrix3<f64> {
+        Self(-self.0).left_jacobian()
+    }
+
+    /// The inverse of [`Skew3::left_jacobian`].
+    pub fn left_jacobian_inverse(self) -> Matrix3<f64> {
+        let theta2 = self.0.norm_squared();
+        let c = if theta2 < SMALL_ANGLE_SQUARED {
+            1.0 / 12.0 + theta2 / 720.0 + theta2 * theta2 / 30240.0
+        } else {
+            let theta = theta2.sqrt();
+            1.0 / theta2 - (1.0 + theta.cos()) / (2.0 * theta * theta.sin())
+        };
+        Matrix3::identity() - self.hat() * 0.5 + self.hat2() * c
+    }
+
+    /// The inverse of [`Skew3::right_jacobian`].
+    pub fn right_jacobian_inverse(self) -> Matrix3<f64> {
+        Self(-self.0).left_jacobian_inverse()
+    }
+}
+
+/// Below this squared angle, the coefficients of the Jacobians are computed with their Taylor series.
+pub(crate) const SMALL_ANGLE_SQUARED: f64 = 1e-4;
+
+/// Computes the coefficients `(1 - cos(theta)) / theta^2` and `(theta - sin(theta)) / theta^3`
+/// of the left Jacobian of SO(3) from the squared angle.
+pub(crate) fn jacobian_coefficients(theta2: f64) -> (f64, f64) {
+    if theta2 < SMALL_ANGLE_SQUARED {
+        (
+            0.5 - theta2 / 24.0 + theta2 * theta2 / 720.0,
+            1.0 / 6.0 - theta2 / 120.0 + theta2 * theta2 / 5040.0,
+        )
+    } else {
+        let theta = theta2.sqrt();
+        (
+            (1.0 - theta.cos()) / theta2,
+            (theta - theta.sin()) / (theta2 * theta),
+        )
+    }
 }
 
 /// This is the exponential map.
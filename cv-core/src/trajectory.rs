@@ -0,0 +1,236 @@
+use crate::{CameraToCamera, Pose, Se3, Skew3, WorldToCamera};
+use nalgebra::{IsometryMatrix3, Rotation3};
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// A [`WorldToCamera`] pose of a camera at a point in time.
+///
+/// The unit of the timestamp is up to the user, but it must be the same for every pose of a [`Trajectory`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct StampedPose {
+    /// The time at which the camera had the pose.
+    pub timestamp: f64,
+    /// The pose of the camera.
+    pub pose: WorldToCamera,
+}
+
+/// The method used to compute the pose of a [`Trajectory`] between two of its poses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Interpolation {
+    /// Follows the geodesic of SE(3) between the poses, which is a screw motion with constant velocity.
+    #[default]
+    Geodesic,
+    /// Interpolates the rotation with SLERP and the optical center of the camera linearly and independently,
+    /// so the camera moves in a straight line.
+    Slerp,
+}
+
+impl Interpolation {
+    /// Computes the pose a fraction `t` of the way from `a` to `b`.
+    ///
+    /// A `t` outside of `[0, 1]` extrapolates the motion between the poses.
+    pub fn interpolate(self, a: WorldToCamera, b: WorldToCamera, t: f64) -> WorldToCamera {
+        let (a, b) = (a.isometry(), b.isometry());
+        match self {
+            Self::Geodesic => {
+                let Se3(delta) = (b * a.inverse()).into();
+                IsometryMatrix3::from(Se3(delta * t)) * a
+            }
+            Self::Slerp => {
+                let Skew3(delta) = (b.rotation * a.rotation.inverse()).into();
+                let rotation: Rotation3<f64> = Rotation3::from(Skew3(delta * t)) * a.rotation;
+                let a_center = a.inverse().translation.vector;
+                let b_center = b.inverse().translation.vector;
+                let center = a_center + (b_center - a_center) * t;
+                IsometryMatrix3::from_parts((-(rotation * center)).into(), rotation)
+            }
+        }
+        .into()
+    }
+}
+
+/// The poses of a camera over time.
+///
+/// The poses are stored in anything that can be borrowed as a slice of [`StampedPose`], such as an array or a `Vec`,
+/// and must be sorted by ascending timestamp. The pose at any time between the first and last pose is interpolated
+/// from the two poses around it with the [`Interpolation`] of the trajectory. This is used to find the pose of a camera
+/// at the time of a measurement from another sensor, such as a lidar or IMU, which isn't synchronized with the camera.
+///
+/// ```
+/// use cv_core::nalgebra::{Rotation3, Vector3};
+/// use cv_core::{Interpolation, Pose, StampedPose, Trajectory, WorldToCamera};
+/// let trajectory = Trajectory::new([
+///     StampedPose { timestamp: 0.0, pose: WorldToCamera::identity() },
+///     StampedPose {
+///         timestamp: 2.0,
+///         pose: WorldToCamera::from_parts(Vector3::new(2.0, 0.0, 0.0), Rotation3::identity()),
+///     },
+/// ]);
+/// let pose = trajectory.pose_at(0.5).unwrap();
+/// assert!((pose.isometry().translation.vector - Vector3::new(0.5, 0.0, 0.0)).norm() < 1e-12);
+///
+/// // Times outside of the trajectory are only available through extrapolation.
+/// assert!(trajectory.pose_at(3.0).is_none());
+/// let pose = trajectory.extrapolate(3.0).unwrap();
+/// assert!((pose.isometry().translation.vector - Vector3::new(3.0, 0.0, 0.0)).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Trajectory<C> {
+    /// The poses of the trajectory, sorted by ascending timestamp.
+    pub poses: C,
+    /// The method used to compute poses between the stored poses.
+    pub interpolation: Interpolation,
+}
+
+impl<C> Trajectory<C>
+where
+    C: AsRef<[StampedPose]>,
+{
+    /// Creates a trajectory with [`Interpolation::Geodesic`].
+    ///
+    /// Panics if a timestamp is not finite or the poses are not sorted by ascending timestamp.
+    pub fn new(poses: C) -> Self {
+        let timestamps = poses.as_ref().iter().map(|p| p.timestamp);
+        assert!(
+            timestamps.clone().all(f64::is_finite),
+            "trajectory timestamps must be finite"
+        );
+        assert!(
+            timestamps
+                .clone()
+                .zip(timestamps.skip(1))
+                .all(|(a, b)| a <= b),
+            "trajectory poses must be sorted by ascending timestamp"
+        );
+        Self {
+            poses,
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    /// Sets the method used to compute poses between the stored poses.
+    ///
+    /// Default is [`Interpolation::Geodesic`].
+    pub fn interpolation(self, interpolation: Interpolation) -> Self {
+        Self {
+            interpolation,
+            ..self
+        }
+    }
+
+    /// Retrieves the stored poses.
+    pub fn poses(&self) -> &[StampedPose] {
+        self.poses.as_ref()
+    }
+
+    /// Iterates over the stored poses in order of time.
+    pub fn iter(&self) -> core::slice::Iter<'_, StampedPose> {
+        self.poses().iter()
+    }
+
+    /// The timestamp of the first pose, if there are any poses.
+    pub fn start(&self) -> Option<f64> {
+        self.poses().first().map(|p| p.timestamp)
+    }
+
+    /// The timestamp of the last pose, if there are any poses.
+    pub fn end(&self) -> Option<f64> {
+        self.poses().last().map(|p| p.timestamp)
+    }
+
+    /// Computes the pose at the given time by interpolating between the poses before and after it.
+    ///
+    /// Returns `None` if the time is outside of the trajectory.
+    pub fn pose_at(&self, timestamp: f64) -> Option<WorldToCamera> {
+        if timestamp < self.start()? || timestamp > self.end()? {
+            return None;
+        }
+        self.extrapolate(timestamp)
+    }
+
+    /// Computes the pose at the given time like [`Trajectory::pose_at`], but times before the first pose
+    /// or after the last pose continue the motion between the first two or last two poses respectively.
+    ///
+    /// A trajectory with a single pose is stationary. Returns `None` if there are no poses.
+    pub fn extrapolate(&self, timestamp: f64) -> Option<WorldToCamera> {
+        let poses = self.poses();
+        match poses.len() {
+            0 => return None,
+            1 => return Some(poses[0].pose),
+            _ => {}
+        }
+        // Find the segment that contains the timestamp, clamped to the first and last segment.
+        let after = poses
+            .partition_point(|p| p.timestamp <= timestamp)
+            .max(1)
+            .min(poses.len() - 1);
+        let (a, b) = (poses[after - 1], poses[after]);
+        let duration = b.timestamp - a.timestamp;
+        if duration == 0.0 {
+            return Some(b.pose);
+        }
+        let t = (timestamp - a.timestamp) / duration;
+        Some(self.interpolation.interpolate(a.pose, b.pose, t))
+    }
+
+    /// Computes the relative motion of the camera from time `from` to time `to`, which transforms points from the
+    /// camera at time `from` into the camera at time `to`.
+    ///
+    /// Returns `None` if either time is outside of the trajectory.
+    pub fn relative(&self, from: f64, to: f64) -> Option<CameraToCamera> {
+        let from = self.pose_at(from)?;
+        let to = self.pose_at(to)?;
+        Some(CameraToCamera(to.isometry() * from.inverse().isometry()))
+    }
+
+    /// Computes the poses at the given times.
+    ///
+    /// Times outside of the trajectory are skipped.
+    pub fn sample<'a>(
+        &'a self,
+        timestamps: impl IntoIterator<Item = f64> + 'a,
+    ) -> impl Iterator<Item = StampedPose> + 'a {
+        timestamps.into_iter().filter_map(move |timestamp| {
+            self.pose_at(timestamp)
+                .map(|pose| StampedPose { timestamp, pose })
+        })
+    }
+
+    /// Computes the poses at a regular interval of `period`, starting at the first pose and ending at
+    /// or before the last pose.
+    ///
+    /// Panics if the period is not positive.
+    pub fn resample(&self, period: f64) -> impl Iterator<Item = StampedPose> + '_ {
+        assert!(
+            period > 0.0,
+            "trajectory must be resampled with a positive period"
+        );
+        let (start, end) = match (self.start(), self.end()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => (0.0, -1.0),
+        };
+        (0..)
+            .map(move |i| start + i as f64 * period)
+            .take_while(move |&timestamp| timestamp <= end)
+            .filter_map(move |timestamp| {
+                self.pose_at(timestamp)
+                    .map(|pose| StampedPose { timestamp, pose })
+            })
+    }
+}
+
+impl<'a, C> IntoIterator for &'a Trajectory<C>
+where
+    C: AsRef<[StampedPose]>,
+{
+    type Item = &'a StampedPose;
+    type IntoIter = core::slice::Iter<'a, StampedPose>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{Bearing, CameraPoint, CameraToCamera, Pose, WorldPoint, WorldToCamera};
+use crate::{Bearing, CameraPoint, CameraToCamera, Pose, Projective, WorldPoint, WorldToCamera};
+use nalgebra::Matrix3;
+use num_traits::Float;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
 
 /// This trait is for algorithms which allow you to triangulate a point from two or more observances.
 /// Each observance is a [`WorldToCamera`] and a [`Bearing`].
@@ -7,6 +12,147 @@ pub trait TriangulatorObservations {
         &self,
         pairs: impl IntoIterator<Item = (WorldToCamera, B)>,
     ) -> Option<WorldPoint>;
+
+    /// Triangulates a point like [`TriangulatorObservations::triangulate_observations`], but also computes how well
+    /// the point is constrained by the observances. See [`Triangulation::evaluate`] for details.
+    fn triangulate_observations_with_quality<B, I, R>(
+        &self,
+        pairs: I,
+        angular_std_dev: f64,
+    ) -> Option<Triangulation<R>>
+    where
+        B: Bearing,
+        I: IntoIterator<Item = (WorldToCamera, B)> + Clone,
+        R: Default + Extend<f64>,
+    {
+        let point = self.triangulate_observations(pairs.clone())?;
+        Some(Triangulation::evaluate(point, pairs, angular_std_dev))
+    }
+}
+
+/// A triangulated point along with metrics of how well the point is constrained by its observances.
+///
+/// The residual of each observance is stored in `R`, which can be anything that can be extended, such as a `Vec<f64>`
+/// or an `ArrayVec`.
+///
+/// ```
+/// use cv_core::nalgebra::{Point3, Vector3, Rotation3};
+/// use cv_core::{Pose, Projective, Triangulation, WorldPoint, WorldToCamera};
+///
+/// let point = WorldPoint::from_point(Point3::new(0.0, 0.0, 10.0));
+/// let observances = [-0.5, 0.5].iter().map(|&x| {
+///     let pose = WorldToCamera::from_parts(Vector3::new(x, 0.0, 0.0), Rotation3::identity());
+///     (pose, pose.transform(point).bearing())
+/// });
+/// let triangulation: Triangulation<Vec<f64>> = Triangulation::evaluate(point, observances, 1e-3);
+/// assert!(triangulation.residuals.iter().all(|&residual| residual < 1e-12));
+/// // The parallax of the two cameras is about 0.1 radians.
+/// assert!((triangulation.angle - 0.1).abs() < 1e-3);
+/// // The point is much less certain along the viewing direction than across it.
+/// let covariance = triangulation.covariance.unwrap();
+/// assert!(covariance[(2, 2)] > 100.0 * covariance[(0, 0)]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Triangulation<R> {
+    /// The triangulated point.
+    pub point: WorldPoint,
+    /// The covariance of the point in world space.
+    ///
+    /// This is `None` if the point is at infinity, the optical center of an observance is at the point,
+    /// or the observances don't constrain it in every direction.
+    pub covariance: Option<Matrix3<f64>>,
+    /// The largest angle in radians between the rays from any two optical centers to the point,
+    /// also known as the parallax or triangulation angle.
+    ///
+    /// Optical centers at the point don't have a ray, so they are ignored.
+    pub angle: f64,
+    /// The cosine distance between the bearing of each observance and the point, in the order of the observances.
+    pub residuals: R,
+}
+
+impl<R> Triangulation<R>
+where
+    R: Default + Extend<f64>,
+{
+    /// Computes the quality of a point which was triangulated from the given observances.
+    ///
+    /// The covariance is the inverse of the Gauss-Newton information matrix of the point, where the error of each
+    /// bearing is an independent angle with a standard deviation of `angular_std_dev` radians in each direction.
+    /// It is only a first-order approximation, which underestimates the uncertainty along the viewing direction
+    /// when the angle is small.
+    pub fn evaluate<B: Bearing>(
+        point: WorldPoint,
+        pairs: impl IntoIterator<Item = (WorldToCamera, B)> + Clone,
+        angular_std_dev: f64,
+    ) -> Self {
+        let mut residuals = R::default();
+        residuals.extend(
+            pairs.clone().into_iter().map(|(pose, bearing)| {
+                1.0 - bearing.bearing().dot(&pose.transform(point).bearing())
+            }),
+        );
+
+        let (covariance, angle) = match point.point() {
+            Some(p) => {
+                // A camera whose optical center is at the point doesn't have a bearing to it.
+                let information = pairs.clone().into_iter().try_fold(
+                    Matrix3::zeros(),
+                    |information, (pose, _)| {
+                        let camera_point = pose.transform(point).point().unwrap().coords;
+                        let distance_squared = camera_point.norm_squared();
+                        if distance_squared == 0.0 {
+                            return None;
+                        }
+                        let bearing = camera_point / Float::sqrt(distance_squared);
+                        let rotation = pose.isometry().rotation;
+                        // The bearing only changes in the directions perpendicular to itself.
+                        let projection = Matrix3::identity() - bearing * bearing.transpose();
+                        Some(
+                            information
+                                + rotation.inverse() * projection * rotation / distance_squared,
+                        )
+                    },
+                );
+                let covariance = information
+                    .and_then(|information| information.try_inverse())
+                    .map(|inverse| inverse * (angular_std_dev * angular_std_dev));
+
+                let rays = || {
+                    pairs.clone().into_iter().filter_map(|(pose, _)| {
+                        let center = pose.inverse().isometry().translation.vector;
+                        (p.coords - center).try_normalize(0.0)
+                    })
+                };
+                let angle = rays()
+                    .enumerate()
+                    .flat_map(|(ix, a)| {
+                        rays()
+                            .skip(ix + 1)
+                            .map(move |b| Float::acos(a.dot(&b).clamp(-1.0, 1.0)))
+                    })
+                    .fold(0.0, f64::max);
+                (covariance, angle)
+            }
+            None => (None, 0.0),
+        };
+
+        Self {
+            point,
+            covariance,
+            angle,
+            residuals,
+        }
+    }
+}
+
+impl<R> Triangulation<R> {
+    /// The standard deviation of the distance between the true point and the triangulated point,
+    /// which is the square root of the trace of the covariance.
+    pub fn std_dev(&self) -> Option<f64> {
+        self.covariance
+            .map(|covariance| Float::sqrt(covariance.trace()))
+    }
 }
 
 /// This trait allows you to take one relative pose from camera `A` to camera `B` and two bearings `a` and `b` from
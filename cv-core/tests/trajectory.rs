@@ -0,0 +1,128 @@
+use cv_core::nalgebra::{Rotation3, Vector3};
+use cv_core::{Interpolation, Pose, StampedPose, Trajectory, WorldToCamera};
+
+const EPSILON: f64 = 1e-12;
+
+/// Creates the pose of a camera with the given optical center and rotation.
+fn pose(center: Vector3<f64>, rotation: Rotation3<f64>) -> WorldToCamera {
+    WorldToCamera::from_parts(-(rotation * center), rotation)
+}
+
+fn center(pose: WorldToCamera) -> Vector3<f64> {
+    pose.isometry().inverse().translation.vector
+}
+
+/// A camera which moves along `x` by one unit per unit of time while turning around `y`.
+fn turning() -> Trajectory<[StampedPose; 2]> {
+    Trajectory::new([
+        StampedPose {
+            timestamp: 0.0,
+            pose: WorldToCamera::identity(),
+        },
+        StampedPose {
+            timestamp: 2.0,
+            pose: pose(
+                Vector3::new(2.0, 0.0, 0.0),
+                Rotation3::from_axis_angle(&Vector3::y_axis(), 1.0),
+            ),
+        },
+    ])
+}
+
+#[test]
+fn slerp_interpolation() {
+    let trajectory = turning().interpolation(Interpolation::Slerp);
+    for &(timestamp, expected) in &[(0.0, 0.0), (0.5, 0.25), (1.0, 0.5), (2.0, 1.0)] {
+        let pose = trajectory.pose_at(timestamp).unwrap();
+        // The center moves in a straight line and the rotation turns at a constant rate.
+        assert!((center(pose) - Vector3::new(2.0 * expected, 0.0, 0.0)).norm() < EPSILON);
+        let rotation = Rotation3::from_axis_angle(&Vector3::y_axis(), expected);
+        assert!((pose.isometry().rotation.matrix() - rotation.matrix()).norm() < EPSILON);
+    }
+}
+
+#[test]
+fn slerp_extrapolation() {
+    let trajectory = turning().interpolation(Interpolation::Slerp);
+    let pose = trajectory.extrapolate(3.0).unwrap();
+    assert!((center(pose) - Vector3::new(3.0, 0.0, 0.0)).norm() < EPSILON);
+    let rotation = Rotation3::from_axis_angle(&Vector3::y_axis(), 1.5);
+    assert!((pose.isometry().rotation.matrix() - rotation.matrix()).norm() < EPSILON);
+}
+
+#[test]
+fn geodesic_differs_from_slerp() {
+    // Both rotate at the same rate, but the screw motion of the geodesic doesn't move the center in a straight line.
+    let geodesic = turning().pose_at(1.0).unwrap();
+    let slerp = turning()
+        .interpolation(Interpolation::Slerp)
+        .pose_at(1.0)
+        .unwrap();
+    assert!(
+        (geodesic.isometry().rotation.matrix() - slerp.isometry().rotation.matrix()).norm()
+            < EPSILON
+    );
+    assert!((center(geodesic) - center(slerp)).norm() > 1e-3);
+}
+
+#[test]
+fn relative() {
+    let trajectory = Trajectory::new(vec![
+        StampedPose {
+            timestamp: 0.0,
+            pose: WorldToCamera::identity(),
+        },
+        StampedPose {
+            timestamp: 4.0,
+            pose: pose(Vector3::new(4.0, 0.0, 0.0), Rotation3::identity()),
+        },
+    ]);
+    // The camera moved one unit along `x`, so points in the camera moved one unit the other way.
+    let relative = trajectory.relative(1.0, 2.0).unwrap();
+    assert!(
+        (relative.isometry().translation.vector - Vector3::new(-1.0, 0.0, 0.0)).norm() < EPSILON
+    );
+    assert!(
+        (relative.isometry().rotation.matrix() - Rotation3::identity().matrix()).norm() < EPSILON
+    );
+    assert!(trajectory.relative(1.0, 5.0).is_none());
+    assert!(trajectory.relative(-1.0, 1.0).is_none());
+}
+
+#[test]
+fn resample() {
+    let trajectory = Trajectory::new([
+        StampedPose {
+            timestamp: 1.0,
+            pose: WorldToCamera::identity(),
+        },
+        StampedPose {
+            timestamp: 3.5,
+            pose: pose(Vector3::new(2.5, 0.0, 0.0), Rotation3::identity()),
+        },
+    ]);
+    let samples: Vec<StampedPose> = trajectory.resample(1.0).collect();
+    let timestamps: Vec<f64> = samples.iter().map(|p| p.timestamp).collect();
+    assert_eq!(timestamps, [1.0, 2.0, 3.0]);
+    for sample in samples {
+        let expected = Vector3::new(sample.timestamp - 1.0, 0.0, 0.0);
+        assert!((center(sample.pose) - expected).norm() < EPSILON);
+    }
+    assert_eq!(Trajectory::new([]).resample(1.0).count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "sorted")]
+fn unsorted_poses() {
+    let mut poses = turning().poses;
+    poses.reverse();
+    Trajectory::new(poses);
+}
+
+#[test]
+#[should_panic(expected = "finite")]
+fn non_finite_timestamp() {
+    let mut poses = turning().poses;
+    poses[1].timestamp = f64::NAN;
+    Trajectory::new(poses);
+}
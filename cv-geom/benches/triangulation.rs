@@ -0,0 +1,142 @@
+//! Compares the accuracy and speed of the triangulators on noisy two-view problems.
+//!
+//! The accuracy is printed before the timings are measured.
+
+use criterion::{black_box, criterion_group, Criterion};
+use cv_core::nalgebra::{Point3, Rotation3, Unit, Vector2, Vector3};
+use cv_core::{CameraPoint, CameraToCamera, Pose, Projective, TriangulatorRelative};
+use cv_geom::{
+    AngularNorm, AngularTriangulator, HartleySturmTriangulator, MidpointTriangulator,
+    MinSquaresTriangulator, RelativeDltTriangulator,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const PROBLEMS: usize = 1000;
+/// The standard deviation of the noise on the virtual image plane, roughly one pixel.
+const NOISE: f64 = 1e-3;
+
+struct Problem {
+    pose: CameraToCamera,
+    point: Point3<f64>,
+    a: Unit<Vector3<f64>>,
+    b: Unit<Vector3<f64>>,
+}
+
+fn noisy(rng: &mut impl Rng, point: Point3<f64>) -> Unit<Vector3<f64>> {
+    let noise = Vector2::new(
+        rng.sample::<f64, _>(rand_distr::StandardNormal),
+        rng.sample::<f64, _>(rand_distr::StandardNormal),
+    ) * NOISE;
+    Unit::new_normalize((point.coords.xy() / point.z + noise).push(1.0))
+}
+
+fn problems() -> Vec<Problem> {
+    let mut rng = SmallRng::from_seed([0; 16]);
+    (0..PROBLEMS)
+        .map(|_| {
+            let pose = CameraToCamera::from_parts(
+                Vector3::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-0.2, 0.2), 0.0),
+                Rotation3::new(Vector3::new(rng.gen(), rng.gen(), rng.gen()) * 0.1),
+            );
+            let point = Point3::new(
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(4.0, 20.0),
+            );
+            let a = noisy(&mut rng, point);
+            let b = noisy(
+                &mut rng,
+                pose.transform(CameraPoint::from_point(point))
+                    .point()
+                    .unwrap(),
+            );
+            Problem { pose, point, a, b }
+        })
+        .collect()
+}
+
+fn report(name: &str, triangulator: impl TriangulatorRelative, problems: &[Problem]) {
+    let errors: Vec<f64> = problems
+        .iter()
+        .filter_map(|p| {
+            let point = triangulator
+                .triangulate_relative(p.pose, p.a, p.b)?
+                .point()?;
+            Some((point - p.point).norm() / p.point.coords.norm())
+        })
+        .collect();
+    let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+    println!(
+        "{:<24} mean relative error {:.5}, failures {}",
+        name,
+        mean,
+        problems.len() - errors.len()
+    );
+}
+
+fn time(
+    c: &mut Criterion,
+    name: &str,
+    triangulator: impl TriangulatorRelative,
+    problems: &[Problem],
+) {
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            for p in problems {
+                black_box(triangulator.triangulate_relative(p.pose, p.a, p.b));
+            }
+        })
+    });
+}
+
+fn triangulators(c: &mut Criterion) {
+    let problems = problems();
+    time(c, "min_squares", MinSquaresTriangulator::new(), &problems);
+    time(c, "relative_dlt", RelativeDltTriangulator::new(), &problems);
+    time(c, "midpoint", MidpointTriangulator::new(), &problems);
+    time(
+        c,
+        "hartley_sturm",
+        HartleySturmTriangulator::new(),
+        &problems,
+    );
+    time(
+        c,
+        "angular_l1",
+        AngularTriangulator::new().norm(AngularNorm::L1),
+        &problems,
+    );
+    time(
+        c,
+        "angular_l_infinity",
+        AngularTriangulator::new().norm(AngularNorm::LInfinity),
+        &problems,
+    );
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = triangulators
+);
+
+fn main() {
+    let problems = problems();
+    report("min_squares", MinSquaresTriangulator::new(), &problems);
+    report("relative_dlt", RelativeDltTriangulator::new(), &problems);
+    report("midpoint", MidpointTriangulator::new(), &problems);
+    report("hartley_sturm", HartleySturmTriangulator::new(), &problems);
+    report(
+        "angular_l1",
+        AngularTriangulator::new().norm(AngularNorm::L1),
+        &problems,
+    );
+    report(
+        "angular_l_infinity",
+        AngularTriangulator::new().norm(AngularNorm::LInfinity),
+        &problems,
+    );
+
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
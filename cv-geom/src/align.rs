@@ -0,0 +1,396 @@
+use cv_core::nalgebra::{
+    IsometryMatrix3, Matrix3, Matrix6, Point3, Rotation3, Similarity3, Translation3, Unit,
+    UnitQuaternion, Vector3, Vector6,
+};
+use cv_core::sample_consensus::Estimator;
+use cv_core::{Projective, WorldMatch, WorldPoint};
+
+/// The running sums of corresponding points needed to align them, which can be accumulated in one pass.
+#[derive(Copy, Clone, Debug)]
+struct Moments {
+    count: f64,
+    a: Vector3<f64>,
+    b: Vector3<f64>,
+    a_squared: f64,
+    ba: Matrix3<f64>,
+}
+
+impl Moments {
+    fn new() -> Self {
+        Self {
+            count: 0.0,
+            a: Vector3::zeros(),
+            b: Vector3::zeros(),
+            a_squared: 0.0,
+            ba: Matrix3::zeros(),
+        }
+    }
+
+    fn add(&mut self, a: Point3<f64>, b: Point3<f64>) {
+        self.count += 1.0;
+        self.a += a.coords;
+        self.b += b.coords;
+        self.a_squared += a.coords.norm_squared();
+        self.ba += b.coords * a.coords.transpose();
+    }
+
+    /// Computes the rotation, scale, and translation (in that order) which best maps `a` onto `b`.
+    fn align(
+        &self,
+        scale: bool,
+        epsilon: f64,
+        max_iterations: usize,
+    ) -> Option<(Rotation3<f64>, f64, Vector3<f64>)> {
+        if self.count < 3.0 {
+            return None;
+        }
+        let mean_a = self.a / self.count;
+        let mean_b = self.b / self.count;
+        let variance_a = self.a_squared / self.count - mean_a.norm_squared();
+        let covariance = self.ba / self.count - mean_b * mean_a.transpose();
+
+        let svd = covariance.try_svd(true, true, epsilon, max_iterations)?;
+        let (u, v_t) = (svd.u?, svd.v_t?);
+        let singular_values = svd.singular_values;
+        // If the points are collinear, the rotation around their line is unknown.
+        let mut sorted = [singular_values[0], singular_values[1], singular_values[2]];
+        sorted.sort_unstable_by_key(|&v| float_ord::FloatOrd(v));
+        if variance_a <= 0.0 || sorted[1] <= epsilon * sorted[2] {
+            return None;
+        }
+
+        // Flip the direction of the smallest singular value if needed to avoid a reflection.
+        let mut correction = Vector3::repeat(1.0);
+        let smallest = singular_values.imin();
+        correction[smallest] = (u * v_t).determinant().signum();
+        let rotation =
+            Rotation3::from_matrix_unchecked(u * Matrix3::from_diagonal(&correction) * v_t);
+        let scale = if scale {
+            singular_values.dot(&correction) / variance_a
+        } else {
+            1.0
+        };
+        let translation = mean_b - scale * (rotation * mean_a);
+        Some((rotation, scale, translation))
+    }
+}
+
+/// Estimates the similarity (or isometry) between two sets of corresponding [`WorldPoint`]s in closed form.
+///
+/// This is the least-squares method from "Least-Squares Estimation of Transformation Parameters Between
+/// Two Point Patterns" by Shinji Umeyama. The resulting transform maps the first point of each [`WorldMatch`]
+/// onto the second point. This can be used to merge two reconstructions which share some points, or to
+/// georeference a reconstruction with surveyed points. As an [`Estimator`], it can be used with a consensus
+/// algorithm to reject incorrect matches.
+///
+/// At least three matches are required, and they must not all lie on a line. Matches with a point at
+/// infinity are skipped.
+///
+/// ```
+/// use cv_core::nalgebra::{Point3, Similarity3, Vector3};
+/// use cv_core::{Projective, WorldMatch, WorldPoint};
+/// use cv_geom::Umeyama;
+///
+/// let similarity = Similarity3::new(Vector3::new(1.0, -2.0, 0.5), Vector3::new(0.1, 0.2, -0.3), 2.5);
+/// let matches = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+///     .iter()
+///     .map(|&[x, y, z]| {
+///         let point = Point3::new(x, y, z);
+///         WorldMatch(WorldPoint::from_point(point), WorldPoint::from_point(similarity * point))
+///     });
+/// let estimated = Umeyama::new().similarity(matches).unwrap();
+/// assert!((estimated.to_homogeneous() - similarity.to_homogeneous()).norm() < 1e-9);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Umeyama {
+    scale: bool,
+    epsilon: f64,
+    max_iterations: usize,
+}
+
+impl Umeyama {
+    /// Creates an `Umeyama` with default values.
+    ///
+    /// Same as calling [`Default::default`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set whether the scale is estimated. If not, the estimated similarity is an isometry.
+    ///
+    /// Default is `true`.
+    pub fn scale(self, scale: bool) -> Self {
+        Self { scale, ..self }
+    }
+
+    /// Set the epsilon used in the SVD solver.
+    ///
+    /// Default is `1e-12`.
+    pub fn epsilon(self, epsilon: f64) -> Self {
+        Self { epsilon, ..self }
+    }
+
+    /// Set the maximum number of iterations for the SVD solver.
+    ///
+    /// Default is `1000`.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Estimates the similarity which maps the first point of each match onto the second point.
+    pub fn similarity(
+        &self,
+        matches: impl IntoIterator<Item = WorldMatch>,
+    ) -> Option<Similarity3<f64>> {
+        let (rotation, scale, translation) =
+            moments(matches).align(self.scale, self.epsilon, self.max_iterations)?;
+        Some(Similarity3::from_parts(
+            Translation3::from(translation),
+            UnitQuaternion::from_rotation_matrix(&rotation),
+            scale,
+        ))
+    }
+
+    /// Estimates the isometry which maps the first point of each match onto the second point.
+    ///
+    /// The scale is never estimated, regardless of [`Umeyama::scale`].
+    pub fn isometry(
+        &self,
+        matches: impl IntoIterator<Item = WorldMatch>,
+    ) -> Option<IsometryMatrix3<f64>> {
+        let (rotation, _, translation) =
+            moments(matches).align(false, self.epsilon, self.max_iterations)?;
+        Some(IsometryMatrix3::from_parts(translation.into(), rotation))
+    }
+}
+
+impl Default for Umeyama {
+    fn default() -> Self {
+        Self {
+            scale: true,
+            epsilon: 1e-12,
+            max_iterations: 1000,
+        }
+    }
+}
+
+impl Estimator<WorldMatch> for Umeyama {
+    type Model = Similarity3<f64>;
+    type ModelIter = Option<Similarity3<f64>>;
+    const MIN_SAMPLES: usize = 3;
+
+    fn estimate<I>(&self, data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = WorldMatch> + Clone,
+    {
+        self.similarity(data)
+    }
+}
+
+/// Accumulates the moments of all of the matches which are not at infinity.
+fn moments(matches: impl IntoIterator<Item = WorldMatch>) -> Moments {
+    let mut moments = Moments::new();
+    for WorldMatch(a, b) in matches {
+        if let (Some(a), Some(b)) = (a.point(), b.point()) {
+            moments.add(a, b);
+        }
+    }
+    moments
+}
+
+/// Aligns two point clouds without known correspondences using iterative closest point (ICP).
+///
+/// Each iteration matches every point of the source cloud to its closest point in the target cloud
+/// and then solves for the isometry which best aligns the matches. This only converges to the correct
+/// alignment when the initial guess is close, so it is typically used to refine an alignment found with [`Umeyama`].
+/// Matches farther apart than the maximum distance are rejected, which allows the clouds to only partially overlap.
+///
+/// The closest points are found by brute force, so every iteration takes time proportional to the product of
+/// the sizes of the clouds. Subsample large clouds before aligning them.
+#[derive(Copy, Clone, Debug)]
+pub struct IterativeClosestPoint {
+    max_distance: f64,
+    tolerance: f64,
+    epsilon: f64,
+    max_iterations: usize,
+}
+
+impl IterativeClosestPoint {
+    /// Creates an `IterativeClosestPoint` with default values.
+    ///
+    /// Same as calling [`Default::default`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the maximum distance between matched points.
+    ///
+    /// Default is `f64::INFINITY`.
+    pub fn max_distance(self, max_distance: f64) -> Self {
+        Self {
+            max_distance,
+            ..self
+        }
+    }
+
+    /// Set the size of an update to the pose below which the alignment is considered converged.
+    ///
+    /// Default is `1e-12`.
+    pub fn tolerance(self, tolerance: f64) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Set the epsilon used in the SVD solver.
+    ///
+    /// Default is `1e-12`.
+    pub fn epsilon(self, epsilon: f64) -> Self {
+        Self { epsilon, ..self }
+    }
+
+    /// Set the maximum number of iterations of ICP.
+    ///
+    /// Default is `100`.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Finds the closest target point to `point` within the maximum distance.
+    fn closest<T>(
+        &self,
+        point: Point3<f64>,
+        target: impl Iterator<Item = (Point3<f64>, T)>,
+    ) -> Option<(Point3<f64>, T)> {
+        target
+            .map(|(candidate, data)| ((candidate - point).norm_squared(), candidate, data))
+            .filter(|&(distance_squared, _, _)| {
+                distance_squared <= self.max_distance * self.max_distance
+            })
+            .min_by_key(|&(distance_squared, _, _)| float_ord::FloatOrd(distance_squared))
+            .map(|(_, candidate, data)| (candidate, data))
+    }
+
+    /// Aligns the source cloud to the target cloud by minimizing the distances between closest points.
+    ///
+    /// The resulting isometry maps the source points into the frame of the target points, starting from `initial`.
+    /// Returns `None` if fewer than three source points have a match or the matches are degenerate.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Vector3};
+    /// use cv_core::{Projective, WorldPoint};
+    /// use cv_geom::IterativeClosestPoint;
+    ///
+    /// let target: Vec<WorldPoint> = (0..64)
+    ///     .map(|i| {
+    ///         let (x, y, z) = ((i % 4) as f64, ((i / 4) % 4) as f64, (i / 16) as f64);
+    ///         WorldPoint::from_point(Point3::new(x, y * y * 0.5, z + x * y * 0.1))
+    ///     })
+    ///     .collect();
+    /// let pose = IsometryMatrix3::from_parts(
+    ///     Vector3::new(0.05, -0.03, 0.02).into(),
+    ///     Rotation3::from_euler_angles(0.02, -0.01, 0.03),
+    /// );
+    /// let source: Vec<WorldPoint> = target
+    ///     .iter()
+    ///     .map(|p| WorldPoint::from_point(pose.inverse() * p.point().unwrap()))
+    ///     .collect();
+    /// let estimated = IterativeClosestPoint::new()
+    ///     .point_to_point(&source, &target, IsometryMatrix3::identity())
+    ///     .unwrap();
+    /// assert!((estimated.to_homogeneous() - pose.to_homogeneous()).norm() < 1e-9);
+    /// ```
+    pub fn point_to_point(
+        &self,
+        source: &[WorldPoint],
+        target: &[WorldPoint],
+        initial: IsometryMatrix3<f64>,
+    ) -> Option<IsometryMatrix3<f64>> {
+        let mut pose = initial;
+        for _ in 0..self.max_iterations {
+            let mut moments = Moments::new();
+            for a in source.iter().filter_map(|p| p.point()) {
+                let targets = target.iter().filter_map(|p| p.point()).map(|p| (p, ()));
+                if let Some((b, ())) = self.closest(pose * a, targets) {
+                    moments.add(a, b);
+                }
+            }
+            let (rotation, _, translation) = moments.align(false, self.epsilon, SVD_ITERATIONS)?;
+            let new_pose = IsometryMatrix3::from_parts(translation.into(), rotation);
+            let update = (new_pose.to_homogeneous() - pose.to_homogeneous()).norm();
+            pose = new_pose;
+            if update < self.tolerance {
+                break;
+            }
+        }
+        Some(pose)
+    }
+
+    /// Aligns the source cloud to the target cloud by minimizing the distances between the source points
+    /// and the planes through their closest target points.
+    ///
+    /// Every target point must come with the normal of the surface at that point. This converges in far fewer
+    /// iterations than [`IterativeClosestPoint::point_to_point`] when the clouds are sampled from smooth surfaces,
+    /// and allows points to slide along the surface, so the clouds don't need to be sampled at the same locations.
+    /// The resulting isometry maps the source points into the frame of the target points, starting from `initial`.
+    ///
+    /// Returns `None` if fewer than six source points have a match or the surface doesn't constrain the pose.
+    pub fn point_to_plane(
+        &self,
+        source: &[WorldPoint],
+        target: &[(WorldPoint, Unit<Vector3<f64>>)],
+        initial: IsometryMatrix3<f64>,
+    ) -> Option<IsometryMatrix3<f64>> {
+        let mut pose = initial;
+        for _ in 0..self.max_iterations {
+            // Linearize the rotation so that each match gives the constraint
+            // `dot(cross(p, n), w) + dot(n, t) = dot(n, q - p)`.
+            let mut jtj = Matrix6::zeros();
+            let mut jtr = Vector6::zeros();
+            let mut count = 0;
+            for p in source.iter().filter_map(|p| p.point()) {
+                let p = pose * p;
+                let targets = target
+                    .iter()
+                    .filter_map(|&(q, normal)| q.point().map(|q| (q, normal)));
+                if let Some((q, normal)) = self.closest(p, targets) {
+                    let cross = p.coords.cross(&normal);
+                    let jacobian =
+                        Vector6::new(cross.x, cross.y, cross.z, normal.x, normal.y, normal.z);
+                    jtj += jacobian * jacobian.transpose();
+                    jtr += jacobian * normal.dot(&(q - p));
+                    count += 1;
+                }
+            }
+            if count < 6 {
+                return None;
+            }
+            let update = jtj.cholesky()?.solve(&jtr);
+            let rotation = Rotation3::new(update.xyz());
+            let translation = Vector3::new(update[3], update[4], update[5]);
+            pose = IsometryMatrix3::from_parts(translation.into(), rotation) * pose;
+            if update.norm() < self.tolerance {
+                break;
+            }
+        }
+        Some(pose)
+    }
+}
+
+impl Default for IterativeClosestPoint {
+    fn default() -> Self {
+        Self {
+            max_distance: f64::INFINITY,
+            tolerance: 1e-12,
+            epsilon: 1e-12,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// The maximum number of iterations of the SVD solver used by ICP.
+const SVD_ITERATIONS: usize = 1000;
@@ -28,6 +28,12 @@
 
 #![no_std]
 
+mod align;
+mod triangulation;
+
+pub use align::*;
+pub use triangulation::*;
+
 use cv_core::nalgebra::{zero, Matrix3x4, Matrix4, RowVector4};
 use cv_core::{
     Bearing, CameraPoint, CameraToCamera, Pose, TriangulatorObservations, TriangulatorRelative,
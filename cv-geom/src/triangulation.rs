@@ -0,0 +1,414 @@
+use crate::RelativeDltTriangulator;
+use cv_core::nalgebra::{Matrix3, Matrix6, Unit, Vector2, Vector3, Vector4};
+use cv_core::{
+    Bearing, CameraPoint, CameraToCamera, Pose, TriangulatorObservations, TriangulatorRelative,
+    WorldPoint, WorldToCamera,
+};
+use num_traits::Float;
+
+/// This solves triangulation problems by finding the point which minimizes the sum of the squared distances
+/// to the rays of all observations, which is the midpoint between the rays when there are two.
+///
+/// This is the fastest triangulator, but it minimizes a 3d distance rather than an image error, so observations
+/// from far away cameras have more weight than observations from nearby cameras.
+///
+/// ```
+/// use cv_core::nalgebra::{Vector3, Point3, Rotation3};
+/// use cv_core::{TriangulatorRelative, CameraToCamera, CameraPoint, Pose, Projective};
+/// use cv_geom::MidpointTriangulator;
+///
+/// let point = CameraPoint::from_point(Point3::new(0.3, 0.1, 2.0));
+/// let pose = CameraToCamera::from_parts(Vector3::new(0.1, 0.1, 0.1), Rotation3::new(Vector3::new(0.1, 0.1, 0.1)));
+/// let bearing_a = point.bearing();
+/// let bearing_b = pose.transform(point).bearing();
+/// let triangulated = MidpointTriangulator::new().triangulate_relative(pose, bearing_a, bearing_b).unwrap();
+/// let distance = (point.point().unwrap().coords - triangulated.point().unwrap().coords).norm();
+/// assert!(distance < 1e-6);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MidpointTriangulator;
+
+impl MidpointTriangulator {
+    /// Creates a `MidpointTriangulator`.
+    ///
+    /// Same as calling [`Default::default`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl TriangulatorObservations for MidpointTriangulator {
+    fn triangulate_observations<B: Bearing>(
+        &self,
+        pairs: impl IntoIterator<Item = (WorldToCamera, B)>,
+    ) -> Option<WorldPoint> {
+        let mut a = Matrix3::zeros();
+        let mut b = Vector3::zeros();
+        let mut count = 0;
+
+        for (pose, bearing) in pairs {
+            count += 1;
+            // Get the ray of the bearing in world space.
+            let camera_to_world = pose.inverse().isometry();
+            let direction = camera_to_world.rotation * bearing.bearing().into_inner();
+            let center = camera_to_world.translation.vector;
+            // Accumulate the projection which removes the component along the ray.
+            let projection = Matrix3::identity() - direction * direction.transpose();
+            a += projection;
+            b += projection * center;
+        }
+
+        if count < 2 {
+            return None;
+        }
+
+        // The system is singular when all of the rays are parallel.
+        a.cholesky()
+            .map(|cholesky| WorldPoint(cholesky.solve(&b).to_homogeneous() + Vector4::w()))
+    }
+}
+
+/// Based on algorithm 12.1 from "Multiple View Geometry in Computer Vision, Second Edition",
+/// also known as the optimal triangulation method of Hartley and Sturm.
+///
+/// The bearings are projected onto the virtual image plane at `z = 1` of each camera and moved the minimum
+/// distance necessary to satisfy the epipolar constraint, after which the rays intersect exactly.
+/// This gives the point which minimizes the squared reprojection error on the virtual image planes, which is
+/// optimal when the noise is gaussian in the image. It requires finding the roots of a polynomial of degree six,
+/// so it is slower than the other triangulators. Bearings which don't point in front of the camera (`z > 0`)
+/// can't be projected onto the image plane, in which case `None` is returned.
+///
+/// ```
+/// use cv_core::nalgebra::{Vector3, Point3, Rotation3};
+/// use cv_core::{TriangulatorRelative, CameraToCamera, CameraPoint, Pose, Projective};
+/// use cv_geom::HartleySturmTriangulator;
+///
+/// let point = CameraPoint::from_point(Point3::new(0.3, 0.1, 2.0));
+/// let pose = CameraToCamera::from_parts(Vector3::new(0.1, 0.1, 0.1), Rotation3::new(Vector3::new(0.1, 0.1, 0.1)));
+/// let bearing_a = point.bearing();
+/// let bearing_b = pose.transform(point).bearing();
+/// let triangulated = HartleySturmTriangulator::new().triangulate_relative(pose, bearing_a, bearing_b).unwrap();
+/// let distance = (point.point().unwrap().coords - triangulated.point().unwrap().coords).norm();
+/// assert!(distance < 1e-6);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct HartleySturmTriangulator {
+    epsilon: f64,
+    max_iterations: usize,
+}
+
+impl HartleySturmTriangulator {
+    /// Creates a `HartleySturmTriangulator` with default values.
+    ///
+    /// Same as calling [`Default::default`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the epsilon used in the polynomial root solver and the SVD solver.
+    ///
+    /// Default is `1e-9`.
+    pub fn epsilon(self, epsilon: f64) -> Self {
+        Self { epsilon, ..self }
+    }
+
+    /// Set the maximum number of iterations for the polynomial root solver and the SVD solver.
+    ///
+    /// Default is `100`.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Finds the real parts of the roots of the polynomial from the eigenvalues of its companion matrix.
+    ///
+    /// The coefficients are ordered from the constant term to the highest degree term. When the degree of
+    /// the polynomial is less than six, the remaining roots are zero.
+    fn roots(&self, coefficients: &[f64; 7]) -> Option<[f64; 6]> {
+        let scale = coefficients
+            .iter()
+            .fold(0.0, |max: f64, &c| max.max(Float::abs(c)));
+        // Drop the highest degree terms when they vanish.
+        let degree = coefficients
+            .iter()
+            .rposition(|&c| Float::abs(c) > scale * 1e-12)?;
+        let mut companion = Matrix6::zeros();
+        let leading = coefficients[degree];
+        for i in 0..degree {
+            companion[(0, i)] = -coefficients[degree - 1 - i] / leading;
+            if i + 1 < degree {
+                companion[(i + 1, i)] = 1.0;
+            }
+        }
+        let eigenvalues = companion
+            .try_schur(self.epsilon, self.max_iterations)?
+            .complex_eigenvalues();
+        let mut roots = [0.0; 6];
+        for (root, eigenvalue) in roots.iter_mut().zip(eigenvalues.iter()) {
+            *root = eigenvalue.re;
+        }
+        Some(roots)
+    }
+}
+
+impl Default for HartleySturmTriangulator {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-9,
+            max_iterations: 100,
+        }
+    }
+}
+
+impl TriangulatorRelative for HartleySturmTriangulator {
+    fn triangulate_relative<A: Bearing, B: Bearing>(
+        &self,
+        relative_pose: CameraToCamera,
+        a: A,
+        b: B,
+    ) -> Option<CameraPoint> {
+        let (a, b) = (a.bearing(), b.bearing());
+        if a.z <= 0.0 || b.z <= 0.0 {
+            return None;
+        }
+        let x = a.xy() / a.z;
+        let x_prime = b.xy() / b.z;
+        let CameraToCamera(pose) = relative_pose;
+        let rotation = *pose.rotation.matrix();
+        let translation = pose.translation.vector;
+
+        // Move both points to the origin, which moves the epipoles as well.
+        let epipole = translate(-(rotation.transpose() * translation), x)?;
+        let epipole_prime = translate(translation, x_prime)?;
+        let essential = translation.cross_matrix() * rotation;
+        let untranslate = |x: Vector2<f64>| {
+            let mut m = Matrix3::identity();
+            m[(0, 2)] = x.x;
+            m[(1, 2)] = x.y;
+            m
+        };
+        let r = epipole_rotation(&epipole);
+        let r_prime = epipole_rotation(&epipole_prime);
+        // Rotate the epipoles onto the x axis.
+        let fundamental =
+            r_prime * untranslate(x_prime).transpose() * essential * untranslate(x) * r.transpose();
+
+        let (f, f_prime) = (epipole.z, epipole_prime.z);
+        let (a, b, c, d) = (
+            fundamental[(1, 1)],
+            fundamental[(1, 2)],
+            fundamental[(2, 1)],
+            fundamental[(2, 2)],
+        );
+
+        // The cost is the sum of the squared distances from the origin to the epipolar lines parameterized by `t`.
+        let cost = |t: f64| {
+            let u = a * t + b;
+            let v = c * t + d;
+            t * t / (1.0 + f * f * t * t) + v * v / (u * u + f_prime * f_prime * v * v)
+        };
+        let mut best_t = None;
+        let mut best_cost = 1.0 / (f * f) + c * c / (a * a + f_prime * f_prime * c * c);
+        for &t in &self.roots(&polynomial(a, b, c, d, f, f_prime))? {
+            let cost = cost(t);
+            if cost < best_cost {
+                best_cost = cost;
+                best_t = Some(t);
+            }
+        }
+
+        // Find the closest point to the origin on the optimal epipolar lines.
+        let (line, line_prime) = match best_t {
+            Some(t) => (
+                Vector3::new(t * f, 1.0, -t),
+                Vector3::new(-f_prime * (c * t + d), a * t + b, c * t + d),
+            ),
+            None => (Vector3::new(f, 0.0, -1.0), Vector3::new(-f_prime * c, a, c)),
+        };
+        let closest = |l: Vector3<f64>| Vector3::new(-l.x * l.z, -l.y * l.z, l.x * l.x + l.y * l.y);
+        let a = untranslate(x) * r.transpose() * closest(line);
+        let b = untranslate(x_prime) * r_prime.transpose() * closest(line_prime);
+
+        let a = Unit::try_new(a / a.z, 0.0)?;
+        let b = Unit::try_new(b / b.z, 0.0)?;
+        RelativeDltTriangulator::new()
+            .epsilon(self.epsilon)
+            .max_iterations(self.max_iterations)
+            .triangulate_relative(relative_pose, a, b)
+    }
+}
+
+/// Translates the epipole so that the point `x` is at the origin and then normalizes it so that `e1^2 + e2^2 = 1`.
+///
+/// Returns `None` if the point is at the epipole.
+fn translate(epipole: Vector3<f64>, x: Vector2<f64>) -> Option<Vector3<f64>> {
+    let translated = Vector3::new(
+        epipole.x - x.x * epipole.z,
+        epipole.y - x.y * epipole.z,
+        epipole.z,
+    );
+    let norm = translated.xy().norm();
+    if norm == 0.0 {
+        None
+    } else {
+        Some(translated / norm)
+    }
+}
+
+/// The rotation which moves the epipole onto the x axis.
+fn epipole_rotation(epipole: &Vector3<f64>) -> Matrix3<f64> {
+    Matrix3::new(
+        epipole.x, epipole.y, 0.0, -epipole.y, epipole.x, 0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// Computes the coefficients of the polynomial from equation 12.7, from the constant term to the degree six term:
+///
+/// `t * ((at + b)^2 + f'^2 (ct + d)^2)^2 - (ad - bc) (1 + f^2 t^2)^2 (at + b) (ct + d)`
+fn polynomial(a: f64, b: f64, c: f64, d: f64, f: f64, f_prime: f64) -> [f64; 7] {
+    let f_prime2 = f_prime * f_prime;
+    // (at + b)^2 + f'^2 (ct + d)^2
+    let quadratic = [
+        b * b + f_prime2 * d * d,
+        2.0 * (a * b + f_prime2 * c * d),
+        a * a + f_prime2 * c * c,
+    ];
+    let quartic = multiply(&quadratic, &quadratic);
+    // (1 + f^2 t^2)^2
+    let f2 = f * f;
+    let squared = [1.0, 0.0, 2.0 * f2, 0.0, f2 * f2];
+    let product = multiply(&squared, &[b * d, a * d + b * c, a * c]);
+    let determinant = a * d - b * c;
+
+    let mut coefficients = [0.0; 7];
+    for (coefficient, q) in coefficients[1..].iter_mut().zip(quartic.iter()) {
+        *coefficient += q;
+    }
+    for (coefficient, p) in coefficients.iter_mut().zip(product.iter()) {
+        *coefficient -= determinant * p;
+    }
+    coefficients
+}
+
+/// Multiplies two polynomials with coefficients from the constant term to the highest degree term.
+fn multiply(a: &[f64], b: &[f64]) -> [f64; 7] {
+    let mut product = [0.0; 7];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            product[i + j] += a * b;
+        }
+    }
+    product
+}
+
+/// The norm of the angular errors which [`AngularTriangulator`] minimizes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AngularNorm {
+    /// Minimizes the sum of the angular errors of both bearings.
+    L1,
+    /// Minimizes the largest angular error of the two bearings.
+    LInfinity,
+}
+
+/// Based on "Closed-Form Optimal Two-View Triangulation Based on Angular Errors" by Seong Hun Lee and Javier Civera.
+///
+/// The bearings are rotated the minimum angle necessary to lie on a common epipolar plane, after which the rays
+/// intersect exactly. The angle is minimized in either the L1 or L∞ norm. Since the error is measured in angles
+/// rather than on an image plane, this works for any bearing, including those of omnidirectional cameras.
+/// It is nearly as fast as [`MidpointTriangulator`].
+///
+/// ```
+/// use cv_core::nalgebra::{Vector3, Point3, Rotation3};
+/// use cv_core::{TriangulatorRelative, CameraToCamera, CameraPoint, Pose, Projective};
+/// use cv_geom::{AngularNorm, AngularTriangulator};
+///
+/// let point = CameraPoint::from_point(Point3::new(0.3, 0.1, 2.0));
+/// let pose = CameraToCamera::from_parts(Vector3::new(0.1, 0.1, 0.1), Rotation3::new(Vector3::new(0.1, 0.1, 0.1)));
+/// let bearing_a = point.bearing();
+/// let bearing_b = pose.transform(point).bearing();
+/// for &norm in &[AngularNorm::L1, AngularNorm::LInfinity] {
+///     let triangulated = AngularTriangulator::new().norm(norm).triangulate_relative(pose, bearing_a, bearing_b).unwrap();
+///     let distance = (point.point().unwrap().coords - triangulated.point().unwrap().coords).norm();
+///     assert!(distance < 1e-6);
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct AngularTriangulator {
+    norm: AngularNorm,
+}
+
+impl AngularTriangulator {
+    /// Creates an `AngularTriangulator` with default values.
+    ///
+    /// Same as calling [`Default::default`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the norm of the angular errors which is minimized.
+    ///
+    /// Default is [`AngularNorm::LInfinity`].
+    pub fn norm(self, norm: AngularNorm) -> Self {
+        Self { norm }
+    }
+}
+
+impl Default for AngularTriangulator {
+    fn default() -> Self {
+        Self {
+            norm: AngularNorm::LInfinity,
+        }
+    }
+}
+
+impl TriangulatorRelative for AngularTriangulator {
+    fn triangulate_relative<A: Bearing, B: Bearing>(
+        &self,
+        relative_pose: CameraToCamera,
+        a: A,
+        b: B,
+    ) -> Option<CameraPoint> {
+        let CameraToCamera(pose) = relative_pose;
+        let translation = pose.translation.vector;
+        // Perform the computation in the frame of camera B.
+        let a = pose.rotation * a.bearing().into_inner();
+        let b = b.bearing().into_inner();
+
+        // The normals of the epipolar planes which contain each bearing, scaled by the sine of
+        // the angle between the bearing and the translation.
+        let normal_a = a.cross(&translation);
+        let normal_b = b.cross(&translation);
+        let normal = match self.norm {
+            // Keep the bearing furthest from the translation fixed, since it is the most sensitive.
+            AngularNorm::L1 => {
+                let normal = if normal_a.norm_squared() >= normal_b.norm_squared() {
+                    normal_a
+                } else {
+                    normal_b
+                };
+                Unit::try_new(normal, 0.0)?
+            }
+            // Both the sum and difference of the normals give planes where the errors of both bearings
+            // are equal, so pick the one with the smaller error.
+            AngularNorm::LInfinity => [normal_a + normal_b, normal_a - normal_b]
+                .iter()
+                .filter_map(|&normal| Unit::try_new(normal, 0.0))
+                .min_by_key(|normal| float_ord::FloatOrd(Float::abs(normal.dot(&a))))?,
+        };
+
+        // Project the bearings onto the common epipolar plane, where they intersect.
+        let a = a - normal.dot(&a) * normal.into_inner();
+        let b = b - normal.dot(&b) * normal.into_inner();
+        let cross = a.cross(&b);
+        let cross_norm_squared = cross.norm_squared();
+        if cross_norm_squared == 0.0 {
+            return None;
+        }
+        // Solve `depth_b * b = depth_a * a + translation` for the depth along `a`.
+        let depth = -translation.cross(&b).dot(&cross) / cross_norm_squared;
+        let point = pose.rotation.inverse() * (depth * a);
+        Some(CameraPoint(point.to_homogeneous() + Vector4::w()))
+    }
+}
@@ -0,0 +1,107 @@
+use arrsac::Arrsac;
+use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Similarity3, Unit, Vector3};
+use cv_core::sample_consensus::Consensus;
+use cv_core::{Projective, WorldMatch, WorldPoint};
+use cv_geom::{IterativeClosestPoint, Umeyama};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const SAMPLE_POINTS: usize = 100;
+
+fn random_similarity(rng: &mut impl Rng) -> Similarity3<f64> {
+    Similarity3::new(
+        Vector3::new(rng.gen(), rng.gen(), rng.gen()) * 10.0,
+        Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+        rng.gen_range(0.5, 4.0),
+    )
+}
+
+fn random_point(rng: &mut impl Rng) -> Point3<f64> {
+    Point3::new(rng.gen(), rng.gen(), rng.gen()) * 10.0
+}
+
+#[test]
+fn umeyama_noisy() {
+    let mut rng = SmallRng::from_seed([0; 16]);
+    for _ in 0..100 {
+        let similarity = random_similarity(&mut rng);
+        let matches: Vec<_> = (0..SAMPLE_POINTS)
+            .map(|_| {
+                let a = random_point(&mut rng);
+                let noise = Vector3::new(rng.gen(), rng.gen(), rng.gen()) * 1e-3;
+                WorldMatch(
+                    WorldPoint::from_point(a),
+                    WorldPoint::from_point(similarity * a + noise),
+                )
+            })
+            .collect();
+        let estimated = Umeyama::new().similarity(matches.iter().copied()).unwrap();
+        assert!((estimated.scaling() - similarity.scaling()).abs() < 1e-3);
+        assert!(
+            (estimated.isometry.rotation.to_rotation_matrix().matrix()
+                - similarity.isometry.rotation.to_rotation_matrix().matrix())
+            .norm()
+                < 1e-3
+        );
+    }
+}
+
+#[test]
+fn umeyama_consensus() {
+    let mut rng = SmallRng::from_seed([0; 16]);
+    let similarity = random_similarity(&mut rng);
+    let mut matches: Vec<_> = (0..SAMPLE_POINTS)
+        .map(|_| {
+            let a = random_point(&mut rng);
+            WorldMatch(
+                WorldPoint::from_point(a),
+                WorldPoint::from_point(similarity * a),
+            )
+        })
+        .collect();
+    // Replace a third of the matches with outliers.
+    for m in matches.iter_mut().step_by(3) {
+        m.1 = WorldPoint::from_point(random_point(&mut rng));
+    }
+
+    let mut arrsac = Arrsac::new(1e-6, SmallRng::from_seed([1; 16]));
+    let (estimated, inliers) = arrsac
+        .model_inliers(&Umeyama::new(), matches.iter().copied())
+        .unwrap();
+    let outliers = matches.iter().step_by(3).count();
+    assert_eq!(inliers.len(), SAMPLE_POINTS - outliers);
+    assert!((estimated.to_homogeneous() - similarity.to_homogeneous()).norm() < 1e-6);
+}
+
+#[test]
+fn icp_point_to_plane() {
+    // Sample an ellipsoid, which has no rotational symmetry.
+    let radii = Vector3::new(3.0, 2.0, 1.0);
+    let mut rng = SmallRng::from_seed([0; 16]);
+    let target: Vec<_> = (0..500)
+        .map(|_| {
+            let direction = Unit::new_normalize(Vector3::new(
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+            ));
+            let point = Point3::from(direction.component_mul(&radii));
+            let normal =
+                Unit::new_normalize(point.coords.component_div(&radii.component_mul(&radii)));
+            (WorldPoint::from_point(point), normal)
+        })
+        .collect();
+
+    let pose = IsometryMatrix3::from_parts(
+        Vector3::new(0.1, -0.05, 0.08).into(),
+        Rotation3::from_euler_angles(0.05, -0.04, 0.1),
+    );
+    let source: Vec<_> = target
+        .iter()
+        .map(|(p, _)| WorldPoint::from_point(pose.inverse() * p.point().unwrap()))
+        .collect();
+
+    let estimated = IterativeClosestPoint::new()
+        .point_to_plane(&source, &target, IsometryMatrix3::identity())
+        .unwrap();
+    assert!((estimated.to_homogeneous() - pose.to_homogeneous()).norm() < 1e-9);
+}
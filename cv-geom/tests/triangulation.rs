@@ -0,0 +1,288 @@
+use cv_core::nalgebra::{Matrix3, Point3, Rotation3, Unit, Vector3};
+use cv_core::{
+    CameraPoint, CameraToCamera, Pose, Projective, Triangulation, TriangulatorObservations,
+    TriangulatorRelative, WorldPoint, WorldToCamera,
+};
+use cv_geom::{
+    AngularNorm, AngularTriangulator, HartleySturmTriangulator, MidpointTriangulator,
+    MinSquaresTriangulator, RelativeDltTriangulator,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rand_distr::{Distribution, Normal};
+
+const SAMPLES: usize = 1000;
+const MONTE_CARLO_SAMPLES: usize = 10000;
+
+fn rng() -> SmallRng {
+    SmallRng::from_seed([7; 16])
+}
+
+/// Creates a relative pose and a point in front of both cameras.
+fn random_problem(rng: &mut impl Rng) -> (CameraToCamera, CameraPoint) {
+    let pose = CameraToCamera::from_parts(
+        Vector3::new(
+            rng.gen_range(-1.0, 1.0),
+            rng.gen_range(-1.0, 1.0),
+            rng.gen_range(-0.2, 0.2),
+        ),
+        Rotation3::new(Vector3::new(rng.gen(), rng.gen(), rng.gen()) * 0.2),
+    );
+    loop {
+        let point = CameraPoint::from_point(Point3::new(
+            rng.gen_range(-1.0, 1.0),
+            rng.gen_range(-1.0, 1.0),
+            rng.gen_range(2.0, 10.0),
+        ));
+        if pose.transform(point).point().unwrap().z > 0.5 {
+            return (pose, point);
+        }
+    }
+}
+
+/// Perturbs the bearing on the virtual image plane at `z = 1`.
+fn noisy(rng: &mut impl Rng, bearing: Unit<Vector3<f64>>, noise: f64) -> Unit<Vector3<f64>> {
+    let image = bearing.xy() / bearing.z
+        + cv_core::nalgebra::Vector2::new(
+            rng.gen_range(-noise, noise),
+            rng.gen_range(-noise, noise),
+        );
+    Unit::new_normalize(image.push(1.0))
+}
+
+fn image_error(point: CameraPoint, bearing: Unit<Vector3<f64>>) -> f64 {
+    let point = point.point().unwrap();
+    (point.coords.xy() / point.z - bearing.xy() / bearing.z).norm_squared()
+}
+
+fn reprojection_error(
+    pose: CameraToCamera,
+    point: CameraPoint,
+    a: Unit<Vector3<f64>>,
+    b: Unit<Vector3<f64>>,
+) -> f64 {
+    image_error(point, a) + image_error(pose.transform(point), b)
+}
+
+fn angular_error(point: CameraPoint, bearing: Unit<Vector3<f64>>) -> f64 {
+    point.bearing().angle(&bearing)
+}
+
+fn check_exact(triangulator: impl TriangulatorRelative) {
+    let mut rng = SmallRng::from_seed([5; 16]);
+    let successes = (0..SAMPLES)
+        .filter(|_| {
+            let (pose, point) = random_problem(&mut rng);
+            triangulator
+                .triangulate_relative(pose, point.bearing(), pose.transform(point).bearing())
+                .and_then(|p| p.point())
+                .map(|p| (p - point.point().unwrap()).norm() < 1e-6)
+                .unwrap_or(false)
+        })
+        .count();
+    assert!(successes > 995, "only {} successes", successes);
+}
+
+#[test]
+fn midpoint_exact() {
+    check_exact(MidpointTriangulator::new());
+}
+
+#[test]
+fn hartley_sturm_exact() {
+    check_exact(HartleySturmTriangulator::new());
+}
+
+#[test]
+fn angular_exact() {
+    check_exact(AngularTriangulator::new().norm(AngularNorm::L1));
+    check_exact(AngularTriangulator::new().norm(AngularNorm::LInfinity));
+}
+
+#[test]
+fn midpoint_many_views() {
+    let mut rng = SmallRng::from_seed([6; 16]);
+    for _ in 0..SAMPLES {
+        let point = Point3::new(rng.gen(), rng.gen(), rng.gen::<f64>() + 5.0);
+        let observations: Vec<_> = (0..5)
+            .map(|_| {
+                let pose = WorldToCamera::from_parts(
+                    Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+                    Rotation3::new(Vector3::new(rng.gen(), rng.gen(), rng.gen()) * 0.1),
+                );
+                let bearing = pose
+                    .transform(cv_core::WorldPoint::from_point(point))
+                    .bearing();
+                (pose, bearing)
+            })
+            .collect();
+        let triangulated = MidpointTriangulator::new()
+            .triangulate_observations(observations)
+            .unwrap();
+        assert!((triangulated.point().unwrap() - point).norm() < 1e-6);
+    }
+}
+
+#[test]
+fn hartley_sturm_minimizes_reprojection() {
+    let mut rng = SmallRng::from_seed([7; 16]);
+    let mut better = 0;
+    for _ in 0..SAMPLES {
+        let (pose, point) = random_problem(&mut rng);
+        let a = noisy(&mut rng, point.bearing(), 1e-2);
+        let b = noisy(&mut rng, pose.transform(point).bearing(), 1e-2);
+        let optimal = HartleySturmTriangulator::new()
+            .triangulate_relative(pose, a, b)
+            .unwrap();
+        let optimal = reprojection_error(pose, optimal, a, b);
+        let others = [
+            RelativeDltTriangulator::new().triangulate_relative(pose, a, b),
+            MinSquaresTriangulator::new().triangulate_relative(pose, a, b),
+            MidpointTriangulator::new().triangulate_relative(pose, a, b),
+        ];
+        if others
+            .iter()
+            .flatten()
+            .all(|&other| optimal <= reprojection_error(pose, other, a, b) + 1e-12)
+        {
+            better += 1;
+        }
+    }
+    assert!(better > 995, "only {} optimal", better);
+}
+
+#[test]
+fn angular_l_infinity_minimizes_max_angle() {
+    let mut rng = SmallRng::from_seed([8; 16]);
+    let max_angle = |pose: CameraToCamera, point: CameraPoint, a, b| {
+        angular_error(point, a).max(angular_error(pose.transform(point), b))
+    };
+    let mut better = 0;
+    for _ in 0..SAMPLES {
+        let (pose, point) = random_problem(&mut rng);
+        let a = noisy(&mut rng, point.bearing(), 1e-2);
+        let b = noisy(&mut rng, pose.transform(point).bearing(), 1e-2);
+        let optimal = AngularTriangulator::new()
+            .norm(AngularNorm::LInfinity)
+            .triangulate_relative(pose, a, b)
+            .unwrap();
+        let optimal = max_angle(pose, optimal, a, b);
+        let others = [
+            HartleySturmTriangulator::new().triangulate_relative(pose, a, b),
+            MidpointTriangulator::new().triangulate_relative(pose, a, b),
+            AngularTriangulator::new()
+                .norm(AngularNorm::L1)
+                .triangulate_relative(pose, a, b),
+        ];
+        if others
+            .iter()
+            .flatten()
+            .all(|&other| optimal <= max_angle(pose, other, a, b) + 1e-12)
+        {
+            better += 1;
+        }
+    }
+    assert!(better > 995, "only {} optimal", better);
+}
+
+/// Creates the pose of a camera at `center` which isn't rotated.
+fn camera_at(center: Vector3<f64>) -> WorldToCamera {
+    WorldToCamera::from_parts(-center, Rotation3::identity())
+}
+
+/// Observes the point from each camera, perturbing each bearing by an angle with the given standard deviation in
+/// both directions perpendicular to it.
+fn observe(
+    rng: &mut impl Rng,
+    point: WorldPoint,
+    cameras: &[WorldToCamera],
+    std_dev: f64,
+) -> Vec<(WorldToCamera, Unit<Vector3<f64>>)> {
+    let normal = Normal::new(0.0, std_dev).unwrap();
+    cameras
+        .iter()
+        .map(|&pose| {
+            let bearing = pose.transform(point).bearing();
+            let u = bearing.cross(&Vector3::x()).normalize();
+            let v = bearing.cross(&u);
+            let noise = u * normal.sample(rng) + v * normal.sample(rng);
+            (pose, Unit::new_normalize(bearing.into_inner() + noise))
+        })
+        .collect()
+}
+
+#[test]
+fn covariance_of_perpendicular_views() {
+    let point = WorldPoint::from_point(Point3::origin());
+    let cameras = [
+        camera_at(Vector3::new(0.0, 0.0, -2.0)),
+        camera_at(Vector3::new(-2.0, 0.0, 0.0)),
+    ];
+    let triangulation: Triangulation<Vec<f64>> =
+        Triangulation::evaluate(point, observe(&mut rng(), point, &cameras, 0.0), 1e-3);
+    // Each camera constrains the two directions perpendicular to its bearing with an information of `1 / 2^2`,
+    // so only the `y` axis is constrained by both.
+    let expected = Matrix3::from_diagonal(&Vector3::new(1.0, 0.5, 1.0)) * 4.0 * 1e-6;
+    assert!((triangulation.covariance.unwrap() - expected).norm() < 1e-15);
+    assert!((triangulation.angle - core::f64::consts::FRAC_PI_2).abs() < 1e-12);
+}
+
+#[test]
+fn covariance_matches_monte_carlo() {
+    let mut rng = rng();
+    let std_dev = 1e-3;
+    let point = WorldPoint::from_point(Point3::new(0.1, -0.2, 0.3));
+    // The cameras are equally far from the point, so the midpoint is the maximum likelihood estimate.
+    let cameras: Vec<WorldToCamera> = [
+        Vector3::new(0.0, 0.0, -1.0),
+        Vector3::new(-1.0, 0.0, -1.0),
+        Vector3::new(0.5, 1.0, -1.0),
+    ]
+    .iter()
+    .map(|direction| camera_at(point.point().unwrap().coords - direction.normalize() * 4.0))
+    .collect();
+
+    let predicted = Triangulation::<Vec<f64>>::evaluate(
+        point,
+        observe(&mut rng, point, &cameras, 0.0),
+        std_dev,
+    )
+    .covariance
+    .unwrap();
+    let errors: Vec<Vector3<f64>> = (0..MONTE_CARLO_SAMPLES)
+        .map(|_| {
+            let observations = observe(&mut rng, point, &cameras, std_dev);
+            let triangulated = MidpointTriangulator::new()
+                .triangulate_observations(observations)
+                .unwrap();
+            triangulated.point().unwrap() - point.point().unwrap()
+        })
+        .collect();
+    let sampled = errors
+        .iter()
+        .map(|error| error * error.transpose())
+        .sum::<Matrix3<f64>>()
+        / MONTE_CARLO_SAMPLES as f64;
+    assert!(
+        (sampled - predicted).norm() < 0.1 * predicted.norm(),
+        "sampled covariance {} doesn't match predicted covariance {}",
+        sampled,
+        predicted
+    );
+}
+
+#[test]
+fn covariance_with_coincident_center() {
+    let point = WorldPoint::from_point(Point3::origin());
+    let cameras = [
+        camera_at(Vector3::new(0.0, 0.0, -2.0)),
+        camera_at(Vector3::new(-2.0, 0.0, 0.0)),
+        camera_at(Vector3::zeros()),
+    ];
+    let observations: Vec<_> = cameras
+        .iter()
+        .map(|&pose| (pose, Unit::new_normalize(Vector3::new(0.0, 0.0, 1.0))))
+        .collect();
+    let triangulation: Triangulation<Vec<f64>> = Triangulation::evaluate(point, observations, 1e-3);
+    assert!(triangulation.covariance.is_none());
+    assert!((triangulation.angle - core::f64::consts::FRAC_PI_2).abs() < 1e-12);
+}
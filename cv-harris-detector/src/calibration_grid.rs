@@ -0,0 +1,242 @@
+// Ordering of detected calibration target points (chessboard corners or circle centers) into a grid.
+
+use nalgebra::{Matrix2, Vector2};
+use std::collections::{HashMap, VecDeque};
+
+/// The layout of the points of a calibration target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridPattern {
+    /// Points on a regular grid, like chessboard inner corners or a symmetric circle grid.
+    Symmetric,
+    /// Every other row is offset by half the spacing, like the OpenCV asymmetric circle grid.
+    /// Each row contains `pattern_size.0` points.
+    Asymmetric,
+}
+
+/// The points of a detected calibration target, in image coordinates.
+///
+/// `points` are stored row by row: the point at column `x` of row `y` is at index `y * pattern_size.0 + x`.
+/// For [`GridPattern::Asymmetric`] grids, the layout matches the object points used by OpenCV,
+/// where the point at column `x` of row `y` is at `(2 * x + y % 2, y)` times the spacing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationGrid {
+    pub pattern_size: (i32, i32),
+    pub pattern: GridPattern,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl CalibrationGrid {
+    /// Returns the image location of the point at column `x` and row `y`.
+    pub fn get(&self, x: i32, y: i32) -> (f64, f64) {
+        self.points[(y * self.pattern_size.0 + x) as usize]
+    }
+}
+
+/// Orders the detected points into a grid of `pattern_size` (points per row, rows).
+///
+/// Every point must belong to the grid and the number of points must match the pattern size,
+/// otherwise `None` is returned. The points are assigned lattice coordinates by walking from
+/// neighbor to neighbor, so moderate perspective and lens distortion is tolerated.
+///
+/// The grid is oriented so that its rows run towards the right of the image as much as possible.
+pub fn order_grid_points(
+    points: &[(f64, f64)],
+    pattern_size: (i32, i32),
+    pattern: GridPattern,
+) -> Option<CalibrationGrid> {
+    let (columns, rows) = pattern_size;
+    if columns < 2 || rows < 2 || points.len() != (columns * rows) as usize {
+        return None;
+    }
+    let points: Vec<Vector2<f64>> = points.iter().map(|&(x, y)| Vector2::new(x, y)).collect();
+
+    // Both grids are square lattices: the asymmetric grid is just rotated by 45 degrees.
+    let (a, b) = estimate_lattice_basis(&points)?;
+    let lattice = assign_lattice_coordinates(&points, a, b)?;
+
+    // Convert the square lattice coordinates into the coordinates of the pattern.
+    let coordinates: Vec<(i32, i32)> = match pattern {
+        GridPattern::Symmetric => lattice,
+        GridPattern::Asymmetric => lattice.iter().map(|&(m, n)| (m - n, m + n)).collect(),
+    };
+
+    let candidates = [(1, 0, 0, 1), (-1, 0, 0, -1), (0, 1, -1, 0), (0, -1, 1, 0)];
+    let mut best: Option<(f64, CalibrationGrid)> = None;
+    for &(xx, xy, yx, yy) in &candidates {
+        let transformed: Vec<(i32, i32)> = coordinates
+            .iter()
+            .map(|&(x, y)| (xx * x + xy * y, yx * x + yy * y))
+            .collect();
+        if let Some(grid) = grid_from_coordinates(&points, &transformed, pattern_size, pattern) {
+            // Prefer the orientation where the rows point towards the right of the image.
+            let first = grid.get(0, 0);
+            let last = grid.get(columns - 1, 0);
+            let score = (last.0 - first.0) / ((last.0 - first.0).hypot(last.1 - first.1));
+            if best.as_ref().map(|(s, _)| score > *s).unwrap_or(true) {
+                best = Some((score, grid));
+            }
+        }
+    }
+    best.map(|(_, grid)| grid)
+}
+
+/// Builds the grid if the lattice coordinates exactly fill the pattern.
+fn grid_from_coordinates(
+    points: &[Vector2<f64>],
+    coordinates: &[(i32, i32)],
+    (columns, rows): (i32, i32),
+    pattern: GridPattern,
+) -> Option<CalibrationGrid> {
+    let min_x = coordinates.iter().map(|c| c.0).min()?;
+    let min_y = coordinates.iter().map(|c| c.1).min()?;
+    // For the asymmetric grid, the first row must start at an even coordinate.
+    let first_row_offset = coordinates
+        .iter()
+        .filter(|c| c.1 == min_y)
+        .map(|c| c.0 - min_x)
+        .min()?;
+    if first_row_offset != 0 {
+        return None;
+    }
+
+    let mut grid_points = vec![None; (columns * rows) as usize];
+    for (point, &(x, y)) in points.iter().zip(coordinates) {
+        let (x, y) = (x - min_x, y - min_y);
+        let column = match pattern {
+            GridPattern::Symmetric => x,
+            GridPattern::Asymmetric => {
+                if (x - y % 2) % 2 != 0 {
+                    return None;
+                }
+                (x - y % 2) / 2
+            }
+        };
+        if column >= columns || y >= rows {
+            return None;
+        }
+        let slot = &mut grid_points[(y * columns + column) as usize];
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some((point.x, point.y));
+    }
+
+    Some(CalibrationGrid {
+        pattern_size: (columns, rows),
+        pattern,
+        points: grid_points.into_iter().collect::<Option<Vec<_>>>()?,
+    })
+}
+
+/// Finds the two shortest lattice vectors from the nearest neighbors of every point.
+///
+/// The returned basis is ordered so that it is right-handed in image coordinates.
+fn estimate_lattice_basis(points: &[Vector2<f64>]) -> Option<(Vector2<f64>, Vector2<f64>)> {
+    // Collect the vectors to the nearest neighbors, folded into a half-plane.
+    let mut neighbor_vectors = vec![];
+    for (i, point) in points.iter().enumerate() {
+        let mut others: Vec<Vector2<f64>> = points
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, other)| other - point)
+            .collect();
+        others.sort_by(|a, b| a.norm_squared().partial_cmp(&b.norm_squared()).unwrap());
+        for v in others.into_iter().take(2) {
+            neighbor_vectors.push(if v.y < 0.0 || (v.y == 0.0 && v.x < 0.0) {
+                -v
+            } else {
+                v
+            });
+        }
+    }
+
+    // The dominant direction is the one that most neighbor vectors agree with.
+    let agreement = |direction: &Vector2<f64>| {
+        neighbor_vectors
+            .iter()
+            .filter(|v| v.normalize().dot(&direction.normalize()).abs() > 0.95)
+            .count()
+    };
+    let a_guess = *neighbor_vectors.iter().max_by_key(|v| agreement(v))?;
+    let b_guess = *neighbor_vectors
+        .iter()
+        .filter(|v| v.normalize().dot(&a_guess.normalize()).abs() < 0.7)
+        .max_by_key(|v| agreement(v))?;
+
+    // Average the vectors which agree with each direction to reduce noise.
+    let average = |direction: Vector2<f64>| {
+        let agreeing: Vec<Vector2<f64>> = neighbor_vectors
+            .iter()
+            .filter(|v| v.normalize().dot(&direction.normalize()) > 0.95)
+            .copied()
+            .collect();
+        agreeing.iter().sum::<Vector2<f64>>() / agreeing.len() as f64
+    };
+    let a = average(a_guess);
+    let b = average(b_guess);
+    if a.perp(&b) > 0.0 {
+        Some((a, b))
+    } else {
+        Some((a, -b))
+    }
+}
+
+/// Assigns integer lattice coordinates in the basis `(a, b)` to every point with a breadth-first walk.
+fn assign_lattice_coordinates(
+    points: &[Vector2<f64>],
+    a: Vector2<f64>,
+    b: Vector2<f64>,
+) -> Option<Vec<(i32, i32)>> {
+    let to_lattice = Matrix2::from_columns(&[a, b]).try_inverse()?;
+    let search_radius = 1.5 * a.norm().max(b.norm());
+    let tolerance = 0.35;
+
+    let mut coordinates: Vec<Option<(i32, i32)>> = vec![None; points.len()];
+    let mut occupied = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    // Start near the middle of the grid where perspective distortion is the smallest.
+    let centroid = points.iter().sum::<Vector2<f64>>() / points.len() as f64;
+    let start = (0..points.len()).min_by(|&i, &j| {
+        (points[i] - centroid)
+            .norm()
+            .partial_cmp(&(points[j] - centroid).norm())
+            .unwrap()
+    })?;
+    coordinates[start] = Some((0, 0));
+    occupied.insert((0, 0), start);
+    queue.push_back(start);
+
+    while let Some(current) = queue.pop_front() {
+        let (m, n) = coordinates[current].unwrap();
+        for (other, point) in points.iter().enumerate() {
+            let delta = point - points[current];
+            if other == current || delta.norm() > search_radius {
+                continue;
+            }
+            let steps = to_lattice * delta;
+            let (dm, dn) = (steps.x.round(), steps.y.round());
+            if (steps.x - dm).abs() > tolerance
+                || (steps.y - dn).abs() > tolerance
+                || dm.abs() + dn.abs() != 1.0
+            {
+                continue;
+            }
+            let coordinate = (m + dm as i32, n + dn as i32);
+            match coordinates[other] {
+                Some(existing) if existing != coordinate => return None,
+                Some(_) => {}
+                None => {
+                    if occupied.insert(coordinate, other).is_some() {
+                        return None;
+                    }
+                    coordinates[other] = Some(coordinate);
+                    queue.push_back(other);
+                }
+            }
+        }
+    }
+
+    coordinates.into_iter().collect()
+}
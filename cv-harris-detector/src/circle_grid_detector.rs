@@ -0,0 +1,194 @@
+// Detection of symmetric and asymmetric circle grid calibration targets.
+// The circles are found as dark blobs on a bright background, their centers are
+// computed from an ellipse fit of the blob moments, and they are ordered into a grid.
+
+use image::{GrayImage, Luma};
+use imageproc::{
+    contrast,
+    region_labelling::{self, Connectivity},
+};
+use std::f64::consts::PI;
+
+use crate::calibration_grid::{order_grid_points, CalibrationGrid, GridPattern};
+
+pub struct CircleGridDetectorParameters {
+    /// Gray level under which a pixel belongs to a circle. Otsu's method is used when `None`.
+    pub threshold: Option<u8>,
+    /// Minimum area of a blob in pixels.
+    pub min_area: f64,
+    /// Maximum area of a blob in pixels.
+    pub max_area: f64,
+    /// Minimum ratio of the minor axis to the major axis of the fitted ellipse.
+    pub min_axis_ratio: f64,
+    /// Minimum ratio of the blob area to the area of the fitted ellipse.
+    pub min_fill_ratio: f64,
+}
+
+impl Default for CircleGridDetectorParameters {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            min_area: 10.0,
+            max_area: 50000.0,
+            min_axis_ratio: 0.2,
+            min_fill_ratio: 0.8,
+        }
+    }
+}
+
+/// An ellipse fitted to a blob.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipse {
+    pub center: (f64, f64),
+    pub semi_major_axis: f64,
+    pub semi_minor_axis: f64,
+    /// Angle of the major axis from the x axis of the image, in radians.
+    pub angle: f64,
+    /// Area of the blob in pixels.
+    pub area: f64,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Moments {
+    m00: f64,
+    m10: f64,
+    m01: f64,
+    m20: f64,
+    m11: f64,
+    m02: f64,
+    touches_border: bool,
+}
+
+/// Finds dark elliptical blobs in the image and fits an ellipse to each of them.
+pub fn detect_circle_blobs(
+    gray_image: &GrayImage,
+    parameters: &CircleGridDetectorParameters,
+) -> Vec<Ellipse> {
+    let (width, height) = gray_image.dimensions();
+    let threshold = parameters
+        .threshold
+        .unwrap_or_else(|| contrast::otsu_level(gray_image));
+
+    // Bright pixels become the background of the labelling.
+    let binary = contrast::threshold(gray_image, threshold);
+    let labels =
+        region_labelling::connected_components(&binary, Connectivity::Eight, Luma([255u8]));
+
+    let mut moments: Vec<Moments> = vec![];
+    for (x, y, label) in labels.enumerate_pixels() {
+        let label = label[0] as usize;
+        if label == 0 {
+            continue;
+        }
+        if moments.len() < label {
+            moments.resize(label, Moments::default());
+        }
+        let m = &mut moments[label - 1];
+        let (xf, yf) = (x as f64, y as f64);
+        m.m00 += 1.0;
+        m.m10 += xf;
+        m.m01 += yf;
+        m.m20 += xf * xf;
+        m.m11 += xf * yf;
+        m.m02 += yf * yf;
+        if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+            m.touches_border = true;
+        }
+    }
+
+    moments
+        .iter()
+        .filter(|m| {
+            !m.touches_border && m.m00 >= parameters.min_area && m.m00 <= parameters.max_area
+        })
+        .filter_map(fit_ellipse)
+        .filter(|ellipse| {
+            let axis_ratio = ellipse.semi_minor_axis / ellipse.semi_major_axis;
+            let fill_ratio =
+                ellipse.area / (PI * ellipse.semi_major_axis * ellipse.semi_minor_axis);
+            axis_ratio >= parameters.min_axis_ratio && fill_ratio >= parameters.min_fill_ratio
+        })
+        .collect()
+}
+
+/// Fits the ellipse which has the same second order moments as the blob.
+fn fit_ellipse(m: &Moments) -> Option<Ellipse> {
+    let area = m.m00;
+    let center = (m.m10 / area, m.m01 / area);
+    // The central moments are the covariance of the pixel positions.
+    // A pixel is a unit square, which adds 1/12 of variance along each axis.
+    let cxx = m.m20 / area - center.0 * center.0 + 1.0 / 12.0;
+    let cxy = m.m11 / area - center.0 * center.1;
+    let cyy = m.m02 / area - center.1 * center.1 + 1.0 / 12.0;
+
+    let half_trace = 0.5 * (cxx + cyy);
+    let discriminant = (0.25 * (cxx - cyy).powi(2) + cxy * cxy).sqrt();
+    let major_variance = half_trace + discriminant;
+    let minor_variance = half_trace - discriminant;
+    if minor_variance <= 0.0 {
+        return None;
+    }
+
+    // A filled ellipse has a variance of a^2 / 4 along an axis with semi-axis a.
+    Some(Ellipse {
+        center,
+        semi_major_axis: 2.0 * major_variance.sqrt(),
+        semi_minor_axis: 2.0 * minor_variance.sqrt(),
+        angle: 0.5 * (2.0 * cxy).atan2(cxx - cyy),
+        area,
+    })
+}
+
+/// Detects a circle grid with `pattern_size` (circles per row, rows) in the image.
+///
+/// When more blobs than circles are found, the blobs whose area is most different from the
+/// median area are discarded before the circles are ordered into a grid.
+///
+/// ```
+/// use cv_harris_detector::*;
+/// use image::{GrayImage, Luma};
+/// use imageproc::drawing::draw_filled_circle_mut;
+///
+/// // Draw an asymmetric circle grid with 4 circles per row and 5 rows.
+/// let mut image = GrayImage::from_pixel(320, 320, Luma([255u8]));
+/// for y in 0..5 {
+///     for x in 0..4 {
+///         let center = (60 + 60 * x + 30 * (y % 2), 40 + 30 * y);
+///         draw_filled_circle_mut(&mut image, center, 10, Luma([0u8]));
+///     }
+/// }
+///
+/// let parameters = CircleGridDetectorParameters::default();
+/// let grid = detect_circle_grid(&image, (4, 5), GridPattern::Asymmetric, &parameters).unwrap();
+/// let (x, y) = grid.get(1, 3);
+/// assert!((x - 150.0).abs() < 0.5 && (y - 130.0).abs() < 0.5);
+///
+/// // The same image is not a symmetric grid.
+/// assert!(detect_circle_grid(&image, (4, 5), GridPattern::Symmetric, &parameters).is_none());
+/// ```
+pub fn detect_circle_grid(
+    gray_image: &GrayImage,
+    pattern_size: (i32, i32),
+    pattern: GridPattern,
+    parameters: &CircleGridDetectorParameters,
+) -> Option<CalibrationGrid> {
+    let mut blobs = detect_circle_blobs(gray_image, parameters);
+    let expected = (pattern_size.0 * pattern_size.1) as usize;
+    if blobs.len() < expected {
+        return None;
+    }
+
+    let mut areas: Vec<f64> = blobs.iter().map(|blob| blob.area).collect();
+    areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let median_area = areas[areas.len() / 2];
+    blobs.sort_by(|a, b| {
+        (a.area - median_area)
+            .abs()
+            .partial_cmp(&(b.area - median_area).abs())
+            .unwrap()
+    });
+    blobs.truncate(expected);
+
+    let centers: Vec<(f64, f64)> = blobs.iter().map(|blob| blob.center).collect();
+    order_grid_points(&centers, pattern_size, pattern)
+}
@@ -1,9 +1,13 @@
 mod harris_detector;
 mod chessboard_filtering;
 mod chessboard_detector;
+mod calibration_grid;
+mod circle_grid_detector;
 mod common;
 
 pub use harris_detector::*;
 pub use chessboard_filtering::*;
 pub use chessboard_detector::*;
+pub use calibration_grid::*;
+pub use circle_grid_detector::*;
 pub use common::*;
\ No newline at end of file
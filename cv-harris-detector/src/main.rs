@@ -60,7 +60,7 @@ fn generate_chessboard_parameters(
 
 pub fn main_harris() {
 
-    let (image_path, _chessboard_size) = get_image_path_and_chessboard_size();
+    let (image_path, chessboard_size) = get_image_path_and_chessboard_size();
     let src_image = image::open(image_path).expect("failed to open image file");
 
     // Probably not the right kind of conversion
@@ -133,12 +133,18 @@ pub fn main_harris() {
     // let out_img = DynamicImage::ImageRgb8(canvas.0.clone());
     // imgshow::imgshow(&out_img);
 
-    run_chessboard_detection(
+    let chessboard = run_chessboard_detection(
         &filtering_result.remaining_corners, 
         &corners_centers,
+        chessboard_size,
         &gray_image
     );
 
+    match chessboard {
+        Some(grid) => println!("found chessboard with {} corners", grid.points.len()),
+        None => println!("could not order the corners into a {:?} chessboard", chessboard_size),
+    }
+
 }
 
 
@@ -0,0 +1,17 @@
+use cv_harris_detector::*;
+use image::{GrayImage, Luma};
+
+#[test]
+fn too_few_corners() {
+    let image = GrayImage::from_pixel(200, 200, Luma([128u8]));
+    let corners = vec![
+        (50, 50),
+        (100, 50),
+        (150, 50),
+        (50, 100),
+        (100, 100),
+        (150, 100),
+    ];
+    let centers = find_corners_mean_and_medium(&corners);
+    assert!(run_chessboard_detection(&corners, &centers, (3, 3), &image).is_none());
+}
@@ -0,0 +1,129 @@
+use cv_harris_detector::*;
+use image::{GrayImage, Luma};
+use imageproc::drawing::draw_filled_circle_mut;
+use imageproc::geometric_transformations::{warp, Interpolation, Projection};
+
+const PATTERN_SIZE: (i32, i32) = (5, 4);
+const SPACING: f64 = 40.0;
+const RADIUS: i32 = 10;
+
+/// The location of the circle at column `x` and row `y` of the symmetric grid before it is transformed.
+fn grid_point(x: i32, y: i32) -> (f64, f64) {
+    (120.0 + SPACING * x as f64, 120.0 + SPACING * y as f64)
+}
+
+/// Draws a dark circle of the symmetric grid at the location given by `transform` for every grid point.
+fn draw_grid(transform: impl Fn((f64, f64)) -> (f64, f64)) -> GrayImage {
+    let mut image = GrayImage::from_pixel(400, 400, Luma([255u8]));
+    for y in 0..PATTERN_SIZE.1 {
+        for x in 0..PATTERN_SIZE.0 {
+            let (cx, cy) = transform(grid_point(x, y));
+            draw_filled_circle_mut(
+                &mut image,
+                (cx.round() as i32, cy.round() as i32),
+                RADIUS,
+                Luma([0u8]),
+            );
+        }
+    }
+    image
+}
+
+/// Checks that every point of the grid is within `tolerance` pixels of where `transform` puts it.
+fn assert_grid(
+    grid: &CalibrationGrid,
+    transform: impl Fn((f64, f64)) -> (f64, f64),
+    tolerance: f64,
+) {
+    assert_eq!(grid.pattern_size, PATTERN_SIZE);
+    assert_eq!(grid.pattern, GridPattern::Symmetric);
+    for y in 0..PATTERN_SIZE.1 {
+        for x in 0..PATTERN_SIZE.0 {
+            let expected = transform(grid_point(x, y));
+            let actual = grid.get(x, y);
+            let error = (actual.0 - expected.0).hypot(actual.1 - expected.1);
+            assert!(
+                error < tolerance,
+                "point ({}, {}) detected at {:?} instead of {:?}",
+                x,
+                y,
+                actual,
+                expected
+            );
+        }
+    }
+}
+
+fn detect(image: &GrayImage) -> Option<CalibrationGrid> {
+    let parameters = CircleGridDetectorParameters::default();
+    detect_circle_grid(image, PATTERN_SIZE, GridPattern::Symmetric, &parameters)
+}
+
+#[test]
+fn symmetric() {
+    let image = draw_grid(|p| p);
+    let grid = detect(&image).expect("failed to detect the grid");
+    assert_grid(&grid, |p| p, 0.5);
+
+    // The same image is not an asymmetric grid.
+    let parameters = CircleGridDetectorParameters::default();
+    assert!(
+        detect_circle_grid(&image, PATTERN_SIZE, GridPattern::Asymmetric, &parameters).is_none()
+    );
+}
+
+#[test]
+fn rotated() {
+    let (sin, cos) = 0.5f64.sin_cos();
+    let rotate = |(x, y): (f64, f64)| {
+        let (x, y) = (x - 200.0, y - 200.0);
+        (200.0 + cos * x - sin * y, 200.0 + sin * x + cos * y)
+    };
+    let grid = detect(&draw_grid(rotate)).expect("failed to detect the grid");
+    // The circles are drawn at rounded centers.
+    assert_grid(&grid, rotate, 1.0);
+}
+
+#[test]
+fn perspective() {
+    #[rustfmt::skip]
+    let projection = Projection::from_matrix([
+        0.9, 0.1, 20.0,
+        -0.05, 0.8, 40.0,
+        0.0006, 0.0004, 1.0,
+    ])
+    .unwrap();
+    let project = |(x, y): (f64, f64)| {
+        let (x, y) = projection * (x as f32, y as f32);
+        (x as f64, y as f64)
+    };
+    let image = warp(
+        &draw_grid(|p| p),
+        &projection,
+        Interpolation::Bilinear,
+        Luma([255u8]),
+    );
+    let grid = detect(&image).expect("failed to detect the grid");
+    // The center of the ellipse a circle is projected to is not exactly the projection of its center.
+    assert_grid(&grid, project, 1.0);
+}
+
+#[test]
+fn extra_blobs() {
+    let mut image = draw_grid(|p| p);
+    // Specks of noise and a large blob, both much smaller or larger than the circles.
+    for &center in &[(30, 30), (370, 40), (50, 360), (360, 200)] {
+        draw_filled_circle_mut(&mut image, center, 3, Luma([0u8]));
+    }
+    draw_filled_circle_mut(&mut image, (300, 340), 30, Luma([0u8]));
+    let grid = detect(&image).expect("failed to detect the grid");
+    assert_grid(&grid, |p| p, 0.5);
+}
+
+#[test]
+fn missing_circle() {
+    let mut image = draw_grid(|p| p);
+    let (x, y) = grid_point(2, 1);
+    draw_filled_circle_mut(&mut image, (x as i32, y as i32), RADIUS, Luma([255u8]));
+    assert!(detect(&image).is_none());
+}
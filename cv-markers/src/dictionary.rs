@@ -0,0 +1,199 @@
+use image::{GrayImage, Luma};
+
+/// A dictionary of square binary markers, like the AprilTag and ArUco families.
+///
+/// Every marker is a grid of `marker_bits` x `marker_bits` data cells surrounded by a black border
+/// one cell wide. A code stores the data cells row by row from the top-left cell of the upright
+/// marker, starting with the most significant of the `marker_bits * marker_bits` bits.
+/// A bit is `1` when the cell is white.
+///
+/// This is the layout used by the AprilTag code tables (such as `tag36h11.c`, where a code like
+/// `0xd7e00984b` can be passed directly), so a tag36h11 dictionary is created with `Dictionary::new(6, codes, 11)`.
+/// The byte tables of the OpenCV ArUco dictionaries (such as `DICT_4X4_1000_BYTES` and `DICT_5X5_1000_BYTES`)
+/// are loaded with [`Dictionary::from_aruco_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictionary {
+    marker_bits: usize,
+    codes: Vec<u64>,
+    max_correction: u32,
+}
+
+impl Dictionary {
+    /// Creates a dictionary of markers with `marker_bits` x `marker_bits` data cells.
+    ///
+    /// `min_distance` is the minimum Hamming distance between the codes of the dictionary
+    /// (the `11` in `36h11`), including their rotations. Markers are decoded if they are within
+    /// `(min_distance - 1) / 2` bit errors of a code, which can be changed with [`Dictionary::max_correction`].
+    ///
+    /// Panics if the markers have more than 64 bits.
+    pub fn new(marker_bits: usize, codes: Vec<u64>, min_distance: u32) -> Self {
+        assert!(
+            marker_bits > 0 && marker_bits * marker_bits <= 64,
+            "markers must have between 1 and 64 bits"
+        );
+        Self {
+            marker_bits,
+            codes,
+            max_correction: min_distance.saturating_sub(1) / 2,
+        }
+    }
+
+    /// Creates a dictionary from the byte table of an OpenCV ArUco dictionary in `predefined_dictionaries.hpp`.
+    ///
+    /// Each item is the bytes of the first rotation of a marker, which is `table[id][0]` in OpenCV.
+    /// The smaller predefined dictionaries are the first markers of the table with 1000 markers, so
+    /// `DICT_4X4_50` is the first 50 markers of `DICT_4X4_1000_BYTES`.
+    ///
+    /// OpenCV packs the cells row by row starting from the most significant bit of the first byte,
+    /// and stores the cells left over after the last whole byte in the low bits of the last byte.
+    ///
+    /// Panics if a marker doesn't have `(marker_bits * marker_bits + 7) / 8` bytes.
+    ///
+    /// ```
+    /// use cv_markers::Dictionary;
+    /// // The first two markers of `DICT_4X4_1000_BYTES`.
+    /// let dictionary = Dictionary::from_aruco_bytes(4, &[[181u8, 50], [15, 154]], 4);
+    /// assert_eq!(dictionary.codes(), &[0xb532, 0x0f9a]);
+    /// ```
+    pub fn from_aruco_bytes<B>(
+        marker_bits: usize,
+        markers: impl IntoIterator<Item = B>,
+        min_distance: u32,
+    ) -> Self
+    where
+        B: AsRef<[u8]>,
+    {
+        let cells = marker_bits * marker_bits;
+        // `usize::div_ceil` needs Rust 1.73.
+        #[allow(clippy::manual_div_ceil)]
+        let length = (cells + 7) / 8;
+        let codes = markers
+            .into_iter()
+            .map(|bytes| {
+                let bytes = bytes.as_ref();
+                assert_eq!(
+                    bytes.len(),
+                    length,
+                    "ArUco markers with {} cells must have {} bytes",
+                    cells,
+                    length
+                );
+                bytes.iter().enumerate().fold(0, |code, (ix, &byte)| {
+                    let bits = (cells - 8 * ix).min(8);
+                    code << bits | u64::from(byte)
+                })
+            })
+            .collect();
+        Self::new(marker_bits, codes, min_distance)
+    }
+
+    /// The original ArUco dictionary of 1024 markers with 5x5 bits (`DICT_ARUCO_ORIGINAL` in OpenCV).
+    ///
+    /// Each row of a marker is one of four 5-bit words of a Hamming code, which encodes two bits of the ID.
+    /// The first row encodes the two most significant bits of the ID.
+    pub fn aruco_original() -> Self {
+        const WORDS: [u64; 4] = [0b10000, 0b10111, 0b01001, 0b01110];
+        let codes = (0..1024)
+            .map(|id| (0..5).fold(0, |code, row| code << 5 | WORDS[id >> (2 * (4 - row)) & 3]))
+            .collect();
+        Self::new(5, codes, 3)
+    }
+
+    /// Sets the maximum number of bit errors that are corrected when decoding a marker.
+    ///
+    /// Correcting more errors than `(min_distance - 1) / 2` increases the chance of decoding the wrong marker.
+    pub fn max_correction(self, max_correction: u32) -> Self {
+        Self {
+            max_correction,
+            ..self
+        }
+    }
+
+    /// The number of data cells along each side of a marker.
+    pub fn marker_bits(&self) -> usize {
+        self.marker_bits
+    }
+
+    /// The codes of the markers, indexed by the marker ID.
+    pub fn codes(&self) -> &[u64] {
+        &self.codes
+    }
+
+    /// Finds the marker ID of the data cells sampled from an image.
+    ///
+    /// `bits` is stored like the codes, but starting from the cell at the first corner of the
+    /// detected quad. Returns the marker ID, the number of quarter turns needed to bring the
+    /// first corner of the quad to the top-left corner of the marker, and the number of corrected bits.
+    ///
+    /// If two different markers or rotations are equally close, the marker is ambiguous and `None` is returned.
+    ///
+    /// ```
+    /// use cv_markers::Dictionary;
+    /// let dictionary = Dictionary::aruco_original();
+    /// let code = dictionary.codes()[300];
+    /// assert_eq!(dictionary.decode(code), Some((300, 0, 0)));
+    /// assert_eq!(dictionary.decode(code ^ 0b100), Some((300, 0, 1)));
+    /// let rotated = dictionary.rotate(dictionary.rotate(code));
+    /// assert_eq!(dictionary.decode(rotated), Some((300, 2, 0)));
+    /// ```
+    pub fn decode(&self, bits: u64) -> Option<(usize, usize, u32)> {
+        let mut best: Option<(usize, usize, u32)> = None;
+        let mut ambiguous = false;
+        let mut rotated = bits;
+        for rotation in 0..4 {
+            for (id, &code) in self.codes.iter().enumerate() {
+                let distance = (rotated ^ code).count_ones();
+                match best {
+                    Some((_, _, best_distance)) if distance > best_distance => {}
+                    Some((_, _, best_distance)) if distance == best_distance => ambiguous = true,
+                    _ => {
+                        best = Some((id, rotation, distance));
+                        ambiguous = false;
+                    }
+                }
+            }
+            rotated = self.rotate(rotated);
+        }
+        best.filter(|&(_, _, distance)| !ambiguous && distance <= self.max_correction)
+    }
+
+    /// Rotates the data cells of a marker by a quarter turn.
+    ///
+    /// The result is the code read when starting from the next corner of the marker, which is
+    /// the code of the marker rotated counter-clockwise.
+    pub fn rotate(&self, bits: u64) -> u64 {
+        let n = self.marker_bits;
+        let mut rotated = 0;
+        for row in 0..n {
+            for column in 0..n {
+                // The new row is read down the last column of the old marker.
+                rotated = rotated << 1 | self.bit(bits, column, n - 1 - row);
+            }
+        }
+        rotated
+    }
+
+    /// Returns the bit of the cell at `row` and `column`.
+    pub(crate) fn bit(&self, bits: u64, row: usize, column: usize) -> u64 {
+        let n = self.marker_bits;
+        bits >> (n * n - 1 - (row * n + column)) & 1
+    }
+
+    /// Renders the marker with the given ID, including its black border, with `cell_size` pixels per cell.
+    ///
+    /// Markers must be surrounded by a white margin to be detected, which is not part of the image.
+    pub fn render(&self, id: usize, cell_size: u32) -> Option<GrayImage> {
+        let code = *self.codes.get(id)?;
+        let cells = self.marker_bits + 2;
+        let size = cells as u32 * cell_size;
+        Some(GrayImage::from_fn(size, size, |x, y| {
+            let (column, row) = ((x / cell_size) as usize, (y / cell_size) as usize);
+            let border = row == 0 || column == 0 || row == cells - 1 || column == cells - 1;
+            if !border && self.bit(code, row - 1, column - 1) == 1 {
+                Luma([255])
+            } else {
+                Luma([0])
+            }
+        }))
+    }
+}
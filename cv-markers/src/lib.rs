@@ -0,0 +1,331 @@
+//! # `cv-markers`
+//!
+//! Detection and decoding of square fiducial markers, such as AprilTags and ArUco markers.
+//!
+//! Markers are detected by thresholding the image, finding the dark regions whose outline is a
+//! quadrilateral, refining the corners of the quadrilateral to sub-pixel accuracy with the image
+//! edges, and then sampling the cells of the marker through the homography of the quadrilateral.
+//! The sampled bits are decoded with a [`Dictionary`], which corrects bit errors.
+//!
+//! Once a marker is detected, [`Marker::pose`] finds the pose of the marker relative to the camera.
+
+mod dictionary;
+mod quad;
+
+pub use dictionary::Dictionary;
+
+use cv_core::nalgebra::{Matrix3, MatrixN, Point2, Point3, Unit, Vector3, VectorN, U8};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{Bearing, CameraModel, FeatureWorldMatch, KeyPoint, WorldPoint, WorldToCamera};
+use image::GrayImage;
+use imageproc::contrast::otsu_level;
+use lambda_twist::LambdaTwist;
+
+/// A marker detected in an image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marker {
+    /// The ID of the marker in the [`Dictionary`].
+    pub id: usize,
+    /// The outer corners of the black border of the marker in the image, with sub-pixel accuracy.
+    ///
+    /// The corners start at the top-left corner of the upright marker and go clockwise around it
+    /// (top-left, top-right, bottom-right, bottom-left).
+    pub corners: [KeyPoint; 4],
+    /// The number of bits which were corrected to decode the marker.
+    pub hamming: u32,
+}
+
+impl Marker {
+    /// The center of the marker in the image, which is where the diagonals of the marker intersect.
+    pub fn center(&self) -> KeyPoint {
+        let [a, b, c, d] = self.corners;
+        let (p, r) = (a.0, c.0 - a.0);
+        let (q, s) = (b.0, d.0 - b.0);
+        let t = (q - p).perp(&s) / r.perp(&s);
+        KeyPoint(p + r * t)
+    }
+
+    /// Computes the pose of the marker relative to the camera, given the length of the sides of the marker.
+    ///
+    /// The marker size is measured on the outside of the black border, in the units the translation
+    /// should be in. The marker coordinate system is centered on the marker, with the x axis pointing to
+    /// the right of the upright marker, the y axis pointing down, and the z axis pointing into the marker.
+    /// A camera looking straight at an upright marker has no rotation.
+    ///
+    /// The pose is computed with [`LambdaTwist`] from three corners, and the fourth corner selects the solution.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{Point2, Vector2, Vector3};
+    /// use cv_core::KeyPoint;
+    /// use cv_markers::Marker;
+    /// use cv_pinhole::CameraIntrinsics;
+    ///
+    /// let intrinsics = CameraIntrinsics::identity()
+    ///     .focals(Vector2::new(800.0, 800.0))
+    ///     .principal_point(Point2::new(320.0, 240.0));
+    /// // Project the corners of a 0.2 meter marker 2 meters in front of the camera.
+    /// let corners = [(-0.1, -0.1), (0.1, -0.1), (0.1, 0.1), (-0.1, 0.1)];
+    /// let project = |(x, y): (f64, f64)| KeyPoint(Point2::new(320.0 + 400.0 * (x + 0.3), 240.0 + 400.0 * y));
+    /// let marker = Marker {
+    ///     id: 0,
+    ///     corners: [project(corners[0]), project(corners[1]), project(corners[2]), project(corners[3])],
+    ///     hamming: 0,
+    /// };
+    ///
+    /// let pose = marker.pose(&intrinsics, 0.2).unwrap();
+    /// assert!((pose.0.translation.vector - Vector3::new(0.3, 0.0, 2.0)).norm() < 1e-6);
+    /// assert!(pose.0.rotation.angle() < 1e-6);
+    /// ```
+    pub fn pose<C>(&self, camera: &C, marker_size: f64) -> Option<WorldToCamera>
+    where
+        C: CameraModel,
+    {
+        let half = 0.5 * marker_size;
+        let object_points = [
+            Point3::new(-half, -half, 0.0),
+            Point3::new(half, -half, 0.0),
+            Point3::new(half, half, 0.0),
+            Point3::new(-half, half, 0.0),
+        ];
+        let matches: Vec<FeatureWorldMatch<Unit<Vector3<f64>>>> = self
+            .corners
+            .iter()
+            .zip(object_points.iter())
+            .map(|(&corner, point)| {
+                FeatureWorldMatch(
+                    camera.calibrate(corner).bearing(),
+                    WorldPoint(point.to_homogeneous()),
+                )
+            })
+            .collect();
+
+        // The fourth corner disambiguates the solutions found from the first three corners.
+        LambdaTwist::new()
+            .estimate(matches.iter().cloned())
+            .into_iter()
+            .map(|pose| {
+                let residual: f64 = matches.iter().map(|m| pose.residual(m)).sum();
+                (pose, residual)
+            })
+            // A degenerate solution can have a NaN residual, which is never the best.
+            .filter(|(_, residual)| residual.is_finite())
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(pose, _)| pose)
+    }
+}
+
+/// Detects the markers of a [`Dictionary`] in grayscale images.
+///
+/// Markers must be surrounded by a white margin at least one cell wide.
+///
+/// ```
+/// use cv_markers::{Dictionary, MarkerDetector};
+/// use image::{imageops, GrayImage, Luma};
+///
+/// let dictionary = Dictionary::aruco_original();
+/// let mut image = GrayImage::from_pixel(320, 240, Luma([230u8]));
+/// imageops::overlay(&mut image, &dictionary.render(42, 12).unwrap(), 40, 40);
+/// imageops::overlay(&mut image, &dictionary.render(713, 10).unwrap(), 200, 100);
+///
+/// let mut markers = MarkerDetector::new(dictionary).detect(&image);
+/// markers.sort_by_key(|marker| marker.id);
+/// assert_eq!(markers.iter().map(|marker| marker.id).collect::<Vec<_>>(), vec![42, 713]);
+/// // The top-left corner of the first marker is at the top-left corner of its first pixel.
+/// assert!((markers[0].corners[0].x - 40.0).abs() < 0.25);
+/// assert!((markers[0].corners[0].y - 40.0).abs() < 0.25);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkerDetector {
+    dictionary: Dictionary,
+    tile_size: u32,
+    min_contrast: u8,
+    min_side: f64,
+    min_fill: f64,
+    refinement_distance: f64,
+}
+
+impl MarkerDetector {
+    /// Creates a detector for the markers of the dictionary with the default parameters.
+    pub fn new(dictionary: Dictionary) -> Self {
+        Self {
+            dictionary,
+            tile_size: 8,
+            min_contrast: 20,
+            min_side: 10.0,
+            min_fill: 0.9,
+            refinement_distance: 2.0,
+        }
+    }
+
+    /// Sets the size in pixels of the tiles used to compute the local threshold of the image.
+    ///
+    /// The threshold of a pixel depends on the surrounding 3x3 tiles. Default is `8`.
+    pub fn tile_size(self, tile_size: u32) -> Self {
+        assert!(tile_size > 0, "the tile size must be positive");
+        Self { tile_size, ..self }
+    }
+
+    /// Sets the minimum difference between the white and black parts of a marker.
+    ///
+    /// Areas with less contrast than this are thresholded globally. Default is `20`.
+    pub fn min_contrast(self, min_contrast: u8) -> Self {
+        Self {
+            min_contrast,
+            ..self
+        }
+    }
+
+    /// Sets the minimum length in pixels of the sides of a marker. Default is `10.0`.
+    pub fn min_side(self, min_side: f64) -> Self {
+        Self { min_side, ..self }
+    }
+
+    /// Sets the minimum fraction of the area of a dark region that its quadrilateral must cover.
+    ///
+    /// Lower values allow more perspective distortion and blur, but produce more false candidates.
+    /// Default is `0.9`.
+    pub fn min_fill(self, min_fill: f64) -> Self {
+        Self { min_fill, ..self }
+    }
+
+    /// Sets how far in pixels from the initial quadrilateral the image edges are searched to refine the corners.
+    ///
+    /// Set to `0.0` to disable the sub-pixel refinement. Default is `2.0`.
+    pub fn refinement_distance(self, refinement_distance: f64) -> Self {
+        Self {
+            refinement_distance,
+            ..self
+        }
+    }
+
+    /// The dictionary of the markers to detect.
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+
+    /// Detects and decodes the markers in the image.
+    pub fn detect(&self, image: &GrayImage) -> Vec<Marker> {
+        let dark = quad::threshold(image, self.tile_size, self.min_contrast, otsu_level(image));
+        let mut markers: Vec<Marker> = quad::find_quads(&dark, self.min_side, self.min_fill)
+            .into_iter()
+            .filter_map(|quad| {
+                let quad = if self.refinement_distance > 0.0 {
+                    quad::refine_corners(image, quad, self.refinement_distance)
+                } else {
+                    quad
+                };
+                self.decode(image, quad)
+            })
+            .collect();
+
+        // Dark cells inside of a marker can look like a small marker, so those are removed.
+        let all = markers.clone();
+        markers.retain(|marker| {
+            let center = marker.center();
+            !all.iter()
+                .any(|other| other != marker && contains(&other.corners, center))
+        });
+        markers
+    }
+
+    /// Samples the cells of the marker in the quad and decodes them.
+    fn decode(&self, image: &GrayImage, quad: [Point2<f64>; 4]) -> Option<Marker> {
+        let n = self.dictionary.marker_bits();
+        let cells = (n + 2) as f64;
+        let to_image = homography(
+            [
+                Point2::new(0.0, 0.0),
+                Point2::new(cells, 0.0),
+                Point2::new(cells, cells),
+                Point2::new(0.0, cells),
+            ],
+            quad,
+        )?;
+        let sample = |x: f64, y: f64| {
+            let point = to_image * Vector3::new(x, y, 1.0);
+            quad::bilinear(image, Point2::new(point.x / point.z, point.y / point.z))
+        };
+        // Average the center of the cell, away from the blurry edges between cells.
+        let sample_cell = |row: f64, column: f64| -> Option<f64> {
+            let mut sum = 0.0;
+            for &dy in &[0.3, 0.5, 0.7] {
+                for &dx in &[0.3, 0.5, 0.7] {
+                    sum += sample(column + dx, row + dy)?;
+                }
+            }
+            Some(sum / 9.0)
+        };
+
+        // The black reference is the border of the marker and the white reference is the margin around it.
+        let mut black = 0.0;
+        let mut white = 0.0;
+        let mut border = vec![];
+        for k in 0..n + 2 {
+            let k = k as f64;
+            for &(row, column) in &[(0.0, k), (cells - 1.0, k), (k, 0.0), (k, cells - 1.0)] {
+                let value = sample_cell(row, column)?;
+                black += value;
+                border.push(value);
+            }
+            for &(row, column) in &[(-1.0, k), (cells, k), (k, -1.0), (k, cells)] {
+                white += sample_cell(row, column)?;
+            }
+        }
+        black /= border.len() as f64;
+        white /= border.len() as f64;
+        if white - black < self.min_contrast as f64 {
+            return None;
+        }
+        let threshold = 0.5 * (black + white);
+        if border.iter().any(|&value| value >= threshold) {
+            return None;
+        }
+
+        let mut bits = 0u64;
+        for row in 0..n {
+            for column in 0..n {
+                let value = sample_cell(row as f64 + 1.0, column as f64 + 1.0)?;
+                bits = bits << 1 | (value >= threshold) as u64;
+            }
+        }
+
+        let (id, rotation, hamming) = self.dictionary.decode(bits)?;
+        let mut corners = [KeyPoint(Point2::origin()); 4];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            *corner = KeyPoint(quad[(i + rotation) % 4]);
+        }
+        Some(Marker {
+            id,
+            corners,
+            hamming,
+        })
+    }
+}
+
+/// Checks if the point is inside the clockwise convex quadrilateral.
+fn contains(corners: &[KeyPoint; 4], point: KeyPoint) -> bool {
+    (0..4).all(|i| {
+        let (a, b) = (corners[i].0, corners[(i + 1) % 4].0);
+        (b - a).perp(&(point.0 - a)) > 0.0
+    })
+}
+
+/// Computes the homography which maps each of the `from` points to the `to` point with the same index.
+fn homography(from: [Point2<f64>; 4], to: [Point2<f64>; 4]) -> Option<Matrix3<f64>> {
+    // Each correspondence gives two rows of the linear system, with the last entry of the homography set to 1.
+    let mut a = MatrixN::<f64, U8>::zeros();
+    let mut b = VectorN::<f64, U8>::zeros();
+    for (i, (p, q)) in from.iter().zip(to.iter()).enumerate() {
+        let (x, y, u, v) = (p.x, p.y, q.x, q.y);
+        a.row_mut(2 * i)
+            .copy_from_slice(&[x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y]);
+        a.row_mut(2 * i + 1)
+            .copy_from_slice(&[0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y]);
+        b[2 * i] = u;
+        b[2 * i + 1] = v;
+    }
+    let h = a.lu().solve(&b)?;
+    Some(Matrix3::new(
+        h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7], 1.0,
+    ))
+}
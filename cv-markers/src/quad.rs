@@ -0,0 +1,320 @@
+//! Detection of the dark quadrilaterals which may be the borders of markers.
+
+use cv_core::nalgebra::{Matrix2, Point2, Vector2};
+use image::{GrayImage, Luma};
+use imageproc::region_labelling::{connected_components, Connectivity};
+
+/// Marks the pixels which are darker than their neighborhood.
+///
+/// The threshold of every tile of `tile_size` pixels is halfway between the darkest and brightest
+/// pixels of the surrounding 3x3 tiles. Tiles without enough contrast are uniform, so they are
+/// thresholded with the `global_threshold` instead.
+pub(crate) fn threshold(
+    image: &GrayImage,
+    tile_size: u32,
+    min_contrast: u8,
+    global_threshold: u8,
+) -> GrayImage {
+    let (width, height) = image.dimensions();
+    let tiles_x = width / tile_size + (width % tile_size != 0) as u32;
+    let tiles_y = height / tile_size + (height % tile_size != 0) as u32;
+
+    let mut extremes = vec![(u8::MAX, u8::MIN); (tiles_x * tiles_y) as usize];
+    for (x, y, &Luma([value])) in image.enumerate_pixels() {
+        let tile = &mut extremes[((y / tile_size) * tiles_x + x / tile_size) as usize];
+        tile.0 = tile.0.min(value);
+        tile.1 = tile.1.max(value);
+    }
+
+    let mut thresholds = vec![global_threshold; extremes.len()];
+    for ty in 0..tiles_y {
+        for tx in 0..tiles_x {
+            let (mut min, mut max) = (u8::MAX, u8::MIN);
+            for ny in ty.saturating_sub(1)..(ty + 2).min(tiles_y) {
+                for nx in tx.saturating_sub(1)..(tx + 2).min(tiles_x) {
+                    let (tile_min, tile_max) = extremes[(ny * tiles_x + nx) as usize];
+                    min = min.min(tile_min);
+                    max = max.max(tile_max);
+                }
+            }
+            if max - min >= min_contrast {
+                thresholds[(ty * tiles_x + tx) as usize] = ((min as u16 + max as u16) / 2) as u8;
+            }
+        }
+    }
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let threshold = thresholds[((y / tile_size) * tiles_x + x / tile_size) as usize];
+        if image.get_pixel(x, y)[0] < threshold {
+            Luma([255])
+        } else {
+            Luma([0])
+        }
+    })
+}
+
+/// Finds the quadrilaterals formed by the convex hulls of the dark regions of the thresholded image.
+///
+/// The corners are returned in clockwise order in the image (with the y axis pointing down).
+pub(crate) fn find_quads(dark: &GrayImage, min_side: f64, min_fill: f64) -> Vec<[Point2<f64>; 4]> {
+    let (width, height) = dark.dimensions();
+    let labels = connected_components(dark, Connectivity::Eight, Luma([0u8]));
+
+    // The leftmost and rightmost pixel of every row of a region are enough to find its convex hull.
+    let mut regions: Vec<Region> = vec![];
+    for (x, y, &Luma([label])) in labels.enumerate_pixels() {
+        if label == 0 {
+            continue;
+        }
+        let label = label as usize;
+        if regions.len() < label {
+            regions.resize_with(label, Region::default);
+        }
+        let region = &mut regions[label - 1];
+        region.touches_border |= x == 0 || y == 0 || x == width - 1 || y == height - 1;
+        match region.rows.last_mut() {
+            Some(row) if row.0 == y => row.2 = x,
+            _ => region.rows.push((y, x, x)),
+        }
+    }
+
+    regions
+        .iter()
+        .filter(|region| !region.touches_border && region.rows.len() as f64 >= min_side)
+        .filter_map(|region| {
+            let points: Vec<Point2<f64>> = region
+                .rows
+                .iter()
+                .flat_map(|&(y, left, right)| {
+                    // Use the outer boundary of the pixels rather than their centers.
+                    let (top, bottom) = (y as f64, y as f64 + 1.0);
+                    let (left, right) = (left as f64, right as f64 + 1.0);
+                    vec![
+                        Point2::new(left, top),
+                        Point2::new(left, bottom),
+                        Point2::new(right, top),
+                        Point2::new(right, bottom),
+                    ]
+                })
+                .collect();
+            fit_quad(&convex_hull(points), min_side, min_fill)
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Region {
+    /// The row, leftmost, and rightmost pixel of every row of the region.
+    rows: Vec<(u32, u32, u32)>,
+    touches_border: bool,
+}
+
+fn cross(o: Point2<f64>, a: Point2<f64>, b: Point2<f64>) -> f64 {
+    (a - o).perp(&(b - o))
+}
+
+/// Computes the convex hull with Andrew's monotone chain, in clockwise order in the image.
+fn convex_hull(mut points: Vec<Point2<f64>>) -> Vec<Point2<f64>> {
+    points.retain(|p| p.x.is_finite() && p.y.is_finite());
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let mut hull: Vec<Point2<f64>> = Vec::with_capacity(2 * points.len());
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &Point2<f64>>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for &point in ordered {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point is the first point of the other half.
+        hull.pop();
+    }
+    hull
+}
+
+fn polygon_area(polygon: &[Point2<f64>]) -> f64 {
+    let n = polygon.len();
+    0.5 * (0..n)
+        .map(|i| polygon[i].coords.perp(&polygon[(i + 1) % n].coords))
+        .sum::<f64>()
+}
+
+/// Finds the quadrilateral with the largest area inscribed in the convex hull.
+///
+/// Returns `None` if the quadrilateral does not cover `min_fill` of the hull area, which means the
+/// region is not a quadrilateral, or if a side is shorter than `min_side`.
+fn fit_quad(hull: &[Point2<f64>], min_side: f64, min_fill: f64) -> Option<[Point2<f64>; 4]> {
+    let n = hull.len();
+    if n < 4 {
+        return None;
+    }
+    let farthest = |from: Point2<f64>| {
+        (0..n).max_by(|&a, &b| {
+            (hull[a] - from)
+                .norm_squared()
+                .total_cmp(&(hull[b] - from).norm_squared())
+        })
+    };
+    // Two opposite corners are the most distant points of the hull.
+    let centroid = Point2::from(hull.iter().map(|p| p.coords).sum::<Vector2<f64>>() / n as f64);
+    let a = farthest(centroid)?;
+    let c = farthest(hull[a])?;
+    // The other two corners are the farthest from the diagonal on each side of it.
+    let (first, second) = if a < c { (a, c) } else { (c, a) };
+    let farthest_from_diagonal = |range: &mut dyn Iterator<Item = usize>| {
+        range.max_by(|&i, &j| {
+            cross(hull[first], hull[second], hull[i])
+                .abs()
+                .total_cmp(&cross(hull[first], hull[second], hull[j]).abs())
+        })
+    };
+    let b = farthest_from_diagonal(&mut (first + 1..second))?;
+    let d = farthest_from_diagonal(&mut (second + 1..n).chain(0..first))?;
+    let quad = [hull[first], hull[b], hull[second], hull[d]];
+
+    let quad_area = polygon_area(&quad);
+    if quad_area < min_fill * polygon_area(hull) {
+        return None;
+    }
+    if (0..4).any(|i| (quad[(i + 1) % 4] - quad[i]).norm() < min_side) {
+        return None;
+    }
+    Some(quad)
+}
+
+/// Refines the corners of a quad by fitting lines to the edges of the image along its sides.
+///
+/// The position of the strongest gradient is searched up to `search_distance` pixels from each side.
+/// Corners which cannot be refined are left unchanged.
+pub(crate) fn refine_corners(
+    image: &GrayImage,
+    quad: [Point2<f64>; 4],
+    search_distance: f64,
+) -> [Point2<f64>; 4] {
+    let lines: Vec<Option<(Point2<f64>, Vector2<f64>)>> = (0..4)
+        .map(|i| fit_edge(image, quad[i], quad[(i + 1) % 4], search_distance))
+        .collect();
+    let mut refined = quad;
+    for i in 0..4 {
+        // Corner `i` is where the side ending at it meets the side starting at it.
+        if let (Some(previous), Some(next)) = (lines[(i + 3) % 4], lines[i]) {
+            if let Some(corner) = intersect(previous, next) {
+                if (corner - quad[i]).norm() <= 2.0 * search_distance {
+                    refined[i] = corner;
+                }
+            }
+        }
+    }
+    refined
+}
+
+/// Fits a line to the edge between the dark inside and bright outside of the quad along one side.
+fn fit_edge(
+    image: &GrayImage,
+    start: Point2<f64>,
+    end: Point2<f64>,
+    search_distance: f64,
+) -> Option<(Point2<f64>, Vector2<f64>)> {
+    let side = end - start;
+    let length = side.norm();
+    let direction = side / length;
+    // The quad is clockwise in the image, so the outside is on the left of the side.
+    let outward = Vector2::new(direction.y, -direction.x);
+    let step = 0.25;
+    let steps = (search_distance / step).ceil() as i32;
+    let samples = (length / 2.0).max(4.0) as usize;
+
+    let mut edge_points = vec![];
+    for sample in 0..samples {
+        // Avoid the corners, where the neighboring sides blur the edge.
+        let t = 0.1 + 0.8 * (sample as f64 + 0.5) / samples as f64;
+        let base = start + side * t;
+        let profile: Option<Vec<f64>> = (-steps..=steps)
+            .map(|s| bilinear(image, base + outward * (s as f64 * step)))
+            .collect();
+        let profile = profile?;
+        // The intensity increases going outward, so the edge is around the largest positive difference.
+        let differences: Vec<f64> = profile.windows(2).map(|w| w[1] - w[0]).collect();
+        let peak =
+            (0..differences.len()).max_by(|&a, &b| differences[a].total_cmp(&differences[b]))?;
+        if differences[peak] <= 0.0 {
+            continue;
+        }
+        // The edge is at the centroid of the increasing part of the profile around the peak.
+        let mut first = peak;
+        while first > 0 && differences[first - 1] > 0.0 {
+            first -= 1;
+        }
+        let mut last = peak;
+        while last + 1 < differences.len() && differences[last + 1] > 0.0 {
+            last += 1;
+        }
+        let rise: f64 = differences[first..=last].iter().sum();
+        let centroid = (first..=last)
+            .map(|k| differences[k] * (k as f64 + 0.5))
+            .sum::<f64>()
+            / rise;
+        let distance = (centroid - steps as f64) * step;
+        edge_points.push(base + outward * distance);
+    }
+    if edge_points.len() < 3 {
+        return None;
+    }
+
+    // The line direction is the principal axis of the edge points.
+    let mean = Point2::from(
+        edge_points.iter().map(|p| p.coords).sum::<Vector2<f64>>() / edge_points.len() as f64,
+    );
+    let covariance = edge_points
+        .iter()
+        .map(|p| (p - mean) * (p - mean).transpose())
+        .sum::<Matrix2<f64>>();
+    let eigen = covariance.symmetric_eigen();
+    let axis = eigen.eigenvalues.imax();
+    Some((mean, eigen.eigenvectors.column(axis).into_owned()))
+}
+
+fn intersect(
+    (p, u): (Point2<f64>, Vector2<f64>),
+    (q, v): (Point2<f64>, Vector2<f64>),
+) -> Option<Point2<f64>> {
+    let denominator = u.perp(&v);
+    if denominator.abs() < 1e-9 {
+        return None;
+    }
+    Some(p + u * ((q - p).perp(&v) / denominator))
+}
+
+/// Samples the image with bilinear interpolation, where pixel centers are at half coordinates.
+pub(crate) fn bilinear(image: &GrayImage, point: Point2<f64>) -> Option<f64> {
+    let (x, y) = (point.x - 0.5, point.y - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    // A degenerate homography can map a point to NaN or infinity.
+    if !x0.is_finite()
+        || !y0.is_finite()
+        || x0 < 0.0
+        || y0 < 0.0
+        || x0 + 1.0 >= image.width() as f64
+        || y0 + 1.0 >= image.height() as f64
+    {
+        return None;
+    }
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as u32, y0 as u32);
+    let pixel = |x, y| image.get_pixel(x, y)[0] as f64;
+    Some(
+        (1.0 - fy) * ((1.0 - fx) * pixel(x0, y0) + fx * pixel(x0 + 1, y0))
+            + fy * ((1.0 - fx) * pixel(x0, y0 + 1) + fx * pixel(x0 + 1, y0 + 1)),
+    )
+}
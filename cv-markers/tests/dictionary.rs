@@ -0,0 +1,73 @@
+use cv_markers::Dictionary;
+
+/// The first two markers of `DICT_4X4_1000_BYTES` in OpenCV, with the bytes of all four rotations.
+const DICT_4X4_BYTES: [[[u8; 2]; 4]; 2] = [
+    [[181, 50], [235, 72], [76, 173], [18, 215]],
+    [[15, 154], [101, 71], [89, 240], [226, 166]],
+];
+
+/// Packs the cells of a code into bytes the way OpenCV does.
+fn opencv_bytes(code: u64, marker_bits: usize) -> Vec<u8> {
+    let cells = marker_bits * marker_bits;
+    // `usize::div_ceil` needs Rust 1.73.
+    #[allow(clippy::manual_div_ceil)]
+    let length = (cells + 7) / 8;
+    (0..length)
+        .map(|ix| {
+            let bits = (cells - 8 * ix).min(8);
+            (code >> (cells - 8 * ix - bits) & ((1 << bits) - 1)) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn aruco_original_decodes_every_rotation() {
+    let dictionary = Dictionary::aruco_original();
+    for (id, &code) in dictionary.codes().iter().enumerate() {
+        let mut rotations = [code; 4];
+        for rotation in 1..4 {
+            rotations[rotation] = dictionary.rotate(rotations[rotation - 1]);
+        }
+        for (rotation, &rotated) in rotations.iter().enumerate() {
+            if rotations[1..].contains(&code) {
+                // The last marker looks the same after a half turn, so its rotation is ambiguous.
+                assert_eq!(id, 1023);
+                assert_eq!(dictionary.decode(rotated), None);
+            } else {
+                // Reading the rotated marker needs the remaining quarter turns to bring it upright.
+                assert_eq!(
+                    dictionary.decode(rotated),
+                    Some((id, (4 - rotation) % 4, 0))
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn aruco_4x4_decodes_every_stored_rotation() {
+    let dictionary = Dictionary::from_aruco_bytes(4, DICT_4X4_BYTES.iter().map(|m| m[0]), 4);
+    for (id, rotations) in DICT_4X4_BYTES.iter().enumerate() {
+        for (rotation, bytes) in rotations.iter().enumerate() {
+            let bits = u64::from(bytes[0]) << 8 | u64::from(bytes[1]);
+            // Each rotation stored by OpenCV is a quarter turn the opposite way to `Dictionary::rotate`.
+            assert_eq!(dictionary.decode(bits), Some((id, (4 - rotation) % 4, 0)));
+        }
+    }
+}
+
+#[test]
+fn aruco_5x5_bytes_keep_the_last_cell() {
+    // A 5x5 marker has 25 cells, so the last byte only holds the last cell.
+    let codes = [0x145_b2bc, 0x1ff_ffff, 0x000_0001, 0x0e2_bb7a];
+    let dictionary = Dictionary::from_aruco_bytes(5, codes.iter().map(|&c| opencv_bytes(c, 5)), 1);
+    assert_eq!(dictionary.codes(), &codes);
+    assert_eq!(opencv_bytes(0x145_b2bc, 5), [162, 217, 94, 0]);
+    assert_eq!(opencv_bytes(0x000_0001, 5), [0, 0, 0, 1]);
+}
+
+#[test]
+#[should_panic]
+fn aruco_bytes_must_match_the_marker_size() {
+    Dictionary::from_aruco_bytes(5, [[0u8, 0]], 1);
+}
@@ -0,0 +1,115 @@
+use cv_core::nalgebra::{
+    IsometryMatrix3, Point2, Rotation3, Translation3, Vector2, Vector3, Vector4,
+};
+use cv_core::{CameraModel, CameraPoint, KeyPoint, Pose, WorldPoint, WorldToCamera};
+use cv_markers::{Dictionary, MarkerDetector};
+use cv_pinhole::{CameraIntrinsics, NormalizedKeyPoint};
+use image::{imageops, GrayImage, Luma};
+use imageproc::geometric_transformations::{warp_into, Interpolation, Projection};
+
+const MARKER_SIZE: f64 = 0.2;
+const CELL_SIZE: u32 = 20;
+
+fn intrinsics() -> CameraIntrinsics {
+    CameraIntrinsics::identity()
+        .focals(Vector2::new(500.0, 500.0))
+        .principal_point(Point2::new(320.0, 240.0))
+}
+
+/// Projects a point on the marker plane, in marker coordinates, into the image.
+fn project(pose: WorldToCamera, x: f64, y: f64) -> KeyPoint {
+    let CameraPoint(point) = pose.transform(WorldPoint(Vector4::new(x, y, 0.0, 1.0)));
+    intrinsics().uncalibrate(NormalizedKeyPoint(Point2::new(
+        point.x / point.z,
+        point.y / point.z,
+    )))
+}
+
+/// Renders the marker with a white margin as seen by a camera at the given pose.
+fn render(dictionary: &Dictionary, id: usize, pose: WorldToCamera) -> (GrayImage, [KeyPoint; 4]) {
+    let marker = dictionary.render(id, CELL_SIZE).unwrap();
+    let margin = CELL_SIZE;
+    let mut canvas = GrayImage::from_pixel(
+        marker.width() + 2 * margin,
+        marker.height() + 2 * margin,
+        Luma([220]),
+    );
+    imageops::overlay(&mut canvas, &marker, margin, margin);
+
+    // Map the canvas onto the marker plane, where the marker spans the marker size.
+    let scale = MARKER_SIZE / marker.width() as f64;
+    let half = 0.5 * canvas.width() as f64;
+    let canvas_corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+    let mut from = [(0.0, 0.0); 4];
+    let mut to = [(0.0, 0.0); 4];
+    for (i, &(u, v)) in canvas_corners.iter().enumerate() {
+        let (u, v) = (u * canvas.width() as f64, v * canvas.height() as f64);
+        let KeyPoint(projected) = project(pose, (u - half) * scale, (v - half) * scale);
+        // The pixel centers are at integer coordinates for the warp.
+        from[i] = (u as f32 - 0.5, v as f32 - 0.5);
+        to[i] = (projected.x as f32 - 0.5, projected.y as f32 - 0.5);
+    }
+    let projection = Projection::from_control_points(from, to).unwrap();
+    let mut image = GrayImage::from_pixel(640, 480, Luma([180]));
+    warp_into(
+        &canvas,
+        &projection,
+        Interpolation::Bilinear,
+        Luma([180]),
+        &mut image,
+    );
+
+    let h = 0.5 * MARKER_SIZE;
+    let corners = [
+        project(pose, -h, -h),
+        project(pose, h, -h),
+        project(pose, h, h),
+        project(pose, -h, h),
+    ];
+    (image, corners)
+}
+
+fn check_pose(rotation: Vector3<f64>, translation: Vector3<f64>) {
+    let dictionary = Dictionary::aruco_original();
+    let pose = WorldToCamera(IsometryMatrix3::from_parts(
+        Translation3::from(translation),
+        Rotation3::new(rotation),
+    ));
+    let (image, expected_corners) = render(&dictionary, 137, pose);
+
+    let markers = MarkerDetector::new(dictionary).detect(&image);
+    assert_eq!(markers.len(), 1);
+    let marker = markers[0];
+    assert_eq!(marker.id, 137);
+    assert_eq!(marker.hamming, 0);
+    for (corner, expected) in marker.corners.iter().zip(expected_corners.iter()) {
+        let error = (corner.0 - expected.0).norm();
+        assert!(error < 0.15, "corner error of {} pixels", error);
+    }
+
+    let estimated = marker.pose(&intrinsics(), MARKER_SIZE).unwrap();
+    let translation_error = (estimated.0.translation.vector - translation).norm();
+    let rotation_error = estimated.0.rotation.rotation_to(&pose.0.rotation).angle();
+    assert!(
+        translation_error < 0.01 * translation.norm(),
+        "translation error of {}",
+        translation_error
+    );
+    assert!(
+        rotation_error < 1f64.to_radians(),
+        "rotation error of {} degrees",
+        rotation_error.to_degrees()
+    );
+}
+
+#[test]
+fn marker_facing_camera() {
+    check_pose(Vector3::zeros(), Vector3::new(0.05, -0.03, 0.6));
+}
+
+#[test]
+fn marker_tilted_and_rotated() {
+    check_pose(Vector3::new(0.5, 0.0, 0.0), Vector3::new(-0.1, 0.05, 0.7));
+    check_pose(Vector3::new(0.0, -0.6, 2.0), Vector3::new(0.0, 0.0, 0.8));
+    check_pose(Vector3::new(0.3, 0.4, -2.5), Vector3::new(0.1, 0.1, 0.9));
+}
@@ -0,0 +1,288 @@
+use cv_core::nalgebra::{
+    Matrix2, Matrix2x3, Matrix2x4, MatrixMN, Point2, RowVector3, Unit, Vector3, VectorN, U2, U3,
+    U5, U7,
+};
+use cv_core::{CameraModel, CameraModelJacobians, CameraPoint, ImagePoint, KeyPoint, Projective};
+use cv_pinhole::{CameraIntrinsics, NormalizedKeyPoint};
+use num_traits::Float;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The double sphere camera model as described by Usenko, Demmel, and Cremers in
+/// "The Double Sphere Camera Model".
+///
+/// A point is projected onto two unit spheres offset by `xi` from each other and then
+/// projected through a pinhole camera with the `simple_intrinsics` that is shifted by
+/// `alpha / (1 - alpha)` from the second sphere. This is the same as the "ds" camera model in Kalibr.
+///
+/// When `alpha` is `0.0` this is identical to the [`UnifiedIntrinsics`](crate::UnifiedIntrinsics).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct DoubleSphereIntrinsics {
+    pub simple_intrinsics: CameraIntrinsics,
+    pub xi: f64,
+    pub alpha: f64,
+}
+
+impl DoubleSphereIntrinsics {
+    /// Creates the camera intrinsics using the pinhole intrinsics, the sphere offset `xi`, and `alpha`.
+    pub fn new(simple_intrinsics: CameraIntrinsics, xi: f64, alpha: f64) -> Self {
+        Self {
+            simple_intrinsics,
+            xi,
+            alpha,
+        }
+    }
+
+    /// Checks if the point (in camera space) lies within the region that this camera can project.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::Vector3;
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::DoubleSphereIntrinsics;
+    /// let intrinsics = DoubleSphereIntrinsics::new(CameraIntrinsics::identity(), -0.2, 0.6);
+    /// assert!(intrinsics.is_point_valid(Vector3::new(1.0, 0.0, -0.2)));
+    /// assert!(!intrinsics.is_point_valid(Vector3::new(0.0, 0.0, -1.0)));
+    /// ```
+    pub fn is_point_valid(&self, point: Vector3<f64>) -> bool {
+        let alpha = self.alpha;
+        let xi = self.xi;
+        let w1 = if alpha <= 0.5 {
+            alpha / (1.0 - alpha)
+        } else {
+            (1.0 - alpha) / alpha
+        };
+        let w2 = (w1 + xi) / Float::sqrt(2.0 * w1 * xi + xi * xi + 1.0);
+        point.z > -w2 * point.norm()
+    }
+
+    /// Checks if the image point lies within the region that this camera can calibrate into a bearing.
+    ///
+    /// When `alpha > 0.5`, there is a circle in the image outside of which no bearing projects.
+    pub fn is_image_point_valid<P>(&self, point: P) -> bool
+    where
+        P: ImagePoint,
+    {
+        let NormalizedKeyPoint(m) = self.simple_intrinsics.calibrate(point);
+        self.alpha <= 0.5 || m.coords.norm_squared() <= 1.0 / (2.0 * self.alpha - 1.0)
+    }
+
+    /// Projects a point in camera space into the image and also computes the Jacobian of
+    /// the pixel coordinates with respect to the point.
+    ///
+    /// Returns `None` if the point is not valid for projection (see [`DoubleSphereIntrinsics::is_point_valid`]).
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{Point2, Vector2, Vector3};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::DoubleSphereIntrinsics;
+    /// let intrinsics = DoubleSphereIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(300.0, 310.0))
+    ///         .principal_point(Point2::new(320.0, 240.0))
+    ///         .skew(0.5),
+    ///     -0.2,
+    ///     0.6,
+    /// );
+    /// let point = Vector3::new(0.3, -0.7, 0.4);
+    /// let (kp, jacobian) = intrinsics.project_jacobian(point).unwrap();
+    /// // Compare against a central finite difference.
+    /// let eps = 1e-6;
+    /// for i in 0..3 {
+    ///     let mut delta = Vector3::zeros();
+    ///     delta[i] = eps;
+    ///     let (a, _) = intrinsics.project_jacobian(point + delta).unwrap();
+    ///     let (b, _) = intrinsics.project_jacobian(point - delta).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian.column(i)).norm() < 1e-4);
+    /// }
+    /// ```
+    pub fn project_jacobian(&self, point: Vector3<f64>) -> Option<(KeyPoint, Matrix2x3<f64>)> {
+        let (m, jacobian_point, _) = self.normalized_jacobians(point)?;
+        let (kp, jacobian_projection, _) = self.simple_intrinsics.normalized_jacobians(m);
+        Some((kp, jacobian_projection * jacobian_point))
+    }
+
+    /// Projects the point onto the normalized image plane of the [`DoubleSphereIntrinsics::simple_intrinsics`],
+    /// while also retrieving the Jacobians in respect to the point and to `[xi, alpha]`.
+    fn normalized_jacobians(
+        &self,
+        point: Vector3<f64>,
+    ) -> Option<(NormalizedKeyPoint, Matrix2x3<f64>, Matrix2<f64>)> {
+        if !self.is_point_valid(point) {
+            return None;
+        }
+        let Self { xi, alpha, .. } = *self;
+        let d1 = point.norm();
+        let z2 = xi * d1 + point.z;
+        let d2 = Float::sqrt(point.x * point.x + point.y * point.y + z2 * z2);
+        let den = alpha * d2 + (1.0 - alpha) * z2;
+        let m = point.xy() / den;
+        // Derivatives of the intermediate values with respect to the point.
+        let dz2 = RowVector3::new(0.0, 0.0, 1.0) + (xi / d1) * point.transpose();
+        let dd2 = (RowVector3::new(point.x, point.y, 0.0) + z2 * dz2) / d2;
+        let dden = alpha * dd2 + (1.0 - alpha) * dz2;
+        #[rustfmt::skip]
+        let numerator = Matrix2x3::new(
+            den, 0.0, 0.0,
+            0.0, den, 0.0,
+        ) - point.xy() * dden;
+        let jacobian_point = numerator / (den * den);
+        // Derivatives of the denominator with respect to `xi` and `alpha`.
+        let dden_xi = alpha * z2 * d1 / d2 + (1.0 - alpha) * d1;
+        let dden_alpha = d2 - z2;
+        let mut jacobian_parameters = Matrix2::zeros();
+        jacobian_parameters
+            .column_mut(0)
+            .copy_from(&(-m * dden_xi / den));
+        jacobian_parameters
+            .column_mut(1)
+            .copy_from(&(-m * dden_alpha / den));
+        Some((
+            NormalizedKeyPoint(Point2::from(m)),
+            jacobian_point,
+            jacobian_parameters,
+        ))
+    }
+}
+
+impl CameraModel for DoubleSphereIntrinsics {
+    type Projection = Unit<Vector3<f64>>;
+
+    /// Takes in a point from an image in pixel coordinates and
+    /// converts it to a unit bearing.
+    ///
+    /// ```
+    /// use cv_core::{KeyPoint, CameraModel};
+    /// use cv_core::nalgebra::{Point2, Vector2};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::DoubleSphereIntrinsics;
+    /// let intrinsics = DoubleSphereIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(300.0, 310.0))
+    ///         .principal_point(Point2::new(320.0, 240.0)),
+    ///     -0.2,
+    ///     0.6,
+    /// );
+    /// let kp = KeyPoint(Point2::new(20.0, 460.0));
+    /// let bearing = intrinsics.calibrate(kp);
+    /// let ukp = intrinsics.uncalibrate(bearing);
+    /// assert!((kp.0 - ukp.0).norm() < 1e-6);
+    /// ```
+    fn calibrate<P>(&self, point: P) -> Unit<Vector3<f64>>
+    where
+        P: ImagePoint,
+    {
+        let NormalizedKeyPoint(m) = self.simple_intrinsics.calibrate(point);
+        let Self { xi, alpha, .. } = *self;
+        let r2 = m.coords.norm_squared();
+        let mz = (1.0 - alpha * alpha * r2)
+            / (alpha * Float::sqrt(1.0 - (2.0 * alpha - 1.0) * r2) + 1.0 - alpha);
+        let factor = (mz * xi + Float::sqrt(mz * mz + (1.0 - xi * xi) * r2)) / (mz * mz + r2);
+        Unit::new_normalize(Vector3::new(factor * m.x, factor * m.y, factor * mz - xi))
+    }
+
+    /// Converts a unit bearing back into pixel coordinates.
+    ///
+    /// ```
+    /// use cv_core::CameraModel;
+    /// use cv_core::nalgebra::{Point2, Unit, Vector2, Vector3};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::DoubleSphereIntrinsics;
+    /// let intrinsics = DoubleSphereIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(300.0, 310.0))
+    ///         .principal_point(Point2::new(320.0, 240.0)),
+    ///     -0.2,
+    ///     0.6,
+    /// );
+    /// // This bearing points slightly behind the camera.
+    /// let bearing = Unit::new_normalize(Vector3::new(0.9, 0.2, -0.1));
+    /// let kp = intrinsics.uncalibrate(bearing);
+    /// assert!((intrinsics.calibrate(kp).into_inner() - bearing.into_inner()).norm() < 1e-6);
+    /// ```
+    fn uncalibrate(&self, projection: Unit<Vector3<f64>>) -> KeyPoint {
+        let Self { xi, alpha, .. } = *self;
+        let z2 = xi + projection.z;
+        let d2 = Float::sqrt(projection.x * projection.x + projection.y * projection.y + z2 * z2);
+        let den = alpha * d2 + (1.0 - alpha) * z2;
+        self.simple_intrinsics
+            .uncalibrate(NormalizedKeyPoint(Point2::from(projection.xy() / den)))
+    }
+}
+
+impl CameraModelJacobians for DoubleSphereIntrinsics {
+    /// The parameters are `[fx, fy, cx, cy, skew, xi, alpha]`.
+    type Parameters = U7;
+
+    fn parameters(&self) -> VectorN<f64, U7> {
+        let simple = self.simple_intrinsics.parameters();
+        VectorN::<f64, U7>::from_iterator(
+            simple
+                .iter()
+                .copied()
+                .chain([self.xi, self.alpha].iter().copied()),
+        )
+    }
+
+    fn from_parameters(parameters: &VectorN<f64, U7>) -> Self {
+        Self::new(
+            CameraIntrinsics::from_parameters(&parameters.fixed_rows::<U5>(0).into_owned()),
+            parameters[5],
+            parameters[6],
+        )
+    }
+
+    /// Projects a [`CameraPoint`] into pixel coordinates and computes the Jacobians.
+    ///
+    /// ```
+    /// use cv_core::{CameraModelJacobians, CameraPoint, Projective};
+    /// use cv_core::nalgebra::{Point2, Point3, Vector2, VectorN, U7};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::DoubleSphereIntrinsics;
+    /// let intrinsics = DoubleSphereIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(300.0, 310.0))
+    ///         .principal_point(Point2::new(320.0, 240.0))
+    ///         .skew(0.5),
+    ///     -0.2,
+    ///     0.6,
+    /// );
+    /// let point = CameraPoint::from_point(Point3::new(0.3, -0.7, 0.4));
+    /// let (_, _, jacobian_intrinsics) = intrinsics.uncalibrate_jacobians(point).unwrap();
+    /// // Compare against a central finite difference.
+    /// let eps = 1e-6;
+    /// for i in 0..7 {
+    ///     let mut delta = VectorN::<f64, U7>::zeros();
+    ///     delta[i] = eps;
+    ///     let a = DoubleSphereIntrinsics::from_parameters(&(intrinsics.parameters() + delta));
+    ///     let b = DoubleSphereIntrinsics::from_parameters(&(intrinsics.parameters() - delta));
+    ///     let (a, _, _) = a.uncalibrate_jacobians(point).unwrap();
+    ///     let (b, _, _) = b.uncalibrate_jacobians(point).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian_intrinsics.column(i)).norm() < 1e-3);
+    /// }
+    /// ```
+    fn uncalibrate_jacobians(
+        &self,
+        point: CameraPoint,
+    ) -> Option<(KeyPoint, Matrix2x4<f64>, MatrixMN<f64, U2, U7>)> {
+        let (m, jacobian_normalized, jacobian_parameters) =
+            self.normalized_jacobians(point.bearing_unnormalized())?;
+        let (kp, jacobian_projection, jacobian_simple) =
+            self.simple_intrinsics.normalized_jacobians(m);
+        let mut jacobian_point = Matrix2x4::zeros();
+        jacobian_point
+            .fixed_columns_mut::<U3>(0)
+            .copy_from(&(jacobian_projection * jacobian_normalized));
+        let mut jacobian_intrinsics = MatrixMN::<f64, U2, U7>::zeros();
+        jacobian_intrinsics
+            .fixed_columns_mut::<U5>(0)
+            .copy_from(&jacobian_simple);
+        jacobian_intrinsics
+            .fixed_columns_mut::<U2>(5)
+            .copy_from(&(jacobian_projection * jacobian_parameters));
+        Some((kp, jacobian_point, jacobian_intrinsics))
+    }
+}
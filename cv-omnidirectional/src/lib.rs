@@ -0,0 +1,19 @@
+//! This crate seamlessly plugs into `cv-core` and provides camera models for wide field-of-view lenses,
+//! such as fisheye and catadioptric cameras. These models can represent light arriving from more than 90 degrees
+//! away from the optical axis, so they calibrate image coordinates directly into unit bearing vectors
+//! (`Unit<Vector3<f64>>`) rather than into normalized keypoints on a virtual image plane.
+//!
+//! The following models are provided:
+//!
+//! * [`UnifiedIntrinsics`] - the unified camera model (Mei), called "omni" in Kalibr
+//! * [`DoubleSphereIntrinsics`] - the double sphere camera model (Usenko et al.), called "ds" in Kalibr
+//!
+//! Both models implement [`cv_core::CameraModelJacobians`], which is necessary to use them in optimization.
+
+#![no_std]
+
+mod double_sphere;
+mod unified;
+
+pub use double_sphere::*;
+pub use unified::*;
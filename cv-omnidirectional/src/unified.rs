@@ -0,0 +1,255 @@
+use cv_core::nalgebra::{
+    Matrix2x3, Matrix2x4, Matrix2x6, Point2, RowVector3, Unit, Vector2, Vector3, Vector6, U3, U5,
+    U6,
+};
+use cv_core::{CameraModel, CameraModelJacobians, CameraPoint, ImagePoint, KeyPoint, Projective};
+use cv_pinhole::{CameraIntrinsics, NormalizedKeyPoint};
+use num_traits::Float;
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The unified camera model (UCM) as described by Mei and Rives in
+/// "Single View Point Omnidirectional Camera Calibration from Planar Grids".
+///
+/// A point is first projected onto the unit sphere, then the sphere is shifted by `xi` along the optical axis
+/// and the point is projected through a pinhole camera with the `simple_intrinsics`. This is the same as the
+/// "omni" camera model in Kalibr (without distortion).
+///
+/// When `xi` is `0.0` this is identical to a pinhole camera.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct UnifiedIntrinsics {
+    pub simple_intrinsics: CameraIntrinsics,
+    pub xi: f64,
+}
+
+impl UnifiedIntrinsics {
+    /// Creates the camera intrinsics using the pinhole intrinsics and the sphere offset `xi`.
+    pub fn new(simple_intrinsics: CameraIntrinsics, xi: f64) -> Self {
+        Self {
+            simple_intrinsics,
+            xi,
+        }
+    }
+
+    /// Checks if the point (in camera space) lies within the region that this camera can project.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::Vector3;
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::UnifiedIntrinsics;
+    /// let intrinsics = UnifiedIntrinsics::new(CameraIntrinsics::identity(), 0.9);
+    /// // Points somewhat behind the camera can still be seen.
+    /// assert!(intrinsics.is_point_valid(Vector3::new(1.0, 0.0, -0.2)));
+    /// // Points directly behind the camera cannot.
+    /// assert!(!intrinsics.is_point_valid(Vector3::new(0.0, 0.0, -1.0)));
+    /// ```
+    pub fn is_point_valid(&self, point: Vector3<f64>) -> bool {
+        let w = if self.xi <= 1.0 {
+            self.xi
+        } else {
+            1.0 / self.xi
+        };
+        point.z > -w * point.norm()
+    }
+
+    /// Checks if the image point lies within the region that this camera can calibrate into a bearing.
+    ///
+    /// When `xi > 1.0`, there is a circle in the image outside of which no bearing projects.
+    pub fn is_image_point_valid<P>(&self, point: P) -> bool
+    where
+        P: ImagePoint,
+    {
+        let NormalizedKeyPoint(m) = self.simple_intrinsics.calibrate(point);
+        self.xi <= 1.0 || m.coords.norm_squared() <= 1.0 / (self.xi * self.xi - 1.0)
+    }
+
+    /// Projects a point in camera space into the image and also computes the Jacobian of
+    /// the pixel coordinates with respect to the point.
+    ///
+    /// Returns `None` if the point is not valid for projection (see [`UnifiedIntrinsics::is_point_valid`]).
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{Point2, Vector2, Vector3};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::UnifiedIntrinsics;
+    /// let intrinsics = UnifiedIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(400.0, 410.0))
+    ///         .principal_point(Point2::new(320.0, 240.0))
+    ///         .skew(0.5),
+    ///     0.8,
+    /// );
+    /// let point = Vector3::new(0.3, -0.7, 0.4);
+    /// let (kp, jacobian) = intrinsics.project_jacobian(point).unwrap();
+    /// // Compare against a central finite difference.
+    /// let eps = 1e-6;
+    /// for i in 0..3 {
+    ///     let mut delta = Vector3::zeros();
+    ///     delta[i] = eps;
+    ///     let (a, _) = intrinsics.project_jacobian(point + delta).unwrap();
+    ///     let (b, _) = intrinsics.project_jacobian(point - delta).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian.column(i)).norm() < 1e-4);
+    /// }
+    /// ```
+    pub fn project_jacobian(&self, point: Vector3<f64>) -> Option<(KeyPoint, Matrix2x3<f64>)> {
+        let (m, jacobian_point, _) = self.normalized_jacobians(point)?;
+        let (kp, jacobian_projection, _) = self.simple_intrinsics.normalized_jacobians(m);
+        Some((kp, jacobian_projection * jacobian_point))
+    }
+
+    /// Projects the point onto the normalized image plane of the [`UnifiedIntrinsics::simple_intrinsics`],
+    /// while also retrieving the Jacobians in respect to the point and to `xi`.
+    fn normalized_jacobians(
+        &self,
+        point: Vector3<f64>,
+    ) -> Option<(NormalizedKeyPoint, Matrix2x3<f64>, Vector2<f64>)> {
+        if !self.is_point_valid(point) {
+            return None;
+        }
+        let d = point.norm();
+        let den = point.z + self.xi * d;
+        let m = point.xy() / den;
+        // Derivative of the denominator with respect to the point.
+        let dden = RowVector3::new(0.0, 0.0, 1.0) + (self.xi / d) * point.transpose();
+        #[rustfmt::skip]
+        let numerator = Matrix2x3::new(
+            den, 0.0, 0.0,
+            0.0, den, 0.0,
+        ) - point.xy() * dden;
+        let jacobian_point = numerator / (den * den);
+        let jacobian_xi = -m * d / den;
+        Some((
+            NormalizedKeyPoint(Point2::from(m)),
+            jacobian_point,
+            jacobian_xi,
+        ))
+    }
+}
+
+impl CameraModel for UnifiedIntrinsics {
+    type Projection = Unit<Vector3<f64>>;
+
+    /// Takes in a point from an image in pixel coordinates and
+    /// converts it to a unit bearing.
+    ///
+    /// ```
+    /// use cv_core::{KeyPoint, CameraModel};
+    /// use cv_core::nalgebra::{Point2, Vector2};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::UnifiedIntrinsics;
+    /// let intrinsics = UnifiedIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(400.0, 410.0))
+    ///         .principal_point(Point2::new(320.0, 240.0)),
+    ///     0.8,
+    /// );
+    /// let kp = KeyPoint(Point2::new(20.0, 460.0));
+    /// let bearing = intrinsics.calibrate(kp);
+    /// let ukp = intrinsics.uncalibrate(bearing);
+    /// assert!((kp.0 - ukp.0).norm() < 1e-6);
+    /// ```
+    fn calibrate<P>(&self, point: P) -> Unit<Vector3<f64>>
+    where
+        P: ImagePoint,
+    {
+        let NormalizedKeyPoint(m) = self.simple_intrinsics.calibrate(point);
+        let r2 = m.coords.norm_squared();
+        let xi = self.xi;
+        let factor = (xi + Float::sqrt(1.0 + (1.0 - xi * xi) * r2)) / (r2 + 1.0);
+        Unit::new_normalize(Vector3::new(factor * m.x, factor * m.y, factor - xi))
+    }
+
+    /// Converts a unit bearing back into pixel coordinates.
+    ///
+    /// ```
+    /// use cv_core::{CameraModel, Bearing};
+    /// use cv_core::nalgebra::{Point2, Unit, Vector2, Vector3};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::UnifiedIntrinsics;
+    /// let intrinsics = UnifiedIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(400.0, 410.0))
+    ///         .principal_point(Point2::new(320.0, 240.0)),
+    ///     1.3,
+    /// );
+    /// // This bearing points slightly behind the camera.
+    /// let bearing = Unit::new_normalize(Vector3::new(0.9, 0.2, -0.1));
+    /// let kp = intrinsics.uncalibrate(bearing);
+    /// assert!((intrinsics.calibrate(kp).into_inner() - bearing.into_inner()).norm() < 1e-6);
+    /// ```
+    fn uncalibrate(&self, projection: Unit<Vector3<f64>>) -> KeyPoint {
+        let den = projection.z + self.xi;
+        self.simple_intrinsics
+            .uncalibrate(NormalizedKeyPoint(Point2::from(projection.xy() / den)))
+    }
+}
+
+impl CameraModelJacobians for UnifiedIntrinsics {
+    /// The parameters are `[fx, fy, cx, cy, skew, xi]`.
+    type Parameters = U6;
+
+    fn parameters(&self) -> Vector6<f64> {
+        self.simple_intrinsics.parameters().push(self.xi)
+    }
+
+    fn from_parameters(parameters: &Vector6<f64>) -> Self {
+        Self::new(
+            CameraIntrinsics::from_parameters(&parameters.fixed_rows::<U5>(0).into_owned()),
+            parameters[5],
+        )
+    }
+
+    /// Projects a [`CameraPoint`] into pixel coordinates and computes the Jacobians.
+    ///
+    /// ```
+    /// use cv_core::{CameraModelJacobians, CameraPoint, Projective};
+    /// use cv_core::nalgebra::{Point2, Point3, Vector2, Vector6};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// use cv_omnidirectional::UnifiedIntrinsics;
+    /// let intrinsics = UnifiedIntrinsics::new(
+    ///     CameraIntrinsics::identity()
+    ///         .focals(Vector2::new(400.0, 410.0))
+    ///         .principal_point(Point2::new(320.0, 240.0))
+    ///         .skew(0.5),
+    ///     0.8,
+    /// );
+    /// let point = CameraPoint::from_point(Point3::new(0.3, -0.7, 0.4));
+    /// let (_, _, jacobian_intrinsics) = intrinsics.uncalibrate_jacobians(point).unwrap();
+    /// // Compare against a central finite difference.
+    /// let eps = 1e-6;
+    /// for i in 0..6 {
+    ///     let mut delta = Vector6::zeros();
+    ///     delta[i] = eps;
+    ///     let a = UnifiedIntrinsics::from_parameters(&(intrinsics.parameters() + delta));
+    ///     let b = UnifiedIntrinsics::from_parameters(&(intrinsics.parameters() - delta));
+    ///     let (a, _, _) = a.uncalibrate_jacobians(point).unwrap();
+    ///     let (b, _, _) = b.uncalibrate_jacobians(point).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian_intrinsics.column(i)).norm() < 1e-3);
+    /// }
+    /// ```
+    fn uncalibrate_jacobians(
+        &self,
+        point: CameraPoint,
+    ) -> Option<(KeyPoint, Matrix2x4<f64>, Matrix2x6<f64>)> {
+        let (m, jacobian_normalized, jacobian_xi) =
+            self.normalized_jacobians(point.bearing_unnormalized())?;
+        let (kp, jacobian_projection, jacobian_simple) =
+            self.simple_intrinsics.normalized_jacobians(m);
+        let mut jacobian_point = Matrix2x4::zeros();
+        jacobian_point
+            .fixed_columns_mut::<U3>(0)
+            .copy_from(&(jacobian_projection * jacobian_normalized));
+        let mut jacobian_intrinsics = Matrix2x6::zeros();
+        jacobian_intrinsics
+            .fixed_columns_mut::<U5>(0)
+            .copy_from(&jacobian_simple);
+        jacobian_intrinsics
+            .column_mut(5)
+            .copy_from(&(jacobian_projection * jacobian_xi));
+        Some((kp, jacobian_point, jacobian_intrinsics))
+    }
+}
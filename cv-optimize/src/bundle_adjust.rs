@@ -0,0 +1,937 @@
+use crate::{
+    damp, DampedLeastSquares, DampedProblem, LinearSolver, NoObserver, NormalEquationsSolver,
+    Observer, RobustLoss, SparseMatrix, SquaredLoss, Termination,
+};
+use cv_core::nalgebra::{
+    allocator::Allocator, DMatrix, DVector, DefaultAllocator, Dynamic, Matrix2x3, Matrix3,
+    MatrixMN, Point3, Vector2, Vector3, VectorN, U2, U3, U6,
+};
+use cv_core::{CameraModelJacobians, KeyPoint, Pose, Projective, Se3, WorldPoint, WorldToCamera};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Determines which degrees of freedom of the reconstruction are held fixed during bundle adjustment.
+///
+/// Reprojection errors don't change when the whole reconstruction is moved, rotated, or scaled,
+/// so these seven degrees of freedom (the gauge) must be fixed for the solution to be unique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Gauge {
+    /// Nothing is fixed, and the damping of the optimizer is relied upon to keep the reconstruction in place.
+    Free,
+    /// The pose of the first view is fixed, leaving the scale free.
+    FirstView,
+    /// The pose of the first view and the distance between the optical centers of the first two views are fixed.
+    ///
+    /// The scale is not removed from the parameters. Each step is solved with the scale free, like
+    /// [`Gauge::FirstView`], and the candidate reconstruction is then scaled around the first view until the
+    /// distance is restored before its cost is evaluated. Reprojection errors don't depend on the scale, so this
+    /// doesn't change the cost, and the damping keeps the normal equations solvable along the scale.
+    #[default]
+    FirstViewAndScale,
+}
+
+/// A summary of a run of [`BundleAdjuster::optimize`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BundleAdjustSummary {
+    /// The number of iterations performed.
+    pub iterations: usize,
+    /// The number of observations used in the optimization.
+    pub observations: usize,
+    /// The sum of the robust loss of the squared reprojection errors before optimization.
+    pub initial_cost: f64,
+    /// The sum of the robust loss of the squared reprojection errors after optimization.
+    pub final_cost: f64,
+    /// Why the optimization stopped.
+    pub termination: Termination,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    view: usize,
+    camera: usize,
+    landmark: usize,
+    keypoint: KeyPoint,
+}
+
+/// The linearization of one observation around the current parameters.
+struct Linearization {
+    landmark: usize,
+    residual: Vector2<f64>,
+    weight: f64,
+    /// The free parameters this observation depends on (other than its landmark).
+    columns: Vec<usize>,
+    /// The Jacobian of the residual in respect to `columns`.
+    jacobian_camera: DMatrix<f64>,
+    /// The Jacobian of the residual in respect to the landmark.
+    jacobian_point: Matrix2x3<f64>,
+    /// The weighted cross term `J_cameraᵀ * w * J_point` of the normal equations.
+    coupling: MatrixMN<f64, Dynamic, U3>,
+}
+
+/// A Gaussian prior on an intrinsic parameter of a camera.
+#[derive(Debug, Clone, Copy)]
+struct IntrinsicPrior {
+    camera: usize,
+    parameter: usize,
+    mean: f64,
+    standard_deviation: f64,
+}
+
+/// The range an intrinsic parameter of a camera is held within.
+#[derive(Debug, Clone, Copy)]
+struct IntrinsicBounds {
+    camera: usize,
+    parameter: usize,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Clone)]
+struct State<C> {
+    cameras: Vec<C>,
+    poses: Vec<WorldToCamera>,
+    points: Vec<Point3<f64>>,
+}
+
+/// Jointly optimizes the poses of views, the 3d points of landmarks, and optionally the camera intrinsics,
+/// by minimizing the reprojection error of every observation of a landmark in a view.
+///
+/// The reprojection error is measured in pixels with any camera model that implements [`CameraModelJacobians`],
+/// so the Jacobians are analytic. This uses Levenberg-Marquardt, where every iteration eliminates the landmarks
+/// from the normal equations with the Schur complement. This leaves a linear system whose size only depends
+/// on the number of views and cameras, so it scales to reconstructions with many landmarks. The reduced system
+/// only couples views that observe a common landmark, so it is stored as a [`SparseMatrix`] and solved with
+/// the [`LinearSolver`] chosen with [`BundleAdjuster::linear_solver`].
+///
+/// Poses are updated on the SE(3) manifold with a left perturbation `exp(δ) * pose`.
+///
+/// ```
+/// use cv_core::nalgebra::{Point2, Point3, Vector2, Vector3, Rotation3};
+/// use cv_core::{CameraModel, Pose, Projective, WorldPoint, WorldToCamera};
+/// use cv_optimize::{BundleAdjuster, HuberLoss, Termination};
+/// use cv_pinhole::{CameraIntrinsics, NormalizedKeyPoint};
+///
+/// let intrinsics = CameraIntrinsics::identity()
+///     .focals(Vector2::new(800.0, 800.0))
+///     .principal_point(Point2::new(500.0, 400.0));
+/// let poses = [
+///     WorldToCamera::identity(),
+///     WorldToCamera::from_parts(Vector3::new(-1.0, 0.0, 0.0), Rotation3::from_euler_angles(0.0, 0.1, 0.0)),
+///     WorldToCamera::from_parts(Vector3::new(-0.5, 0.5, 0.0), Rotation3::from_euler_angles(0.05, 0.0, 0.0)),
+/// ];
+/// let points: Vec<Point3<f64>> = (0..20)
+///     .map(|i| Point3::new((i % 5) as f64 - 2.0, (i / 5) as f64 - 1.5, 6.0 + (i % 3) as f64))
+///     .collect();
+///
+/// let mut adjuster = BundleAdjuster::new().loss(HuberLoss(2.0));
+/// let camera = adjuster.add_camera(intrinsics);
+/// for (ix, pose) in poses.iter().enumerate() {
+///     // Start every view but the first (which fixes the gauge) at a perturbed pose.
+///     let noise = WorldToCamera::from_parts(Vector3::new(0.02, -0.01, 0.0), Rotation3::identity());
+///     let pose = if ix == 0 { *pose } else { (noise.isometry() * pose.isometry()).into() };
+///     adjuster.add_view(camera, pose);
+/// }
+/// for point in &points {
+///     let landmark = adjuster.add_landmark(WorldPoint::from_point(point + Vector3::new(0.05, 0.0, -0.05)));
+///     for (view, pose) in poses.iter().enumerate() {
+///         let camera_point = pose.transform(WorldPoint::from_point(*point));
+///         let keypoint = intrinsics.uncalibrate(NormalizedKeyPoint::from_camera_point(camera_point).unwrap());
+///         adjuster.add_observation(view, landmark, keypoint);
+///     }
+/// }
+///
+/// let summary = adjuster.optimize();
+/// assert_eq!(summary.termination, Termination::Converged);
+/// assert!(summary.final_cost < 1e-12);
+/// ```
+#[derive(Clone)]
+pub struct BundleAdjuster<C, L = SquaredLoss> {
+    state: State<C>,
+    view_cameras: Vec<usize>,
+    observations: Vec<Observation>,
+    loss: L,
+    gauge: Gauge,
+    intrinsics: Vec<usize>,
+    intrinsic_priors: Vec<IntrinsicPrior>,
+    intrinsic_bounds: Vec<IntrinsicBounds>,
+    max_iterations: usize,
+    tolerance: f64,
+    initial_damping: f64,
+    linear_solver: LinearSolver,
+}
+
+impl<C> BundleAdjuster<C, SquaredLoss> {
+    /// Creates an empty bundle adjuster.
+    pub fn new() -> Self {
+        Self {
+            state: State {
+                cameras: vec![],
+                poses: vec![],
+                points: vec![],
+            },
+            view_cameras: vec![],
+            observations: vec![],
+            loss: SquaredLoss,
+            gauge: Gauge::default(),
+            intrinsics: vec![],
+            intrinsic_priors: vec![],
+            intrinsic_bounds: vec![],
+            max_iterations: 100,
+            tolerance: 1e-10,
+            initial_damping: 1e-4,
+            linear_solver: LinearSolver::default(),
+        }
+    }
+}
+
+impl<C> Default for BundleAdjuster<C, SquaredLoss> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, L> BundleAdjuster<C, L> {
+    /// Sets the robust loss applied to the squared reprojection error (in pixels) of each observation.
+    ///
+    /// Default is [`SquaredLoss`].
+    pub fn loss<L2>(self, loss: L2) -> BundleAdjuster<C, L2> {
+        BundleAdjuster {
+            state: self.state,
+            view_cameras: self.view_cameras,
+            observations: self.observations,
+            loss,
+            gauge: self.gauge,
+            intrinsics: self.intrinsics,
+            intrinsic_priors: self.intrinsic_priors,
+            intrinsic_bounds: self.intrinsic_bounds,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            initial_damping: self.initial_damping,
+            linear_solver: self.linear_solver,
+        }
+    }
+
+    /// Sets how the gauge freedom of the reconstruction is fixed.
+    ///
+    /// Default is [`Gauge::FirstViewAndScale`].
+    pub fn gauge(self, gauge: Gauge) -> Self {
+        Self { gauge, ..self }
+    }
+
+    /// Sets the indices into [`CameraModelJacobians::parameters`] of the intrinsic parameters to refine.
+    ///
+    /// The same parameters are refined for every camera. Default is none.
+    pub fn refine_intrinsics(self, intrinsics: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            intrinsics: intrinsics.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of iterations.
+    ///
+    /// Default is `100`.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the relative decrease of the cost in an iteration below which the optimization has converged.
+    ///
+    /// Default is `1e-10`.
+    pub fn tolerance(self, tolerance: f64) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the initial Levenberg-Marquardt damping, relative to the diagonal of the normal equations.
+    ///
+    /// Default is `1e-4`.
+    pub fn initial_damping(self, initial_damping: f64) -> Self {
+        Self {
+            initial_damping,
+            ..self
+        }
+    }
+
+    /// Sets how the reduced camera system is solved at each iteration.
+    ///
+    /// Default is [`LinearSolver::SparseCholesky`].
+    pub fn linear_solver(self, linear_solver: LinearSolver) -> Self {
+        Self {
+            linear_solver,
+            ..self
+        }
+    }
+
+    /// Adds a camera, which can be shared by several views, and returns its index.
+    pub fn add_camera(&mut self, camera: C) -> usize {
+        self.state.cameras.push(camera);
+        self.state.cameras.len() - 1
+    }
+
+    /// Adds a Gaussian prior on an intrinsic parameter of the camera at index `camera`, where `parameter` is an index
+    /// into [`CameraModelJacobians::parameters`].
+    ///
+    /// The prior adds the squared error `((value - mean) / standard_deviation)^2` to the cost, which keeps a
+    /// parameter that is poorly constrained by the observations close to what is known about it. The prior
+    /// has no effect unless the parameter is refined with [`BundleAdjuster::refine_intrinsics`].
+    pub fn add_intrinsic_prior(
+        &mut self,
+        camera: usize,
+        parameter: usize,
+        mean: f64,
+        standard_deviation: f64,
+    ) {
+        assert!(
+            camera < self.state.cameras.len(),
+            "intrinsic prior added to a camera that doesn't exist"
+        );
+        self.intrinsic_priors.push(IntrinsicPrior {
+            camera,
+            parameter,
+            mean,
+            standard_deviation,
+        });
+    }
+
+    /// Holds an intrinsic parameter of the camera at index `camera` within `min..=max` while it is refined,
+    /// where `parameter` is an index into [`CameraModelJacobians::parameters`].
+    ///
+    /// Every step of the optimization is clamped to the bounds.
+    pub fn set_intrinsic_bounds(&mut self, camera: usize, parameter: usize, min: f64, max: f64) {
+        assert!(
+            camera < self.state.cameras.len(),
+            "intrinsic bounds set on a camera that doesn't exist"
+        );
+        assert!(min <= max, "intrinsic bounds are empty");
+        self.intrinsic_bounds.push(IntrinsicBounds {
+            camera,
+            parameter,
+            min,
+            max,
+        });
+    }
+
+    /// Adds a view taken by the camera at index `camera` and returns its index.
+    pub fn add_view(&mut self, camera: usize, pose: WorldToCamera) -> usize {
+        assert!(
+            camera < self.state.cameras.len(),
+            "view added with a camera that doesn't exist"
+        );
+        self.view_cameras.push(camera);
+        self.state.poses.push(pose);
+        self.state.poses.len() - 1
+    }
+
+    /// Adds a landmark and returns its index.
+    ///
+    /// Panics if the point is at infinity, since it must be optimized in Euclidean coordinates.
+    pub fn add_landmark(&mut self, point: WorldPoint) -> usize {
+        let point = point
+            .point()
+            .expect("landmark added to bundle adjuster is at infinity");
+        self.state.points.push(point);
+        self.state.points.len() - 1
+    }
+
+    /// Adds an observation of a landmark at a pixel location in a view.
+    pub fn add_observation(&mut self, view: usize, landmark: usize, keypoint: KeyPoint) {
+        assert!(
+            view < self.state.poses.len() && landmark < self.state.points.len(),
+            "observation added with a view or landmark that doesn't exist"
+        );
+        self.observations.push(Observation {
+            view,
+            camera: self.view_cameras[view],
+            landmark,
+            keypoint,
+        });
+    }
+
+    /// Retrieves the cameras in the order they were added.
+    pub fn cameras(&self) -> &[C] {
+        &self.state.cameras
+    }
+
+    /// Retrieves the poses of the views in the order they were added.
+    pub fn poses(&self) -> &[WorldToCamera] {
+        &self.state.poses
+    }
+
+    /// Retrieves the points of the landmarks in the order they were added.
+    pub fn points(&self) -> impl Iterator<Item = WorldPoint> + '_ {
+        self.state.points.iter().map(|&p| WorldPoint::from_point(p))
+    }
+}
+
+impl<C, L> BundleAdjuster<C, L>
+where
+    C: CameraModelJacobians + Clone,
+    L: RobustLoss,
+    DefaultAllocator: Allocator<f64, C::Parameters> + Allocator<f64, U2, C::Parameters>,
+{
+    /// Computes the reprojection error of each observation in pixels, or `None` if the camera model can't
+    /// project the landmark into the view (such as when it is behind a pinhole camera).
+    pub fn residuals(&self) -> impl Iterator<Item = Option<Vector2<f64>>> + '_ {
+        self.observations
+            .iter()
+            .map(move |observation| self.state.residual(observation))
+    }
+
+    /// Computes the sum of the robust loss of the squared reprojection errors of the observations
+    /// which can be projected, plus the squared errors of the priors on the intrinsics.
+    pub fn cost(&self) -> f64 {
+        self.residuals()
+            .flatten()
+            .map(|residual| self.loss.loss(residual.norm_squared()).0)
+            .sum::<f64>()
+            + self.prior_cost(&self.state.cameras)
+    }
+
+    /// Runs Levenberg-Marquardt until it converges, stalls, or reaches the maximum number of iterations.
+    ///
+    /// Observations which can't be projected with the initial parameters are excluded from both the cost and
+    /// the normal equations, and steps which would make any of the remaining observations impossible to project
+    /// are rejected.
+    pub fn optimize(&mut self) -> BundleAdjustSummary {
+        self.optimize_with(&mut NoObserver)
+    }
+
+    /// Runs [`BundleAdjuster::optimize`], reporting the progress to `observer` after every iteration
+    /// which decreases the cost.
+    ///
+    /// If the observer cancels, the parameters are left at their values after the last iteration.
+    pub fn optimize_with<O>(&mut self, observer: &mut O) -> BundleAdjustSummary
+    where
+        O: Observer + ?Sized,
+    {
+        let (pose_columns, camera_columns, num_columns) = self.columns();
+        let active: Vec<Observation> = self
+            .observations
+            .iter()
+            .filter(|observation| {
+                self.linearize(observation, &pose_columns, &camera_columns)
+                    .is_some()
+            })
+            .copied()
+            .collect();
+        let reference_scale = self.reference_scale();
+        let pattern = self.reduced_pattern(&active, &pose_columns, &camera_columns, num_columns);
+        let solver = NormalEquationsSolver::new(self.linear_solver, 6);
+
+        let initial_cost = self.state.cost(&active, &self.loss).unwrap_or(0.0)
+            + self.prior_cost(&self.state.cameras);
+        let optimizer = DampedLeastSquares {
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            initial_damping: self.initial_damping,
+        };
+        let observations = active.len();
+        let mut problem = BundleAdjustProblem {
+            adjuster: self,
+            active,
+            pose_columns,
+            camera_columns,
+            num_columns,
+            pattern,
+            reference_scale,
+            solver,
+        };
+        let summary = optimizer.minimize(&mut problem, initial_cost, observer);
+
+        BundleAdjustSummary {
+            iterations: summary.iterations,
+            observations,
+            initial_cost,
+            final_cost: summary.final_cost,
+            termination: summary.termination,
+        }
+    }
+
+    /// The sum of the squared errors of the priors on the intrinsics of the cameras.
+    fn prior_cost(&self, cameras: &[C]) -> f64 {
+        self.intrinsic_priors
+            .iter()
+            .map(|prior| {
+                let value = cameras[prior.camera].parameters()[prior.parameter];
+                ((value - prior.mean) / prior.standard_deviation).powi(2)
+            })
+            .sum()
+    }
+
+    /// The column of the intrinsic parameter of a prior, if it is refined.
+    fn intrinsic_column(
+        &self,
+        camera_columns: &[Option<usize>],
+        prior: &IntrinsicPrior,
+    ) -> Option<usize> {
+        let start = camera_columns[prior.camera]?;
+        let offset = self
+            .intrinsics
+            .iter()
+            .position(|&parameter| parameter == prior.parameter)?;
+        Some(start + offset)
+    }
+
+    /// Assigns the first column of the free parameters of every view and camera, and counts the columns.
+    #[allow(clippy::type_complexity)]
+    fn columns(&self) -> (Vec<Option<usize>>, Vec<Option<usize>>, usize) {
+        let mut num_columns = 0;
+        let pose_columns = (0..self.state.poses.len())
+            .map(|view| {
+                if view == 0 && self.gauge != Gauge::Free {
+                    None
+                } else {
+                    num_columns += 6;
+                    Some(num_columns - 6)
+                }
+            })
+            .collect();
+        let camera_columns = (0..self.state.cameras.len())
+            .map(|_| {
+                if self.intrinsics.is_empty() {
+                    None
+                } else {
+                    num_columns += self.intrinsics.len();
+                    Some(num_columns - self.intrinsics.len())
+                }
+            })
+            .collect();
+        (pose_columns, camera_columns, num_columns)
+    }
+
+    /// Creates the sparsity pattern of the reduced camera system, where the parameters of two views are coupled
+    /// if they observe a common landmark.
+    fn reduced_pattern(
+        &self,
+        active: &[Observation],
+        pose_columns: &[Option<usize>],
+        camera_columns: &[Option<usize>],
+        num_columns: usize,
+    ) -> SparseMatrix {
+        let mut view_columns: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        let mut landmark_views = vec![BTreeSet::new(); self.state.points.len()];
+        for observation in active {
+            view_columns.entry(observation.view).or_insert_with(|| {
+                let pose = pose_columns[observation.view].map(|start| start..start + 6);
+                let camera = camera_columns[observation.camera]
+                    .map(|start| start..start + self.intrinsics.len());
+                pose.into_iter()
+                    .flatten()
+                    .chain(camera.into_iter().flatten())
+                    .collect()
+            });
+            landmark_views[observation.landmark].insert(observation.view);
+        }
+        let mut view_pairs = BTreeSet::new();
+        for views in &landmark_views {
+            for &a in views {
+                for &b in views {
+                    view_pairs.insert((a, b));
+                }
+            }
+        }
+        let mut triplets: Vec<(usize, usize, f64)> =
+            (0..num_columns).map(|i| (i, i, 0.0)).collect();
+        for (a, b) in view_pairs {
+            for &column_a in &view_columns[&a] {
+                for &column_b in &view_columns[&b] {
+                    triplets.push((column_a, column_b, 0.0));
+                }
+            }
+        }
+        SparseMatrix::from_triplets(num_columns, num_columns, &triplets)
+    }
+
+    /// The distance between the optical centers of the first two views, if the gauge fixes it.
+    fn reference_scale(&self) -> Option<f64> {
+        if self.gauge != Gauge::FirstViewAndScale || self.state.poses.len() < 2 {
+            return None;
+        }
+        let scale = self.state.baseline();
+        if scale > 0.0 {
+            Some(scale)
+        } else {
+            None
+        }
+    }
+
+    fn linearize(
+        &self,
+        observation: &Observation,
+        pose_columns: &[Option<usize>],
+        camera_columns: &[Option<usize>],
+    ) -> Option<Linearization> {
+        let camera = &self.state.cameras[observation.camera];
+        let pose = self.state.poses[observation.view];
+        let point = WorldPoint::from_point(self.state.points[observation.landmark]);
+        let (camera_point, jacobian_pose) = pose.transform_jacobian_left(point);
+        // The camera model decides which points it can project, since some can see behind the image plane.
+        let (keypoint, jacobian_projection, jacobian_intrinsics) =
+            camera.uncalibrate_jacobians(camera_point)?;
+        let residual = keypoint.0 - observation.keypoint.0;
+        if !residual.iter().all(|r| r.is_finite()) {
+            return None;
+        }
+        let weight = self.loss.weight(residual.norm_squared());
+
+        // The homogeneous camera point changes by the rotation of the pose when the Euclidean point moves.
+        let jacobian_projection_xyz = jacobian_projection.fixed_columns::<U3>(0);
+        let jacobian_point = jacobian_projection_xyz * pose.isometry().rotation.matrix();
+
+        let mut columns = vec![];
+        let mut jacobian_columns: Vec<Vector2<f64>> = vec![];
+        if let Some(start) = pose_columns[observation.view] {
+            let jacobian_pose = jacobian_projection * jacobian_pose;
+            for i in 0..6 {
+                columns.push(start + i);
+                jacobian_columns.push(jacobian_pose.column(i).into_owned());
+            }
+        }
+        if let Some(start) = camera_columns[observation.camera] {
+            for (i, &parameter) in self.intrinsics.iter().enumerate() {
+                columns.push(start + i);
+                jacobian_columns.push(jacobian_intrinsics.column(parameter).into_owned());
+            }
+        }
+        let jacobian_camera = DMatrix::from_iterator(
+            2,
+            columns.len(),
+            jacobian_columns.iter().flat_map(|c| c.iter().copied()),
+        );
+        let coupling = jacobian_camera.transpose() * (jacobian_point * weight);
+        Some(Linearization {
+            landmark: observation.landmark,
+            residual,
+            weight,
+            columns,
+            jacobian_camera,
+            jacobian_point,
+            coupling,
+        })
+    }
+
+    /// Applies a step to a copy of the parameters.
+    fn step(
+        &self,
+        delta_camera: &DVector<f64>,
+        delta_points: &[Vector3<f64>],
+        pose_columns: &[Option<usize>],
+        camera_columns: &[Option<usize>],
+    ) -> State<C> {
+        let poses = self
+            .state
+            .poses
+            .iter()
+            .zip(pose_columns)
+            .map(|(&pose, &start)| match start {
+                Some(start) => {
+                    let delta = delta_camera.fixed_rows::<U6>(start);
+                    (WorldToCamera::exp(Se3(delta.into_owned())).isometry() * pose.isometry())
+                        .into()
+                }
+                None => pose,
+            })
+            .collect();
+        let points = self
+            .state
+            .points
+            .iter()
+            .zip(delta_points)
+            .map(|(point, delta)| point + delta)
+            .collect();
+        let cameras = self
+            .state
+            .cameras
+            .iter()
+            .zip(camera_columns)
+            .enumerate()
+            .map(|(ix, (camera, &start))| match start {
+                Some(start) => {
+                    let mut parameters: VectorN<f64, C::Parameters> = camera.parameters();
+                    for (i, &parameter) in self.intrinsics.iter().enumerate() {
+                        parameters[parameter] += delta_camera[start + i];
+                    }
+                    for bounds in self
+                        .intrinsic_bounds
+                        .iter()
+                        .filter(|bounds| bounds.camera == ix)
+                    {
+                        parameters[bounds.parameter] =
+                            parameters[bounds.parameter].max(bounds.min).min(bounds.max);
+                    }
+                    C::from_parameters(&parameters)
+                }
+                None => camera.clone(),
+            })
+            .collect();
+        State {
+            cameras,
+            poses,
+            points,
+        }
+    }
+}
+
+/// The normal equations of every observation, with the landmarks not yet eliminated.
+struct BundleAdjustNormalEquations {
+    linearizations: Vec<Linearization>,
+    /// The block of the normal equations of the poses and intrinsics.
+    u: SparseMatrix,
+    gradient_camera: DVector<f64>,
+    /// The block of the normal equations of each landmark.
+    v: Vec<Matrix3<f64>>,
+    gradient_point: Vec<Vector3<f64>>,
+    /// The indices in `linearizations` of the observations of each landmark.
+    landmark_linearizations: Vec<Vec<usize>>,
+}
+
+/// A step of the poses and intrinsics, followed by the step of each landmark.
+type BundleAdjustStep = (DVector<f64>, Vec<Vector3<f64>>);
+
+/// A [`BundleAdjuster`] with the observations which can be projected and the columns of its free parameters.
+struct BundleAdjustProblem<'a, C, L> {
+    adjuster: &'a mut BundleAdjuster<C, L>,
+    active: Vec<Observation>,
+    pose_columns: Vec<Option<usize>>,
+    camera_columns: Vec<Option<usize>>,
+    num_columns: usize,
+    pattern: SparseMatrix,
+    reference_scale: Option<f64>,
+    solver: NormalEquationsSolver,
+}
+
+impl<C, L> DampedProblem for BundleAdjustProblem<'_, C, L>
+where
+    C: CameraModelJacobians + Clone,
+    L: RobustLoss,
+    DefaultAllocator: Allocator<f64, C::Parameters> + Allocator<f64, U2, C::Parameters>,
+{
+    type NormalEquations = BundleAdjustNormalEquations;
+    type Step = BundleAdjustStep;
+    type Parameters = State<C>;
+
+    fn linearize(&self) -> Self::NormalEquations {
+        let adjuster = &*self.adjuster;
+        // Linearize every observation and accumulate the normal equations. Steps which make an active
+        // observation impossible to project are rejected, so every one of them can be linearized here.
+        let linearizations: Vec<Linearization> = self
+            .active
+            .iter()
+            .filter_map(|observation| {
+                adjuster.linearize(observation, &self.pose_columns, &self.camera_columns)
+            })
+            .collect();
+        let num_points = adjuster.state.points.len();
+        let mut u = self.pattern.clone();
+        let mut gradient_camera = DVector::<f64>::zeros(self.num_columns);
+        let mut v = vec![Matrix3::<f64>::zeros(); num_points];
+        let mut gradient_point = vec![Vector3::<f64>::zeros(); num_points];
+        let mut landmark_linearizations = vec![vec![]; num_points];
+        for (ix, lin) in linearizations.iter().enumerate() {
+            let weighted = lin.jacobian_camera.transpose() * lin.weight;
+            let block = &weighted * &lin.jacobian_camera;
+            let gradient = &weighted * lin.residual;
+            for (a, &column_a) in lin.columns.iter().enumerate() {
+                gradient_camera[column_a] += gradient[a];
+                for (b, &column_b) in lin.columns.iter().enumerate() {
+                    u.add_to(column_a, column_b, block[(a, b)]);
+                }
+            }
+            let weighted_point = lin.jacobian_point.transpose() * lin.weight;
+            v[lin.landmark] += weighted_point * lin.jacobian_point;
+            gradient_point[lin.landmark] += weighted_point * lin.residual;
+            landmark_linearizations[lin.landmark].push(ix);
+        }
+        // The priors only depend on the intrinsics, so they are unaffected by the Schur complement.
+        for prior in &adjuster.intrinsic_priors {
+            if let Some(column) = adjuster.intrinsic_column(&self.camera_columns, prior) {
+                let value = adjuster.state.cameras[prior.camera].parameters()[prior.parameter];
+                let information = prior.standard_deviation.powi(-2);
+                u.add_to(column, column, information);
+                gradient_camera[column] += information * (value - prior.mean);
+            }
+        }
+        BundleAdjustNormalEquations {
+            linearizations,
+            u,
+            gradient_camera,
+            v,
+            gradient_point,
+            landmark_linearizations,
+        }
+    }
+
+    fn solve(&mut self, equations: &Self::NormalEquations, damping: f64) -> Option<Self::Step> {
+        let BundleAdjustNormalEquations {
+            linearizations,
+            u,
+            gradient_camera,
+            v,
+            gradient_point,
+            landmark_linearizations,
+        } = equations;
+        let mut s = u.clone();
+        s.add_diagonal(&u.diagonal().map(|d| damp(d, damping)));
+        let mut rhs = -gradient_camera;
+        // Invert each damped landmark block and eliminate it from the camera system (Schur complement).
+        let v_inverses: Vec<Option<Matrix3<f64>>> = v
+            .iter()
+            .zip(landmark_linearizations)
+            .map(|(v, lins)| {
+                if lins.is_empty() {
+                    return None;
+                }
+                let mut v = *v;
+                for i in 0..3 {
+                    v[(i, i)] += damp(v[(i, i)], damping);
+                }
+                v.try_inverse()
+            })
+            .collect();
+        for (v_inverse, lins) in v_inverses.iter().zip(landmark_linearizations) {
+            let v_inverse = match v_inverse {
+                Some(v_inverse) => v_inverse,
+                None => continue,
+            };
+            let gradient = gradient_point[linearizations[lins[0]].landmark];
+            for &a in lins {
+                let a = &linearizations[a];
+                let coupling_v_inverse = &a.coupling * v_inverse;
+                let correction = &coupling_v_inverse * gradient;
+                for (i, &column) in a.columns.iter().enumerate() {
+                    rhs[column] += correction[i];
+                }
+                for &b in lins {
+                    let b = &linearizations[b];
+                    let block = &coupling_v_inverse * b.coupling.transpose();
+                    for (i, &column_a) in a.columns.iter().enumerate() {
+                        for (j, &column_b) in b.columns.iter().enumerate() {
+                            s.add_to(column_a, column_b, -block[(i, j)]);
+                        }
+                    }
+                }
+            }
+        }
+        let delta_camera = self.solver.solve(&s, &rhs)?;
+
+        // Back-substitute the camera step to find the step of each landmark.
+        let delta_points: Vec<Vector3<f64>> = v_inverses
+            .iter()
+            .zip(landmark_linearizations)
+            .zip(gradient_point)
+            .map(|((v_inverse, lins), &gradient)| {
+                let v_inverse = match v_inverse {
+                    Some(v_inverse) => v_inverse,
+                    None => return Vector3::zeros(),
+                };
+                let mut rhs = -gradient;
+                for &a in lins {
+                    let a = &linearizations[a];
+                    let delta = DVector::from_iterator(
+                        a.columns.len(),
+                        a.columns.iter().map(|&c| delta_camera[c]),
+                    );
+                    rhs -= a.coupling.transpose() * delta;
+                }
+                v_inverse * rhs
+            })
+            .collect();
+        Some((delta_camera, delta_points))
+    }
+
+    fn step_norm(&self, (delta_camera, delta_points): &Self::Step) -> f64 {
+        (delta_camera.norm_squared() + delta_points.iter().map(|d| d.norm_squared()).sum::<f64>())
+            .sqrt()
+    }
+
+    fn parameter_norm(&self) -> f64 {
+        self.adjuster.state.norm()
+    }
+
+    fn apply(&self, (delta_camera, delta_points): &Self::Step) -> Self::Parameters {
+        let mut candidate = self.adjuster.step(
+            delta_camera,
+            delta_points,
+            &self.pose_columns,
+            &self.camera_columns,
+        );
+        if let Some(reference_scale) = self.reference_scale {
+            candidate.rescale(reference_scale);
+        }
+        candidate
+    }
+
+    fn cost(&self, candidate: &Self::Parameters) -> Option<f64> {
+        candidate
+            .cost(&self.active, &self.adjuster.loss)
+            .map(|cost| cost + self.adjuster.prior_cost(&candidate.cameras))
+    }
+
+    fn accept(&mut self, candidate: Self::Parameters) {
+        self.adjuster.state = candidate;
+    }
+}
+
+impl<C> State<C> {
+    /// The norm of the positions in the reconstruction, which gives the scale of a step.
+    fn norm(&self) -> f64 {
+        let translations = self
+            .poses
+            .iter()
+            .map(|pose| pose.isometry().translation.vector.norm_squared());
+        let points = self.points.iter().map(|point| point.coords.norm_squared());
+        translations.chain(points).sum::<f64>().sqrt()
+    }
+
+    /// The distance between the optical centers of the first two views.
+    fn baseline(&self) -> f64 {
+        let center = |pose: WorldToCamera| pose.isometry().inverse().translation.vector;
+        (center(self.poses[1]) - center(self.poses[0])).norm()
+    }
+
+    /// Scales the reconstruction about the optical center of the first view so that the baseline
+    /// between the first two views is `reference_scale`.
+    fn rescale(&mut self, reference_scale: f64) {
+        let baseline = self.baseline();
+        if baseline <= 0.0 {
+            return;
+        }
+        let scale = reference_scale / baseline;
+        let origin = self.poses[0].isometry().inverse().translation.vector;
+        for pose in &mut self.poses {
+            let isometry = pose.isometry();
+            let center = isometry.inverse().translation.vector;
+            let center = origin + (center - origin) * scale;
+            *pose = WorldToCamera::from_parts(-(isometry.rotation * center), isometry.rotation);
+        }
+        for point in &mut self.points {
+            point.coords = origin + (point.coords - origin) * scale;
+        }
+    }
+}
+
+impl<C> State<C>
+where
+    C: CameraModelJacobians,
+    DefaultAllocator: Allocator<f64, C::Parameters> + Allocator<f64, U2, C::Parameters>,
+{
+    fn residual(&self, observation: &Observation) -> Option<Vector2<f64>> {
+        let pose = self.poses[observation.view];
+        let camera_point =
+            pose.transform(WorldPoint::from_point(self.points[observation.landmark]));
+        let camera = &self.cameras[observation.camera];
+        let (keypoint, _, _) = camera.uncalibrate_jacobians(camera_point)?;
+        Some(keypoint.0 - observation.keypoint.0)
+            .filter(|residual| residual.iter().all(|r| r.is_finite()))
+    }
+
+    /// Computes the total loss of the observations, or `None` if any can't be projected.
+    fn cost(&self, observations: &[Observation], loss: &impl RobustLoss) -> Option<f64> {
+        observations
+            .iter()
+            .map(|observation| Some(loss.loss(self.residual(observation)?.norm_squared()).0))
+            .sum()
+    }
+}
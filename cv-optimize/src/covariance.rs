@@ -0,0 +1,292 @@
+use cv_core::nalgebra::{
+    DMatrix, Matrix2x4, Matrix2x6, Matrix3, Matrix3x2, Matrix3x4, Matrix4, Matrix4x3, Matrix4x6,
+    Matrix6, Matrix6x3, SymmetricEigen, Unit, Vector2, Vector3, Vector4, U1, U2, U3, U6,
+};
+
+/// Determines how the gauge freedom of a reconstruction is removed when recovering its covariance.
+///
+/// Bearing residuals don't change when the whole reconstruction is moved, rotated, or scaled, so the
+/// covariance of the poses is only defined once these degrees of freedom are fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CovarianceGauge {
+    /// The first pose is held fixed, so the covariance of every other pose is relative to it.
+    /// The remaining freedom of the scale is removed in the way that minimizes the trace of the covariance.
+    #[default]
+    FirstView,
+    /// Every gauge freedom is removed in the way that minimizes the trace of the covariance of the poses,
+    /// which is the pseudo-inverse of the normal equations.
+    MinimumTrace,
+}
+
+/// The marginal covariances of the parameters of an optimized reconstruction.
+///
+/// The covariances are the inverse of the normal equations `J^T * J` of the final Jacobian, scaled by the
+/// residual variance estimated from the final residuals. They are only meaningful once the optimizer converges.
+///
+/// The cosine distance minimized by the optimizers is flat at its minimum, so the Jacobian is of the angular
+/// error of each bearing instead, which is the predicted bearing projected onto the plane tangent to the
+/// observed bearing. The residual variance is then the variance of the angular error in each direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginalCovariances {
+    /// The 6x6 covariance of each pose over the translation of [`Pose::se3`](cv_core::Pose::se3), followed by
+    /// a rotation vector `w` which perturbs the rotation on the left as `exp(w) * R`.
+    ///
+    /// A pose held fixed by the gauge has a covariance of zero.
+    pub poses: Vec<Matrix6<f64>>,
+    /// The 3x3 covariance of the Euclidean position of each landmark, if landmark covariances were requested.
+    ///
+    /// This is `None` for a landmark which wasn't triangulated, isn't observed, or is at infinity.
+    pub landmarks: Vec<Option<Matrix3<f64>>>,
+    /// The estimated variance of a single residual, which scales every covariance.
+    pub residual_variance: f64,
+}
+
+/// The linearization of the angular error of one bearing, which is the predicted bearing projected onto the
+/// plane tangent to the observed bearing.
+pub(crate) struct BearingLinearization {
+    /// The pose the bearing is observed from, or `None` if it is observed from the origin.
+    pub pose: Option<usize>,
+    /// The point the bearing observes.
+    pub point: usize,
+    /// The IRLS weight of the observation.
+    pub weight: f64,
+    pub residual: Vector2<f64>,
+    pub jacobian_pose: Matrix2x6<f64>,
+    pub jacobian_point: Matrix2x4<f64>,
+}
+
+impl BearingLinearization {
+    /// Linearizes the observation of a bearing from a point transformed into the camera, with the Jacobians of
+    /// the homogeneous camera point in respect to the pose and the point.
+    ///
+    /// Returns `None` if the camera point is at the optical center.
+    pub(crate) fn new(
+        pose: Option<usize>,
+        point: usize,
+        weight: f64,
+        observed: Unit<Vector3<f64>>,
+        camera_point: Vector4<f64>,
+        jacobian_pose: Matrix4x6<f64>,
+        jacobian_point: Matrix4<f64>,
+    ) -> Option<Self> {
+        let xyz = camera_point.xyz();
+        let norm = xyz.norm();
+        if norm == 0.0 {
+            return None;
+        }
+        let predicted = xyz / norm;
+        let tangent = tangent_basis(&observed);
+        // The Jacobian of the tangent-plane error in respect to the camera point (the homogeneous part has no effect).
+        let jacobian_xyz =
+            tangent.transpose() * (Matrix3::identity() - predicted * predicted.transpose()) / norm;
+        let mut jacobian_camera_point = Matrix2x4::zeros();
+        jacobian_camera_point
+            .fixed_slice_mut::<U2, U3>(0, 0)
+            .copy_from(&jacobian_xyz);
+        Some(Self {
+            pose,
+            point,
+            weight,
+            residual: tangent.transpose() * predicted,
+            jacobian_pose: jacobian_camera_point * jacobian_pose,
+            jacobian_point: jacobian_camera_point * jacobian_point,
+        })
+    }
+}
+
+/// A least-squares problem over poses and homogeneous points with bearing observations, linearized at its solution.
+pub(crate) struct CovarianceProblem {
+    /// The number of poses.
+    pub poses: usize,
+    /// The number of poses at the start which are held fixed.
+    pub fixed_poses: usize,
+    /// The number of degrees of freedom of the gauge that remain after fixing poses.
+    pub gauge_freedom: usize,
+    /// The homogeneous coordinates of each point, or `None` if the point has no parameters in use.
+    pub points: Vec<Option<Vector4<f64>>>,
+    pub observations: Vec<BearingLinearization>,
+    /// Whether to compute the covariance of the landmarks.
+    pub landmarks: bool,
+}
+
+impl CovarianceProblem {
+    /// Computes the marginal covariances by eliminating the points from the normal equations with the
+    /// Schur complement, so only a system the size of the poses needs to be inverted.
+    ///
+    /// Returns `None` if there are not enough observations to estimate the variance, or if the poses are not
+    /// constrained beyond the gauge freedom.
+    pub(crate) fn solve(&self) -> Option<MarginalCovariances> {
+        let free_poses = self.poses - self.fixed_poses;
+        let column = |pose: Option<usize>| {
+            pose.filter(|&pose| pose >= self.fixed_poses)
+                .map(|pose| 6 * (pose - self.fixed_poses))
+        };
+
+        // A homogeneous point can be scaled without changing any residual, so each point is parameterized
+        // by the three directions orthogonal to it.
+        let bases: Vec<Option<Matrix4x3<f64>>> = self
+            .points
+            .iter()
+            .map(|point| point.as_ref().map(orthogonal_basis))
+            .collect();
+        let mut u = DMatrix::<f64>::zeros(6 * free_poses, 6 * free_poses);
+        let mut v = vec![Matrix3::<f64>::zeros(); self.points.len()];
+        let mut couplings: Vec<Vec<(usize, Matrix6x3<f64>)>> = vec![vec![]; self.points.len()];
+        let mut observations = 0;
+        let mut residual_sum = 0.0;
+        for observation in &self.observations {
+            let basis = match bases[observation.point] {
+                Some(basis) => basis,
+                None => continue,
+            };
+            observations += 1;
+            residual_sum += observation.weight * observation.residual.norm_squared();
+            let jacobian_point = observation.jacobian_point * basis;
+            v[observation.point] +=
+                jacobian_point.transpose() * observation.weight * jacobian_point;
+            if let Some(start) = column(observation.pose) {
+                let weighted = observation.jacobian_pose.transpose() * observation.weight;
+                let block = weighted * observation.jacobian_pose;
+                let mut slice = u.fixed_slice_mut::<U6, U6>(start, start);
+                slice += block;
+                couplings[observation.point].push((start, weighted * jacobian_point));
+            }
+        }
+
+        // Eliminate each point from the normal equations of the poses.
+        let v_inverses: Vec<Option<Matrix3<f64>>> = v
+            .iter()
+            .zip(&bases)
+            .map(|(v, basis)| basis.and_then(|_| v.cholesky()).map(|c| c.inverse()))
+            .collect();
+        let mut schur = u;
+        for (v_inverse, couplings) in v_inverses.iter().zip(&couplings) {
+            let v_inverse = match v_inverse {
+                Some(v_inverse) => v_inverse,
+                None => continue,
+            };
+            for &(start_a, w_a) in couplings {
+                for &(start_b, w_b) in couplings {
+                    let mut slice = schur.fixed_slice_mut::<U6, U6>(start_a, start_b);
+                    slice -= w_a * v_inverse * w_b.transpose();
+                }
+            }
+        }
+
+        // Invert the poses with the gauge freedom removed.
+        let pose_covariance = pseudo_inverse(schur, self.gauge_freedom)?;
+
+        // Estimate the variance of the residuals from the redundancy of the problem.
+        let residuals = 2 * observations;
+        let parameters = (6 * free_poses + 3 * v_inverses.iter().filter(|v| v.is_some()).count())
+            .saturating_sub(self.gauge_freedom);
+        if residuals <= parameters {
+            return None;
+        }
+        let residual_variance = residual_sum / (residuals - parameters) as f64;
+
+        let poses = (0..self.poses)
+            .map(|pose| match column(Some(pose)) {
+                Some(start) => {
+                    pose_covariance
+                        .fixed_slice::<U6, U6>(start, start)
+                        .into_owned()
+                        * residual_variance
+                }
+                None => Matrix6::zeros(),
+            })
+            .collect();
+
+        let landmarks = if self.landmarks {
+            (0..self.points.len())
+                .map(|ix| {
+                    let point = self.points[ix]?;
+                    let basis = bases[ix]?;
+                    let v_inverse = v_inverses[ix]?;
+                    if point.w.abs() < 1e-12 * point.xyz().norm() {
+                        return None;
+                    }
+                    let mut propagated = Matrix3::zeros();
+                    for &(start_a, w_a) in &couplings[ix] {
+                        for &(start_b, w_b) in &couplings[ix] {
+                            let block = pose_covariance.fixed_slice::<U6, U6>(start_a, start_b);
+                            propagated += w_a.transpose() * block * w_b;
+                        }
+                    }
+                    let tangent = v_inverse + v_inverse * propagated * v_inverse;
+                    // Convert from the homogeneous coordinates to the Euclidean position `xyz / w`.
+                    let mut dehomogenize = Matrix3x4::zeros();
+                    dehomogenize
+                        .fixed_slice_mut::<U3, U3>(0, 0)
+                        .copy_from(&(Matrix3::identity() / point.w));
+                    dehomogenize
+                        .fixed_slice_mut::<U3, U1>(0, 3)
+                        .copy_from(&(-point.xyz() / (point.w * point.w)));
+                    let jacobian = dehomogenize * basis;
+                    Some(jacobian * tangent * jacobian.transpose() * residual_variance)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Some(MarginalCovariances {
+            poses,
+            landmarks,
+            residual_variance,
+        })
+    }
+}
+
+/// Computes an orthonormal basis of the plane tangent to a bearing.
+fn tangent_basis(bearing: &Unit<Vector3<f64>>) -> Matrix3x2<f64> {
+    // Cross with the axis least aligned with the bearing to avoid a degenerate cross product.
+    let mut axis = Vector3::zeros();
+    axis[bearing.iamin()] = 1.0;
+    let first = bearing.cross(&axis).normalize();
+    let second = bearing.cross(&first);
+    Matrix3x2::from_columns(&[first, second])
+}
+
+/// Computes an orthonormal basis of the directions orthogonal to a homogeneous point with a Householder reflection.
+fn orthogonal_basis(point: &Vector4<f64>) -> Matrix4x3<f64> {
+    let unit = point.normalize();
+    let k = unit.iamax();
+    let mut v = unit;
+    v[k] += unit[k].signum();
+    let reflection = Matrix4::identity() - v * v.transpose() * (2.0 / v.norm_squared());
+    // The reflection maps the `k` axis onto the point, so the other columns are orthogonal to it.
+    let mut basis = Matrix4x3::zeros();
+    for (column, axis) in (0..4).filter(|&axis| axis != k).enumerate() {
+        basis.column_mut(column).copy_from(&reflection.column(axis));
+    }
+    basis
+}
+
+/// Inverts a symmetric positive semi-definite matrix after discarding its `nullity` smallest eigenvalues.
+///
+/// Returns `None` if any of the remaining eigenvalues are not positive.
+fn pseudo_inverse(matrix: DMatrix<f64>, nullity: usize) -> Option<DMatrix<f64>> {
+    let n = matrix.nrows();
+    if nullity > n {
+        return None;
+    }
+    let eigen = SymmetricEigen::new(matrix);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_unstable_by(|&a, &b| {
+        eigen.eigenvalues[a]
+            .partial_cmp(&eigen.eigenvalues[b])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let largest = order.last().map_or(0.0, |&i| eigen.eigenvalues[i]);
+    let mut inverse = DMatrix::zeros(n, n);
+    for &i in &order[nullity..] {
+        let value = eigen.eigenvalues[i];
+        if value.is_nan() || value <= largest * 1e-14 {
+            return None;
+        }
+        let vector = eigen.eigenvectors.column(i);
+        inverse += vector * vector.transpose() / value;
+    }
+    Some(inverse)
+}
@@ -0,0 +1,156 @@
+use crate::{Control, Observer, Progress};
+use std::time::Instant;
+
+/// The smallest diagonal entry of the normal equations used to scale the damping.
+const MIN_DIAGONAL: f64 = 1e-6;
+/// The smallest damping the optimizer will decrease to.
+const MIN_DAMPING: f64 = 1e-12;
+/// The damping beyond which the optimizer gives up on finding a step that reduces the cost.
+const MAX_DAMPING: f64 = 1e16;
+
+/// The reason that [`BundleAdjuster::optimize`](crate::BundleAdjuster::optimize),
+/// [`PoseGraph::optimize`](crate::PoseGraph::optimize), or
+/// [`ManyViewOptimizer::optimize`](crate::ManyViewOptimizer::optimize) stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The relative decrease of the cost fell below the tolerance.
+    Converged,
+    /// The maximum number of iterations was reached.
+    MaxIterations,
+    /// No step could be found that decreases the cost.
+    Stalled,
+    /// An [`Observer`] cancelled the optimization, which keeps the parameters of the last iteration.
+    Cancelled,
+}
+
+/// Computes the damping added to a diagonal entry of the normal equations.
+pub(crate) fn damp(diagonal: f64, damping: f64) -> f64 {
+    damping * diagonal.max(MIN_DIAGONAL)
+}
+
+/// A least squares problem which [`DampedLeastSquares`] can minimize.
+pub(crate) trait DampedProblem {
+    /// The undamped normal equations at the current parameters.
+    type NormalEquations;
+    /// A solution of the damped normal equations.
+    type Step;
+    /// The parameters after a step is applied.
+    type Parameters;
+
+    /// Linearizes the problem at the current parameters.
+    fn linearize(&self) -> Self::NormalEquations;
+
+    /// Solves the normal equations with [`damp`] applied to their diagonal, or returns `None` if they are singular.
+    fn solve(&mut self, equations: &Self::NormalEquations, damping: f64) -> Option<Self::Step>;
+
+    /// The norm of a step.
+    fn step_norm(&self, step: &Self::Step) -> f64;
+
+    /// The norm of the current parameters, which a step must be significant compared to.
+    fn parameter_norm(&self) -> f64;
+
+    /// Applies a step to the current parameters.
+    fn apply(&self, step: &Self::Step) -> Self::Parameters;
+
+    /// Computes the cost of some parameters, or returns `None` if they are invalid.
+    fn cost(&self, parameters: &Self::Parameters) -> Option<f64>;
+
+    /// Replaces the current parameters.
+    fn accept(&mut self, parameters: Self::Parameters);
+}
+
+/// The result of [`DampedLeastSquares::minimize`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DampedSummary {
+    pub(crate) iterations: usize,
+    pub(crate) final_cost: f64,
+    pub(crate) termination: Termination,
+}
+
+/// The Levenberg-Marquardt loop shared by [`PoseGraph`](crate::PoseGraph),
+/// [`BundleAdjuster`](crate::BundleAdjuster), and [`ManyViewOptimizer`](crate::ManyViewOptimizer).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DampedLeastSquares {
+    pub(crate) max_iterations: usize,
+    pub(crate) tolerance: f64,
+    pub(crate) initial_damping: f64,
+}
+
+impl DampedLeastSquares {
+    /// Minimizes `problem` starting with parameters of cost `initial_cost`, reporting the progress to `observer`
+    /// after every iteration which decreases the cost.
+    ///
+    /// At each iteration, the damping is increased until a step decreases the cost, and it is decreased after
+    /// the step is accepted.
+    pub(crate) fn minimize<P, O>(
+        &self,
+        problem: &mut P,
+        initial_cost: f64,
+        observer: &mut O,
+    ) -> DampedSummary
+    where
+        P: DampedProblem,
+        O: Observer + ?Sized,
+    {
+        let start = Instant::now();
+        let mut cost = initial_cost;
+        let mut damping = self.initial_damping;
+        let mut iterations = 0;
+        let termination = 'outer: loop {
+            if iterations >= self.max_iterations {
+                break Termination::MaxIterations;
+            }
+            iterations += 1;
+            let equations = problem.linearize();
+
+            // Find the smallest damping that produces a step which decreases the cost.
+            loop {
+                if damping > MAX_DAMPING {
+                    break 'outer Termination::Stalled;
+                }
+                let step = match problem.solve(&equations, damping) {
+                    Some(step) => step,
+                    None => {
+                        damping *= 10.0;
+                        continue;
+                    }
+                };
+                // A step which is negligible compared to the parameters can't make progress.
+                let step_norm = problem.step_norm(&step);
+                if step_norm <= self.tolerance * (problem.parameter_norm() + self.tolerance) {
+                    break 'outer Termination::Converged;
+                }
+                let candidate = problem.apply(&step);
+                match problem.cost(&candidate) {
+                    Some(new_cost) if new_cost < cost => {
+                        problem.accept(candidate);
+                        damping = (damping * 0.1).max(MIN_DAMPING);
+                        let decrease = cost - new_cost;
+                        let old_cost = cost;
+                        cost = new_cost;
+                        let progress = Progress {
+                            iteration: iterations,
+                            cost,
+                            step_norm,
+                            elapsed: start.elapsed(),
+                        };
+                        if observer.observe(&progress) == Control::Cancel {
+                            break 'outer Termination::Cancelled;
+                        }
+                        if decrease <= self.tolerance * old_cost {
+                            break 'outer Termination::Converged;
+                        }
+                        break;
+                    }
+                    _ => damping *= 10.0,
+                }
+            }
+        };
+
+        DampedSummary {
+            iterations,
+            final_cost: cost,
+            termination,
+        }
+    }
+}
@@ -1,7 +1,21 @@
+mod bundle_adjust;
+mod covariance;
+mod damped_least_squares;
+mod loss;
 mod many_view_optimizer;
+mod observer;
+mod pose_graph;
 mod single_view_optimizer;
+mod sparse;
 mod two_view_optimizer;
 
+pub use bundle_adjust::*;
+pub use covariance::*;
+pub use damped_least_squares::*;
+pub use loss::*;
 pub use many_view_optimizer::*;
+pub use observer::*;
+pub use pose_graph::*;
 pub use single_view_optimizer::*;
+pub use sparse::*;
 pub use two_view_optimizer::*;
@@ -0,0 +1,199 @@
+use crate::{Control, NoObserver, Observer, Progress};
+use cv_core::nalgebra::Dynamic;
+use levenberg_marquardt::{LeastSquaresProblem, LevenbergMarquardt, MinimizationReport};
+use std::time::Instant;
+
+/// A robust loss function, which reduces the influence of large residuals (outliers) on an optimization.
+///
+/// The loss is applied to the squared norm `s` of a residual, so a loss of `s` is ordinary least squares.
+/// Optimizers minimize the sum of the losses, and use the derivative of the loss to weight each residual
+/// when solving the normal equations.
+///
+/// The scale of each loss in this crate is given in the units of the residual, not of `s`. The optimizers of
+/// bearings use the cosine distance of each bearing as its residual, so the scale of their loss is a cosine
+/// distance. For instance, `TruncatedLoss(0.001)` ignores bearings with a cosine distance above `0.001`.
+/// The Nelder-Mead constraints, such as [`TwoViewConstraint`](crate::TwoViewConstraint), minimize the mean of
+/// `sqrt(loss(s))`, which is the cosine distance itself below the scale of a [`TruncatedLoss`].
+pub trait RobustLoss {
+    /// Computes the loss of a residual with the squared norm `s` and the derivative of the loss in respect to `s`.
+    fn loss(&self, s: f64) -> (f64, f64);
+
+    /// Computes the weight of a residual with the squared norm `s`, which is the derivative of the loss.
+    fn weight(&self, s: f64) -> f64 {
+        self.loss(s).1
+    }
+}
+
+impl<L: RobustLoss + ?Sized> RobustLoss for &L {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        (**self).loss(s)
+    }
+}
+
+impl<L: RobustLoss + ?Sized> RobustLoss for Box<L> {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        (**self).loss(s)
+    }
+}
+
+/// Ordinary least squares, which doesn't reduce the influence of outliers.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SquaredLoss;
+
+impl RobustLoss for SquaredLoss {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        (s, 1.0)
+    }
+}
+
+/// The Huber loss, which is quadratic for residuals smaller than `scale` and linear beyond it.
+#[derive(Copy, Clone, Debug)]
+pub struct HuberLoss(pub f64);
+
+impl RobustLoss for HuberLoss {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        let HuberLoss(scale) = *self;
+        let scale2 = scale * scale;
+        if s <= scale2 {
+            (s, 1.0)
+        } else {
+            let r = s.sqrt();
+            (2.0 * scale * r - scale2, scale / r)
+        }
+    }
+}
+
+/// The Cauchy loss, which grows logarithmically for residuals larger than `scale`.
+#[derive(Copy, Clone, Debug)]
+pub struct CauchyLoss(pub f64);
+
+impl RobustLoss for CauchyLoss {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        let CauchyLoss(scale) = *self;
+        let scale2 = scale * scale;
+        let ratio = s / scale2;
+        (scale2 * ratio.ln_1p(), 1.0 / (1.0 + ratio))
+    }
+}
+
+/// The Tukey biweight loss, which ignores residuals larger than `scale` entirely.
+///
+/// This is not convex, so the optimization must start close to the solution.
+#[derive(Copy, Clone, Debug)]
+pub struct TukeyLoss(pub f64);
+
+impl RobustLoss for TukeyLoss {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        let TukeyLoss(scale) = *self;
+        let scale2 = scale * scale;
+        if s <= scale2 {
+            let remaining = 1.0 - s / scale2;
+            (
+                scale2 / 3.0 * (1.0 - remaining * remaining * remaining),
+                remaining * remaining,
+            )
+        } else {
+            (scale2 / 3.0, 0.0)
+        }
+    }
+}
+
+/// The Geman-McClure loss, which smoothly approaches `scale²` for residuals much larger than `scale`.
+///
+/// This is not convex, so the optimization must start close to the solution.
+#[derive(Copy, Clone, Debug)]
+pub struct GemanMcClureLoss(pub f64);
+
+impl RobustLoss for GemanMcClureLoss {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        let GemanMcClureLoss(scale) = *self;
+        let scale2 = scale * scale;
+        let denominator = scale2 + s;
+        (
+            scale2 * s / denominator,
+            scale2 * scale2 / (denominator * denominator),
+        )
+    }
+}
+
+/// The truncated least squares loss, which is quadratic for residuals smaller than `scale` and constant beyond it.
+///
+/// The loss is continuous, but its derivative is not, and residuals beyond `scale` have no influence at all.
+#[derive(Copy, Clone, Debug)]
+pub struct TruncatedLoss(pub f64);
+
+impl RobustLoss for TruncatedLoss {
+    fn loss(&self, s: f64) -> (f64, f64) {
+        let TruncatedLoss(scale) = *self;
+        let scale2 = scale * scale;
+        if s <= scale2 {
+            (s, 1.0)
+        } else {
+            (scale2, 0.0)
+        }
+    }
+}
+
+/// Computes the cost of a bearing with the given cosine distance in a Nelder-Mead constraint.
+pub(crate) fn cosine_distance_cost(loss: &impl RobustLoss, cosine_distance: f64) -> f64 {
+    loss.loss(cosine_distance * cosine_distance).0.sqrt()
+}
+
+/// A least-squares problem which weights its residuals with a [`RobustLoss`], so that it can be solved
+/// with iteratively reweighted least squares (IRLS).
+///
+/// The weights are held constant while the problem is minimized, which makes it an ordinary least-squares problem.
+pub trait Reweight {
+    /// Recomputes the weight of each residual from its value at the current parameters.
+    fn reweight(&mut self);
+}
+
+/// Minimizes a [`Reweight`] problem with iteratively reweighted least squares.
+///
+/// Each iteration recomputes the weights of the residuals and then minimizes the weighted problem with `lm`.
+/// At least one iteration is always performed. The report of the last minimization is returned.
+pub fn iteratively_reweighted_least_squares<P>(
+    lm: &LevenbergMarquardt<f64>,
+    problem: P,
+    iterations: usize,
+) -> (P, MinimizationReport<f64>)
+where
+    P: LeastSquaresProblem<f64, Dynamic, Dynamic> + Reweight,
+{
+    iteratively_reweighted_least_squares_with(lm, problem, iterations, &mut NoObserver)
+}
+
+/// Runs [`iteratively_reweighted_least_squares`], reporting the progress to `observer` after every
+/// minimization of the weighted problem.
+///
+/// The cost reported is the objective function of the weighted problem. If the observer cancels,
+/// no further iterations are performed and the report of the last minimization is returned.
+pub fn iteratively_reweighted_least_squares_with<P, O>(
+    lm: &LevenbergMarquardt<f64>,
+    mut problem: P,
+    iterations: usize,
+    observer: &mut O,
+) -> (P, MinimizationReport<f64>)
+where
+    P: LeastSquaresProblem<f64, Dynamic, Dynamic> + Reweight,
+    O: Observer + ?Sized,
+{
+    let start = Instant::now();
+    let mut iteration = 0;
+    loop {
+        iteration += 1;
+        problem.reweight();
+        let previous = problem.params();
+        let (next_problem, report) = lm.minimize(problem);
+        problem = next_problem;
+        let progress = Progress {
+            iteration,
+            cost: report.objective_function,
+            step_norm: (problem.params() - previous).norm(),
+            elapsed: start.elapsed(),
+        };
+        if observer.observe(&progress) == Control::Cancel || iteration >= iterations {
+            return (problem, report);
+        }
+    }
+}
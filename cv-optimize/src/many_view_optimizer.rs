@@ -1,3 +1,9 @@
+use crate::{
+    cosine_distance_cost, damp, BearingLinearization, CovarianceGauge, CovarianceProblem,
+    DampedLeastSquares, DampedProblem, LinearSolver, MarginalCovariances, NoObserver,
+    NormalEquationsSolver, Observer, Reweight, RobustLoss, SparseMatrix, Termination,
+    TruncatedLoss,
+};
 use argmin::{
     core::{ArgminOp, Error},
     solver::neldermead::NelderMead,
@@ -5,7 +11,7 @@ use argmin::{
 use average::Mean;
 use cv_core::nalgebra::{
     dimension::{Dynamic, U1, U4, U6},
-    DMatrix, DVector, Matrix3, VecStorage, Vector4, Vector6,
+    DMatrix, DVector, Matrix1x4, Matrix1x6, Matrix3, VecStorage, Vector4, Vector6,
 };
 use cv_core::{Bearing, Pose, Projective, TriangulatorObservations, WorldPoint, WorldToCamera};
 use levenberg_marquardt::LeastSquaresProblem;
@@ -47,8 +53,8 @@ pub fn many_view_nelder_mead(poses: Vec<WorldToCamera>) -> NelderMead<Array2<f64
 }
 
 #[derive(Clone)]
-pub struct ManyViewConstraint<B, T> {
-    loss_cutoff: f64,
+pub struct ManyViewConstraint<B, T, L = TruncatedLoss> {
+    loss: L,
     // Stored as a list of landmarks, each of which contains a list of observations in (view, bearing) format.
     landmarks: Vec<Vec<(usize, B)>>,
     triangulator: T,
@@ -82,30 +88,41 @@ where
             })
             .collect();
         Self {
-            loss_cutoff: 0.01,
+            loss: TruncatedLoss(0.01),
             landmarks,
             triangulator,
         }
     }
+}
 
-    pub fn loss_cutoff(self, loss_cutoff: f64) -> Self {
-        Self {
-            loss_cutoff,
-            ..self
+impl<B, T, L> ManyViewConstraint<B, T, L>
+where
+    B: Bearing + Clone,
+    T: TriangulatorObservations,
+    L: RobustLoss,
+{
+    /// Sets the robust loss applied to the cosine distance of each observation, which is the residual.
+    ///
+    /// Default is `TruncatedLoss(0.01)`.
+    pub fn loss<L2>(self, loss: L2) -> ManyViewConstraint<B, T, L2> {
+        ManyViewConstraint {
+            loss,
+            landmarks: self.landmarks,
+            triangulator: self.triangulator,
         }
     }
 
+    /// Caps the cosine distance of each observation at `loss_cutoff`.
+    #[deprecated(note = "use `loss(TruncatedLoss(loss_cutoff))` instead")]
+    pub fn loss_cutoff(self, loss_cutoff: f64) -> ManyViewConstraint<B, T> {
+        self.loss(TruncatedLoss(loss_cutoff))
+    }
+
     pub fn residuals<'a>(
         &'a self,
         poses: impl Iterator<Item = WorldToCamera> + Clone + 'a,
     ) -> impl Iterator<Item = f64> + 'a {
-        let loss = move |n: f64| {
-            if n > self.loss_cutoff {
-                self.loss_cutoff
-            } else {
-                n
-            }
-        };
+        let loss = move |n: f64| cosine_distance_cost(&self.loss, n);
         let poses_res = poses.clone();
         self.landmarks.iter().flat_map(move |observations| {
             if let Some(world_point) =
@@ -137,18 +154,18 @@ where
                         }),
                 )
             } else {
-                itertools::Either::Right(
-                    std::iter::repeat(self.loss_cutoff).take(observations.len()),
-                )
+                // The landmark can't be triangulated, so give every observation the loss of the largest cosine distance.
+                itertools::Either::Right(std::iter::repeat_n(loss(2.0), observations.len()))
             }
         })
     }
 }
 
-impl<B, T> ArgminOp for ManyViewConstraint<B, T>
+impl<B, T, L> ArgminOp for ManyViewConstraint<B, T, L>
 where
     B: Bearing + Clone,
     T: TriangulatorObservations,
+    L: RobustLoss,
 {
     type Param = Array2<f64>;
     type Output = f64;
@@ -170,12 +187,37 @@ where
     }
 }
 
+/// A summary of a run of [`ManyViewOptimizer::optimize`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManyViewSummary {
+    /// The number of iterations performed.
+    pub iterations: usize,
+    /// The sum of the weighted squared residuals before optimization.
+    pub initial_cost: f64,
+    /// The sum of the weighted squared residuals after optimization.
+    pub final_cost: f64,
+    /// Why the optimization stopped.
+    pub termination: Termination,
+}
+
+/// Optimizes the poses of many views and the homogeneous points of their landmarks.
+///
+/// [`ManyViewOptimizer::optimize`] runs Levenberg-Marquardt on the sparse normal equations, which are solved
+/// with the [`LinearSolver`] chosen with [`ManyViewOptimizer::linear_solver`], so the memory only grows with the
+/// number of observations. The optimizer is also a [`LeastSquaresProblem`] for the `levenberg_marquardt` crate,
+/// but its Jacobian is dense, so its memory grows with the product of the number of views and landmarks.
 #[derive(Clone)]
-pub struct ManyViewOptimizer<B> {
+pub struct ManyViewOptimizer<B, L = TruncatedLoss> {
     pub poses: Vec<WorldToCamera>,
     pub points: Vec<Option<WorldPoint>>,
     landmarks: Vec<Vec<Option<B>>>,
-    loss_cutoff: f64,
+    loss: L,
+    /// The IRLS weight of each residual.
+    weights: Vec<f64>,
+    max_iterations: usize,
+    tolerance: f64,
+    initial_damping: f64,
+    linear_solver: LinearSolver,
 }
 
 impl<B> ManyViewOptimizer<B>
@@ -211,24 +253,147 @@ where
                 )
             })
             .collect();
-        let landmarks = landmarks.map(|observances| observances.collect()).collect();
-        Self {
+        let landmarks: Vec<Vec<Option<B>>> =
+            landmarks.map(|observances| observances.collect()).collect();
+        let weights = vec![1.0; landmarks.len() * poses.len()];
+        let mut optimizer = Self {
             poses,
             points,
             landmarks,
-            loss_cutoff: 0.01,
+            loss: TruncatedLoss(0.01),
+            weights,
+            max_iterations: 100,
+            tolerance: 1e-10,
+            initial_damping: 1e-4,
+            linear_solver: LinearSolver::default(),
+        };
+        optimizer.reweight();
+        optimizer
+    }
+}
+
+impl<B, L> ManyViewOptimizer<B, L>
+where
+    B: Bearing,
+{
+    /// Sets the robust loss applied to the cosine distance of each observation, which is the residual.
+    ///
+    /// The loss is applied with iteratively reweighted least squares. The weights are computed from the
+    /// residuals at the current parameters when the loss is set, and recomputed by [`Reweight::reweight`].
+    /// Default is `TruncatedLoss(0.01)`.
+    pub fn loss<L2: RobustLoss>(self, loss: L2) -> ManyViewOptimizer<B, L2> {
+        let mut optimizer = ManyViewOptimizer {
+            poses: self.poses,
+            points: self.points,
+            landmarks: self.landmarks,
+            loss,
+            weights: self.weights,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            initial_damping: self.initial_damping,
+            linear_solver: self.linear_solver,
+        };
+        optimizer.reweight();
+        optimizer
+    }
+
+    /// Sets the maximum number of iterations of [`ManyViewOptimizer::optimize`].
+    ///
+    /// Default is `100`.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the relative decrease of the cost in an iteration below which [`ManyViewOptimizer::optimize`]
+    /// has converged.
+    ///
+    /// Default is `1e-10`.
+    pub fn tolerance(self, tolerance: f64) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the initial Levenberg-Marquardt damping, relative to the diagonal of the normal equations.
+    ///
+    /// Default is `1e-4`.
+    pub fn initial_damping(self, initial_damping: f64) -> Self {
+        Self {
+            initial_damping,
+            ..self
         }
     }
 
-    pub fn loss_cutoff(self, loss_cutoff: f64) -> Self {
+    /// Sets how [`ManyViewOptimizer::optimize`] solves the normal equations at each iteration.
+    ///
+    /// Default is [`LinearSolver::SparseCholesky`].
+    pub fn linear_solver(self, linear_solver: LinearSolver) -> Self {
         Self {
-            loss_cutoff,
+            linear_solver,
             ..self
         }
     }
+
+    /// Caps the cosine distance of each observation at `loss_cutoff`.
+    #[deprecated(note = "use `loss(TruncatedLoss(loss_cutoff))` instead")]
+    pub fn loss_cutoff(self, loss_cutoff: f64) -> ManyViewOptimizer<B> {
+        self.loss(TruncatedLoss(loss_cutoff))
+    }
+
+    /// Computes the cosine distance of every observation without weighting.
+    fn unweighted_residuals(&self) -> DVector<f64> {
+        self.unweighted_residuals_of(&self.poses, &self.points)
+    }
+
+    /// Computes the cosine distance of every observation with the given parameters without weighting.
+    fn unweighted_residuals_of(
+        &self,
+        poses: &[WorldToCamera],
+        points: &[Option<WorldPoint>],
+    ) -> DVector<f64> {
+        DVector::from_iterator(
+            points.len() * poses.len(),
+            points
+                .iter()
+                .zip(self.landmarks.iter())
+                .flat_map(|(&pw, lms)| {
+                    poses.iter().zip(lms.iter()).map(move |(pose, lm)| {
+                        // TODO: Once try blocks get added, this should be replaced with a try block.
+                        let res = || -> Option<f64> {
+                            let pc = pose.transform(pw?);
+                            Some(1.0 - lm.as_ref()?.bearing().dot(&pc.bearing()))
+                        };
+                        res().unwrap_or(0.0)
+                    })
+                }),
+        )
+    }
+
+    /// Computes the sum of the weighted squared residuals with the given parameters.
+    fn cost_of(&self, poses: &[WorldToCamera], points: &[Option<WorldPoint>]) -> f64 {
+        self.unweighted_residuals_of(poses, points)
+            .iter()
+            .zip(&self.weights)
+            .map(|(residual, weight)| weight * residual * residual)
+            .sum()
+    }
+}
+
+impl<B, L> Reweight for ManyViewOptimizer<B, L>
+where
+    B: Bearing,
+    L: RobustLoss,
+{
+    fn reweight(&mut self) {
+        let residuals = self.unweighted_residuals();
+        for (weight, residual) in self.weights.iter_mut().zip(residuals.iter()) {
+            *weight = self.loss.weight(residual * residual);
+        }
+    }
 }
 
-impl<B> LeastSquaresProblem<f64, Dynamic, Dynamic> for ManyViewOptimizer<B>
+impl<B, L> LeastSquaresProblem<f64, Dynamic, Dynamic> for ManyViewOptimizer<B, L>
 where
     B: Bearing + Clone,
 {
@@ -269,29 +434,11 @@ where
 
     /// Compute the residual vector.
     fn residuals(&self) -> Option<DVector<f64>> {
-        let loss = |n: f64| {
-            if n > self.loss_cutoff {
-                self.loss_cutoff
-            } else {
-                n
-            }
-        };
-        Some(DVector::from_iterator(
-            self.points.len() * self.poses.len(),
-            self.points
-                .iter()
-                .zip(self.landmarks.iter())
-                .flat_map(|(&pw, lms)| {
-                    self.poses.iter().zip(lms.iter()).map(move |(pose, lm)| {
-                        // TODO: Once try blocks get added, this should be replaced with a try block.
-                        let res = || -> Option<f64> {
-                            let pc = pose.transform(pw?);
-                            Some(loss(1.0 - lm.as_ref()?.bearing().dot(&pc.bearing())))
-                        };
-                        res().unwrap_or(0.0)
-                    })
-                }),
-        ))
+        let mut residuals = self.unweighted_residuals();
+        for (residual, weight) in residuals.iter_mut().zip(&self.weights) {
+            *residual *= weight.sqrt();
+        }
+        Some(residuals)
     }
 
     /// Compute the Jacobian of the residual vector.
@@ -313,26 +460,7 @@ where
         {
             // Get the row corresponding to this index.
             let mut row = mat.row_mut(ix);
-            // Transform the point into the camera space and retrieve the jacobians.
-            let (cp, jacobian_cp_wp, jacobian_cp_pose) = pose.transform_jacobians(wp);
-            // Get the cp bearing and norm.
-            let cp_bearing = cp.bearing().into_inner();
-            let cp_norm = cp.bearing_unnormalized().norm();
-            // The jacobian relating the residual to the normalized form of cp (cpn).
-            let jacobian_res_cpn = -lm.bearing().into_inner().transpose();
-            // The jacobian relating cpn to cp.
-            let jacobian_cpn_cp =
-                (Matrix3::identity() - cp_bearing * cp_bearing.transpose()) / cp_norm;
-
-            // The jacobian relating residual to cp (0.0 appended to convert to homogeneous coordinates).
-            let jacobian_res_cp = (jacobian_res_cpn * jacobian_cpn_cp)
-                .transpose()
-                .push(0.0)
-                .transpose();
-
-            // Compute the jacobians that will go into the row relating this residual to the pose and the point.
-            let jacobian_res_pose = jacobian_res_cp * jacobian_cp_pose;
-            let jacobian_res_wp = jacobian_res_cp * jacobian_cp_wp;
+            let (jacobian_res_pose, jacobian_res_wp) = residual_jacobians(pose, wp, &lm);
 
             let pose_ix = ix % self.poses.len();
             let point_ix = ix / self.poses.len();
@@ -340,7 +468,257 @@ where
                 .copy_from(&jacobian_res_pose);
             row.fixed_columns_mut::<U4>(pose_len + 4 * point_ix)
                 .copy_from(&jacobian_res_wp);
+            row *= self.weights[ix].sqrt();
         }
         Some(mat)
     }
 }
+
+impl<B, L> ManyViewOptimizer<B, L>
+where
+    B: Bearing,
+{
+    /// Computes the marginal covariance of each pose, and optionally each landmark, at the current parameters,
+    /// which should be the converged solution. See [`MarginalCovariances`] for details.
+    ///
+    /// With [`CovarianceGauge::FirstView`], the first pose has a covariance of zero and every other pose is
+    /// uncertain relative to it. Returns `None` if the poses are not constrained by enough observations.
+    pub fn marginal_covariances(
+        &self,
+        gauge: CovarianceGauge,
+        landmarks: bool,
+    ) -> Option<MarginalCovariances> {
+        let (fixed_poses, gauge_freedom) = match gauge {
+            // Only the scale remains once the first pose is fixed.
+            CovarianceGauge::FirstView => (1, 1),
+            CovarianceGauge::MinimumTrace => (0, 7),
+        };
+        let observations = self
+            .points
+            .iter()
+            .zip(&self.landmarks)
+            .enumerate()
+            .flat_map(|(point_ix, (&point, observances))| {
+                self.poses.iter().zip(observances).enumerate().filter_map(
+                    move |(pose_ix, (&pose, observance))| {
+                        let (camera_point, jacobian_point, jacobian_pose) =
+                            pose.transform_jacobians(point?);
+                        BearingLinearization::new(
+                            Some(pose_ix),
+                            point_ix,
+                            self.weights[point_ix * self.poses.len() + pose_ix],
+                            observance.as_ref()?.bearing(),
+                            camera_point.0,
+                            jacobian_pose,
+                            jacobian_point,
+                        )
+                    },
+                )
+            })
+            .collect();
+        CovarianceProblem {
+            poses: self.poses.len(),
+            fixed_poses: fixed_poses.min(self.poses.len()),
+            gauge_freedom,
+            points: self.points.iter().map(|point| point.map(|p| p.0)).collect(),
+            observations,
+            landmarks,
+        }
+        .solve()
+    }
+}
+
+impl<B, L> ManyViewOptimizer<B, L>
+where
+    B: Bearing + Clone,
+{
+    /// Runs Levenberg-Marquardt with the current IRLS weights until it converges, stalls, or reaches the maximum
+    /// number of iterations.
+    ///
+    /// Only the points of landmarks which could be triangulated are optimized. Nothing is held fixed, so the
+    /// damping is relied upon to keep the reconstruction in place.
+    pub fn optimize(&mut self) -> ManyViewSummary {
+        self.optimize_with(&mut NoObserver)
+    }
+
+    /// Runs [`ManyViewOptimizer::optimize`], reporting the progress to `observer` after every iteration
+    /// which decreases the cost.
+    ///
+    /// If the observer cancels, the parameters are left at their values after the last iteration.
+    pub fn optimize_with<O>(&mut self, observer: &mut O) -> ManyViewSummary
+    where
+        O: Observer + ?Sized,
+    {
+        let mut num_columns = 6 * self.poses.len();
+        let point_columns = self
+            .points
+            .iter()
+            .map(|point| {
+                point.map(|_| {
+                    num_columns += 4;
+                    num_columns - 4
+                })
+            })
+            .collect();
+        let initial_cost = self.cost_of(&self.poses, &self.points);
+        let optimizer = DampedLeastSquares {
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            initial_damping: self.initial_damping,
+        };
+        // Every pose has six columns and every point has four, so blocks of two never span two parameters.
+        let solver = NormalEquationsSolver::new(self.linear_solver, 2);
+        let mut problem = ManyViewProblem {
+            optimizer: self,
+            point_columns,
+            num_columns,
+            solver,
+        };
+        let summary = optimizer.minimize(&mut problem, initial_cost, observer);
+
+        ManyViewSummary {
+            iterations: summary.iterations,
+            initial_cost,
+            final_cost: summary.final_cost,
+            termination: summary.termination,
+        }
+    }
+}
+
+/// A [`ManyViewOptimizer`] with the columns of the normal equations assigned to each point.
+struct ManyViewProblem<'a, B, L> {
+    optimizer: &'a mut ManyViewOptimizer<B, L>,
+    point_columns: Vec<Option<usize>>,
+    num_columns: usize,
+    solver: NormalEquationsSolver,
+}
+
+impl<B, L> DampedProblem for ManyViewProblem<'_, B, L>
+where
+    B: Bearing + Clone,
+{
+    type NormalEquations = (SparseMatrix, DVector<f64>);
+    type Step = DVector<f64>;
+    type Parameters = (Vec<WorldToCamera>, Vec<Option<WorldPoint>>);
+
+    fn linearize(&self) -> Self::NormalEquations {
+        let optimizer = &*self.optimizer;
+        let residuals = optimizer.unweighted_residuals();
+        let mut triplets: Vec<(usize, usize, f64)> =
+            (0..self.num_columns).map(|i| (i, i, 0.0)).collect();
+        let mut gradient = DVector::<f64>::zeros(self.num_columns);
+        for (point_ix, (point, observances)) in optimizer
+            .points
+            .iter()
+            .zip(&optimizer.landmarks)
+            .enumerate()
+        {
+            let (point, point_column) = match (point, self.point_columns[point_ix]) {
+                (&Some(point), Some(point_column)) => (point, point_column),
+                _ => continue,
+            };
+            for (pose_ix, (&pose, observance)) in
+                optimizer.poses.iter().zip(observances).enumerate()
+            {
+                let bearing = match observance {
+                    Some(bearing) => bearing,
+                    None => continue,
+                };
+                let ix = point_ix * optimizer.poses.len() + pose_ix;
+                let weight = optimizer.weights[ix];
+                let (jacobian_pose, jacobian_point) = residual_jacobians(pose, point, bearing);
+                let blocks = [
+                    (6 * pose_ix, jacobian_pose.transpose().as_slice().to_vec()),
+                    (point_column, jacobian_point.transpose().as_slice().to_vec()),
+                ];
+                for (column_a, jacobian_a) in &blocks {
+                    for (i, a) in jacobian_a.iter().enumerate() {
+                        gradient[column_a + i] += weight * a * residuals[ix];
+                        for (column_b, jacobian_b) in &blocks {
+                            for (j, b) in jacobian_b.iter().enumerate() {
+                                triplets.push((column_a + i, column_b + j, weight * a * b));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let hessian = SparseMatrix::from_triplets(self.num_columns, self.num_columns, &triplets);
+        (hessian, gradient)
+    }
+
+    fn solve(
+        &mut self,
+        (hessian, gradient): &Self::NormalEquations,
+        damping: f64,
+    ) -> Option<Self::Step> {
+        let mut damped = hessian.clone();
+        damped.add_diagonal(&hessian.diagonal().map(|d| damp(d, damping)));
+        self.solver.solve(&damped, &-gradient)
+    }
+
+    fn step_norm(&self, step: &Self::Step) -> f64 {
+        step.norm()
+    }
+
+    fn parameter_norm(&self) -> f64 {
+        self.optimizer.params().norm()
+    }
+
+    fn apply(&self, step: &Self::Step) -> Self::Parameters {
+        let poses = self
+            .optimizer
+            .poses
+            .iter()
+            .enumerate()
+            .map(|(ix, pose)| Pose::from_se3(pose.se3() + step.fixed_rows::<U6>(6 * ix)))
+            .collect();
+        let points = self
+            .optimizer
+            .points
+            .iter()
+            .zip(&self.point_columns)
+            .map(|(&point, &column)| Some(WorldPoint(point?.0 + step.fixed_rows::<U4>(column?))))
+            .collect();
+        (poses, points)
+    }
+
+    fn cost(&self, (poses, points): &Self::Parameters) -> Option<f64> {
+        Some(self.optimizer.cost_of(poses, points))
+    }
+
+    fn accept(&mut self, (poses, points): Self::Parameters) {
+        self.optimizer.poses = poses;
+        self.optimizer.points = points;
+    }
+}
+
+/// Computes the Jacobians of the cosine distance between a bearing and the bearing of a point in a pose's view,
+/// in respect to the pose and the homogeneous point.
+fn residual_jacobians<B: Bearing>(
+    pose: WorldToCamera,
+    point: WorldPoint,
+    bearing: &B,
+) -> (Matrix1x6<f64>, Matrix1x4<f64>) {
+    // Transform the point into the camera space and retrieve the jacobians.
+    let (cp, jacobian_cp_wp, jacobian_cp_pose) = pose.transform_jacobians(point);
+    // Get the cp bearing and norm.
+    let cp_bearing = cp.bearing().into_inner();
+    let cp_norm = cp.bearing_unnormalized().norm();
+    // The jacobian relating the residual to the normalized form of cp (cpn).
+    let jacobian_res_cpn = -bearing.bearing().into_inner().transpose();
+    // The jacobian relating cpn to cp.
+    let jacobian_cpn_cp = (Matrix3::identity() - cp_bearing * cp_bearing.transpose()) / cp_norm;
+
+    // The jacobian relating residual to cp (0.0 appended to convert to homogeneous coordinates).
+    let jacobian_res_cp = (jacobian_res_cpn * jacobian_cpn_cp)
+        .transpose()
+        .push(0.0)
+        .transpose();
+
+    // Compute the jacobians relating this residual to the pose and the point.
+    (
+        jacobian_res_cp * jacobian_cp_pose,
+        jacobian_res_cp * jacobian_cp_wp,
+    )
+}
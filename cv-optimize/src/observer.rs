@@ -0,0 +1,227 @@
+use argmin::core::{ArgminKV, ArgminOp, Error, IterState, Observe};
+use ndarray::{Array, Dimension};
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+/// The progress of an optimization, which is reported to an [`Observer`] after every iteration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// The number of iterations performed so far, starting at `1` for the first report.
+    pub iteration: usize,
+    /// The cost of the current parameters.
+    pub cost: f64,
+    /// The norm of the change of the parameters in this iteration.
+    pub step_norm: f64,
+    /// The wall-clock time since the optimization started.
+    pub elapsed: Duration,
+}
+
+/// Whether an optimization should continue after an [`Observer`] is notified of its progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Run the next iteration.
+    Continue,
+    /// Stop the optimization, keeping the best parameters found so far where the optimizer allows it.
+    Cancel,
+}
+
+/// Receives the progress of an optimization after every iteration, and decides whether it should continue.
+///
+/// This is implemented for closures, so progress can be displayed with:
+///
+/// ```
+/// use cv_optimize::{Control, Observer, Progress};
+///
+/// let mut observer = |progress: &Progress| {
+///     println!("iteration {} has cost {}", progress.iteration, progress.cost);
+///     Control::Continue
+/// };
+/// # fn takes(_: &mut impl Observer) {}
+/// # takes(&mut observer);
+/// ```
+///
+/// A pair of observers notifies both and cancels if either of them does, so a [`TimeLimit`] can be added
+/// to any other observer.
+pub trait Observer {
+    /// Notifies the observer of the progress after an iteration.
+    fn observe(&mut self, progress: &Progress) -> Control;
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&Progress) -> Control,
+{
+    fn observe(&mut self, progress: &Progress) -> Control {
+        self(progress)
+    }
+}
+
+impl<A, B> Observer for (A, B)
+where
+    A: Observer,
+    B: Observer,
+{
+    fn observe(&mut self, progress: &Progress) -> Control {
+        let a = self.0.observe(progress);
+        let b = self.1.observe(progress);
+        if a == Control::Cancel || b == Control::Cancel {
+            Control::Cancel
+        } else {
+            Control::Continue
+        }
+    }
+}
+
+/// An observer which does nothing, which is used when an optimizer is run without one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoObserver;
+
+impl Observer for NoObserver {
+    fn observe(&mut self, _: &Progress) -> Control {
+        Control::Continue
+    }
+}
+
+/// Cancels an optimization once it has run for longer than a wall-clock duration.
+///
+/// The iteration in progress when the time runs out is always finished, so the limit can be exceeded
+/// by the duration of one iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLimit(pub Duration);
+
+impl Observer for TimeLimit {
+    fn observe(&mut self, progress: &Progress) -> Control {
+        if progress.elapsed >= self.0 {
+            Control::Cancel
+        } else {
+            Control::Continue
+        }
+    }
+}
+
+/// Cancels an optimization from another thread, or from anywhere else a clone of the token is held.
+///
+/// ```
+/// use cv_optimize::{CancellationToken, Control, Observer, Progress};
+/// use std::time::Duration;
+///
+/// let mut token = CancellationToken::new();
+/// let handle = token.clone();
+/// std::thread::spawn(move || handle.cancel()).join().unwrap();
+/// let progress = Progress {
+///     iteration: 1,
+///     cost: 1.0,
+///     step_norm: 0.1,
+///     elapsed: Duration::from_millis(10),
+/// };
+/// assert_eq!(token.observe(&progress), Control::Cancel);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token which hasn't been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that every optimization observed by a clone of this token stops after its current iteration.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Clears a cancellation so the token and its clones can observe another optimization.
+    ///
+    /// A cancelled token stays cancelled until it is reset, so reset it before starting the next optimization.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    /// Checks if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Observer for CancellationToken {
+    fn observe(&mut self, _: &Progress) -> Control {
+        if self.is_cancelled() {
+            Control::Cancel
+        } else {
+            Control::Continue
+        }
+    }
+}
+
+/// The error returned from an argmin [`Executor`](argmin::core::Executor) when an [`ArgminObserver`] cancels it.
+///
+/// Use [`Error::is`] on the error of the executor to tell it apart from a failure of the optimization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "optimization cancelled by an observer")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Adapts an [`Observer`] so it can observe the argmin solvers, such as the Nelder-Mead solvers
+/// of the single-view, two-view, and many-view constraints.
+///
+/// argmin can only be stopped early with an error, so when the observer cancels, the executor
+/// returns [`Cancelled`] as its error and the parameters found so far are lost. The elapsed time is measured
+/// from when the adapter is created, so create a new one for each run.
+pub struct ArgminObserver<O> {
+    observer: O,
+    start: Instant,
+    previous: Option<Vec<f64>>,
+}
+
+impl<O> ArgminObserver<O> {
+    /// Wraps `observer`, starting the clock for [`Progress::elapsed`].
+    pub fn new(observer: O) -> Self {
+        Self {
+            observer,
+            start: Instant::now(),
+            previous: None,
+        }
+    }
+}
+
+impl<T, D, O> Observe<T> for ArgminObserver<O>
+where
+    T: ArgminOp<Param = Array<f64, D>, Float = f64>,
+    D: Dimension,
+    O: Observer,
+{
+    fn observe_iter(&mut self, state: &IterState<T>, _kv: &ArgminKV) -> Result<(), Error> {
+        let param: Vec<f64> = state.param.iter().copied().collect();
+        // The first report has no previous parameters to compare to, so its step is zero.
+        let step_norm = match &self.previous {
+            Some(previous) if previous.len() == param.len() => previous
+                .iter()
+                .zip(&param)
+                .map(|(a, b)| (b - a).powi(2))
+                .sum::<f64>()
+                .sqrt(),
+            _ => 0.0,
+        };
+        self.previous = Some(param);
+        let progress = Progress {
+            iteration: state.iter as usize + 1,
+            cost: state.cost,
+            step_norm,
+            elapsed: self.start.elapsed(),
+        };
+        match self.observer.observe(&progress) {
+            Control::Continue => Ok(()),
+            Control::Cancel => Err(Error::new(Cancelled)),
+        }
+    }
+}
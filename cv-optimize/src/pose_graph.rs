@@ -0,0 +1,778 @@
+use crate::{
+    damp, DampedLeastSquares, DampedProblem, LinearSolver, NoObserver, NormalEquationsSolver,
+    Observer, RobustLoss, SparseMatrix, SquaredLoss, Termination,
+};
+use cv_core::nalgebra::{
+    DVector, IsometryMatrix3, Matrix3, Matrix6, MatrixN, Quaternion, Rotation3, UnitQuaternion,
+    Vector3, Vector6, VectorN, U1, U3, U6, U7,
+};
+use cv_core::{CameraToCamera, Pose, Se3, Sim3, WorldToCamera};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The coefficients `B_2n / (2n)!` of the even powers of the series of the inverse left Jacobian of Sim(3).
+const JACOBIAN_SERIES: [f64; 5] = [
+    1.0 / 12.0,
+    -1.0 / 720.0,
+    1.0 / 30240.0,
+    -1.0 / 1209600.0,
+    1.0 / 47900160.0,
+];
+
+/// A 7x7 matrix over sim(3), such as the information matrix of a [`PoseGraphEdge`].
+pub type Matrix7 = MatrixN<f64, U7>;
+/// A tangent vector of sim(3), with the translation components first, then the rotation, then the log of the scale.
+pub type Vector7 = VectorN<f64, U7>;
+
+/// A relative measurement between two nodes of a [`PoseGraph`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoseGraphEdge {
+    /// The node the measurement is relative to.
+    pub from: usize,
+    /// The node which is measured.
+    pub to: usize,
+    /// The transform from the camera of node `from` to the camera of node `to`.
+    ///
+    /// The scale of the measurement is only used when the graph is optimized over Sim(3).
+    pub measurement: Sim3,
+    /// The information matrix (inverse covariance) of the error of the measurement in sim(3).
+    ///
+    /// The last row and column, which correspond to the scale, are only used when the graph is optimized over Sim(3).
+    pub information: Matrix7,
+}
+
+/// A summary of a run of [`PoseGraph::optimize`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoseGraphSummary {
+    /// The number of iterations performed.
+    pub iterations: usize,
+    /// The sum of the robust loss of the squared Mahalanobis norm of the edge errors before optimization.
+    pub initial_cost: f64,
+    /// The sum of the robust loss of the squared Mahalanobis norm of the edge errors after optimization.
+    pub final_cost: f64,
+    /// Why the optimization stopped.
+    pub termination: Termination,
+}
+
+/// The error produced when a pose graph cannot be read from or written to a g2o file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum G2oError {
+    /// A line of the file could not be parsed.
+    Parse {
+        /// The line number, starting at one.
+        line: usize,
+        /// What was wrong with the line.
+        message: String,
+    },
+    /// The pose graph is optimized over Sim(3), which the `SE3:QUAT` types of g2o cannot represent.
+    Similarity,
+}
+
+impl fmt::Display for G2oError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "g2o line {}: {}", line, message),
+            Self::Similarity => write!(f, "similarity pose graphs cannot be written to g2o"),
+        }
+    }
+}
+
+impl std::error::Error for G2oError {}
+
+/// Optimizes the poses of cameras from relative measurements between them.
+///
+/// Each node is the [`WorldToCamera`] pose of a camera, and each edge is a measured [`CameraToCamera`]
+/// transform between two nodes together with its information matrix. The error of an edge is the sim(3)
+/// log of the difference between the measured and estimated relative transform, which is weighted by the
+/// information matrix and then by a [`RobustLoss`]. Levenberg-Marquardt minimizes the total error, updating
+/// each node on the manifold with a left perturbation `exp(δ) * pose`.
+///
+/// By default the nodes are optimized over SE(3). With [`PoseGraph::similarity`], they are optimized over Sim(3)
+/// instead, which allows the scale drift of a monocular reconstruction to be corrected when a loop is closed.
+/// Each edge only couples two nodes, so the normal equations are stored as a [`SparseMatrix`].
+///
+/// If no node is fixed with [`PoseGraph::fix_node`], the first node is fixed to remove the gauge freedom.
+///
+/// ```
+/// use cv_core::nalgebra::{Matrix6, Rotation3, Vector3};
+/// use cv_core::{CameraToCamera, Pose, WorldToCamera};
+/// use cv_optimize::PoseGraph;
+///
+/// // Four cameras on the corners of a square, each turned 90 degrees from the last.
+/// let step = CameraToCamera::from_parts(
+///     Vector3::new(-1.0, 0.0, 0.0),
+///     Rotation3::from_euler_angles(0.0, std::f64::consts::FRAC_PI_2, 0.0),
+/// );
+/// let mut graph = PoseGraph::new();
+/// let mut pose = WorldToCamera::identity();
+/// for i in 0..4 {
+///     // Start each node with some drift.
+///     let drift = WorldToCamera::from_parts(Vector3::new(0.05 * i as f64, 0.0, 0.0), Rotation3::identity());
+///     graph.add_node((drift.isometry() * pose.isometry()).into());
+///     pose = (step.isometry() * pose.isometry()).into();
+/// }
+/// for i in 0..4 {
+///     graph.add_edge(i, (i + 1) % 4, step, Matrix6::identity());
+/// }
+///
+/// graph.optimize();
+/// assert!(graph.cost() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoseGraph<L = SquaredLoss> {
+    nodes: Vec<Sim3>,
+    fixed: Vec<bool>,
+    edges: Vec<PoseGraphEdge>,
+    similarity: bool,
+    loss: L,
+    max_iterations: usize,
+    tolerance: f64,
+    initial_damping: f64,
+    linear_solver: LinearSolver,
+}
+
+impl PoseGraph<SquaredLoss> {
+    /// Creates an empty pose graph.
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            fixed: vec![],
+            edges: vec![],
+            similarity: false,
+            loss: SquaredLoss,
+            max_iterations: 100,
+            tolerance: 1e-10,
+            initial_damping: 1e-4,
+            linear_solver: LinearSolver::default(),
+        }
+    }
+
+    /// Reads a pose graph from the contents of a g2o file.
+    ///
+    /// The `VERTEX_SE3:QUAT`, `EDGE_SE3:QUAT`, and `FIX` types are supported. The vertices of g2o are the poses
+    /// of the cameras in the world (the inverse of [`WorldToCamera`]), and they are added as nodes in the order
+    /// they appear in the file. The information matrices are converted from the quaternion rotation error
+    /// used by g2o to the rotation vector error used by [`PoseGraph`].
+    pub fn read_g2o(file: &str) -> Result<Self, G2oError> {
+        let mut graph = Self::new();
+        let mut ids: HashMap<i64, usize> = HashMap::new();
+        for (ix, line) in file.lines().enumerate() {
+            let error = |message: String| G2oError::Parse {
+                line: ix + 1,
+                message,
+            };
+            let mut tokens = line.split_whitespace();
+            let tag = match tokens.next() {
+                Some(tag) if !tag.starts_with('#') => tag,
+                _ => continue,
+            };
+            let tokens: Vec<&str> = tokens.collect();
+            let node = |token: &str| -> Result<usize, G2oError> {
+                let id: i64 = token
+                    .parse()
+                    .map_err(|_| error(format!("invalid vertex id {:?}", token)))?;
+                ids.get(&id)
+                    .copied()
+                    .ok_or_else(|| error(format!("unknown vertex id {}", id)))
+            };
+            let numbers = |tokens: &[&str]| -> Result<Vec<f64>, G2oError> {
+                tokens
+                    .iter()
+                    .map(|token| {
+                        token
+                            .parse()
+                            .map_err(|_| error(format!("invalid number {:?}", token)))
+                    })
+                    .collect()
+            };
+            match tag {
+                "VERTEX_SE3:QUAT" => {
+                    if tokens.len() != 8 {
+                        return Err(error(format!(
+                            "expected 8 values after VERTEX_SE3:QUAT, found {}",
+                            tokens.len()
+                        )));
+                    }
+                    let id: i64 = tokens[0]
+                        .parse()
+                        .map_err(|_| error(format!("invalid vertex id {:?}", tokens[0])))?;
+                    let camera_to_world = g2o_isometry(&numbers(&tokens[1..])?);
+                    let node = graph.add_node(WorldToCamera(camera_to_world.inverse()));
+                    if ids.insert(id, node).is_some() {
+                        return Err(error(format!("duplicate vertex id {}", id)));
+                    }
+                }
+                "EDGE_SE3:QUAT" => {
+                    if tokens.len() != 30 {
+                        return Err(error(format!(
+                            "expected 30 values after EDGE_SE3:QUAT, found {}",
+                            tokens.len()
+                        )));
+                    }
+                    let from = node(tokens[0])?;
+                    let to = node(tokens[1])?;
+                    let values = numbers(&tokens[2..])?;
+                    // g2o measures the pose of `to` in the camera of `from`, which is the inverse of the measurement.
+                    let measurement = CameraToCamera(g2o_isometry(&values[..7]).inverse());
+                    let mut information = Matrix6::zeros();
+                    let mut upper = values[7..].iter();
+                    for row in 0..6 {
+                        for column in row..6 {
+                            let value = *upper.next().unwrap();
+                            information[(row, column)] = value;
+                            information[(column, row)] = value;
+                        }
+                    }
+                    let scale = g2o_rotation_scale();
+                    graph.add_edge(from, to, measurement, scale * information * scale);
+                }
+                "FIX" => {
+                    for token in &tokens {
+                        let node = node(token)?;
+                        graph.fix_node(node);
+                    }
+                }
+                _ => return Err(error(format!("unsupported type {}", tag))),
+            }
+        }
+        Ok(graph)
+    }
+}
+
+impl Default for PoseGraph<SquaredLoss> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L> PoseGraph<L> {
+    /// Sets the robust loss applied to the squared Mahalanobis norm of the error of each edge.
+    ///
+    /// Default is [`SquaredLoss`].
+    pub fn loss<L2>(self, loss: L2) -> PoseGraph<L2> {
+        PoseGraph {
+            nodes: self.nodes,
+            fixed: self.fixed,
+            edges: self.edges,
+            similarity: self.similarity,
+            loss,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            initial_damping: self.initial_damping,
+            linear_solver: self.linear_solver,
+        }
+    }
+
+    /// Sets whether the nodes are optimized over Sim(3) rather than SE(3).
+    ///
+    /// Default is `false`.
+    pub fn similarity(self, similarity: bool) -> Self {
+        Self { similarity, ..self }
+    }
+
+    /// Sets the maximum number of iterations.
+    ///
+    /// Default is `100`.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    /// Sets the relative decrease of the cost in an iteration below which the optimization has converged.
+    ///
+    /// Default is `1e-10`.
+    pub fn tolerance(self, tolerance: f64) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Sets the initial Levenberg-Marquardt damping, relative to the diagonal of the normal equations.
+    ///
+    /// Default is `1e-4`.
+    pub fn initial_damping(self, initial_damping: f64) -> Self {
+        Self {
+            initial_damping,
+            ..self
+        }
+    }
+
+    /// Sets how the normal equations are solved at each iteration.
+    ///
+    /// Default is [`LinearSolver::SparseCholesky`].
+    pub fn linear_solver(self, linear_solver: LinearSolver) -> Self {
+        Self {
+            linear_solver,
+            ..self
+        }
+    }
+
+    /// Adds a node with a pose and returns its index.
+    pub fn add_node(&mut self, pose: WorldToCamera) -> usize {
+        self.add_similarity_node(Sim3::from_isometry(pose.isometry()))
+    }
+
+    /// Adds a node with a similarity from the world to the camera and returns its index.
+    ///
+    /// The scale of the node is only changed when the graph is optimized over Sim(3).
+    pub fn add_similarity_node(&mut self, pose: Sim3) -> usize {
+        self.nodes.push(pose);
+        self.fixed.push(false);
+        self.nodes.len() - 1
+    }
+
+    /// Prevents the optimizer from changing a node.
+    pub fn fix_node(&mut self, node: usize) {
+        self.fixed[node] = true;
+    }
+
+    /// Checks if a node is fixed.
+    pub fn is_fixed(&self, node: usize) -> bool {
+        self.fixed[node]
+    }
+
+    /// Adds an edge with a measured transform from the camera of node `from` to the camera of node `to`,
+    /// and returns its index.
+    ///
+    /// The information matrix is over se(3), with the translation components before the rotation components.
+    pub fn add_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        measurement: CameraToCamera,
+        information: Matrix6<f64>,
+    ) -> usize {
+        let mut information7 = Matrix7::zeros();
+        information7
+            .fixed_slice_mut::<U6, U6>(0, 0)
+            .copy_from(&information);
+        self.add_similarity_edge(
+            from,
+            to,
+            Sim3::from_isometry(measurement.isometry()),
+            information7,
+        )
+    }
+
+    /// Adds an edge with a measured similarity from the camera of node `from` to the camera of node `to`,
+    /// and returns its index.
+    pub fn add_similarity_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        measurement: Sim3,
+        information: Matrix7,
+    ) -> usize {
+        assert!(
+            from < self.nodes.len() && to < self.nodes.len(),
+            "edge added with a node that doesn't exist"
+        );
+        self.edges.push(PoseGraphEdge {
+            from,
+            to,
+            measurement,
+            information,
+        });
+        self.edges.len() - 1
+    }
+
+    /// Retrieves the pose of a node.
+    ///
+    /// If the node has a scale other than one, the pose is of the camera in the world after it is scaled by
+    /// the inverse of the node's scale, which brings the node to the same scale as the world.
+    pub fn node(&self, node: usize) -> WorldToCamera {
+        let similarity = self.nodes[node];
+        WorldToCamera::from_parts(
+            similarity.translation() / similarity.scale(),
+            similarity.rotation(),
+        )
+    }
+
+    /// Retrieves the similarity from the world to the camera of a node.
+    pub fn similarity_node(&self, node: usize) -> Sim3 {
+        self.nodes[node]
+    }
+
+    /// Retrieves the number of nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks if there are no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Retrieves the edges in the order they were added.
+    pub fn edges(&self) -> &[PoseGraphEdge] {
+        &self.edges
+    }
+
+    /// Computes the error of an edge in sim(3).
+    pub fn edge_error(&self, edge: usize) -> Vector7 {
+        self.error(&self.nodes, &self.edges[edge])
+    }
+
+    /// Writes the pose graph to the contents of a g2o file with the same conventions as [`PoseGraph::read_g2o`].
+    ///
+    /// The index of each node is used as the id of its vertex.
+    pub fn write_g2o(&self) -> Result<String, G2oError> {
+        if self.similarity {
+            return Err(G2oError::Similarity);
+        }
+        let mut file = String::new();
+        for node in 0..self.nodes.len() {
+            let camera_to_world = self.node(node).isometry().inverse();
+            file += &format!(
+                "VERTEX_SE3:QUAT {} {}\n",
+                node,
+                g2o_isometry_values(camera_to_world)
+            );
+        }
+        for edge in &self.edges {
+            let measurement = Pose::isometry(self.edge_isometry(edge)).inverse();
+            let scale = g2o_rotation_scale()
+                .try_inverse()
+                .expect("g2o rotation scale is invertible");
+            let information: Matrix6<f64> =
+                scale * edge.information.fixed_slice::<U6, U6>(0, 0) * scale;
+            let mut upper = vec![];
+            for row in 0..6 {
+                for column in row..6 {
+                    upper.push(information[(row, column)].to_string());
+                }
+            }
+            file += &format!(
+                "EDGE_SE3:QUAT {} {} {} {}\n",
+                edge.from,
+                edge.to,
+                g2o_isometry_values(measurement),
+                upper.join(" ")
+            );
+        }
+        for node in (0..self.nodes.len()).filter(|&node| self.fixed[node]) {
+            file += &format!("FIX {}\n", node);
+        }
+        Ok(file)
+    }
+
+    fn edge_isometry(&self, edge: &PoseGraphEdge) -> CameraToCamera {
+        CameraToCamera::from_parts(edge.measurement.translation(), edge.measurement.rotation())
+    }
+
+    /// The dimension of the tangent space of each node.
+    fn dimension(&self) -> usize {
+        if self.similarity {
+            7
+        } else {
+            6
+        }
+    }
+
+    /// Computes the error `log(Z * T_from * T_to^-1)` of an edge with measurement `Z`.
+    fn error(&self, nodes: &[Sim3], edge: &PoseGraphEdge) -> Vector7 {
+        let measurement = if self.similarity {
+            edge.measurement
+        } else {
+            Sim3::from_isometry(self.edge_isometry(edge).isometry())
+        };
+        (measurement * nodes[edge.from] * nodes[edge.to].inverse()).log()
+    }
+
+    /// Computes the inverse of the left Jacobian of the log map at the error `e`.
+    fn left_jacobian_inverse(&self, e: &Vector7) -> Matrix7 {
+        let mut inverse = Matrix7::identity();
+        if self.similarity {
+            // Sim(3) has no simple closed form, so the Bernoulli series `ad / (exp(ad) - 1)` is used instead.
+            let ad = sim3_ad(e);
+            let ad2 = ad * ad;
+            let mut power = ad2;
+            inverse -= 0.5 * ad;
+            for &coefficient in &JACOBIAN_SERIES {
+                inverse += coefficient * power;
+                power *= ad2;
+            }
+        } else {
+            let se3 = Se3(e.fixed_rows::<U6>(0).into_owned());
+            inverse
+                .fixed_slice_mut::<U6, U6>(0, 0)
+                .copy_from(&se3.left_jacobian_inverse());
+        }
+        inverse
+    }
+}
+
+impl<L> PoseGraph<L>
+where
+    L: RobustLoss,
+{
+    /// Computes the sum of the robust loss of the squared Mahalanobis norm of the edge errors.
+    pub fn cost(&self) -> f64 {
+        self.cost_of(&self.nodes)
+    }
+
+    fn cost_of(&self, nodes: &[Sim3]) -> f64 {
+        self.edges
+            .iter()
+            .map(|edge| {
+                let e = self.masked(self.error(nodes, edge));
+                self.loss.loss((e.transpose() * edge.information * e)[0]).0
+            })
+            .sum()
+    }
+
+    /// Zeros the scale component of an error when optimizing over SE(3).
+    fn masked(&self, mut e: Vector7) -> Vector7 {
+        if !self.similarity {
+            e[6] = 0.0;
+        }
+        e
+    }
+
+    /// Runs Levenberg-Marquardt until it converges, stalls, or reaches the maximum number of iterations.
+    pub fn optimize(&mut self) -> PoseGraphSummary {
+        self.optimize_with(&mut NoObserver)
+    }
+
+    /// Runs [`PoseGraph::optimize`], reporting the progress to `observer` after every iteration
+    /// which decreases the cost.
+    ///
+    /// If the observer cancels, the nodes are left at their values after the last iteration.
+    pub fn optimize_with<O>(&mut self, observer: &mut O) -> PoseGraphSummary
+    where
+        O: Observer + ?Sized,
+    {
+        let dimension = self.dimension();
+        // Fix the first node if no node is fixed to remove the gauge freedom.
+        let any_fixed = self.fixed.iter().any(|&fixed| fixed);
+        let mut num_columns = 0;
+        let columns: Vec<Option<usize>> = self
+            .fixed
+            .iter()
+            .enumerate()
+            .map(|(node, &fixed)| {
+                if fixed || (!any_fixed && node == 0) {
+                    None
+                } else {
+                    num_columns += dimension;
+                    Some(num_columns - dimension)
+                }
+            })
+            .collect();
+
+        let initial_cost = self.cost();
+        let optimizer = DampedLeastSquares {
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            initial_damping: self.initial_damping,
+        };
+        let solver = NormalEquationsSolver::new(self.linear_solver, dimension);
+        let mut problem = PoseGraphProblem {
+            graph: self,
+            columns,
+            num_columns,
+            solver,
+        };
+        let summary = optimizer.minimize(&mut problem, initial_cost, observer);
+
+        PoseGraphSummary {
+            iterations: summary.iterations,
+            initial_cost,
+            final_cost: summary.final_cost,
+            termination: summary.termination,
+        }
+    }
+}
+
+/// A [`PoseGraph`] with a column of the normal equations assigned to each node which isn't fixed.
+struct PoseGraphProblem<'a, L> {
+    graph: &'a mut PoseGraph<L>,
+    columns: Vec<Option<usize>>,
+    num_columns: usize,
+    solver: NormalEquationsSolver,
+}
+
+impl<L> DampedProblem for PoseGraphProblem<'_, L>
+where
+    L: RobustLoss,
+{
+    type NormalEquations = (SparseMatrix, DVector<f64>);
+    type Step = DVector<f64>;
+    type Parameters = Vec<Sim3>;
+
+    fn linearize(&self) -> Self::NormalEquations {
+        let graph = &*self.graph;
+        let columns = &self.columns;
+        let dimension = graph.dimension();
+        // Accumulate the normal equations of every edge.
+        let mut triplets: Vec<(usize, usize, f64)> =
+            (0..self.num_columns).map(|i| (i, i, 0.0)).collect();
+        let mut gradient = DVector::<f64>::zeros(self.num_columns);
+        for edge in &graph.edges {
+            let e = graph.masked(graph.error(&graph.nodes, edge));
+            let weight = graph.loss.weight((e.transpose() * edge.information * e)[0]);
+            let information = edge.information * weight;
+            let jacobian_inverse = graph.left_jacobian_inverse(&e);
+            let measurement = if graph.similarity {
+                edge.measurement
+            } else {
+                Sim3::from_isometry(graph.edge_isometry(edge).isometry())
+            };
+            let error_similarity = Sim3::exp(e);
+            let blocks = [
+                (
+                    columns[edge.from],
+                    jacobian_inverse * sim3_adjoint(measurement),
+                ),
+                (
+                    columns[edge.to],
+                    -jacobian_inverse * sim3_adjoint(error_similarity),
+                ),
+            ];
+            for &(column_a, jacobian_a) in &blocks {
+                let column_a = match column_a {
+                    Some(column_a) => column_a,
+                    None => continue,
+                };
+                let weighted = jacobian_a.transpose() * information;
+                let g = weighted * e;
+                for i in 0..dimension {
+                    gradient[column_a + i] += g[i];
+                }
+                for &(column_b, jacobian_b) in &blocks {
+                    let column_b = match column_b {
+                        Some(column_b) => column_b,
+                        None => continue,
+                    };
+                    let block = weighted * jacobian_b;
+                    for i in 0..dimension {
+                        for j in 0..dimension {
+                            triplets.push((column_a + i, column_b + j, block[(i, j)]));
+                        }
+                    }
+                }
+            }
+        }
+        let hessian = SparseMatrix::from_triplets(self.num_columns, self.num_columns, &triplets);
+        (hessian, gradient)
+    }
+
+    fn solve(
+        &mut self,
+        (hessian, gradient): &Self::NormalEquations,
+        damping: f64,
+    ) -> Option<Self::Step> {
+        let mut damped = hessian.clone();
+        damped.add_diagonal(&hessian.diagonal().map(|d| damp(d, damping)));
+        self.solver.solve(&damped, &-gradient)
+    }
+
+    fn step_norm(&self, step: &Self::Step) -> f64 {
+        step.norm()
+    }
+
+    fn parameter_norm(&self) -> f64 {
+        self.graph
+            .nodes
+            .iter()
+            .map(|node| node.translation().norm_squared())
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn apply(&self, step: &Self::Step) -> Self::Parameters {
+        let dimension = self.graph.dimension();
+        self.graph
+            .nodes
+            .iter()
+            .zip(&self.columns)
+            .map(|(&node, &column)| match column {
+                Some(column) => {
+                    let mut delta = Vector7::zeros();
+                    for i in 0..dimension {
+                        delta[i] = step[column + i];
+                    }
+                    Sim3::exp(delta) * node
+                }
+                None => node,
+            })
+            .collect()
+    }
+
+    fn cost(&self, nodes: &Self::Parameters) -> Option<f64> {
+        Some(self.graph.cost_of(nodes))
+    }
+
+    fn accept(&mut self, nodes: Self::Parameters) {
+        self.graph.nodes = nodes;
+    }
+}
+
+/// The adjoint of a similarity, which maps a tangent on its right side to a tangent on its left side.
+fn sim3_adjoint(similarity: Sim3) -> Matrix7 {
+    let rotation = *similarity.rotation().matrix();
+    let translation = similarity.translation();
+    let mut adjoint = Matrix7::zeros();
+    adjoint
+        .fixed_slice_mut::<U3, U3>(0, 0)
+        .copy_from(&(rotation * similarity.scale()));
+    adjoint
+        .fixed_slice_mut::<U3, U3>(0, 3)
+        .copy_from(&(translation.cross_matrix() * rotation));
+    adjoint
+        .fixed_slice_mut::<U3, U1>(0, 6)
+        .copy_from(&-translation);
+    adjoint.fixed_slice_mut::<U3, U3>(3, 3).copy_from(&rotation);
+    adjoint[(6, 6)] = 1.0;
+    adjoint
+}
+
+/// The matrix form of the lie bracket of sim(3), `ad(a) * b = [a, b]`.
+fn sim3_ad(tangent: &Vector7) -> Matrix7 {
+    let rho = Vector3::new(tangent[0], tangent[1], tangent[2]);
+    let omega = Vector3::new(tangent[3], tangent[4], tangent[5]);
+    let sigma = tangent[6];
+    let omega_hat = omega.cross_matrix();
+    let mut ad = Matrix7::zeros();
+    ad.fixed_slice_mut::<U3, U3>(0, 0)
+        .copy_from(&(omega_hat + Matrix3::identity() * sigma));
+    ad.fixed_slice_mut::<U3, U3>(0, 3)
+        .copy_from(&rho.cross_matrix());
+    ad.fixed_slice_mut::<U3, U1>(0, 6).copy_from(&-rho);
+    ad.fixed_slice_mut::<U3, U3>(3, 3).copy_from(&omega_hat);
+    ad
+}
+
+/// Scales the rotation block of an information matrix, since g2o uses the vector part of a unit quaternion
+/// as the rotation error, which is half of the rotation vector for small rotations.
+fn g2o_rotation_scale() -> Matrix6<f64> {
+    Matrix6::from_diagonal(&Vector6::new(1.0, 1.0, 1.0, 0.5, 0.5, 0.5))
+}
+
+/// Creates an isometry from the `x y z qx qy qz qw` values of g2o.
+fn g2o_isometry(values: &[f64]) -> IsometryMatrix3<f64> {
+    let rotation: Rotation3<f64> = UnitQuaternion::from_quaternion(Quaternion::new(
+        values[6], values[3], values[4], values[5],
+    ))
+    .to_rotation_matrix();
+    IsometryMatrix3::from_parts(
+        Vector3::new(values[0], values[1], values[2]).into(),
+        rotation,
+    )
+}
+
+/// Formats an isometry as the `x y z qx qy qz qw` values of g2o.
+fn g2o_isometry_values(isometry: IsometryMatrix3<f64>) -> String {
+    let translation = isometry.translation.vector;
+    let quaternion = UnitQuaternion::from_rotation_matrix(&isometry.rotation);
+    format!(
+        "{} {} {} {} {} {} {}",
+        translation.x,
+        translation.y,
+        translation.z,
+        quaternion.i,
+        quaternion.j,
+        quaternion.k,
+        quaternion.w
+    )
+}
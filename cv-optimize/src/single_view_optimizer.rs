@@ -1,3 +1,4 @@
+use crate::{cosine_distance_cost, RobustLoss, TruncatedLoss};
 use argmin::{
     core::{ArgminOp, Error},
     solver::neldermead::NelderMead,
@@ -31,8 +32,8 @@ pub fn single_view_nelder_mead(pose: WorldToCamera) -> NelderMead<Array1<f64>, f
 }
 
 #[derive(Clone)]
-pub struct SingleViewConstraint<B> {
-    loss_cutoff: f64,
+pub struct SingleViewConstraint<B, L = TruncatedLoss> {
+    loss: L,
     landmarks: Vec<FeatureWorldMatch<B>>,
 }
 
@@ -50,24 +51,31 @@ where
     /// ambiguity between "camera tracking" and "a track".
     pub fn new(landmarks: Vec<FeatureWorldMatch<B>>) -> Self {
         Self {
-            loss_cutoff: 0.0001,
+            loss: TruncatedLoss(0.0001),
             landmarks,
         }
     }
+}
 
-    pub fn loss_cutoff(self, loss_cutoff: f64) -> Self {
-        Self {
-            loss_cutoff,
-            ..self
+impl<B, L> SingleViewConstraint<B, L>
+where
+    B: Bearing + Clone,
+    L: RobustLoss,
+{
+    /// Sets the robust loss applied to the cosine distance of each landmark, which is the residual.
+    ///
+    /// Default is `TruncatedLoss(0.0001)`.
+    pub fn loss<L2>(self, loss: L2) -> SingleViewConstraint<B, L2> {
+        SingleViewConstraint {
+            loss,
+            landmarks: self.landmarks,
         }
     }
 
-    fn loss(&self, n: f64) -> f64 {
-        if n < self.loss_cutoff {
-            n
-        } else {
-            self.loss_cutoff
-        }
+    /// Caps the cosine distance of each landmark at `loss_cutoff`.
+    #[deprecated(note = "use `loss(TruncatedLoss(loss_cutoff))` instead")]
+    pub fn loss_cutoff(self, loss_cutoff: f64) -> SingleViewConstraint<B> {
+        self.loss(TruncatedLoss(loss_cutoff))
     }
 
     pub fn residuals<'a>(&'a self, pose: WorldToCamera) -> impl Iterator<Item = f64> + 'a {
@@ -75,14 +83,18 @@ where
             .iter()
             .map(move |FeatureWorldMatch(bearing, world_point)| {
                 let camera_point = pose.transform(*world_point);
-                self.loss(1.0 - bearing.bearing().dot(&camera_point.bearing()))
+                cosine_distance_cost(
+                    &self.loss,
+                    1.0 - bearing.bearing().dot(&camera_point.bearing()),
+                )
             })
     }
 }
 
-impl<B> ArgminOp for SingleViewConstraint<B>
+impl<B, L> ArgminOp for SingleViewConstraint<B, L>
 where
     B: Bearing + Clone,
+    L: RobustLoss,
 {
     type Param = Array1<f64>;
     type Output = f64;
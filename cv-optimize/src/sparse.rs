@@ -0,0 +1,709 @@
+use cv_core::nalgebra::{DMatrix, DVector};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+
+/// A sparse matrix in compressed sparse column (CSC) form.
+///
+/// Only the entries that are stored take up memory, which makes it possible to represent the Jacobians and
+/// normal equations of problems with many parameters, such as bundle adjustment and pose graphs, where each
+/// residual only depends on a few parameters.
+///
+/// ```
+/// use cv_core::nalgebra::DVector;
+/// use cv_optimize::{SparseCholesky, SparseMatrix};
+///
+/// // A Jacobian where each residual depends on two neighboring parameters.
+/// let jacobian = SparseMatrix::from_triplets(
+///     3,
+///     3,
+///     &[(0, 0, 2.0), (0, 1, -1.0), (1, 1, 2.0), (1, 2, -1.0), (2, 2, 2.0)],
+/// );
+/// let residuals = DVector::from_column_slice(&[1.0, 2.0, 3.0]);
+/// let (hessian, gradient) = jacobian.normal_equations(&residuals);
+/// let step = SparseCholesky::new(&hessian).unwrap().solve(&-gradient);
+/// assert!((jacobian.mul_vector(&step) + residuals).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix {
+    nrows: usize,
+    ncols: usize,
+    /// The index into `row_indices` and `values` where each column starts, followed by the number of entries.
+    column_offsets: Vec<usize>,
+    /// The row of each entry, sorted within each column.
+    row_indices: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl SparseMatrix {
+    /// Creates a matrix from `(row, column, value)` triplets in any order.
+    ///
+    /// Triplets with the same row and column are summed, which allows blocks to be accumulated into the matrix.
+    ///
+    /// Panics if a triplet is outside of the matrix.
+    pub fn from_triplets(nrows: usize, ncols: usize, triplets: &[(usize, usize, f64)]) -> Self {
+        let mut sorted = triplets.to_vec();
+        sorted.sort_unstable_by_key(|&(row, column, _)| (column, row));
+        let mut column_offsets = vec![0; ncols + 1];
+        let mut row_indices: Vec<usize> = Vec::with_capacity(sorted.len());
+        let mut values: Vec<f64> = Vec::with_capacity(sorted.len());
+        let mut last = None;
+        for (row, column, value) in sorted {
+            assert!(
+                row < nrows && column < ncols,
+                "triplet ({}, {}) is outside of a {}x{} matrix",
+                row,
+                column,
+                nrows,
+                ncols
+            );
+            if last == Some((row, column)) {
+                *values.last_mut().unwrap() += value;
+            } else {
+                row_indices.push(row);
+                values.push(value);
+                column_offsets[column + 1] += 1;
+                last = Some((row, column));
+            }
+        }
+        for column in 0..ncols {
+            column_offsets[column + 1] += column_offsets[column];
+        }
+        Self {
+            nrows,
+            ncols,
+            column_offsets,
+            row_indices,
+            values,
+        }
+    }
+
+    /// Creates a sparse matrix from the nonzero entries of a dense matrix.
+    pub fn from_dense(matrix: &DMatrix<f64>) -> Self {
+        let triplets: Vec<(usize, usize, f64)> = (0..matrix.ncols())
+            .flat_map(|column| (0..matrix.nrows()).map(move |row| (row, column)))
+            .filter(|&(row, column)| matrix[(row, column)] != 0.0)
+            .map(|(row, column)| (row, column, matrix[(row, column)]))
+            .collect();
+        Self::from_triplets(matrix.nrows(), matrix.ncols(), &triplets)
+    }
+
+    /// Converts the matrix to a dense matrix.
+    pub fn to_dense(&self) -> DMatrix<f64> {
+        let mut dense = DMatrix::zeros(self.nrows, self.ncols);
+        for (row, column, value) in self.triplets() {
+            dense[(row, column)] = value;
+        }
+        dense
+    }
+
+    /// The number of rows.
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// The number of columns.
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// The number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Iterates over the `(row, column, value)` of every stored entry in column-major order.
+    pub fn triplets(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        (0..self.ncols).flat_map(move |column| {
+            self.column_range(column)
+                .map(move |p| (self.row_indices[p], column, self.values[p]))
+        })
+    }
+
+    /// Retrieves an entry, which is zero if it isn't stored.
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.position(row, column)
+            .map(|p| self.values[p])
+            .unwrap_or(0.0)
+    }
+
+    /// Computes `A * x`.
+    pub fn mul_vector(&self, x: &DVector<f64>) -> DVector<f64> {
+        assert_eq!(x.len(), self.ncols, "vector has the wrong dimension");
+        let mut y = DVector::zeros(self.nrows);
+        for (row, column, value) in self.triplets() {
+            y[row] += value * x[column];
+        }
+        y
+    }
+
+    /// Computes `A^T * x`.
+    pub fn transpose_mul_vector(&self, x: &DVector<f64>) -> DVector<f64> {
+        assert_eq!(x.len(), self.nrows, "vector has the wrong dimension");
+        DVector::from_iterator(
+            self.ncols,
+            (0..self.ncols).map(|column| {
+                self.column_range(column)
+                    .map(|p| self.values[p] * x[self.row_indices[p]])
+                    .sum::<f64>()
+            }),
+        )
+    }
+
+    /// Computes the transpose of the matrix.
+    pub fn transpose(&self) -> Self {
+        let triplets: Vec<(usize, usize, f64)> = self
+            .triplets()
+            .map(|(row, column, value)| (column, row, value))
+            .collect();
+        Self::from_triplets(self.ncols, self.nrows, &triplets)
+    }
+
+    /// Computes the normal equations `J^T * J` and `J^T * r` of a Jacobian `J` and the residuals `r`.
+    ///
+    /// The product `J^T * J` only has entries between parameters that share a residual, so it stays sparse.
+    pub fn normal_equations(&self, residuals: &DVector<f64>) -> (Self, DVector<f64>) {
+        let rows = self.transpose();
+        let mut triplets = vec![];
+        for row in 0..rows.ncols {
+            for a in rows.column_range(row) {
+                for b in rows.column_range(row) {
+                    triplets.push((
+                        rows.row_indices[a],
+                        rows.row_indices[b],
+                        rows.values[a] * rows.values[b],
+                    ));
+                }
+            }
+        }
+        (
+            Self::from_triplets(self.ncols, self.ncols, &triplets),
+            self.transpose_mul_vector(residuals),
+        )
+    }
+
+    /// Retrieves the diagonal of the matrix.
+    pub fn diagonal(&self) -> DVector<f64> {
+        DVector::from_iterator(
+            self.nrows.min(self.ncols),
+            (0..self.nrows.min(self.ncols)).map(|i| self.get(i, i)),
+        )
+    }
+
+    /// Adds a vector to the diagonal of the matrix, storing any diagonal entries that are missing.
+    pub fn add_diagonal(&mut self, diagonal: &DVector<f64>) {
+        assert_eq!(
+            diagonal.len(),
+            self.nrows.min(self.ncols),
+            "diagonal has the wrong dimension"
+        );
+        let positions: Option<Vec<usize>> =
+            (0..diagonal.len()).map(|i| self.position(i, i)).collect();
+        match positions {
+            Some(positions) => {
+                for (i, p) in positions.into_iter().enumerate() {
+                    self.values[p] += diagonal[i];
+                }
+            }
+            None => {
+                let triplets: Vec<(usize, usize, f64)> = self
+                    .triplets()
+                    .chain(diagonal.iter().enumerate().map(|(i, &value)| (i, i, value)))
+                    .collect();
+                *self = Self::from_triplets(self.nrows, self.ncols, &triplets);
+            }
+        }
+    }
+
+    /// Adds a value to a stored entry, which allows normal equations to be accumulated into a matrix created
+    /// with their sparsity pattern.
+    ///
+    /// Panics if the entry is not stored, even if it is zero.
+    pub fn add_to(&mut self, row: usize, column: usize, value: f64) {
+        let p = self
+            .position(row, column)
+            .expect("entry is not in the sparsity pattern");
+        self.values[p] += value;
+    }
+
+    fn column_range(&self, column: usize) -> std::ops::Range<usize> {
+        self.column_offsets[column]..self.column_offsets[column + 1]
+    }
+
+    fn position(&self, row: usize, column: usize) -> Option<usize> {
+        let range = self.column_range(column);
+        self.row_indices[range.clone()]
+            .binary_search(&row)
+            .ok()
+            .map(|offset| range.start + offset)
+    }
+
+    /// Checks if two matrices store entries in the same positions.
+    fn same_pattern(&self, other: &Self) -> bool {
+        self.nrows == other.nrows
+            && self.ncols == other.ncols
+            && self.column_offsets == other.column_offsets
+            && self.row_indices == other.row_indices
+    }
+}
+
+/// The analysis of the sparsity pattern of a symmetric matrix, which can be reused to factor any matrix with
+/// the same pattern.
+///
+/// Optimizers solve normal equations with the same pattern at every iteration, so the ordering and the
+/// structure of the factor only need to be computed once.
+#[derive(Debug, Clone)]
+pub struct SymbolicCholesky {
+    pattern: SparseMatrix,
+    /// The fill-reducing permutation, where `permutation[new] = old`.
+    permutation: Vec<usize>,
+    /// The parent of each column in the elimination tree of the permuted matrix.
+    parent: Vec<Option<usize>>,
+    /// The index where each column of the factor starts, followed by the number of entries.
+    column_offsets: Vec<usize>,
+}
+
+impl SymbolicCholesky {
+    /// Analyzes the sparsity pattern of a symmetric matrix.
+    ///
+    /// The rows and columns are reordered with a minimum degree ordering to reduce the fill of the factor.
+    ///
+    /// Panics if the matrix is not square.
+    pub fn new(matrix: &SparseMatrix) -> Self {
+        assert_eq!(matrix.nrows, matrix.ncols, "matrix must be square");
+        let n = matrix.ncols;
+        let permutation = minimum_degree(matrix);
+        let permuted = permute(matrix, &permutation);
+
+        // Compute the elimination tree and the number of entries in each column of the factor.
+        let mut parent = vec![None; n];
+        let mut flag = vec![0; n];
+        let mut counts = vec![0; n];
+        for k in 0..n {
+            flag[k] = k;
+            for p in permuted.column_range(k) {
+                let mut i = permuted.row_indices[p];
+                if i >= k {
+                    continue;
+                }
+                while flag[i] != k {
+                    let next = *parent[i].get_or_insert(k);
+                    counts[i] += 1;
+                    flag[i] = k;
+                    i = next;
+                }
+            }
+        }
+        let mut column_offsets = vec![0; n + 1];
+        for k in 0..n {
+            column_offsets[k + 1] = column_offsets[k] + counts[k];
+        }
+
+        Self {
+            pattern: matrix.clone(),
+            permutation,
+            parent,
+            column_offsets,
+        }
+    }
+
+    /// The number of entries below the diagonal of the factor.
+    pub fn nnz(&self) -> usize {
+        *self.column_offsets.last().unwrap()
+    }
+
+    /// Checks if a matrix has the same sparsity pattern as the analyzed matrix.
+    pub fn matches(&self, matrix: &SparseMatrix) -> bool {
+        self.pattern.same_pattern(matrix)
+    }
+
+    /// Factors a symmetric matrix with the analyzed sparsity pattern.
+    ///
+    /// Returns `None` if the matrix is not positive definite.
+    ///
+    /// Panics if the matrix doesn't have the analyzed pattern.
+    pub fn factor(&self, matrix: &SparseMatrix) -> Option<SparseCholesky> {
+        assert!(
+            self.matches(matrix),
+            "matrix doesn't have the analyzed sparsity pattern"
+        );
+        let n = matrix.ncols;
+        let permuted = permute(matrix, &self.permutation);
+        let nnz = self.nnz();
+        let mut row_indices = vec![0; nnz];
+        let mut values = vec![0.0; nnz];
+        let mut diagonal = DVector::zeros(n);
+        let mut y = vec![0.0; n];
+        let mut pattern = vec![0; n];
+        let mut flag = vec![0; n];
+        let mut counts = vec![0; n];
+
+        // Compute each row of the factor by solving a triangular system with the previous rows (up-looking LDL^T).
+        for k in 0..n {
+            let mut top = n;
+            flag[k] = k;
+            for p in permuted.column_range(k) {
+                let mut i = permuted.row_indices[p];
+                if i > k {
+                    continue;
+                }
+                y[i] += permuted.values[p];
+                let mut len = 0;
+                while flag[i] != k {
+                    pattern[len] = i;
+                    len += 1;
+                    flag[i] = k;
+                    i = self.parent[i].expect("elimination tree reaches every row");
+                }
+                while len > 0 {
+                    len -= 1;
+                    top -= 1;
+                    pattern[top] = pattern[len];
+                }
+            }
+            diagonal[k] = y[k];
+            y[k] = 0.0;
+            for &i in &pattern[top..n] {
+                let yi = y[i];
+                y[i] = 0.0;
+                let end = self.column_offsets[i] + counts[i];
+                for p in self.column_offsets[i]..end {
+                    y[row_indices[p]] -= values[p] * yi;
+                }
+                let l_ki = yi / diagonal[i];
+                diagonal[k] -= l_ki * yi;
+                row_indices[end] = k;
+                values[end] = l_ki;
+                counts[i] += 1;
+            }
+            if diagonal[k].is_nan() || diagonal[k] <= 0.0 {
+                return None;
+            }
+        }
+
+        Some(SparseCholesky {
+            permutation: self.permutation.clone(),
+            column_offsets: self.column_offsets.clone(),
+            row_indices,
+            values,
+            diagonal,
+        })
+    }
+}
+
+/// The factorization `P * A * P^T = L * D * L^T` of a sparse symmetric positive-definite matrix `A`, which is
+/// the Cholesky factorization without square roots.
+#[derive(Debug, Clone)]
+pub struct SparseCholesky {
+    permutation: Vec<usize>,
+    column_offsets: Vec<usize>,
+    row_indices: Vec<usize>,
+    values: Vec<f64>,
+    diagonal: DVector<f64>,
+}
+
+impl SparseCholesky {
+    /// Analyzes and factors a symmetric matrix.
+    ///
+    /// Use [`SymbolicCholesky`] instead to factor several matrices with the same pattern.
+    ///
+    /// Returns `None` if the matrix is not positive definite.
+    pub fn new(matrix: &SparseMatrix) -> Option<Self> {
+        SymbolicCholesky::new(matrix).factor(matrix)
+    }
+
+    /// Solves `A * x = b`.
+    pub fn solve(&self, b: &DVector<f64>) -> DVector<f64> {
+        let n = self.diagonal.len();
+        assert_eq!(b.len(), n, "vector has the wrong dimension");
+        let mut x = DVector::from_iterator(n, self.permutation.iter().map(|&old| b[old]));
+        for j in 0..n {
+            for p in self.column_offsets[j]..self.column_offsets[j + 1] {
+                x[self.row_indices[p]] -= self.values[p] * x[j];
+            }
+        }
+        x.component_div_assign(&self.diagonal);
+        for j in (0..n).rev() {
+            for p in self.column_offsets[j]..self.column_offsets[j + 1] {
+                x[j] -= self.values[p] * x[self.row_indices[p]];
+            }
+        }
+        let mut solution = DVector::zeros(n);
+        for (new, &old) in self.permutation.iter().enumerate() {
+            solution[old] = x[new];
+        }
+        solution
+    }
+
+    /// The diagonal `D` of the factorization, in the permuted order.
+    pub fn diagonal(&self) -> &DVector<f64> {
+        &self.diagonal
+    }
+}
+
+/// Computes `P * A * P^T`, where `permutation[new] = old`.
+fn permute(matrix: &SparseMatrix, permutation: &[usize]) -> SparseMatrix {
+    let mut inverse = vec![0; permutation.len()];
+    for (new, &old) in permutation.iter().enumerate() {
+        inverse[old] = new;
+    }
+    let triplets: Vec<(usize, usize, f64)> = matrix
+        .triplets()
+        .map(|(row, column, value)| (inverse[row], inverse[column], value))
+        .collect();
+    SparseMatrix::from_triplets(matrix.nrows, matrix.ncols, &triplets)
+}
+
+/// Orders the rows and columns of a symmetric matrix by repeatedly eliminating the one with the fewest neighbors.
+fn minimum_degree(matrix: &SparseMatrix) -> Vec<usize> {
+    let n = matrix.ncols;
+    let mut neighbors = vec![BTreeSet::new(); n];
+    for (row, column, _) in matrix.triplets() {
+        if row != column {
+            neighbors[row].insert(column);
+            neighbors[column].insert(row);
+        }
+    }
+    let mut eliminated = vec![false; n];
+    let mut queue: BinaryHeap<Reverse<(usize, usize)>> = neighbors
+        .iter()
+        .enumerate()
+        .map(|(i, neighbors)| Reverse((neighbors.len(), i)))
+        .collect();
+    let mut permutation = Vec::with_capacity(n);
+    while let Some(Reverse((degree, i))) = queue.pop() {
+        // Skip entries which were superseded when the degree changed.
+        if eliminated[i] || degree != neighbors[i].len() {
+            continue;
+        }
+        eliminated[i] = true;
+        permutation.push(i);
+        // Eliminating a node connects all of its neighbors to each other.
+        let clique: Vec<usize> = std::mem::take(&mut neighbors[i]).into_iter().collect();
+        for &a in &clique {
+            neighbors[a].remove(&i);
+            for &b in &clique {
+                if a != b {
+                    neighbors[a].insert(b);
+                }
+            }
+            queue.push(Reverse((neighbors[a].len(), a)));
+        }
+    }
+    permutation
+}
+
+/// A preconditioner for [`conjugate_gradient`], which approximates the inverse of the matrix.
+pub trait Preconditioner {
+    /// Applies the approximate inverse to a residual.
+    fn precondition(&self, residual: &DVector<f64>) -> DVector<f64>;
+}
+
+/// Preconditions with the inverse of the diagonal of the matrix.
+#[derive(Debug, Clone)]
+pub struct JacobiPreconditioner {
+    inverse_diagonal: DVector<f64>,
+}
+
+impl JacobiPreconditioner {
+    /// Creates the preconditioner of a symmetric matrix.
+    ///
+    /// Rows with a diagonal entry that isn't positive are left unscaled.
+    pub fn new(matrix: &SparseMatrix) -> Self {
+        Self {
+            inverse_diagonal: matrix
+                .diagonal()
+                .map(|value| if value > 0.0 { value.recip() } else { 1.0 }),
+        }
+    }
+}
+
+impl Preconditioner for JacobiPreconditioner {
+    fn precondition(&self, residual: &DVector<f64>) -> DVector<f64> {
+        residual.component_mul(&self.inverse_diagonal)
+    }
+}
+
+/// Preconditions with the inverse of the blocks on the diagonal of the matrix, such as the parameters of a
+/// single pose or camera.
+#[derive(Debug, Clone)]
+pub struct BlockJacobiPreconditioner {
+    block_size: usize,
+    inverse_blocks: Vec<DMatrix<f64>>,
+}
+
+impl BlockJacobiPreconditioner {
+    /// Creates the preconditioner of a symmetric matrix with blocks of `block_size` rows and columns.
+    ///
+    /// The last block is smaller if the dimension is not a multiple of `block_size`. Blocks which are not
+    /// positive definite fall back to the inverse of their diagonal.
+    pub fn new(matrix: &SparseMatrix, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must be positive");
+        let n = matrix.ncols;
+        let inverse_blocks = (0..n)
+            .step_by(block_size)
+            .map(|start| {
+                let size = block_size.min(n - start);
+                let block = DMatrix::from_fn(size, size, |row, column| {
+                    matrix.get(start + row, start + column)
+                });
+                block
+                    .clone()
+                    .cholesky()
+                    .map(|c| c.inverse())
+                    .unwrap_or_else(|| {
+                        DMatrix::from_diagonal(&block.diagonal().map(|value| {
+                            if value > 0.0 {
+                                value.recip()
+                            } else {
+                                1.0
+                            }
+                        }))
+                    })
+            })
+            .collect();
+        Self {
+            block_size,
+            inverse_blocks,
+        }
+    }
+}
+
+impl Preconditioner for BlockJacobiPreconditioner {
+    fn precondition(&self, residual: &DVector<f64>) -> DVector<f64> {
+        let mut result = DVector::zeros(residual.len());
+        for (ix, inverse) in self.inverse_blocks.iter().enumerate() {
+            let start = ix * self.block_size;
+            let size = inverse.nrows();
+            let block = inverse * residual.rows(start, size);
+            result.rows_mut(start, size).copy_from(&block);
+        }
+        result
+    }
+}
+
+/// The result of [`conjugate_gradient`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConjugateGradientReport {
+    /// The approximate solution.
+    pub solution: DVector<f64>,
+    /// The number of iterations performed.
+    pub iterations: usize,
+    /// The norm of the residual `b - A * x` of the solution relative to the norm of `b`.
+    pub relative_residual: f64,
+}
+
+/// Solves `A * x = b` for a symmetric positive-definite matrix `A` with the preconditioned conjugate gradient
+/// method, starting from zero.
+///
+/// Only products with `A` are needed, so unlike [`SparseCholesky`] there is no fill, at the cost of an inexact
+/// solution. The iteration stops once the norm of the residual is below `tolerance` relative to the norm of `b`,
+/// or after `max_iterations`.
+pub fn conjugate_gradient<P: Preconditioner>(
+    matrix: &SparseMatrix,
+    b: &DVector<f64>,
+    preconditioner: &P,
+    max_iterations: usize,
+    tolerance: f64,
+) -> ConjugateGradientReport {
+    let b_norm = b.norm();
+    let mut x = DVector::zeros(b.len());
+    if b_norm == 0.0 {
+        return ConjugateGradientReport {
+            solution: x,
+            iterations: 0,
+            relative_residual: 0.0,
+        };
+    }
+    let mut r = b.clone();
+    let mut z = preconditioner.precondition(&r);
+    let mut p = z.clone();
+    let mut rz = r.dot(&z);
+    let mut iterations = 0;
+    while iterations < max_iterations && r.norm() > tolerance * b_norm {
+        iterations += 1;
+        let ap = matrix.mul_vector(&p);
+        let pap = p.dot(&ap);
+        if pap.is_nan() || pap <= 0.0 {
+            break;
+        }
+        let alpha = rz / pap;
+        x.axpy(alpha, &p, 1.0);
+        r.axpy(-alpha, &ap, 1.0);
+        z = preconditioner.precondition(&r);
+        let rz_next = r.dot(&z);
+        p = &z + &p * (rz_next / rz);
+        rz = rz_next;
+    }
+    ConjugateGradientReport {
+        solution: x,
+        iterations,
+        relative_residual: r.norm() / b_norm,
+    }
+}
+
+/// The method used by an optimizer to solve the normal equations at each iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LinearSolver {
+    /// Factors the normal equations with [`SparseCholesky`], which is exact.
+    #[default]
+    SparseCholesky,
+    /// Solves the normal equations with [`conjugate_gradient`], preconditioned by [`BlockJacobiPreconditioner`]
+    /// with the parameters of each pose or camera as a block.
+    ///
+    /// This uses less memory than [`LinearSolver::SparseCholesky`] when the factor would fill in, but the inexact
+    /// steps may need more iterations of the optimizer.
+    ConjugateGradient {
+        /// The maximum number of conjugate gradient iterations per step.
+        max_iterations: usize,
+        /// The residual relative to the right-hand side below which the step is accepted.
+        tolerance: f64,
+    },
+}
+
+/// Solves normal equations with a [`LinearSolver`], reusing the analysis of the sparsity pattern between solves.
+#[derive(Debug, Clone)]
+pub(crate) struct NormalEquationsSolver {
+    solver: LinearSolver,
+    block_size: usize,
+    symbolic: Option<SymbolicCholesky>,
+}
+
+impl NormalEquationsSolver {
+    pub(crate) fn new(solver: LinearSolver, block_size: usize) -> Self {
+        Self {
+            solver,
+            block_size: block_size.max(1),
+            symbolic: None,
+        }
+    }
+
+    /// Solves `A * x = b`, returning `None` if `A` is not positive definite.
+    pub(crate) fn solve(
+        &mut self,
+        matrix: &SparseMatrix,
+        b: &DVector<f64>,
+    ) -> Option<DVector<f64>> {
+        match self.solver {
+            LinearSolver::SparseCholesky => {
+                if !matches!(&self.symbolic, Some(symbolic) if symbolic.matches(matrix)) {
+                    self.symbolic = Some(SymbolicCholesky::new(matrix));
+                }
+                let symbolic = self.symbolic.as_ref().unwrap();
+                symbolic.factor(matrix).map(|factor| factor.solve(b))
+            }
+            LinearSolver::ConjugateGradient {
+                max_iterations,
+                tolerance,
+            } => {
+                let preconditioner = BlockJacobiPreconditioner::new(matrix, self.block_size);
+                let report =
+                    conjugate_gradient(matrix, b, &preconditioner, max_iterations, tolerance);
+                if report.solution.iter().all(|value| value.is_finite()) {
+                    Some(report.solution)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
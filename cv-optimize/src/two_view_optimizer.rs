@@ -1,3 +1,7 @@
+use crate::{
+    cosine_distance_cost, BearingLinearization, CovarianceProblem, MarginalCovariances, Reweight,
+    RobustLoss, SquaredLoss, TruncatedLoss,
+};
 use argmin::{
     core::{ArgminOp, Error},
     solver::neldermead::NelderMead,
@@ -6,7 +10,7 @@ use average::Mean;
 use core::iter::once;
 use cv_core::nalgebra::{
     dimension::{Dynamic, U1, U4, U6},
-    DMatrix, DVector, Matrix3, VecStorage, Vector4, Vector6,
+    DMatrix, DVector, Matrix3, Matrix4, Matrix4x6, VecStorage, Vector4, Vector6,
 };
 use cv_core::{
     Bearing, CameraPoint, CameraToCamera, FeatureMatch, Pose, Projective, TriangulatorRelative,
@@ -38,8 +42,8 @@ pub fn two_view_nelder_mead(pose: CameraToCamera) -> NelderMead<Array1<f64>, f64
 }
 
 #[derive(Clone)]
-pub struct TwoViewConstraint<I, T> {
-    loss_cutoff: f64,
+pub struct TwoViewConstraint<I, T, L = TruncatedLoss> {
+    loss: L,
     matches: I,
     triangulator: T,
 }
@@ -52,23 +56,42 @@ where
 {
     pub fn new(matches: I, triangulator: T) -> Self {
         Self {
-            loss_cutoff: 0.001,
+            loss: TruncatedLoss(0.001),
             matches,
             triangulator,
         }
     }
+}
 
-    pub fn loss_cutoff(self, loss_cutoff: f64) -> Self {
-        Self {
-            loss_cutoff,
-            ..self
+impl<I, P, T, L> TwoViewConstraint<I, T, L>
+where
+    I: Iterator<Item = FeatureMatch<P>> + Clone,
+    P: Bearing,
+    T: TriangulatorRelative,
+    L: RobustLoss,
+{
+    /// Sets the robust loss applied to the cosine distance of each bearing, which is the residual.
+    ///
+    /// Default is `TruncatedLoss(0.001)`.
+    pub fn loss<L2>(self, loss: L2) -> TwoViewConstraint<I, T, L2> {
+        TwoViewConstraint {
+            loss,
+            matches: self.matches,
+            triangulator: self.triangulator,
         }
     }
 
+    /// Caps the cosine distance of each bearing at `loss_cutoff`.
+    #[deprecated(note = "use `loss(TruncatedLoss(loss_cutoff))` instead")]
+    pub fn loss_cutoff(self, loss_cutoff: f64) -> TwoViewConstraint<I, T> {
+        self.loss(TruncatedLoss(loss_cutoff))
+    }
+
     pub fn residuals(&self, pose: CameraToCamera) -> impl Iterator<Item = f64> + '_
     where
         P: Clone,
     {
+        let loss = move |n: f64| cosine_distance_cost(&self.loss, n);
         self.matches.clone().flat_map(move |FeatureMatch(a, b)| {
             if let Some(pa) = self
                 .triangulator
@@ -77,26 +100,21 @@ where
                 let pb = pose.transform(pa);
                 let sim_a = pa.bearing().dot(&a.bearing());
                 let sim_b = pb.bearing().dot(&b.bearing());
-                let loss = |n: f64| {
-                    if n > self.loss_cutoff {
-                        self.loss_cutoff
-                    } else {
-                        n
-                    }
-                };
                 once(loss(1.0 - sim_a)).chain(once(loss(1.0 - sim_b)))
             } else {
-                once(self.loss_cutoff).chain(once(self.loss_cutoff))
+                // The match can't be triangulated, so give it the loss of the largest cosine distance.
+                once(loss(2.0)).chain(once(loss(2.0)))
             }
         })
     }
 }
 
-impl<I, P, T> ArgminOp for TwoViewConstraint<I, T>
+impl<I, P, T, L> ArgminOp for TwoViewConstraint<I, T, L>
 where
     I: Iterator<Item = FeatureMatch<P>> + Clone,
     P: Bearing + Clone,
     T: TriangulatorRelative + Clone,
+    L: RobustLoss,
 {
     type Param = Array1<f64>;
     type Output = f64;
@@ -114,12 +132,19 @@ where
 }
 
 #[derive(Clone)]
-pub struct TwoViewOptimizer<I, T> {
+pub struct TwoViewOptimizer<I, T, L = SquaredLoss> {
     pub pose: CameraToCamera,
+    /// The cosine distance beyond which a bearing is ignored, regardless of the loss.
+    ///
+    /// This is `0.001` by default and infinite once a loss is set. It is applied when the weights are computed.
+    #[deprecated(note = "use `loss(TruncatedLoss(loss_cutoff))` instead")]
     pub loss_cutoff: f64,
     matches: I,
     points: Vec<Option<CameraPoint>>,
     triangulator: T,
+    loss: L,
+    /// The IRLS weight of each residual.
+    weights: Vec<f64>,
 }
 
 impl<I, P, T> TwoViewOptimizer<I, T>
@@ -129,28 +154,99 @@ where
     T: TriangulatorRelative,
 {
     pub fn new(matches: I, pose: CameraToCamera, triangulator: T) -> Self {
-        let points = matches
+        let points: Vec<Option<CameraPoint>> = matches
             .clone()
             .map(|FeatureMatch(a, b)| triangulator.triangulate_relative(pose, a, b))
             .collect();
-        Self {
+        let weights = vec![1.0; points.len() * 2];
+        #[allow(deprecated)]
+        let mut optimizer = Self {
             pose,
             loss_cutoff: 0.001,
             matches,
             points,
             triangulator,
-        }
+            loss: SquaredLoss,
+            weights,
+        };
+        optimizer.reweight();
+        optimizer
     }
+}
 
-    pub fn loss_cutoff(self, loss_cutoff: f64) -> Self {
-        Self {
-            loss_cutoff,
-            ..self
+impl<I, P, T, L> TwoViewOptimizer<I, T, L>
+where
+    I: Iterator<Item = FeatureMatch<P>> + Clone,
+    P: Bearing,
+{
+    /// Sets the robust loss applied to the cosine distance of each bearing, which is the residual.
+    ///
+    /// The loss is applied with iteratively reweighted least squares. The weights are computed from the
+    /// residuals at the current parameters when the loss is set, and recomputed by [`Reweight::reweight`].
+    /// Default is [`SquaredLoss`] with a `loss_cutoff` of `0.001`, which is the same as `TruncatedLoss(0.001)`.
+    pub fn loss<L2: RobustLoss>(self, loss: L2) -> TwoViewOptimizer<I, T, L2> {
+        #[allow(deprecated)]
+        let mut optimizer = TwoViewOptimizer {
+            pose: self.pose,
+            loss_cutoff: f64::INFINITY,
+            matches: self.matches,
+            points: self.points,
+            triangulator: self.triangulator,
+            loss,
+            weights: self.weights,
+        };
+        optimizer.reweight();
+        optimizer
+    }
+
+    /// Caps the cosine distance of each bearing at `loss_cutoff`.
+    #[deprecated(note = "use `loss(TruncatedLoss(loss_cutoff))` instead")]
+    pub fn loss_cutoff(self, loss_cutoff: f64) -> TwoViewOptimizer<I, T, TruncatedLoss> {
+        self.loss(TruncatedLoss(loss_cutoff))
+    }
+
+    /// Computes the cosine distance of both bearings of every match without weighting.
+    fn unweighted_residuals(&self) -> DVector<f64> {
+        DVector::from_iterator(
+            self.points.len() * 2,
+            self.points
+                .iter()
+                .zip(self.matches.clone())
+                .flat_map(|(pa, FeatureMatch(a, b))| {
+                    if let Some(pa) = *pa {
+                        let pb = self.pose.transform(pa);
+                        let sim_a = pa.bearing().dot(&a.bearing());
+                        let sim_b = pb.bearing().dot(&b.bearing());
+                        once(1.0 - sim_a).chain(once(1.0 - sim_b))
+                    } else {
+                        once(0.0).chain(once(0.0))
+                    }
+                }),
+        )
+    }
+}
+
+impl<I, P, T, L> Reweight for TwoViewOptimizer<I, T, L>
+where
+    I: Iterator<Item = FeatureMatch<P>> + Clone,
+    P: Bearing,
+    L: RobustLoss,
+{
+    fn reweight(&mut self) {
+        let residuals = self.unweighted_residuals();
+        #[allow(deprecated)]
+        let loss_cutoff = self.loss_cutoff;
+        for (weight, residual) in self.weights.iter_mut().zip(residuals.iter()) {
+            *weight = if residual.abs() > loss_cutoff {
+                0.0
+            } else {
+                self.loss.weight(residual * residual)
+            };
         }
     }
 }
 
-impl<I, P, T> LeastSquaresProblem<f64, Dynamic, Dynamic> for TwoViewOptimizer<I, T>
+impl<I, P, T, L> LeastSquaresProblem<f64, Dynamic, Dynamic> for TwoViewOptimizer<I, T, L>
 where
     I: Iterator<Item = FeatureMatch<P>> + Clone,
     P: Bearing,
@@ -189,29 +285,11 @@ where
 
     /// Compute the residual vector.
     fn residuals(&self) -> Option<DVector<f64>> {
-        Some(DVector::from_iterator(
-            self.points.len() * 2,
-            self.points
-                .iter()
-                .zip(self.matches.clone())
-                .flat_map(|(pa, FeatureMatch(a, b))| {
-                    if let Some(pa) = *pa {
-                        let pb = self.pose.transform(pa);
-                        let sim_a = pa.bearing().dot(&a.bearing());
-                        let sim_b = pb.bearing().dot(&b.bearing());
-                        let loss = |n: f64| {
-                            if n > self.loss_cutoff {
-                                self.loss_cutoff
-                            } else {
-                                n
-                            }
-                        };
-                        once(loss(1.0 - sim_a)).chain(once(loss(1.0 - sim_b)))
-                    } else {
-                        once(0.0).chain(once(0.0))
-                    }
-                }),
-        ))
+        let mut residuals = self.unweighted_residuals();
+        for (residual, weight) in residuals.iter_mut().zip(&self.weights) {
+            *residual *= weight.sqrt();
+        }
+        Some(residuals)
     }
 
     /// Compute the Jacobian of the residual vector.
@@ -272,6 +350,7 @@ where
             sim_a_row
                 .fixed_columns_mut::<U4>(6 + 4 * ix)
                 .copy_from(&jacobian_ares_ap);
+            sim_a_row *= self.weights[ix * 2].sqrt();
 
             // Assign the b_res jacobians for both the pose and the point.
             let mut sim_b_row = mat.row_mut(ix * 2 + 1);
@@ -281,7 +360,63 @@ where
             sim_b_row
                 .fixed_columns_mut::<U4>(6 + 4 * ix)
                 .copy_from(&jacobian_bres_ap);
+            sim_b_row *= self.weights[ix * 2 + 1].sqrt();
         }
         Some(mat)
     }
 }
+
+impl<I, P, T, L> TwoViewOptimizer<I, T, L>
+where
+    I: Iterator<Item = FeatureMatch<P>> + Clone,
+    P: Bearing,
+{
+    /// Computes the marginal covariance of the relative pose, and optionally each landmark, at the current
+    /// parameters, which should be the converged solution. See [`MarginalCovariances`] for details.
+    ///
+    /// The first camera is the origin of the relative pose, so only the scale is a gauge freedom, which is removed
+    /// in the way that minimizes the trace of the covariance. The landmarks are in the space of the first camera.
+    /// The returned [`MarginalCovariances`] has a single pose. Returns `None` if the pose is not constrained by
+    /// enough matches.
+    pub fn marginal_covariances(&self, landmarks: bool) -> Option<MarginalCovariances> {
+        let observations = self
+            .points
+            .iter()
+            .zip(self.matches.clone())
+            .enumerate()
+            .filter_map(|(ix, (&point, FeatureMatch(a, b)))| Some((ix, point?, a, b)))
+            .flat_map(|(ix, point, a, b)| {
+                let (camera_point, jacobian_point, jacobian_pose) =
+                    self.pose.transform_jacobians(point);
+                let observation_a = BearingLinearization::new(
+                    None,
+                    ix,
+                    self.weights[2 * ix],
+                    a.bearing(),
+                    point.0,
+                    Matrix4x6::zeros(),
+                    Matrix4::identity(),
+                );
+                let observation_b = BearingLinearization::new(
+                    Some(0),
+                    ix,
+                    self.weights[2 * ix + 1],
+                    b.bearing(),
+                    camera_point.0,
+                    jacobian_pose,
+                    jacobian_point,
+                );
+                observation_a.into_iter().chain(observation_b)
+            })
+            .collect();
+        CovarianceProblem {
+            poses: 1,
+            fixed_poses: 0,
+            gauge_freedom: 1,
+            points: self.points.iter().map(|point| point.map(|p| p.0)).collect(),
+            observations,
+            landmarks,
+        }
+        .solve()
+    }
+}
@@ -0,0 +1,263 @@
+mod common;
+
+use common::{max_error, perturb, pose};
+use cv_core::nalgebra::{Point2, Point3, Vector2, Vector3};
+use cv_core::{
+    CameraModel, CameraModelJacobians, KeyPoint, Pose, Projective, WorldPoint, WorldToCamera,
+};
+use cv_omnidirectional::UnifiedIntrinsics;
+use cv_optimize::{
+    BundleAdjuster, Gauge, HuberLoss, LinearSolver, RobustLoss, SquaredLoss, Termination,
+};
+use cv_pinhole::{CameraIntrinsics, CameraIntrinsicsK1Distortion, NormalizedKeyPoint};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const VIEWS: usize = 8;
+const POINTS: usize = 200;
+
+struct Scene {
+    poses: Vec<WorldToCamera>,
+    points: Vec<Point3<f64>>,
+}
+
+/// Creates views on a circle looking at a cloud of points around the origin.
+fn scene(rng: &mut SmallRng) -> Scene {
+    let poses = (0..VIEWS)
+        .map(|i| {
+            let angle = i as f64 * 0.08;
+            let center = Vector3::new(10.0 * angle.sin(), 0.3 * i as f64, -10.0 * angle.cos());
+            pose(center, angle)
+        })
+        .collect();
+    let points = (0..POINTS)
+        .map(|_| {
+            Point3::new(
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(-2.0, 2.0),
+            )
+        })
+        .collect();
+    Scene { poses, points }
+}
+
+fn project<C: CameraModel<Projection = NormalizedKeyPoint>>(
+    camera: &C,
+    pose: WorldToCamera,
+    point: Point3<f64>,
+) -> KeyPoint {
+    let camera_point = pose.transform(WorldPoint::from_point(point));
+    camera.uncalibrate(NormalizedKeyPoint::from_camera_point(camera_point).unwrap())
+}
+
+fn perturb_point(rng: &mut SmallRng, point: Point3<f64>) -> WorldPoint {
+    WorldPoint::from_point(
+        point
+            + Vector3::new(
+                rng.gen_range(-0.1, 0.1),
+                rng.gen_range(-0.1, 0.1),
+                rng.gen_range(-0.1, 0.1),
+            ),
+    )
+}
+
+/// Adds the scene to a bundle adjuster with every view but the first two perturbed, since those fix the gauge.
+fn adjuster<C, L>(
+    rng: &mut SmallRng,
+    scene: &Scene,
+    camera: C,
+    initial_camera: C,
+    loss: L,
+) -> BundleAdjuster<C, L>
+where
+    C: CameraModel<Projection = NormalizedKeyPoint>,
+{
+    let mut adjuster = BundleAdjuster::new().loss(loss);
+    let camera_ix = adjuster.add_camera(initial_camera);
+    for (ix, &pose) in scene.poses.iter().enumerate() {
+        let pose = if ix < 2 {
+            pose
+        } else {
+            perturb(rng, pose, 0.1, 0.01)
+        };
+        adjuster.add_view(camera_ix, pose);
+    }
+    for &point in &scene.points {
+        let landmark = adjuster.add_landmark(perturb_point(rng, point));
+        for (view, &pose) in scene.poses.iter().enumerate() {
+            adjuster.add_observation(view, landmark, project(&camera, pose, point));
+        }
+    }
+    adjuster
+}
+
+fn max_pose_error<C, L>(adjuster: &BundleAdjuster<C, L>, scene: &Scene) -> f64 {
+    max_error(adjuster.poses().iter().copied(), &scene.poses)
+}
+
+fn intrinsics() -> CameraIntrinsics {
+    CameraIntrinsics::identity()
+        .focals(Vector2::new(800.0, 800.0))
+        .principal_point(Point2::new(640.0, 360.0))
+}
+
+#[test]
+fn converges_to_truth() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let scene = scene(&mut rng);
+    let mut adjuster = adjuster(&mut rng, &scene, intrinsics(), intrinsics(), SquaredLoss);
+    let summary = adjuster.optimize();
+    assert_eq!(summary.termination, Termination::Converged);
+    assert_eq!(summary.observations, VIEWS * POINTS);
+    assert!(summary.final_cost < 1e-12 * summary.initial_cost);
+    assert!(max_pose_error(&adjuster, &scene) < 1e-6);
+    for (estimated, truth) in adjuster.points().zip(&scene.points) {
+        assert!((estimated.point().unwrap() - truth).norm() < 1e-6);
+    }
+}
+
+#[test]
+fn omnidirectional_points_behind_the_image_plane() {
+    let mut rng = SmallRng::seed_from_u64(5);
+    // A wide angle camera sees points more than 90 degrees away from its optical axis.
+    let camera = UnifiedIntrinsics::new(intrinsics(), 0.9);
+    let poses = [
+        pose(Vector3::zeros(), 0.0),
+        pose(Vector3::new(0.0, 0.5, 0.0), 0.0),
+    ];
+    let points: Vec<Point3<f64>> = (0..40)
+        .map(|i| {
+            let angle = 1.65 + 0.015 * i as f64;
+            let distance = 3.0 + (i % 5) as f64;
+            let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+            Point3::new(
+                side * distance * angle.sin(),
+                0.1 * (i % 7) as f64 - 0.3,
+                distance * angle.cos(),
+            )
+        })
+        .collect();
+
+    let mut adjuster = BundleAdjuster::new();
+    let camera_ix = adjuster.add_camera(camera);
+    for &pose in &poses {
+        adjuster.add_view(camera_ix, pose);
+    }
+    for &point in &points {
+        let landmark = adjuster.add_landmark(perturb_point(&mut rng, point));
+        for (view, &pose) in poses.iter().enumerate() {
+            let camera_point = pose.transform(WorldPoint::from_point(point));
+            assert!(camera_point.0.z < 0.0);
+            let (keypoint, _, _) = camera.uncalibrate_jacobians(camera_point).unwrap();
+            adjuster.add_observation(view, landmark, keypoint);
+        }
+    }
+    let summary = adjuster.optimize();
+    assert_eq!(summary.observations, 2 * points.len());
+    for (estimated, truth) in adjuster.points().zip(&points) {
+        assert!((estimated.point().unwrap() - truth).norm() < 1e-6);
+    }
+}
+
+#[test]
+fn conjugate_gradient_solver_converges() {
+    let mut rng = SmallRng::seed_from_u64(4);
+    let scene = scene(&mut rng);
+    let mut adjuster = adjuster(&mut rng, &scene, intrinsics(), intrinsics(), SquaredLoss)
+        .linear_solver(LinearSolver::ConjugateGradient {
+            max_iterations: 500,
+            tolerance: 1e-12,
+        });
+    let summary = adjuster.optimize();
+    assert!(
+        summary.final_cost < 1e-12 * summary.initial_cost,
+        "{:?}",
+        summary
+    );
+    assert!(max_pose_error(&adjuster, &scene) < 1e-6);
+}
+
+#[test]
+fn first_view_gauge_keeps_first_pose() {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let scene = scene(&mut rng);
+    let mut adjuster =
+        adjuster(&mut rng, &scene, intrinsics(), intrinsics(), SquaredLoss).gauge(Gauge::FirstView);
+    adjuster.optimize();
+    assert_eq!(adjuster.poses()[0], scene.poses[0]);
+    assert!(adjuster.cost() < 1e-12);
+}
+
+/// Optimizes the scene after corrupting every tenth observation in the last view and returns the pose error.
+fn outlier_pose_error<L: RobustLoss>(loss: L) -> f64 {
+    let mut rng = SmallRng::seed_from_u64(2);
+    let scene = scene(&mut rng);
+    let mut adjuster = adjuster(&mut rng, &scene, intrinsics(), intrinsics(), loss);
+    let view = VIEWS - 1;
+    for landmark in (0..POINTS).step_by(10) {
+        let keypoint = project(&intrinsics(), scene.poses[view], scene.points[landmark]);
+        adjuster.add_observation(
+            view,
+            landmark,
+            KeyPoint(keypoint.0 + Vector2::new(40.0, -30.0)),
+        );
+    }
+    adjuster.optimize();
+    max_pose_error(&adjuster, &scene)
+}
+
+#[test]
+fn huber_rejects_outliers() {
+    let squared = outlier_pose_error(SquaredLoss);
+    let huber = outlier_pose_error(HuberLoss(1.0));
+    assert!(huber < 0.1 * squared, "huber {} squared {}", huber, squared);
+}
+
+#[test]
+fn refines_intrinsics() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    let scene = scene(&mut rng);
+    let truth = CameraIntrinsicsK1Distortion::new(intrinsics(), -0.1);
+    let initial = CameraIntrinsicsK1Distortion::new(
+        intrinsics()
+            .focals(Vector2::new(840.0, 840.0))
+            .principal_point(Point2::new(630.0, 365.0)),
+        -0.05,
+    );
+    // Refine everything except the skew.
+    let mut adjuster = adjuster(&mut rng, &scene, truth, initial, SquaredLoss)
+        .refine_intrinsics(vec![0, 1, 2, 3, 5]);
+    let summary = adjuster.optimize();
+    assert!(summary.final_cost < 1e-10, "{:?}", summary);
+    let parameters = adjuster.cameras()[0].parameters();
+    assert!((parameters - truth.parameters()).norm() < 1e-4);
+}
+
+#[test]
+fn intrinsic_priors_and_bounds_constrain_refinement() {
+    let mut rng = SmallRng::seed_from_u64(4);
+    let scene = scene(&mut rng);
+    let truth = CameraIntrinsicsK1Distortion::new(intrinsics(), -0.1);
+    let initial =
+        CameraIntrinsicsK1Distortion::new(intrinsics().focals(Vector2::new(840.0, 840.0)), -0.05);
+    let mut adjuster = adjuster(&mut rng, &scene, truth, initial, SquaredLoss)
+        .refine_intrinsics(vec![0, 1, 2, 3, 5]);
+    // A weak prior on the principal point at its true value doesn't get in the way.
+    adjuster.add_intrinsic_prior(0, 2, 640.0, 10.0);
+    adjuster.add_intrinsic_prior(0, 3, 360.0, 10.0);
+    // A strong prior holds the focal lengths, and the bounds hold k1 away from the truth.
+    adjuster.add_intrinsic_prior(0, 0, 840.0, 1e-6);
+    adjuster.add_intrinsic_prior(0, 1, 840.0, 1e-6);
+    adjuster.set_intrinsic_bounds(0, 5, -0.07, 0.0);
+    let summary = adjuster.optimize();
+    assert!(summary.final_cost < summary.initial_cost, "{:?}", summary);
+    let parameters = adjuster.cameras()[0].parameters();
+    assert!((parameters[0] - 840.0).abs() < 1e-3, "{}", parameters);
+    assert!((parameters[1] - 840.0).abs() < 1e-3, "{}", parameters);
+    assert!(
+        parameters[5] >= -0.07 && parameters[5] <= 0.0,
+        "{}",
+        parameters
+    );
+    assert!((adjuster.cost() - summary.final_cost).abs() < 1e-9 * summary.final_cost);
+}
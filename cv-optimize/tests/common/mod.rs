@@ -0,0 +1,64 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use cv_core::nalgebra::{Rotation3, Vector3};
+use cv_core::{CameraToCamera, Pose, WorldToCamera};
+use rand::{rngs::SmallRng, Rng};
+
+/// Creates the pose of a camera at `center` which is turned by `angle` about the y axis.
+pub fn pose(center: Vector3<f64>, angle: f64) -> WorldToCamera {
+    let rotation = Rotation3::from_euler_angles(0.0, -angle, 0.0);
+    WorldToCamera::from_parts(-(rotation * center), rotation)
+}
+
+/// Creates `count` poses on a wavy circle of radius `10`, each looking at the center.
+pub fn circle(count: usize) -> Vec<WorldToCamera> {
+    (0..count)
+        .map(|i| {
+            let angle = i as f64 * 2.0 * std::f64::consts::PI / count as f64;
+            let center = Vector3::new(10.0 * angle.sin(), 0.5 * angle.cos(), -10.0 * angle.cos());
+            pose(center, angle)
+        })
+        .collect()
+}
+
+/// Computes the transform from the camera of `from` to the camera of `to`.
+pub fn relative(from: WorldToCamera, to: WorldToCamera) -> CameraToCamera {
+    (to.isometry() * from.isometry().inverse()).into()
+}
+
+/// Moves a pose by up to `translation` along each axis and turns it by up to `rotation` about each axis.
+pub fn perturb(
+    rng: &mut SmallRng,
+    pose: WorldToCamera,
+    translation: f64,
+    rotation: f64,
+) -> WorldToCamera {
+    let mut sample = |range: f64| rng.gen_range(-range, range);
+    let noise = WorldToCamera::from_parts(
+        Vector3::new(
+            sample(translation),
+            sample(translation),
+            sample(translation),
+        ),
+        Rotation3::from_euler_angles(sample(rotation), sample(rotation), sample(rotation)),
+    );
+    (noise.isometry() * pose.isometry()).into()
+}
+
+/// Finds the largest translation between the cameras of the estimated poses and the cameras of the true poses.
+pub fn max_error(
+    estimated: impl IntoIterator<Item = WorldToCamera>,
+    truth: &[WorldToCamera],
+) -> f64 {
+    estimated
+        .into_iter()
+        .zip(truth)
+        .map(|(estimated, truth)| {
+            (estimated.isometry().inverse() * truth.isometry())
+                .translation
+                .vector
+                .norm()
+        })
+        .fold(0.0, f64::max)
+}
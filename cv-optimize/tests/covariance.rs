@@ -0,0 +1,326 @@
+mod common;
+
+use common::{pose, relative};
+use cv_core::nalgebra::{
+    DMatrix, DVector, Matrix3, Matrix6, Point3, Rotation3, Unit, Vector3, Vector6,
+};
+use cv_core::{FeatureMatch, Pose, Projective, WorldPoint, WorldToCamera};
+use cv_geom::MidpointTriangulator;
+use cv_optimize::{CovarianceGauge, ManyViewOptimizer, TwoViewOptimizer};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const VIEWS: usize = 4;
+const POINTS: usize = 60;
+
+type Bearing = Unit<Vector3<f64>>;
+
+struct Scene {
+    poses: Vec<WorldToCamera>,
+    points: Vec<Point3<f64>>,
+    /// The noisy bearing of each point in each view.
+    landmarks: Vec<Vec<Option<Bearing>>>,
+}
+
+/// Creates views on an arc looking at points at increasing depths, and their noisy bearings.
+fn scene(rng: &mut SmallRng) -> Scene {
+    let poses: Vec<WorldToCamera> = (0..VIEWS)
+        .map(|i| {
+            let angle = i as f64 * 0.05;
+            let center = Vector3::new(
+                10.0 * angle.sin(),
+                0.1 * i as f64,
+                10.0 - 10.0 * angle.cos(),
+            );
+            pose(center, angle)
+        })
+        .collect();
+    let points: Vec<Point3<f64>> = (0..POINTS)
+        .map(|i| {
+            Point3::new(
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(-2.0, 2.0),
+                5.0 + 15.0 * i as f64 / POINTS as f64,
+            )
+        })
+        .collect();
+    let landmarks = points
+        .iter()
+        .map(|&point| {
+            poses
+                .iter()
+                .map(|pose| {
+                    Some(noisy_bearing(
+                        rng,
+                        pose.transform(WorldPoint::from_point(point)).bearing(),
+                    ))
+                })
+                .collect()
+        })
+        .collect();
+    Scene {
+        poses,
+        points,
+        landmarks,
+    }
+}
+
+fn noisy_bearing(rng: &mut SmallRng, bearing: Bearing) -> Bearing {
+    let noise = Vector3::new(
+        rng.gen_range(-1e-3, 1e-3),
+        rng.gen_range(-1e-3, 1e-3),
+        rng.gen_range(-1e-3, 1e-3),
+    );
+    Unit::new_normalize(bearing.into_inner() + noise)
+}
+
+fn many_view(seed: u64) -> ManyViewOptimizer<Bearing> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let Scene {
+        poses, landmarks, ..
+    } = scene(&mut rng);
+    ManyViewOptimizer::new(
+        poses,
+        landmarks
+            .iter()
+            .map(|observations| observations.iter().copied()),
+        MidpointTriangulator,
+    )
+}
+
+fn assert_positive_semidefinite(covariance: &Matrix6<f64>) {
+    assert!((covariance - covariance.transpose()).norm() <= 1e-9 * covariance.norm());
+    let eigenvalues = covariance.symmetric_eigenvalues();
+    assert!(eigenvalues.min() >= -1e-9 * eigenvalues.max());
+}
+
+#[test]
+fn first_view_gauge_fixes_first_pose() {
+    let optimizer = many_view(0);
+    let covariances = optimizer
+        .marginal_covariances(CovarianceGauge::FirstView, false)
+        .unwrap();
+    // The noise of each bearing is uniform in [-1e-3, 1e-3], which has a variance of 1e-6 / 3.
+    let expected = 1e-6 / 3.0;
+    assert!(
+        (covariances.residual_variance / expected - 1.0).abs() < 0.5,
+        "{}",
+        covariances.residual_variance
+    );
+    assert!(covariances.landmarks.is_empty());
+    assert_eq!(covariances.poses.len(), VIEWS);
+    assert_eq!(covariances.poses[0], Matrix6::zeros());
+    for covariance in &covariances.poses[1..] {
+        assert!(covariance.trace() > 0.0);
+        assert_positive_semidefinite(covariance);
+    }
+}
+
+#[test]
+fn minimum_trace_gauge_has_the_smallest_trace() {
+    let optimizer = many_view(1);
+    let trace = |gauge| {
+        optimizer
+            .marginal_covariances(gauge, false)
+            .unwrap()
+            .poses
+            .iter()
+            .map(|covariance| covariance.trace())
+            .sum::<f64>()
+    };
+    let first_view = trace(CovarianceGauge::FirstView);
+    let minimum = trace(CovarianceGauge::MinimumTrace);
+    assert!(minimum > 0.0);
+    assert!(
+        minimum <= first_view * (1.0 + 1e-9),
+        "minimum {} first view {}",
+        minimum,
+        first_view
+    );
+}
+
+#[test]
+fn landmark_uncertainty_grows_with_depth() {
+    let optimizer = many_view(2);
+    let covariances = optimizer
+        .marginal_covariances(CovarianceGauge::FirstView, true)
+        .unwrap();
+    let traces: Vec<f64> = covariances
+        .landmarks
+        .iter()
+        .map(|covariance| covariance.map(|c: Matrix3<f64>| c.trace()).unwrap())
+        .collect();
+    let near: f64 = traces[..POINTS / 4].iter().sum();
+    let far: f64 = traces[3 * POINTS / 4..].iter().sum();
+    assert!(far > 4.0 * near, "near {} far {}", near, far);
+}
+
+/// Computes the angular error of every observation in the plane tangent to its bearing.
+fn angular_errors(
+    poses: &[WorldToCamera],
+    points: &[Point3<f64>],
+    landmarks: &[Vec<Option<Bearing>>],
+) -> Vec<f64> {
+    let mut errors = vec![];
+    for (&point, observations) in points.iter().zip(landmarks) {
+        for (pose, bearing) in poses.iter().zip(observations) {
+            let bearing = match bearing {
+                Some(bearing) => bearing,
+                None => continue,
+            };
+            let predicted = pose.transform(WorldPoint::from_point(point)).bearing();
+            // Any orthonormal basis of the tangent plane gives the same normal equations.
+            let mut axis = Vector3::zeros();
+            axis[bearing.iamin()] = 1.0;
+            let first = bearing.cross(&axis).normalize();
+            let second = bearing.cross(&first);
+            errors.push(first.dot(&predicted));
+            errors.push(second.dot(&predicted));
+        }
+    }
+    errors
+}
+
+#[test]
+fn matches_dense_pseudo_inverse() {
+    let mut rng = SmallRng::seed_from_u64(4);
+    let Scene {
+        poses, landmarks, ..
+    } = scene(&mut rng);
+    let optimizer = ManyViewOptimizer::new(
+        poses,
+        landmarks
+            .iter()
+            .map(|observations| observations.iter().copied()),
+        MidpointTriangulator,
+    );
+    let covariances = optimizer
+        .marginal_covariances(CovarianceGauge::FirstView, true)
+        .unwrap();
+
+    // Parameterize every pose but the first with its translation and a rotation vector on the left of its rotation,
+    // and every point with its Euclidean position.
+    let pose_parameters = 6 * (VIEWS - 1);
+    let mut parameters = vec![0.0; pose_parameters];
+    for point in &optimizer.points {
+        parameters.extend(point.unwrap().point().unwrap().coords.iter());
+    }
+    let errors = |parameters: &[f64]| {
+        let mut poses = vec![optimizer.poses[0]];
+        poses.extend(
+            optimizer.poses[1..]
+                .iter()
+                .zip(parameters[..pose_parameters].chunks(6))
+                .map(|(pose, delta)| {
+                    let isometry = pose.isometry();
+                    WorldToCamera::from_parts(
+                        isometry.translation.vector + Vector3::new(delta[0], delta[1], delta[2]),
+                        Rotation3::new(Vector3::new(delta[3], delta[4], delta[5]))
+                            * isometry.rotation,
+                    )
+                }),
+        );
+        let points: Vec<Point3<f64>> = parameters[pose_parameters..]
+            .chunks(3)
+            .map(|xyz| Point3::new(xyz[0], xyz[1], xyz[2]))
+            .collect();
+        DVector::from_column_slice(&angular_errors(&poses, &points, &landmarks))
+    };
+
+    // Find the Jacobian with central differences.
+    let residuals = errors(&parameters);
+    let n = parameters.len();
+    let mut jacobian = DMatrix::zeros(residuals.len(), n);
+    let h = 1e-6;
+    for column in 0..n {
+        let mut forward = parameters.clone();
+        forward[column] += h;
+        let mut backward = parameters.clone();
+        backward[column] -= h;
+        jacobian.set_column(
+            column,
+            &((errors(&forward) - errors(&backward)) / (2.0 * h)),
+        );
+    }
+    let hessian = jacobian.transpose() * &jacobian;
+
+    // The scale is the remaining gauge freedom. It is removed from the poses in the direction of the smallest
+    // eigenvector of the normal equations of the poses with the points eliminated.
+    let pose_block = hessian.slice((0, 0), (pose_parameters, pose_parameters));
+    let coupling = hessian.slice((0, pose_parameters), (pose_parameters, n - pose_parameters));
+    let point_block = hessian.slice(
+        (pose_parameters, pose_parameters),
+        (n - pose_parameters, n - pose_parameters),
+    );
+    let schur = pose_block
+        - coupling * point_block.clone_owned().try_inverse().unwrap() * coupling.transpose();
+    let eigen = schur.symmetric_eigen();
+    let scale = eigen.eigenvalues.imin();
+    let mut basis = DMatrix::zeros(n, n - 1);
+    for (column, eigenvector) in (0..pose_parameters).filter(|&ix| ix != scale).enumerate() {
+        basis
+            .slice_mut((0, column), (pose_parameters, 1))
+            .copy_from(&eigen.eigenvectors.column(eigenvector));
+    }
+    for ix in pose_parameters..n {
+        basis[(ix, ix - 1)] = 1.0;
+    }
+    let reduced = (basis.transpose() * &hessian * &basis)
+        .try_inverse()
+        .unwrap();
+    let residual_variance = residuals.norm_squared() / (residuals.len() - (n - 1)) as f64;
+    let covariance = &basis * reduced * basis.transpose() * residual_variance;
+
+    assert!((covariances.residual_variance / residual_variance - 1.0).abs() < 1e-6);
+    let close = |expected: DMatrix<f64>, actual: DMatrix<f64>| {
+        assert!(
+            (&expected - &actual).norm() < 1e-4 * expected.norm(),
+            "expected {} actual {}",
+            expected,
+            actual
+        );
+    };
+    for (view, actual) in covariances.poses.iter().enumerate().skip(1) {
+        let start = 6 * (view - 1);
+        close(
+            covariance.slice((start, start), (6, 6)).into_owned(),
+            DMatrix::from_iterator(6, 6, actual.iter().copied()),
+        );
+    }
+    for (ix, actual) in covariances.landmarks.iter().enumerate() {
+        let start = pose_parameters + 3 * ix;
+        close(
+            covariance.slice((start, start), (3, 3)).into_owned(),
+            DMatrix::from_iterator(3, 3, actual.unwrap().iter().copied()),
+        );
+    }
+}
+
+#[test]
+fn two_view_removes_scale() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    let Scene { poses, points, .. } = scene(&mut rng);
+    let relative = relative(poses[0], poses[VIEWS - 1]);
+    let matches: Vec<FeatureMatch<Bearing>> = points
+        .iter()
+        .map(|&point| {
+            let a = poses[0].transform(WorldPoint::from_point(point)).bearing();
+            let b = poses[VIEWS - 1]
+                .transform(WorldPoint::from_point(point))
+                .bearing();
+            FeatureMatch(noisy_bearing(&mut rng, a), noisy_bearing(&mut rng, b))
+        })
+        .collect();
+    let optimizer = TwoViewOptimizer::new(matches.iter().copied(), relative, MidpointTriangulator);
+    let covariances = optimizer.marginal_covariances(true).unwrap();
+    assert_eq!(covariances.poses.len(), 1);
+    let covariance = covariances.poses[0];
+    assert_positive_semidefinite(&covariance);
+    // Scaling the translation doesn't change the residuals, so there is no uncertainty in that direction.
+    let se3 = relative.se3();
+    let scale = Vector6::new(se3[0], se3[1], se3[2], 0.0, 0.0, 0.0).normalize();
+    assert!((covariance * scale).norm() < 1e-6 * covariance.norm());
+    assert!(covariances
+        .landmarks
+        .iter()
+        .all(|landmark| landmark.is_some()));
+}
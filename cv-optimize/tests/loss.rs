@@ -0,0 +1,175 @@
+use cv_core::nalgebra::{DMatrix, DVector, Dynamic, VecStorage, Vector2, U1};
+use cv_optimize::{
+    iteratively_reweighted_least_squares, CauchyLoss, GemanMcClureLoss, HuberLoss, Reweight,
+    RobustLoss, SquaredLoss, TruncatedLoss, TukeyLoss,
+};
+use levenberg_marquardt::{LeastSquaresProblem, LevenbergMarquardt};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+fn losses() -> Vec<Box<dyn RobustLoss>> {
+    vec![
+        Box::new(SquaredLoss),
+        Box::new(HuberLoss(0.5)),
+        Box::new(CauchyLoss(0.5)),
+        Box::new(TukeyLoss(0.5)),
+        Box::new(GemanMcClureLoss(0.5)),
+        Box::new(TruncatedLoss(0.5)),
+    ]
+}
+
+#[test]
+fn derivatives_match_finite_differences() {
+    let eps = 1e-7;
+    for loss in losses() {
+        // Avoid the kinks of the Huber, Tukey, and truncated losses at 0.25.
+        for &s in &[0.0001, 0.01, 0.1, 0.2, 0.3, 1.0, 10.0] {
+            let numeric = (loss.loss(s + eps).0 - loss.loss(s - eps).0) / (2.0 * eps);
+            let (_, derivative) = loss.loss(s);
+            assert!((numeric - derivative).abs() < 1e-5);
+            assert_eq!(loss.weight(s), derivative);
+        }
+    }
+}
+
+#[test]
+fn losses_are_continuous_and_quadratic_near_zero() {
+    for loss in losses() {
+        let (below, _) = loss.loss(0.25 - 1e-9);
+        let (above, _) = loss.loss(0.25 + 1e-9);
+        assert!((below - above).abs() < 1e-6);
+        let (small, weight) = loss.loss(1e-8);
+        assert!((small - 1e-8).abs() < 1e-12);
+        assert!((weight - 1.0).abs() < 1e-6);
+    }
+}
+
+/// Fits a line `y = a * x + b` to points, weighting each residual for IRLS.
+struct LineFit<L> {
+    params: Vector2<f64>,
+    points: Vec<(f64, f64)>,
+    weights: Vec<f64>,
+    loss: L,
+}
+
+impl<L> LineFit<L> {
+    fn unweighted_residuals(&self) -> DVector<f64> {
+        DVector::from_iterator(
+            self.points.len(),
+            self.points
+                .iter()
+                .map(|&(x, y)| self.params[0] * x + self.params[1] - y),
+        )
+    }
+}
+
+impl<L: RobustLoss> Reweight for LineFit<L> {
+    fn reweight(&mut self) {
+        let residuals = self.unweighted_residuals();
+        for (weight, residual) in self.weights.iter_mut().zip(residuals.iter()) {
+            *weight = self.loss.weight(residual * residual);
+        }
+    }
+}
+
+impl<L> LeastSquaresProblem<f64, Dynamic, Dynamic> for LineFit<L> {
+    type ResidualStorage = VecStorage<f64, Dynamic, U1>;
+    type JacobianStorage = VecStorage<f64, Dynamic, Dynamic>;
+    type ParameterStorage = VecStorage<f64, Dynamic, U1>;
+
+    fn set_params(&mut self, params: &DVector<f64>) {
+        self.params = Vector2::new(params[0], params[1]);
+    }
+
+    fn params(&self) -> DVector<f64> {
+        DVector::from_column_slice(self.params.as_slice())
+    }
+
+    fn residuals(&self) -> Option<DVector<f64>> {
+        let mut residuals = self.unweighted_residuals();
+        for (residual, weight) in residuals.iter_mut().zip(&self.weights) {
+            *residual *= weight.sqrt();
+        }
+        Some(residuals)
+    }
+
+    fn jacobian(&self) -> Option<DMatrix<f64>> {
+        Some(DMatrix::from_fn(self.points.len(), 2, |row, column| {
+            let x = self.points[row].0;
+            let derivative = if column == 0 { x } else { 1.0 };
+            derivative * self.weights[row].sqrt()
+        }))
+    }
+}
+
+/// Fits a line to points on `y = 2 * x + 1` where every fifth point is an outlier and returns the error of the slope.
+fn line_slope_error<L: RobustLoss>(loss: L) -> f64 {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let points: Vec<(f64, f64)> = (0..50)
+        .map(|i| {
+            let x = i as f64 * 0.1;
+            let y = 2.0 * x + 1.0 + rng.gen_range(-0.01, 0.01);
+            if i % 5 == 0 {
+                (x, y + 5.0)
+            } else {
+                (x, y)
+            }
+        })
+        .collect();
+    // Start close to the line, since the non-convex losses ignore residuals that are too large.
+    let problem = LineFit {
+        params: Vector2::new(1.9, 1.1),
+        weights: vec![1.0; points.len()],
+        points,
+        loss,
+    };
+    let (problem, report) =
+        iteratively_reweighted_least_squares(&LevenbergMarquardt::new(), problem, 10);
+    assert!(report.termination.was_successful());
+    (problem.params[0] - 2.0).abs()
+}
+
+#[test]
+fn irls_reduces_the_influence_of_outliers() {
+    let squared = line_slope_error(SquaredLoss);
+    for loss in losses().into_iter().skip(1) {
+        let robust = line_slope_error(loss);
+        assert!(
+            robust < 0.2 * squared,
+            "robust {} squared {}",
+            robust,
+            squared
+        );
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn constraint_loss_caps_the_cosine_distance() {
+    use cv_core::nalgebra::{Point3, Unit, Vector3};
+    use cv_core::{FeatureWorldMatch, Pose, Projective, WorldPoint, WorldToCamera};
+    use cv_optimize::SingleViewConstraint;
+
+    let point = WorldPoint::from_point(Point3::new(0.0, 0.0, 1.0));
+    let landmarks = vec![
+        FeatureWorldMatch(Unit::new_normalize(Vector3::new(0.01, 0.0, 1.0)), point),
+        FeatureWorldMatch(Unit::new_normalize(Vector3::new(1.0, 0.0, 1.0)), point),
+    ];
+    let inlier = 1.0 - Unit::new_normalize(Vector3::new(0.01, 0.0, 1.0)).z;
+    let pose = WorldToCamera::identity();
+    // The inlier costs its cosine distance and the outlier is capped at exactly the cutoff.
+    let residuals: Vec<f64> = SingleViewConstraint::new(landmarks.clone())
+        .residuals(pose)
+        .collect();
+    assert!((residuals[0] - inlier).abs() < 1e-15);
+    assert_eq!(residuals[1], 0.0001);
+    let deprecated: Vec<f64> = SingleViewConstraint::new(landmarks.clone())
+        .loss_cutoff(0.01)
+        .residuals(pose)
+        .collect();
+    let truncated: Vec<f64> = SingleViewConstraint::new(landmarks)
+        .loss(TruncatedLoss(0.01))
+        .residuals(pose)
+        .collect();
+    assert_eq!(deprecated, truncated);
+    assert_eq!(truncated[1], 0.01);
+}
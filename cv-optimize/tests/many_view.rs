@@ -0,0 +1,89 @@
+mod common;
+
+use common::{perturb, pose};
+use cv_core::nalgebra::{Point3, Unit, Vector3};
+use cv_core::{Pose, Projective, WorldPoint, WorldToCamera};
+use cv_geom::MidpointTriangulator;
+use cv_optimize::{LinearSolver, ManyViewOptimizer, Termination};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const VIEWS: usize = 5;
+const POINTS: usize = 40;
+
+type Bearing = Unit<Vector3<f64>>;
+
+/// Creates an optimizer for views on an arc looking at a cloud of points, with every view but the first perturbed.
+fn optimizer(seed: u64) -> ManyViewOptimizer<Bearing> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let poses: Vec<WorldToCamera> = (0..VIEWS)
+        .map(|i| {
+            let angle = i as f64 * 0.1;
+            pose(
+                Vector3::new(10.0 * angle.sin(), 0.2 * i as f64, -10.0 * angle.cos()),
+                angle,
+            )
+        })
+        .collect();
+    let points: Vec<Point3<f64>> = (0..POINTS)
+        .map(|_| {
+            Point3::new(
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(-2.0, 2.0),
+                rng.gen_range(-2.0, 2.0),
+            )
+        })
+        .collect();
+    let landmarks: Vec<Vec<Option<Bearing>>> = points
+        .iter()
+        .map(|&point| {
+            poses
+                .iter()
+                .map(|pose| Some(pose.transform(WorldPoint::from_point(point)).bearing()))
+                .collect()
+        })
+        .collect();
+    let perturbed = poses
+        .iter()
+        .enumerate()
+        .map(|(ix, &pose)| {
+            if ix == 0 {
+                pose
+            } else {
+                perturb(&mut rng, pose, 0.1, 0.01)
+            }
+        })
+        .collect();
+    ManyViewOptimizer::new(
+        perturbed,
+        landmarks
+            .iter()
+            .map(|observances| observances.iter().copied()),
+        MidpointTriangulator,
+    )
+}
+
+#[test]
+fn sparse_cholesky_converges() {
+    let mut optimizer = optimizer(0);
+    let summary = optimizer.optimize();
+    assert_eq!(summary.termination, Termination::Converged);
+    assert!(
+        summary.final_cost < 1e-12 * summary.initial_cost,
+        "{:?}",
+        summary
+    );
+}
+
+#[test]
+fn conjugate_gradient_solver_converges() {
+    let mut optimizer = optimizer(1).linear_solver(LinearSolver::ConjugateGradient {
+        max_iterations: 500,
+        tolerance: 1e-12,
+    });
+    let summary = optimizer.optimize();
+    assert!(
+        summary.final_cost < 1e-12 * summary.initial_cost,
+        "{:?}",
+        summary
+    );
+}
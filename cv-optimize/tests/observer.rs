@@ -0,0 +1,174 @@
+mod common;
+
+use argmin::core::{ArgminOp, Error, Executor, ObserverMode};
+use argmin::solver::neldermead::NelderMead;
+use common::{circle, relative};
+use cv_core::nalgebra::{Matrix6, Rotation3, Vector3};
+use cv_core::{Pose, WorldToCamera};
+use cv_optimize::{
+    ArgminObserver, CancellationToken, Cancelled, Control, PoseGraph, Progress, Termination,
+    TimeLimit,
+};
+use ndarray::{array, Array1};
+use std::time::Duration;
+
+const NODES: usize = 12;
+
+/// Creates a graph of poses on a circle where every node but the first has drifted away from its measurements.
+fn graph() -> PoseGraph {
+    let poses = circle(NODES);
+    let mut graph = PoseGraph::new();
+    for (ix, pose) in poses.iter().enumerate() {
+        let drift = WorldToCamera::from_parts(
+            Vector3::new(0.2, -0.1, 0.3) * ix as f64,
+            Rotation3::from_euler_angles(0.01, 0.02, -0.01),
+        );
+        graph.add_node((drift.isometry() * pose.isometry()).into());
+    }
+    for i in 0..NODES {
+        let j = (i + 1) % NODES;
+        graph.add_edge(i, j, relative(poses[i], poses[j]), Matrix6::identity());
+    }
+    graph
+}
+
+#[test]
+fn reports_decreasing_cost() {
+    let mut graph = graph();
+    let mut reports: Vec<Progress> = vec![];
+    let summary = graph.optimize_with(&mut |progress: &Progress| {
+        reports.push(*progress);
+        Control::Continue
+    });
+    assert_eq!(summary.termination, Termination::Converged);
+    // The last iteration may converge without taking a step, so it isn't reported.
+    assert!(reports.len() > 1 && reports.len() <= summary.iterations);
+    let mut cost = summary.initial_cost;
+    for (ix, report) in reports.iter().enumerate() {
+        assert_eq!(report.iteration, ix + 1);
+        assert!(report.cost < cost);
+        assert!(report.step_norm > 0.0);
+        cost = report.cost;
+    }
+    assert_eq!(cost, summary.final_cost);
+}
+
+#[test]
+fn cancel_keeps_last_iteration() {
+    let mut graph = graph();
+    let summary = graph.optimize_with(&mut |progress: &Progress| {
+        if progress.iteration == 2 {
+            Control::Cancel
+        } else {
+            Control::Continue
+        }
+    });
+    assert_eq!(summary.termination, Termination::Cancelled);
+    assert_eq!(summary.iterations, 2);
+    assert!(summary.final_cost < summary.initial_cost);
+    assert_eq!(graph.cost(), summary.final_cost);
+}
+
+#[test]
+fn time_limit_stops_after_first_iteration() {
+    let mut graph = graph();
+    let summary = graph.optimize_with(&mut TimeLimit(Duration::from_secs(0)));
+    assert_eq!(summary.termination, Termination::Cancelled);
+    assert_eq!(summary.iterations, 1);
+}
+
+#[test]
+fn cancellation_token_notifies_both_observers() {
+    let mut graph = graph();
+    let token = CancellationToken::new();
+    let mut reports = 0;
+    let summary = graph.optimize_with(&mut (
+        |_: &Progress| {
+            reports += 1;
+            Control::Continue
+        },
+        token.clone(),
+    ));
+    assert_eq!(summary.termination, Termination::Converged);
+    assert!(reports > 1);
+
+    let mut graph = self::graph();
+    token.cancel();
+    let mut reports = 0;
+    let summary = graph.optimize_with(&mut (
+        |_: &Progress| {
+            reports += 1;
+            Control::Continue
+        },
+        token,
+    ));
+    assert_eq!(summary.termination, Termination::Cancelled);
+    assert_eq!(reports, 1);
+}
+
+#[test]
+fn reset_token_observes_another_optimization() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let summary = graph().optimize_with(&mut token.clone());
+    assert_eq!(summary.termination, Termination::Cancelled);
+
+    token.reset();
+    assert!(!token.is_cancelled());
+    let summary = graph().optimize_with(&mut token.clone());
+    assert_eq!(summary.termination, Termination::Converged);
+}
+
+#[derive(Clone)]
+struct Paraboloid;
+
+impl ArgminOp for Paraboloid {
+    type Param = Array1<f64>;
+    type Output = f64;
+    type Hessian = ();
+    type Jacobian = ();
+    type Float = f64;
+
+    fn apply(&self, p: &Self::Param) -> Result<Self::Output, Error> {
+        Ok((p[0] - 1.0).powi(2) + 2.0 * (p[1] + 3.0).powi(2))
+    }
+}
+
+#[test]
+fn argmin_observer_cancels_executor() {
+    let solver = || {
+        NelderMead::new().with_initial_params(vec![
+            array![0.0, 0.0],
+            array![1.0, 0.0],
+            array![0.0, 1.0],
+        ])
+    };
+    let mut costs = vec![];
+    let observer = ArgminObserver::new(move |progress: &Progress| {
+        costs.push(progress.cost);
+        if costs.len() == 3 {
+            Control::Cancel
+        } else {
+            Control::Continue
+        }
+    });
+    let error = match Executor::new(Paraboloid, solver(), array![])
+        .add_observer(observer, ObserverMode::Always)
+        .max_iters(100)
+        .run()
+    {
+        Ok(_) => panic!("the executor wasn't cancelled"),
+        Err(error) => error,
+    };
+    assert!(error.is::<Cancelled>());
+
+    let result = Executor::new(Paraboloid, solver(), array![])
+        .add_observer(
+            ArgminObserver::new(TimeLimit(Duration::from_secs(60))),
+            ObserverMode::Always,
+        )
+        .max_iters(100)
+        .run()
+        .unwrap();
+    assert!(result.state.best_cost < 1e-6);
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::{circle, perturb, relative};
+use cv_core::nalgebra::{Matrix3, Matrix6, Rotation3, Vector3};
+use cv_core::{Pose, Sim3, WorldToCamera};
+use cv_optimize::{
+    CauchyLoss, LinearSolver, Matrix7, PoseGraph, RobustLoss, SquaredLoss, Termination,
+};
+use rand::{rngs::SmallRng, SeedableRng};
+
+const NODES: usize = 12;
+
+/// Creates a graph over the circle with odometry edges, a loop closure, and perturbed nodes.
+fn graph<L>(rng: &mut SmallRng, poses: &[WorldToCamera], loss: L) -> PoseGraph<L> {
+    let mut graph = PoseGraph::new().loss(loss);
+    for (ix, &pose) in poses.iter().enumerate() {
+        graph.add_node(if ix == 0 {
+            pose
+        } else {
+            perturb(rng, pose, 0.5, 0.05)
+        });
+    }
+    for i in 0..NODES {
+        let j = (i + 1) % NODES;
+        graph.add_edge(i, j, relative(poses[i], poses[j]), Matrix6::identity());
+    }
+    graph.add_edge(
+        0,
+        NODES / 2,
+        relative(poses[0], poses[NODES / 2]),
+        Matrix6::identity(),
+    );
+    graph
+}
+
+fn max_error<L>(graph: &PoseGraph<L>, poses: &[WorldToCamera]) -> f64 {
+    common::max_error((0..graph.len()).map(|ix| graph.node(ix)), poses)
+}
+
+#[test]
+fn recovers_circle() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let poses = circle(NODES);
+    let mut graph = graph(&mut rng, &poses, SquaredLoss);
+    let summary = graph.optimize();
+    assert_eq!(summary.termination, Termination::Converged);
+    assert!(summary.final_cost < 1e-12, "{:?}", summary);
+    assert_eq!(graph.node(0), poses[0]);
+    assert!(max_error(&graph, &poses) < 1e-6);
+}
+
+#[test]
+fn conjugate_gradient_solver_recovers_circle() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    let poses = circle(NODES);
+    let mut graph =
+        graph(&mut rng, &poses, SquaredLoss).linear_solver(LinearSolver::ConjugateGradient {
+            max_iterations: 200,
+            tolerance: 1e-12,
+        });
+    let summary = graph.optimize();
+    assert!(summary.final_cost < 1e-12, "{:?}", summary);
+    assert!(max_error(&graph, &poses) < 1e-6);
+}
+
+/// Optimizes the circle with a corrupted loop closure and returns the error of the poses.
+fn outlier_error<L: RobustLoss>(loss: L) -> f64 {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let poses = circle(NODES);
+    let mut graph = graph(&mut rng, &poses, loss);
+    let corrupted = WorldToCamera::from_parts(
+        Vector3::new(3.0, -2.0, 1.0),
+        Rotation3::from_euler_angles(0.3, 0.2, 0.1),
+    );
+    graph.add_edge(2, 8, relative(poses[2], corrupted), Matrix6::identity());
+    graph.optimize();
+    max_error(&graph, &poses)
+}
+
+#[test]
+fn cauchy_rejects_outlier_loop_closure() {
+    let squared = outlier_error(SquaredLoss);
+    let cauchy = outlier_error(CauchyLoss(0.1));
+    assert!(
+        cauchy < 0.1 * squared,
+        "cauchy {} squared {}",
+        cauchy,
+        squared
+    );
+}
+
+#[test]
+fn similarity_corrects_scale_drift() {
+    let poses = circle(NODES);
+    // The scale of the reconstruction drifts by 5% at every node, as in monocular odometry.
+    let drift = |ix: usize| 1.05f64.powi(ix as i32);
+    let truth: Vec<Sim3> = poses
+        .iter()
+        .enumerate()
+        .map(|(ix, pose)| {
+            let isometry = pose.isometry();
+            Sim3::from_parts(
+                isometry.translation.vector * drift(ix),
+                isometry.rotation,
+                drift(ix),
+            )
+        })
+        .collect();
+    // Start from the drifted poses, which have the right orientation but the wrong position and scale.
+    let mut graph = PoseGraph::new().similarity(true);
+    for similarity in &truth {
+        let drifted = Sim3::from_parts(similarity.translation(), similarity.rotation(), 1.0);
+        graph.add_similarity_node(drifted);
+    }
+    for i in 0..NODES {
+        let j = (i + 1) % NODES;
+        let measurement = truth[j] * truth[i].inverse();
+        graph.add_similarity_edge(i, j, measurement, Matrix7::identity());
+    }
+
+    let before = max_error(&graph, &poses);
+    let summary = graph.optimize();
+    assert!(summary.final_cost < 1e-12, "{:?}", summary);
+    let after = max_error(&graph, &poses);
+    assert!(after < 1e-6, "before {} after {}", before, after);
+    assert!((graph.similarity_node(NODES - 1).scale() - drift(NODES - 1)).abs() < 1e-6);
+}
+
+#[test]
+fn g2o_round_trip() {
+    let mut rng = SmallRng::seed_from_u64(2);
+    let poses = circle(NODES);
+    let mut graph = graph(&mut rng, &poses, SquaredLoss);
+    graph.fix_node(3);
+    let mut information = Matrix6::identity() * 2.0;
+    information[(1, 4)] = 0.5;
+    information[(4, 1)] = 0.5;
+    graph.add_edge(1, 5, relative(poses[1], poses[5]), information);
+
+    let file = graph.write_g2o().unwrap();
+    let read = PoseGraph::read_g2o(&file).unwrap();
+    assert_eq!(read.len(), graph.len());
+    assert!(read.is_fixed(3));
+    for ix in 0..graph.len() {
+        let difference = read.node(ix).isometry().inverse() * graph.node(ix).isometry();
+        assert!(difference.translation.vector.norm() < 1e-12);
+        assert!((difference.rotation.matrix() - Matrix3::identity()).norm() < 1e-12);
+    }
+    for (read, written) in read.edges().iter().zip(graph.edges()) {
+        assert_eq!((read.from, read.to), (written.from, written.to));
+        assert!(
+            (read.measurement.homogeneous() - written.measurement.homogeneous()).norm() < 1e-12
+        );
+        assert!((read.information - written.information).norm() < 1e-12);
+    }
+    assert!((read.cost() - graph.cost()).abs() < 1e-9 * graph.cost());
+}
+
+#[test]
+fn g2o_reports_errors() {
+    let file = "# a comment\nVERTEX_SE3:QUAT 0 0 0 0 0 0 0 1\nEDGE_SE3:QUAT 0 1 0 0 0 0 0 0 1\n";
+    let error = PoseGraph::read_g2o(file).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "g2o line 3: expected 30 values after EDGE_SE3:QUAT, found 9"
+    );
+    assert!(PoseGraph::new().similarity(true).write_g2o().is_err());
+}
@@ -0,0 +1,94 @@
+use cv_core::nalgebra::{DMatrix, DVector};
+use cv_optimize::{
+    conjugate_gradient, BlockJacobiPreconditioner, JacobiPreconditioner, SparseCholesky,
+    SparseMatrix, SymbolicCholesky,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// Creates a random sparse Jacobian where each residual depends on a few parameters.
+fn jacobian(rng: &mut SmallRng, rows: usize, columns: usize) -> SparseMatrix {
+    let mut triplets = vec![];
+    for row in 0..rows {
+        for _ in 0..3 {
+            triplets.push((row, rng.gen_range(0, columns), rng.gen_range(-1.0, 1.0)));
+        }
+    }
+    // Make sure every parameter is constrained.
+    for column in 0..columns {
+        triplets.push((column, column, 1.0));
+    }
+    SparseMatrix::from_triplets(rows, columns, &triplets)
+}
+
+#[test]
+fn normal_equations_match_dense() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let jacobian = jacobian(&mut rng, 80, 40);
+    let residuals = DVector::from_fn(80, |_, _| rng.gen_range(-1.0, 1.0));
+    let (hessian, gradient) = jacobian.normal_equations(&residuals);
+    let dense = jacobian.to_dense();
+    assert!((hessian.to_dense() - dense.transpose() * &dense).norm() < 1e-12);
+    assert!((gradient - dense.transpose() * &residuals).norm() < 1e-12);
+    assert_eq!(SparseMatrix::from_dense(&dense), jacobian);
+}
+
+#[test]
+fn cholesky_matches_dense() {
+    let mut rng = SmallRng::seed_from_u64(1);
+    let (hessian, _) = jacobian(&mut rng, 300, 100).normal_equations(&DVector::zeros(300));
+    let b = DVector::from_fn(100, |_, _| rng.gen_range(-1.0, 1.0));
+    let expected = hessian.to_dense().cholesky().unwrap().solve(&b);
+    let solution = SparseCholesky::new(&hessian).unwrap().solve(&b);
+    assert!((solution - expected).norm() < 1e-9);
+}
+
+#[test]
+fn symbolic_analysis_is_reused() {
+    let mut rng = SmallRng::seed_from_u64(2);
+    let (hessian, _) = jacobian(&mut rng, 200, 60).normal_equations(&DVector::zeros(200));
+    let symbolic = SymbolicCholesky::new(&hessian);
+    let b = DVector::from_fn(60, |_, _| rng.gen_range(-1.0, 1.0));
+    for &damping in &[0.0, 1e-3, 10.0] {
+        let mut damped = hessian.clone();
+        damped.add_diagonal(&(hessian.diagonal() * damping));
+        assert!(symbolic.matches(&damped));
+        let solution = symbolic.factor(&damped).unwrap().solve(&b);
+        assert!((damped.mul_vector(&solution) - &b).norm() < 1e-9);
+    }
+}
+
+#[test]
+fn cholesky_rejects_indefinite_matrices() {
+    let matrix = SparseMatrix::from_dense(&DMatrix::from_row_slice(
+        3,
+        3,
+        &[2.0, 1.0, 0.0, 1.0, -1.0, 0.0, 0.0, 0.0, 1.0],
+    ));
+    assert!(SparseCholesky::new(&matrix).is_none());
+}
+
+#[test]
+fn conjugate_gradient_converges() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    let (hessian, _) = jacobian(&mut rng, 300, 100).normal_equations(&DVector::zeros(300));
+    let b = DVector::from_fn(100, |_, _| rng.gen_range(-1.0, 1.0));
+    let expected = SparseCholesky::new(&hessian).unwrap().solve(&b);
+    let jacobi = conjugate_gradient(
+        &hessian,
+        &b,
+        &JacobiPreconditioner::new(&hessian),
+        1000,
+        1e-12,
+    );
+    let block_jacobi = conjugate_gradient(
+        &hessian,
+        &b,
+        &BlockJacobiPreconditioner::new(&hessian, 6),
+        1000,
+        1e-12,
+    );
+    for report in &[jacobi, block_jacobi] {
+        assert!(report.relative_residual <= 1e-12);
+        assert!((&report.solution - &expected).norm() < 1e-8);
+    }
+}
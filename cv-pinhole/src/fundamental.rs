@@ -0,0 +1,165 @@
+use crate::{CameraIntrinsics, EssentialMatrix};
+use cv_core::nalgebra::{Matrix3, Point2, Vector3};
+use cv_core::sample_consensus::Model;
+use cv_core::{FeatureMatch, KeyPoint};
+use derive_more::{AsMut, AsRef, Deref, DerefMut, From, Into};
+use num_traits::Float;
+
+/// This stores a fundamental matrix, which is satisfied by the following constraint:
+///
+/// transpose(x') * F * x = 0
+///
+/// Where `x'` and `x` are homogeneous pixel coordinates. You can get a homogeneous
+/// pixel coordinate by appending `1.0` to a [`KeyPoint`].
+///
+/// The fundamental matrix embodies the same epipolar constraint as the [`EssentialMatrix`],
+/// but between pixel coordinates rather than normalized image coordinates. This means that it can
+/// be estimated from images taken by cameras with unknown intrinsics. If the intrinsic matrices
+/// `K` and `K'` of both cameras are known, the fundamental matrix is related to the essential
+/// matrix by:
+///
+/// ```text
+/// E = transpose(K') * F * K
+/// ```
+///
+/// A fundamental matrix must have rank 2, which can be enforced with [`FundamentalMatrix::recondition`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, AsMut, AsRef, Deref, DerefMut, From, Into)]
+pub struct FundamentalMatrix(pub Matrix3<f64>);
+
+impl FundamentalMatrix {
+    /// Creates the fundamental matrix between the pixel coordinates of two cameras from
+    /// the essential matrix between them and their intrinsics.
+    ///
+    /// Returns `None` if either intrinsic matrix is not invertible.
+    pub fn from_essential(
+        essential: EssentialMatrix,
+        a: &CameraIntrinsics,
+        b: &CameraIntrinsics,
+    ) -> Option<Self> {
+        let a_inverse = a.matrix().try_inverse()?;
+        let b_inverse = b.matrix().try_inverse()?;
+        Some(Self(b_inverse.transpose() * essential.0 * a_inverse))
+    }
+
+    /// Can be used to enforce the rank 2 constraint of a fundamental matrix to fix it.
+    ///
+    /// This finds the closest rank 2 matrix in frobenius form by forcing the smallest
+    /// singular value to zero.
+    pub fn recondition(self, epsilon: f64, max_iterations: usize) -> Option<Self> {
+        let mut svd = self.try_svd(true, true, epsilon, max_iterations)?;
+        let smallest = svd.singular_values.imin();
+        svd.singular_values[smallest] = 0.0;
+        // Cannot fail because we asked for both U and V* on decomp.
+        let mat = svd.recompose().unwrap();
+        Some(Self(mat))
+    }
+
+    /// Converts the fundamental matrix into an essential matrix using the intrinsics of both cameras.
+    ///
+    /// `a` is the camera of the first image and `b` is the camera of the second image in the matches.
+    /// The resulting essential matrix generally doesn't satisfy the constraints of an essential matrix
+    /// exactly, which can be fixed with [`EssentialMatrix::recondition`].
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{IsometryMatrix3, Point2, Rotation3, Vector2, Vector3};
+    /// use cv_core::CameraToCamera;
+    /// use cv_pinhole::{CameraIntrinsics, EssentialMatrix, FundamentalMatrix};
+    /// let pose = CameraToCamera(IsometryMatrix3::from_parts(
+    ///     Vector3::new(-0.8, 0.4, 0.5).into(),
+    ///     Rotation3::from_euler_angles(0.2, 0.3, 0.4),
+    /// ));
+    /// let a = CameraIntrinsics::identity()
+    ///     .focal(800.0)
+    ///     .principal_point(Point2::new(320.0, 240.0));
+    /// let b = CameraIntrinsics::identity()
+    ///     .focals(Vector2::new(600.0, 610.0))
+    ///     .principal_point(Point2::new(400.0, 300.0));
+    /// let essential = EssentialMatrix::from(pose);
+    /// let fundamental = FundamentalMatrix::from_essential(essential, &a, &b).unwrap();
+    /// let residual = (fundamental.essential(&a, &b).0 - essential.0).norm();
+    /// assert!(residual < 1e-9);
+    /// ```
+    pub fn essential(&self, a: &CameraIntrinsics, b: &CameraIntrinsics) -> EssentialMatrix {
+        EssentialMatrix(b.matrix().transpose() * self.0 * a.matrix())
+    }
+
+    /// Estimates the focal lengths of the cameras of both images from their principal points with the
+    /// closed-form solution of Bougnoux, which assumes square pixels and no skew.
+    ///
+    /// `a` is the principal point of the first image and `b` is the principal point of the second image.
+    /// This is useful to initialize self-calibration when the focal length is only known roughly.
+    ///
+    /// Returns `None` if either focal length can't be recovered, which happens when the optical axes of
+    /// the cameras intersect (such as when both cameras look at the same point) or there is no translation.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{IsometryMatrix3, Point2, Rotation3, Vector3};
+    /// use cv_core::CameraToCamera;
+    /// use cv_pinhole::{CameraIntrinsics, EssentialMatrix, FundamentalMatrix};
+    /// let pose = CameraToCamera(IsometryMatrix3::from_parts(
+    ///     Vector3::new(-0.8, 0.4, 0.5).into(),
+    ///     Rotation3::from_euler_angles(0.2, 0.3, 0.4),
+    /// ));
+    /// let a = CameraIntrinsics::identity()
+    ///     .focal(800.0)
+    ///     .principal_point(Point2::new(320.0, 240.0));
+    /// let b = CameraIntrinsics::identity()
+    ///     .focal(600.0)
+    ///     .principal_point(Point2::new(400.0, 300.0));
+    /// let fundamental = FundamentalMatrix::from_essential(EssentialMatrix::from(pose), &a, &b).unwrap();
+    /// let (focal_a, focal_b) = fundamental
+    ///     .focal_lengths(a.principal_point, b.principal_point)
+    ///     .unwrap();
+    /// assert!((focal_a - 800.0).abs() < 1e-6);
+    /// assert!((focal_b - 600.0).abs() < 1e-6);
+    /// ```
+    pub fn focal_lengths(&self, a: Point2<f64>, b: Point2<f64>) -> Option<(f64, f64)> {
+        let (a, b) = (a.to_homogeneous(), b.to_homogeneous());
+        let svd = self.0.try_svd(true, true, 1e-12, 1000)?;
+        let smallest = svd.singular_values.imin();
+        // The epipoles are the null vectors of the fundamental matrix on either side.
+        let epipole_a = svd.v_t?.row(smallest).transpose();
+        let epipole_b = svd.u?.column(smallest).into_owned();
+        let focal_a = bougnoux(&self.0, &epipole_b, &a, &b)?;
+        let focal_b = bougnoux(&self.0.transpose(), &epipole_a, &b, &a)?;
+        Some((focal_a, focal_b))
+    }
+}
+
+/// Computes the focal length of the first camera of a fundamental matrix with the formula of Bougnoux, where
+/// `epipole` is the epipole in the second image and `a` and `b` are the homogeneous principal points.
+fn bougnoux(
+    fundamental: &Matrix3<f64>,
+    epipole: &Vector3<f64>,
+    a: &Vector3<f64>,
+    b: &Vector3<f64>,
+) -> Option<f64> {
+    let truncate = Matrix3::from_diagonal(&Vector3::new(1.0, 1.0, 0.0));
+    let left = b.transpose() * epipole.cross_matrix() * truncate * fundamental;
+    let numerator = (left * a * a.transpose() * fundamental.transpose() * b)[0];
+    let denominator = (left * truncate * fundamental.transpose() * b)[0];
+    let squared = -numerator / denominator;
+    if squared.is_finite() && squared > 0.0 {
+        Some(Float::sqrt(squared))
+    } else {
+        None
+    }
+}
+
+impl Model<FeatureMatch<KeyPoint>> for FundamentalMatrix {
+    /// Computes the Sampson distance, which is a first order approximation of the distance (in pixels)
+    /// that the points must be moved to satisfy the epipolar constraint.
+    fn residual(&self, data: &FeatureMatch<KeyPoint>) -> f64 {
+        let Self(mat) = *self;
+        let FeatureMatch(a, b) = *data;
+        let (a, b) = (a.0.to_homogeneous(), b.0.to_homogeneous());
+        let fa = mat * a;
+        let ftb = mat.transpose() * b;
+        let error = b.dot(&fa);
+        let gradient = fa.xy().norm_squared() + ftb.xy().norm_squared();
+        if gradient == 0.0 {
+            return if error == 0.0 { 0.0 } else { f64::INFINITY };
+        }
+        Float::sqrt(error * error / gradient)
+    }
+}
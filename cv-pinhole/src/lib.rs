@@ -9,13 +9,18 @@
 extern crate alloc;
 
 mod essential;
+mod fundamental;
 
 pub use essential::*;
+pub use fundamental::*;
 
-use cv_core::nalgebra::{Matrix3, Point2, Point3, Vector2, Vector3};
+use cv_core::nalgebra::{
+    Matrix2, Matrix2x4, Matrix2x5, Matrix2x6, Matrix3, Point2, Point3, Vector2, Vector3, Vector5,
+    Vector6, U5, U6,
+};
 use cv_core::{
-    Bearing, CameraModel, CameraPoint, CameraToCamera, FeatureMatch, ImagePoint, KeyPoint, Pose,
-    Projective, TriangulatorRelative,
+    Bearing, CameraModel, CameraModelJacobians, CameraPoint, CameraToCamera, FeatureMatch,
+    ImagePoint, KeyPoint, Pose, Projective, TriangulatorRelative,
 };
 use derive_more::{AsMut, AsRef, Deref, DerefMut, From, Into};
 use num_traits::Float;
@@ -136,6 +141,34 @@ impl CameraIntrinsics {
             0.0,            0.0,            1.0,
         )
     }
+
+    /// Converts a [`NormalizedKeyPoint`] into pixel coordinates, while also retrieving both Jacobians.
+    ///
+    /// The following things are returned in this order:
+    ///
+    /// * The pixel location of the keypoint
+    /// * The Jacobian of the pixel location in respect to the normalized keypoint
+    /// * The Jacobian of the pixel location in respect to the intrinsic parameters
+    ///   (in the order of [`CameraModelJacobians::parameters`])
+    ///
+    /// This is useful to implement [`CameraModelJacobians`] for camera models which are built
+    /// on top of these intrinsics.
+    #[rustfmt::skip]
+    pub fn normalized_jacobians(
+        &self,
+        projection: NormalizedKeyPoint,
+    ) -> (KeyPoint, Matrix2<f64>, Matrix2x5<f64>) {
+        let NormalizedKeyPoint(Point2 { coords: m }) = projection;
+        let jacobian_projection = Matrix2::new(
+            self.focals.x,  self.skew,
+            0.0,            self.focals.y,
+        );
+        let jacobian_intrinsics = Matrix2x5::new(
+            m.x,    0.0,    1.0,    0.0,    m.y,
+            0.0,    m.y,    0.0,    1.0,    0.0,
+        );
+        (self.uncalibrate(projection), jacobian_projection, jacobian_intrinsics)
+    }
 }
 
 impl CameraModel for CameraIntrinsics {
@@ -193,6 +226,95 @@ impl CameraModel for CameraIntrinsics {
     }
 }
 
+/// Projects the [`CameraPoint`] onto the virtual image plane, while also retrieving the Jacobian
+/// of the [`NormalizedKeyPoint`] in respect to the homogeneous point.
+///
+/// Returns `None` if the point is not in front of the camera.
+#[rustfmt::skip]
+fn normalize_jacobian(point: CameraPoint) -> Option<(NormalizedKeyPoint, Matrix2x4<f64>)> {
+    let p = point.bearing_unnormalized();
+    if p.z <= 0.0 {
+        return None;
+    }
+    let iz = 1.0 / p.z;
+    let nkp = NormalizedKeyPoint(Point2::new(p.x * iz, p.y * iz));
+    let jacobian = Matrix2x4::new(
+        iz,     0.0,    -nkp.x * iz,    0.0,
+        0.0,    iz,     -nkp.y * iz,    0.0,
+    );
+    Some((nkp, jacobian))
+}
+
+impl CameraModelJacobians for CameraIntrinsics {
+    /// The parameters are `[fx, fy, cx, cy, skew]`.
+    type Parameters = U5;
+
+    fn parameters(&self) -> Vector5<f64> {
+        Vector5::new(
+            self.focals.x,
+            self.focals.y,
+            self.principal_point.x,
+            self.principal_point.y,
+            self.skew,
+        )
+    }
+
+    fn from_parameters(parameters: &Vector5<f64>) -> Self {
+        Self {
+            focals: Vector2::new(parameters[0], parameters[1]),
+            principal_point: Point2::new(parameters[2], parameters[3]),
+            skew: parameters[4],
+        }
+    }
+
+    /// Projects a [`CameraPoint`] into pixel coordinates and computes the Jacobians.
+    ///
+    /// ```
+    /// use cv_core::{CameraModelJacobians, CameraPoint, Projective};
+    /// use cv_core::nalgebra::{Point2, Point3, Vector2, Vector4, Vector5};
+    /// use cv_pinhole::CameraIntrinsics;
+    /// let intrinsics = CameraIntrinsics {
+    ///     focals: Vector2::new(800.0, 900.0),
+    ///     principal_point: Point2::new(500.0, 600.0),
+    ///     skew: 1.7,
+    /// };
+    /// let point = CameraPoint::from_point(Point3::new(0.3, -0.2, 2.0));
+    /// let (kp, jacobian_point, jacobian_intrinsics) = intrinsics.uncalibrate_jacobians(point).unwrap();
+    /// // Compare against central finite differences.
+    /// let eps = 1e-6;
+    /// for i in 0..4 {
+    ///     let mut delta = Vector4::zeros();
+    ///     delta[i] = eps;
+    ///     let (a, _, _) = intrinsics.uncalibrate_jacobians(CameraPoint(point.0 + delta)).unwrap();
+    ///     let (b, _, _) = intrinsics.uncalibrate_jacobians(CameraPoint(point.0 - delta)).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian_point.column(i)).norm() < 1e-3);
+    /// }
+    /// for i in 0..5 {
+    ///     let mut delta = Vector5::zeros();
+    ///     delta[i] = eps;
+    ///     let a = CameraIntrinsics::from_parameters(&(intrinsics.parameters() + delta));
+    ///     let b = CameraIntrinsics::from_parameters(&(intrinsics.parameters() - delta));
+    ///     let (a, _, _) = a.uncalibrate_jacobians(point).unwrap();
+    ///     let (b, _, _) = b.uncalibrate_jacobians(point).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian_intrinsics.column(i)).norm() < 1e-3);
+    /// }
+    /// ```
+    fn uncalibrate_jacobians(
+        &self,
+        point: CameraPoint,
+    ) -> Option<(KeyPoint, Matrix2x4<f64>, Matrix2x5<f64>)> {
+        let (nkp, jacobian_normalize) = normalize_jacobian(point)?;
+        let (kp, jacobian_projection, jacobian_intrinsics) = self.normalized_jacobians(nkp);
+        Some((
+            kp,
+            jacobian_projection * jacobian_normalize,
+            jacobian_intrinsics,
+        ))
+    }
+}
+
 /// This contains intrinsic camera parameters as per
 /// [this Wikipedia page](https://en.wikipedia.org/wiki/Camera_resectioning#Intrinsic_parameters).
 ///
@@ -212,6 +334,18 @@ impl CameraIntrinsicsK1Distortion {
             k1,
         }
     }
+    /// Applies the K1 distortion to an undistorted [`NormalizedKeyPoint`].
+    fn distort(&self, projection: NormalizedKeyPoint) -> NormalizedKeyPoint {
+        let NormalizedKeyPoint(undistorted) = projection;
+        // This was not easy to compute, but you can set up a quadratic to solve
+        // for r^2 with the undistorted keypoint. This is the result.
+        let k1_mul_u2 = self.k1 * undistorted.coords.norm_squared();
+        // This is actually r^2 * k1. The root of the quadratic is rationalized so that it is
+        // still defined when there is no distortion (`k1 * u^2 == 0`).
+        let r2_mul_k1 =
+            2.0 * k1_mul_u2 / (1.0 - 2.0 * k1_mul_u2 + Float::sqrt(1.0 - 4.0 * k1_mul_u2));
+        NormalizedKeyPoint((undistorted.coords * (1.0 + r2_mul_k1)).into())
+    }
 }
 
 impl CameraModel for CameraIntrinsicsK1Distortion {
@@ -270,17 +404,95 @@ impl CameraModel for CameraIntrinsicsK1Distortion {
     /// let nkp = intrinsics.calibrate(kp);
     /// let ukp = intrinsics.uncalibrate(nkp);
     /// assert!((kp.0 - ukp.0).norm() < 1e-6, "{:?}", (kp.0 - ukp.0).norm());
+    /// // Without distortion it is the same as the simple intrinsics.
+    /// let undistorted = CameraIntrinsicsK1Distortion::new(intrinsics.simple_intrinsics, 0.0);
+    /// assert_eq!(undistorted.uncalibrate(nkp), intrinsics.simple_intrinsics.uncalibrate(nkp));
     /// ```
     fn uncalibrate(&self, projection: NormalizedKeyPoint) -> KeyPoint {
-        let NormalizedKeyPoint(undistorted) = projection;
-        // This was not easy to compute, but you can set up a quadratic to solve
-        // for r^2 with the undistorted keypoint. This is the result.
-        let u2 = undistorted.coords.norm_squared();
-        // This is actually r^2 * k1.
-        let r2_mul_k1 = -(2.0 * self.k1 * u2 + Float::sqrt(1.0 - 4.0 * self.k1 * u2) - 1.0)
-            / (2.0 * self.k1 * u2);
-        self.simple_intrinsics.uncalibrate(NormalizedKeyPoint(
-            (undistorted.coords * (1.0 + r2_mul_k1)).into(),
+        self.simple_intrinsics.uncalibrate(self.distort(projection))
+    }
+}
+
+impl CameraModelJacobians for CameraIntrinsicsK1Distortion {
+    /// The parameters are `[fx, fy, cx, cy, skew, k1]`.
+    type Parameters = U6;
+
+    fn parameters(&self) -> Vector6<f64> {
+        self.simple_intrinsics.parameters().push(self.k1)
+    }
+
+    fn from_parameters(parameters: &Vector6<f64>) -> Self {
+        Self::new(
+            CameraIntrinsics::from_parameters(&parameters.fixed_rows::<U5>(0).into_owned()),
+            parameters[5],
+        )
+    }
+
+    /// Projects a [`CameraPoint`] into pixel coordinates and computes the Jacobians.
+    ///
+    /// ```
+    /// use cv_core::{CameraModelJacobians, CameraPoint, Projective};
+    /// use cv_core::nalgebra::{Point2, Point3, Vector2, Vector4, Vector6};
+    /// use cv_pinhole::{CameraIntrinsics, CameraIntrinsicsK1Distortion};
+    /// let intrinsics = CameraIntrinsicsK1Distortion::new(
+    ///     CameraIntrinsics {
+    ///         focals: Vector2::new(800.0, 900.0),
+    ///         principal_point: Point2::new(500.0, 600.0),
+    ///         skew: 1.7,
+    ///     },
+    ///     -0.164624,
+    /// );
+    /// let point = CameraPoint::from_point(Point3::new(0.3, -0.2, 2.0));
+    /// let (kp, jacobian_point, jacobian_intrinsics) = intrinsics.uncalibrate_jacobians(point).unwrap();
+    /// // Compare against central finite differences.
+    /// let eps = 1e-6;
+    /// for i in 0..4 {
+    ///     let mut delta = Vector4::zeros();
+    ///     delta[i] = eps;
+    ///     let (a, _, _) = intrinsics.uncalibrate_jacobians(CameraPoint(point.0 + delta)).unwrap();
+    ///     let (b, _, _) = intrinsics.uncalibrate_jacobians(CameraPoint(point.0 - delta)).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian_point.column(i)).norm() < 1e-3);
+    /// }
+    /// for i in 0..6 {
+    ///     let mut delta = Vector6::zeros();
+    ///     delta[i] = eps;
+    ///     let a = CameraIntrinsicsK1Distortion::from_parameters(&(intrinsics.parameters() + delta));
+    ///     let b = CameraIntrinsicsK1Distortion::from_parameters(&(intrinsics.parameters() - delta));
+    ///     let (a, _, _) = a.uncalibrate_jacobians(point).unwrap();
+    ///     let (b, _, _) = b.uncalibrate_jacobians(point).unwrap();
+    ///     let numeric = (a.0 - b.0) / (2.0 * eps);
+    ///     assert!((numeric - jacobian_intrinsics.column(i)).norm() < 1e-3);
+    /// }
+    /// ```
+    fn uncalibrate_jacobians(
+        &self,
+        point: CameraPoint,
+    ) -> Option<(KeyPoint, Matrix2x4<f64>, Matrix2x6<f64>)> {
+        let (undistorted, jacobian_normalize) = normalize_jacobian(point)?;
+        let distorted = self.distort(undistorted);
+        // The distorted point `d` is defined implicitly by `d = u * (1 + k1 * |d|^2)`,
+        // so the implicit function theorem is used to find its derivatives.
+        let u = undistorted.coords;
+        let d = distorted.coords;
+        let r2 = d.norm_squared();
+        let implicit = (Matrix2::identity() - 2.0 * self.k1 * u * d.transpose()).try_inverse()?;
+        let jacobian_distort = implicit * (1.0 + self.k1 * r2);
+        let jacobian_k1 = implicit * u * r2;
+
+        let (kp, jacobian_projection, jacobian_simple) =
+            self.simple_intrinsics.normalized_jacobians(distorted);
+        let mut jacobian_intrinsics = Matrix2x6::zeros();
+        jacobian_intrinsics
+            .fixed_columns_mut::<U5>(0)
+            .copy_from(&jacobian_simple);
+        jacobian_intrinsics
+            .column_mut(5)
+            .copy_from(&(jacobian_projection * jacobian_k1));
+        Some((
+            kp,
+            jacobian_projection * jacobian_distort * jacobian_normalize,
+            jacobian_intrinsics,
         ))
     }
 }
@@ -1,35 +1,43 @@
 mod bicubic;
 mod export;
+mod model_selection;
 mod settings;
 
 pub use export::*;
+pub use model_selection::*;
 pub use settings::*;
 
-use argmin::core::{ArgminKV, ArgminOp, Error, Executor, IterState, Observe, ObserverMode};
+use argmin::core::{Error, Executor, ObserverMode};
 use bitarray::BitArray;
-use cv_core::nalgebra::{Unit, Vector3, Vector6};
+use cv_core::nalgebra::Vector6;
 use cv_core::{
-    sample_consensus::{Consensus, Estimator},
-    Bearing, CameraModel, CameraToCamera, FeatureMatch, FeatureWorldMatch, Pose, Projective,
+    sample_consensus::{Consensus, Estimator, Model},
+    Bearing, CameraModel, CameraModelJacobians, CameraToCamera, FeatureMatch, FeatureWorldMatch,
+    ImagePoint, KeyPoint, Pose, Projective, Sim3, StampedPose, Trajectory, Triangulation,
     TriangulatorObservations, TriangulatorRelative, WorldPoint, WorldToCamera,
 };
 use cv_optimize::{
-    many_view_nelder_mead, single_view_nelder_mead, two_view_nelder_mead, ManyViewConstraint,
-    SingleViewConstraint, TwoViewConstraint,
+    single_view_nelder_mead, two_view_nelder_mead, ArgminObserver, BundleAdjuster, Cancelled,
+    Control, Gauge, HuberLoss, NoObserver, Observer, Progress, SingleViewConstraint, TruncatedLoss,
+    TwoViewConstraint,
 };
 use cv_pinhole::{CameraIntrinsicsK1Distortion, EssentialMatrix, NormalizedKeyPoint};
+use eight_point::EightPoint;
+use epnp::EPnP;
+use four_point::{FourPoint, Homography};
 use hnsw::{Searcher, HNSW};
 use image::DynamicImage;
 use itertools::{izip, Itertools};
 use log::*;
 use maplit::hashmap;
-use ndarray::{array, Array2};
+use ndarray::array;
 use rand::{seq::SliceRandom, Rng};
 use slotmap::{new_key_type, DenseSlotMap};
 use space::Neighbor;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
@@ -42,25 +50,19 @@ new_key_type! {
     pub struct ReconstructionKey;
 }
 
-struct OptimizationObserver;
-
-impl<T: ArgminOp> Observe<T> for OptimizationObserver
-where
-    T::Param: std::fmt::Debug,
-{
-    fn observe_iter(&mut self, state: &IterState<T>, _kv: &ArgminKV) -> Result<(), Error> {
-        debug!(
-            "on iteration {} out of {} with total evaluations {} and current cost {}, params {:?}",
-            state.iter, state.max_iters, state.cost_func_count, state.cost, state.param
-        );
-        Ok(())
-    }
-}
+/// The indices of the focal lengths in the parameters of [`CameraIntrinsicsK1Distortion`].
+const FOCAL_PARAMETERS: [usize; 2] = [0, 1];
+/// The indices of the principal point in the parameters of [`CameraIntrinsicsK1Distortion`].
+const PRINCIPAL_POINT_PARAMETERS: [usize; 2] = [2, 3];
+/// The index of the K1 distortion in the parameters of [`CameraIntrinsicsK1Distortion`].
+const K1_PARAMETER: usize = 5;
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct Feature {
     pub keypoint: NormalizedKeyPoint,
+    /// The pixel coordinates the keypoint was detected at, which it is calibrated from
+    pub pixel: KeyPoint,
     pub descriptor: BitArray<64>,
     pub color: [u8; 3],
 }
@@ -80,6 +82,8 @@ impl Pair {
 pub struct Frame {
     /// A VSlam::feeds index
     pub feed: FeedKey,
+    /// The time the frame was captured, if it is known
+    pub timestamp: Option<f64>,
     /// The keypoints and corresponding descriptors observed on this frame
     pub features: Vec<Feature>,
 }
@@ -93,6 +97,10 @@ impl Frame {
         self.features[ix].keypoint
     }
 
+    pub fn pixel(&self, ix: usize) -> KeyPoint {
+        self.features[ix].pixel
+    }
+
     pub fn descriptor(&self, ix: usize) -> &BitArray<64> {
         &self.features[ix].descriptor
     }
@@ -108,6 +116,11 @@ impl Frame {
 pub struct Landmark {
     /// Contains a map from VSlam::views indices to Frame::features indices.
     pub observations: HashMap<ViewKey, usize>,
+    /// The point optimized by the last bundle adjust, which is cleared when the observations change.
+    ///
+    /// If this is `None`, the landmark is triangulated from its observations when its point is needed.
+    #[cfg_attr(feature = "serde-serialize", serde(default))]
+    pub point: Option<WorldPoint>,
 }
 
 /// A frame which has been incorporated into a reconstruction.
@@ -128,6 +141,11 @@ pub struct View {
 pub struct Feed {
     /// The camera intrinsics for this feed
     intrinsics: CameraIntrinsicsK1Distortion,
+    /// The camera intrinsics the feed was added with, which are the priors of self-calibration
+    prior_intrinsics: CameraIntrinsicsK1Distortion,
+    /// Whether the focal length has been estimated from the fundamental matrix for self-calibration,
+    /// which is only attempted once, whether or not the estimate is used
+    focal_attempted: bool,
     /// VSlam::frames indices corresponding to each frame of the feed
     frames: Vec<FrameKey>,
     /// The VSlam::reconstructions index currently being tracked
@@ -135,6 +153,13 @@ pub struct Feed {
     reconstruction: Option<ReconstructionKey>,
 }
 
+impl Feed {
+    /// The current camera intrinsics of the feed, which are refined if self-calibration is enabled.
+    pub fn intrinsics(&self) -> CameraIntrinsicsK1Distortion {
+        self.intrinsics
+    }
+}
+
 /// A series of views and points which exist in the same world space
 #[derive(Clone, Default)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -157,6 +182,10 @@ pub struct BundleAdjustment {
     reconstruction: ReconstructionKey,
     /// Maps VSlam::views IDs to poses
     poses: Vec<(ViewKey, WorldToCamera)>,
+    /// Maps VSlam::feeds IDs to refined intrinsics
+    intrinsics: Vec<(FeedKey, CameraIntrinsicsK1Distortion)>,
+    /// Maps Reconstruction::landmarks IDs to optimized points
+    landmarks: Vec<(LandmarkKey, WorldPoint)>,
 }
 
 /// The mapping data for VSlam.
@@ -216,6 +245,32 @@ impl VSlamData {
         self.view(reconstruction, view).pose
     }
 
+    /// Retrieves the trajectory of a feed within a reconstruction.
+    ///
+    /// Only the frames of the feed which have a finite timestamp and a view in the reconstruction are included.
+    pub fn feed_trajectory(
+        &self,
+        reconstruction: ReconstructionKey,
+        feed: FeedKey,
+    ) -> Trajectory<Vec<StampedPose>> {
+        let mut poses: Vec<StampedPose> = self.reconstructions[reconstruction]
+            .views
+            .values()
+            .filter_map(|view| {
+                let frame = &self.frames[view.frame];
+                if frame.feed != feed {
+                    return None;
+                }
+                Some(StampedPose {
+                    timestamp: frame.timestamp.filter(|timestamp| timestamp.is_finite())?,
+                    pose: view.pose,
+                })
+            })
+            .collect();
+        poses.sort_unstable_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Trajectory::new(poses)
+    }
+
     pub fn observation_landmark(
         &self,
         reconstruction: ReconstructionKey,
@@ -243,6 +298,16 @@ impl VSlamData {
         self.keypoint(self.view_frame(reconstruction, view), feature)
     }
 
+    pub fn observation_pixel(
+        &self,
+        reconstruction: ReconstructionKey,
+        view: ViewKey,
+        feature: usize,
+    ) -> KeyPoint {
+        self.frame(self.view_frame(reconstruction, view))
+            .pixel(feature)
+    }
+
     pub fn is_observation_good(
         &self,
         reconstruction: ReconstructionKey,
@@ -363,9 +428,9 @@ impl VSlamData {
             // Check if the feature is part of an existing landmark.
             let landmark = if let Some(landmark) = existing_landmark(feature) {
                 // Add this observation to the observations of this landmark.
-                self.reconstructions[reconstruction].landmarks[landmark]
-                    .observations
-                    .insert(view, feature);
+                let landmark_object = &mut self.reconstructions[reconstruction].landmarks[landmark];
+                landmark_object.observations.insert(view, feature);
+                landmark_object.point = None;
                 landmark
             } else {
                 // Create the landmark.
@@ -391,6 +456,7 @@ impl VSlamData {
                 observations: hashmap! {
                     view => feature,
                 },
+                point: None,
             })
     }
 
@@ -436,14 +502,75 @@ impl VSlamData {
         }
     }
 
+    /// Transforms the world space of a reconstruction by a similarity, moving every view with it.
+    ///
+    /// This can bring a reconstruction into the world space of another reconstruction (or of surveyed points)
+    /// before they are merged, which is needed since each reconstruction has its own arbitrary scale.
+    pub fn transform_reconstruction(
+        &mut self,
+        reconstruction: ReconstructionKey,
+        similarity: Sim3,
+    ) {
+        for view in self.reconstructions[reconstruction].views.values_mut() {
+            view.pose = similarity.transform_pose(view.pose);
+        }
+        for landmark in self.reconstructions[reconstruction].landmarks.values_mut() {
+            landmark.point = landmark.point.map(|point| similarity.transform(point));
+        }
+    }
+
     fn apply_bundle_adjust(&mut self, bundle_adjust: BundleAdjustment) {
         let BundleAdjustment {
             reconstruction,
             poses,
+            intrinsics,
+            landmarks,
         } = bundle_adjust;
+        for (feed, intrinsics) in intrinsics {
+            self.set_intrinsics(feed, intrinsics);
+        }
         for (view, pose) in poses {
             self.reconstructions[reconstruction].views[view].pose = pose;
         }
+        // The points of the landmarks which weren't optimized are stale now that the views moved.
+        for landmark in self.reconstructions[reconstruction].landmarks.values_mut() {
+            landmark.point = None;
+        }
+        for (landmark, point) in landmarks {
+            if let Some(landmark) = self.reconstructions[reconstruction]
+                .landmarks
+                .get_mut(landmark)
+            {
+                landmark.point = Some(point);
+            }
+        }
+    }
+
+    /// Changes the intrinsics of a feed, recomputing the keypoints of all of its frames from the pixel
+    /// coordinates they were detected at.
+    ///
+    /// The points of every landmark observed by the feed are cleared, since they were computed with the old
+    /// keypoints.
+    fn set_intrinsics(&mut self, feed: FeedKey, intrinsics: CameraIntrinsicsK1Distortion) {
+        for &frame in &self.feeds[feed].frames {
+            for feature in &mut self.frames[frame].features {
+                feature.keypoint = intrinsics.calibrate(feature.pixel);
+            }
+        }
+        self.feeds[feed].intrinsics = intrinsics;
+        let frames = &self.frames;
+        for reconstruction in self.reconstructions.values_mut() {
+            let Reconstruction {
+                views, landmarks, ..
+            } = reconstruction;
+            for view in views.values() {
+                if frames[view.frame].feed == feed {
+                    for &landmark in &view.landmarks {
+                        landmarks[landmark].point = None;
+                    }
+                }
+            }
+        }
     }
 
     /// Splits the observation into its own landmark.
@@ -464,12 +591,13 @@ impl VSlamData {
         {
             // Since this wasnt the only observation in the landmark, we can split it.
             // Remove the observation from the old_landmark.
+            let old_landmark_object =
+                &mut self.reconstructions[reconstruction].landmarks[old_landmark];
             assert_eq!(
-                self.reconstructions[reconstruction].landmarks[old_landmark]
-                    .observations
-                    .remove(&view),
+                old_landmark_object.observations.remove(&view),
                 Some(feature)
             );
+            old_landmark_object.point = None;
             // Create the new landmark.
             let new_landmark = self.reconstructions[reconstruction]
                 .landmarks
@@ -477,6 +605,7 @@ impl VSlamData {
                     observations: hashmap! {
                         view => feature,
                     },
+                    point: None,
                 });
             // Assign the landmark ID to the observation.
             self.reconstructions[reconstruction].views[view].landmarks[feature] = new_landmark;
@@ -506,6 +635,7 @@ impl VSlamData {
                 .insert(view, feature)
                 .is_none());
         }
+        self.reconstructions[reconstruction].landmarks[landmark_a].point = None;
         landmark_a
     }
 }
@@ -516,6 +646,9 @@ pub struct VSlam<C, EE, PE, T, R> {
     /// Settings variables
     pub settings: VSlamSettings,
     /// The consensus algorithm
+    ///
+    /// Besides the essential matrix and pose estimators, it must also support [`FourPoint`], which estimates
+    /// the homography used to diagnose degenerate views during initialization.
     pub consensus: RefCell<C>,
     /// The essential matrix estimator
     pub essential_estimator: EE,
@@ -525,11 +658,14 @@ pub struct VSlam<C, EE, PE, T, R> {
     pub triangulator: T,
     /// The random number generator
     pub rng: RefCell<R>,
+    /// Receives the progress of every optimization and can cancel it
+    observer: Arc<Mutex<dyn Observer + Send>>,
 }
 
 impl<C, EE, PE, T, R> VSlam<C, EE, PE, T, R>
 where
     C: Consensus<EE, FeatureMatch<NormalizedKeyPoint>>
+        + Consensus<FourPoint, FeatureMatch<NormalizedKeyPoint>>
         + Consensus<PE, FeatureWorldMatch<NormalizedKeyPoint>>,
     EE: Estimator<FeatureMatch<NormalizedKeyPoint>, Model = EssentialMatrix>,
     PE: Estimator<FeatureWorldMatch<NormalizedKeyPoint>, Model = WorldToCamera>,
@@ -537,6 +673,9 @@ where
     R: Rng,
 {
     /// Creates an empty vSLAM reconstruction.
+    ///
+    /// The `consensus` algorithm must implement `Consensus<FourPoint, FeatureMatch<NormalizedKeyPoint>>`
+    /// in addition to the essential matrix and pose estimators.
     pub fn new(
         data: VSlamData,
         settings: VSlamSettings,
@@ -554,6 +693,36 @@ where
             pose_estimator,
             triangulator,
             rng: RefCell::new(rng),
+            observer: Arc::new(Mutex::new(NoObserver)),
+        }
+    }
+
+    /// Sets the observer which receives the progress of every optimization, such as bundle adjustment.
+    ///
+    /// When the observer cancels a bundle adjustment, the poses from its last iteration are kept.
+    /// When it cancels the optimization of the pose of a new frame, that frame is neither tracked nor
+    /// used to initialize a reconstruction. A cancelled [`CancellationToken`](cv_optimize::CancellationToken)
+    /// cancels every later optimization too, so [`reset`](cv_optimize::CancellationToken::reset) it to resume.
+    pub fn set_observer(&mut self, observer: impl Observer + Send + 'static) {
+        self.observer = Arc::new(Mutex::new(observer));
+    }
+
+    /// Creates an observer which logs the progress of an optimization and passes it on to the observer of the `VSlam`.
+    fn optimization_observer(&self, name: &'static str) -> impl Observer + Send + 'static {
+        let observer = self.observer.clone();
+        move |progress: &Progress| {
+            debug!(
+                "{} on iteration {} with cost {} and step {} after {:?}",
+                name, progress.iteration, progress.cost, progress.step_norm, progress.elapsed
+            );
+            let control = observer
+                .lock()
+                .expect("optimization observer panicked")
+                .observe(progress);
+            if control == Control::Cancel {
+                info!("{} cancelled by observer", name);
+            }
+            control
         }
     }
 
@@ -565,6 +734,8 @@ where
     ) -> FeedKey {
         self.data.feeds.insert(Feed {
             intrinsics,
+            prior_intrinsics: intrinsics,
+            focal_attempted: false,
             frames: vec![],
             reconstruction,
         })
@@ -576,9 +747,23 @@ where
     ///
     /// Returns a VSlam::reconstructions index if the frame was incorporated in a reconstruction.
     pub fn add_frame(&mut self, feed: FeedKey, image: &DynamicImage) -> Option<ReconstructionKey> {
+        self.add_frame_with_timestamp(feed, None, image)
+    }
+
+    /// Add frame which was captured at a known time.
+    ///
+    /// The timestamp allows the pose of the frame to be used in [`VSlamData::feed_trajectory`].
+    /// Otherwise, this is the same as [`VSlam::add_frame`].
+    pub fn add_frame_with_timestamp(
+        &mut self,
+        feed: FeedKey,
+        timestamp: Option<f64>,
+        image: &DynamicImage,
+    ) -> Option<ReconstructionKey> {
         // Extract the features for the frame and add the frame object.
         let next_id = self.data.frames.insert(Frame {
             feed,
+            timestamp,
             features: self.kps_descriptors(&self.data.feeds[feed].intrinsics, image),
         });
         // Add the frame to the feed.
@@ -606,8 +791,21 @@ where
     ///
     /// Returns the VSlam::reconstructions ID if successful.
     fn try_init(&mut self, frame_a: FrameKey, frame_b: FrameKey) -> Option<ReconstructionKey> {
+        let a = self.data.frame(frame_a);
+        let b = self.data.frame(frame_b);
+        let matches = self.match_frames(a, b);
+        let (mut essential, mut inliers) = self.estimate_essential(a, b, &matches)?;
+        if self.settings.self_calibration && self.initialize_focal(frame_a, frame_b, &inliers) {
+            // The keypoints were calibrated again with the new focal length, so the essential matrix is stale.
+            let a = self.data.frame(frame_a);
+            let b = self.data.frame(frame_b);
+            let (new_essential, new_inliers) = self.estimate_essential(a, b, &inliers)?;
+            essential = new_essential;
+            inliers = new_inliers;
+        }
         // Add the outcome.
-        let (pose, matches) = self.init_reconstruction(frame_a, frame_b)?;
+        let (pose, matches) =
+            self.init_reconstruction(frame_a, frame_b, &matches, essential, &inliers)?;
         Some(
             self.data
                 .add_reconstruction(frame_a, frame_b, pose, matches),
@@ -663,13 +861,253 @@ where
         })
     }
 
-    /// This creates a covisibility between frames `a` and `b` using the essential matrix estimator.
+    /// Retrieves the matches between frames `a` and `b` which agree with each other and are within the match threshold.
+    fn match_frames(&self, a: &Frame, b: &Frame) -> Vec<FeatureMatch<usize>> {
+        info!(
+            "performing brute-force matching between {} and {} features",
+            a.features.len(),
+            b.features.len(),
+        );
+        symmetric_matching(a, b)
+            .filter(|&(_, distance)| distance < self.settings.match_threshold)
+            .map(|(m, _)| m)
+            .collect()
+    }
+
+    /// Estimates the essential matrix between frames `a` and `b` from their matches with sample consensus.
+    ///
+    /// Returns the essential matrix and the inlier matches.
+    fn estimate_essential(
+        &self,
+        a: &Frame,
+        b: &Frame,
+        matches: &[FeatureMatch<usize>],
+    ) -> Option<(EssentialMatrix, Vec<FeatureMatch<usize>>)> {
+        info!("estimate essential on {} matches", matches.len());
+        let keypoint_matches: Vec<FeatureMatch<NormalizedKeyPoint>> = matches
+            .iter()
+            .map(|&FeatureMatch(feature_a, feature_b)| {
+                FeatureMatch(a.keypoint(feature_a), b.keypoint(feature_b))
+            })
+            .collect();
+        let (essential, inliers) = self
+            .consensus
+            .borrow_mut()
+            .model_inliers(&self.essential_estimator, keypoint_matches.iter().copied())?;
+        // Reconstitute only the inlier matches into a matches vector.
+        Some((
+            essential,
+            inliers.into_iter().map(|ix| matches[ix]).collect(),
+        ))
+    }
+
+    /// Diagnoses whether the matches between frames `a` and `b` are degenerate for the essential matrix.
+    ///
+    /// Returns the model to initialize a reconstruction from, or `None` if the camera only rotated between them.
+    fn select_two_view_model(
+        &self,
+        a: &Frame,
+        b: &Frame,
+        essential: EssentialMatrix,
+        matches: &[FeatureMatch<usize>],
+    ) -> Option<TwoViewModel> {
+        let keypoint_matches: Vec<FeatureMatch<NormalizedKeyPoint>> = matches
+            .iter()
+            .map(|&FeatureMatch(feature_a, feature_b)| {
+                FeatureMatch(a.keypoint(feature_a), b.keypoint(feature_b))
+            })
+            .collect();
+        // The residual of the homography is a distance in normalized image coordinates like the residual of
+        // the essential matrix, so both are found with the same consensus threshold.
+        let homography = match self
+            .consensus
+            .borrow_mut()
+            .model(&FourPoint::new(), keypoint_matches.iter().copied())
+        {
+            Some(homography) => homography,
+            None => {
+                info!("failed to estimate homography, so using essential matrix");
+                return Some(TwoViewModel::Essential(essential));
+            }
+        };
+        // An angular error of the bearing is about the same error in normalized image coordinates
+        // near the optical axis, and only grows by a factor of `1 + x² + y²` towards the edges of the image.
+        let selection = TwoViewModelSelector::new()
+            .std_dev(self.settings.bearing_std_dev)
+            .rotation_threshold(self.settings.two_view_rotation_threshold)
+            .select(essential, homography, keypoint_matches);
+        info!(
+            "diagnosed two views as {:?} with essential GRIC {} and homography GRIC {}",
+            selection.diagnosis, selection.essential_gric, selection.homography_gric
+        );
+        if selection.diagnosis == TwoViewDiagnosis::PureRotation {
+            info!("camera only rotated, so not initializing reconstruction");
+            return None;
+        }
+        Some(selection.model)
+    }
+
+    /// Chooses the decomposition of a homography between frames `a` and `b` which
+    /// places the most matches in front of both cameras.
+    fn homography_pose(
+        &self,
+        a: &Frame,
+        b: &Frame,
+        homography: Homography,
+        matches: &[FeatureMatch<usize>],
+    ) -> Option<CameraToCamera> {
+        info!("perform chirality test of homography on {}", matches.len());
+        homography
+            .possible_unscaled_poses(1e-9, 100)?
+            .into_iter()
+            .map(|(pose, _)| pose)
+            .max_by_key(|&pose| {
+                self.camera_to_camera_match_points(a, b, pose, matches.iter().copied())
+                    .count()
+            })
+    }
+
+    /// Estimates the focal length of the feed of frames `a` and `b` from the fundamental matrix of the inliers
+    /// of their essential matrix, and starts self-calibration from it if it is within the bounds of self-calibration.
+    ///
+    /// This is only attempted once for each feed, and only if both frames are from the same feed.
+    /// Returns `true` if the intrinsics of the feed were changed.
+    fn initialize_focal(
+        &mut self,
+        frame_a: FrameKey,
+        frame_b: FrameKey,
+        inliers: &[FeatureMatch<usize>],
+    ) -> bool {
+        let feed = self.data.frame(frame_a).feed;
+        if self.data.frame(frame_b).feed != feed || self.data.feed(feed).focal_attempted {
+            return false;
+        }
+        self.data.feeds[feed].focal_attempted = true;
+        if inliers.len() < <EightPoint as Estimator<FeatureMatch<KeyPoint>>>::MIN_SAMPLES {
+            return false;
+        }
+        let a = self.data.frame(frame_a);
+        let b = self.data.frame(frame_b);
+
+        // The keypoints are already undistorted, so the fundamental matrix is between undistorted pixels.
+        let intrinsics = self.data.feed(feed).intrinsics;
+        let simple = intrinsics.simple_intrinsics;
+        let pixel_matches: Vec<FeatureMatch<KeyPoint>> = inliers
+            .iter()
+            .map(|&FeatureMatch(feature_a, feature_b)| {
+                FeatureMatch(
+                    simple.uncalibrate(a.keypoint(feature_a)),
+                    simple.uncalibrate(b.keypoint(feature_b)),
+                )
+            })
+            .collect();
+        let focals = EightPoint::new()
+            .estimate(pixel_matches.iter().copied())
+            .and_then(|fundamental| {
+                fundamental.focal_lengths(simple.principal_point, simple.principal_point)
+            });
+        let focal = match focals {
+            Some((focal_a, focal_b)) => (focal_a * focal_b).sqrt(),
+            None => {
+                info!("failed to estimate the focal length from the fundamental matrix");
+                return false;
+            }
+        };
+
+        let prior = self
+            .data
+            .feed(feed)
+            .prior_intrinsics
+            .simple_intrinsics
+            .focals
+            .x;
+        let ratio = self.settings.self_calibration_focal_ratio;
+        if focal < prior / ratio || focal > prior * ratio {
+            info!(
+                "estimated focal length {} is outside of the self-calibration bounds of focal length {}",
+                focal, prior
+            );
+            return false;
+        }
+        info!(
+            "initializing self-calibration with focal length {} (was {})",
+            focal, simple.focals.x
+        );
+        let mut intrinsics = intrinsics;
+        intrinsics.simple_intrinsics.focals *= focal / simple.focals.x;
+        self.data.set_intrinsics(feed, intrinsics);
+        true
+    }
+
+    /// Adds the camera of a feed to a bundle adjuster, along with the priors and bounds of its intrinsics
+    /// if self-calibration is enabled.
+    fn add_feed_camera<L>(
+        &self,
+        adjuster: &mut BundleAdjuster<CameraIntrinsicsK1Distortion, L>,
+        feed: FeedKey,
+    ) -> usize {
+        let feed = self.data.feed(feed);
+        let camera = adjuster.add_camera(feed.intrinsics);
+        if self.settings.self_calibration {
+            let prior = feed.prior_intrinsics.parameters();
+            let ratio = self.settings.self_calibration_focal_ratio;
+            for &focal in &FOCAL_PARAMETERS {
+                adjuster.add_intrinsic_prior(
+                    camera,
+                    focal,
+                    prior[focal],
+                    self.settings.self_calibration_focal_std_dev * prior[focal],
+                );
+                adjuster.set_intrinsic_bounds(
+                    camera,
+                    focal,
+                    prior[focal] / ratio,
+                    prior[focal] * ratio,
+                );
+            }
+            let range = self.settings.self_calibration_principal_point_range;
+            for &center in &PRINCIPAL_POINT_PARAMETERS {
+                adjuster.add_intrinsic_prior(
+                    camera,
+                    center,
+                    prior[center],
+                    self.settings.self_calibration_principal_point_std_dev,
+                );
+                adjuster.set_intrinsic_bounds(
+                    camera,
+                    center,
+                    prior[center] - range,
+                    prior[center] + range,
+                );
+            }
+            let range = self.settings.self_calibration_k1_range;
+            adjuster.add_intrinsic_prior(
+                camera,
+                K1_PARAMETER,
+                prior[K1_PARAMETER],
+                self.settings.self_calibration_k1_std_dev,
+            );
+            adjuster.set_intrinsic_bounds(
+                camera,
+                K1_PARAMETER,
+                prior[K1_PARAMETER] - range,
+                prior[K1_PARAMETER] + range,
+            );
+        }
+        camera
+    }
+
+    /// This creates a covisibility between frames `a` and `b` from their `original_matches` and the
+    /// essential matrix estimated from them with its `inliers`.
     ///
     /// This method resolves to an undefined scale, and thus is only appropriate for initialization.
     fn init_reconstruction(
         &self,
         frame_a: FrameKey,
         frame_b: FrameKey,
+        original_matches: &[FeatureMatch<usize>],
+        essential: EssentialMatrix,
+        inliers: &[FeatureMatch<usize>],
     ) -> Option<(CameraToCamera, Vec<FeatureMatch<usize>>)> {
         let a = self.data.frame(frame_a);
         let b = self.data.frame(frame_b);
@@ -678,41 +1116,20 @@ where
             FeatureMatch(a.keypoint(feature_a), b.keypoint(feature_b))
         };
 
-        info!(
-            "performing brute-force matching between {} and {} features",
-            a.features.len(),
-            b.features.len(),
-        );
-        // Retrieve the matches which agree with each other from each frame and filter out ones that aren't within the match threshold.
-        let matches: Vec<FeatureMatch<usize>> = symmetric_matching(a, b)
-            .filter(|&(_, distance)| distance < self.settings.match_threshold)
-            .map(|(m, _)| m)
-            .collect();
+        // Refuse to initialize from a pure rotation, and use the homography if the scene is planar.
+        let mut pose = match self.select_two_view_model(a, b, essential, original_matches)? {
+            TwoViewModel::Essential(essential) => {
+                info!("perform chirality test on {}", inliers.len());
 
-        let original_matches = matches.clone();
-
-        info!("estimate essential on {} matches", matches.len());
-
-        // Estimate the essential matrix and retrieve the inliers
-        let (essential, inliers) = self.consensus.borrow_mut().model_inliers(
-            &self.essential_estimator,
-            matches
-                .iter()
-                .copied()
-                .map(match_ix_kps)
-                .collect::<Vec<_>>()
-                .iter()
-                .copied(),
-        )?;
-        // Reconstitute only the inlier matches into a matches vector.
-        let matches: Vec<FeatureMatch<usize>> = inliers.into_iter().map(|ix| matches[ix]).collect();
-
-        info!("perform chirality test on {}", matches.len());
-
-        // Perform a chirality test to retain only the points in front of both cameras.
-        let mut pose = essential
-            .pose_solver()
-            .solve_unscaled(matches.iter().copied().map(match_ix_kps))?;
+                // Perform a chirality test to retain only the points in front of both cameras.
+                essential
+                    .pose_solver()
+                    .solve_unscaled(inliers.iter().copied().map(match_ix_kps))?
+            }
+            TwoViewModel::Homography(homography) => {
+                self.homography_pose(a, b, homography, original_matches)?
+            }
+        };
 
         // Initialize the camera points.
         let mut matches: Vec<FeatureMatch<usize>> = self
@@ -739,15 +1156,20 @@ where
                 two_view_nelder_mead(pose).sd_tolerance(self.settings.two_view_std_dev_threshold);
             let constraint =
                 TwoViewConstraint::new(opti_matches.iter().copied(), self.triangulator.clone())
-                    .loss_cutoff(self.settings.loss_cutoff);
+                    .loss(TruncatedLoss(self.settings.loss_cutoff));
 
             // The initial parameter is empty becasue nelder mead is passed its own initial parameter directly.
-            let opti_state = Executor::new(constraint, solver, array![])
-                .add_observer(OptimizationObserver, ObserverMode::Always)
+            let opti_state = match Executor::new(constraint, solver, array![])
+                .add_observer(
+                    ArgminObserver::new(self.optimization_observer("two-view optimization")),
+                    ObserverMode::Always,
+                )
                 .max_iters(self.settings.two_view_patience as u64)
                 .run()
-                .expect("two-view optimization failed")
-                .state;
+            {
+                Ok(result) => result.state,
+                Err(e) => return optimization_cancelled(e),
+            };
 
             info!(
                 "extracted pose with mean capped cosine distance of {}",
@@ -861,24 +1283,46 @@ where
         );
 
         // Estimate the pose and retrieve the inliers.
-        let pose = self
+        let (pose, inliers) = self
             .consensus
             .borrow_mut()
-            .model(&self.pose_estimator, matches_3d.iter().copied())?;
+            .model_inliers(&self.pose_estimator, matches_3d.iter().copied())?;
+
+        // Refit the pose to all of the inliers in closed form to give the optimizer a better start.
+        // EPnP can do worse than the consensus pose on noisy or near-planar inliers,
+        // so it is only kept if it fits them better.
+        let inliers: Vec<_> = inliers.into_iter().map(|ix| matches_3d[ix]).collect();
+        let inlier_residual =
+            |pose: WorldToCamera| inliers.iter().map(|m| pose.residual(m)).sum::<f64>();
+        let pose = if inliers.len()
+            >= <EPnP as Estimator<FeatureWorldMatch<NormalizedKeyPoint>>>::MIN_SAMPLES
+        {
+            match EPnP::new().estimate(inliers.iter().copied()) {
+                Some(refit) if inlier_residual(refit) < inlier_residual(pose) => refit,
+                _ => pose,
+            }
+        } else {
+            pose
+        };
 
         // Create solver and constraint for single-view optimizer.
         let solver =
             single_view_nelder_mead(pose).sd_tolerance(self.settings.single_view_std_dev_threshold);
         let constraint =
-            SingleViewConstraint::new(matches_3d).loss_cutoff(self.settings.loss_cutoff);
+            SingleViewConstraint::new(matches_3d).loss(TruncatedLoss(self.settings.loss_cutoff));
 
         // The initial parameter is empty becasue nelder mead is passed its own initial parameter directly.
-        let opti_state = Executor::new(constraint, solver, array![])
-            .add_observer(OptimizationObserver, ObserverMode::Always)
+        let opti_state = match Executor::new(constraint, solver, array![])
+            .add_observer(
+                ArgminObserver::new(self.optimization_observer("single-view optimization")),
+                ObserverMode::Always,
+            )
             .max_iters(self.settings.single_view_patience as u64)
             .run()
-            .expect("single-view optimization failed")
-            .state;
+        {
+            Ok(result) => result.state,
+            Err(e) => return optimization_cancelled(e),
+        };
 
         info!(
             "extracted single-view pose with mean capped cosine distance of {}",
@@ -943,17 +1387,14 @@ where
             .collect();
 
         // Calibrate keypoint and combine into features.
-        izip!(
-            keypoints.into_iter().map(|kp| intrinsics.calibrate(kp)),
-            descriptors,
-            colors
-        )
-        .map(|(keypoint, descriptor, color)| Feature {
-            keypoint,
-            descriptor,
-            color,
-        })
-        .collect()
+        izip!(keypoints, descriptors, colors)
+            .map(|(keypoint, descriptor, color)| Feature {
+                keypoint: intrinsics.calibrate(keypoint),
+                pixel: KeyPoint(keypoint.image_point()),
+                descriptor,
+                color,
+            })
+            .collect()
     }
 
     pub fn export_reconstruction(&self, reconstruction: ReconstructionKey, path: impl AsRef<Path>) {
@@ -1106,6 +1547,8 @@ where
                     return BundleAdjustment {
                         reconstruction,
                         poses: vec![],
+                        intrinsics: vec![],
+                        landmarks: vec![],
                     };
                 } else {
                     info!("succeeded with {} landmarks", opti_landmarks.len());
@@ -1131,76 +1574,94 @@ where
                 .into_iter()
                 .collect();
 
-            // Form a vector over each landmark that contains a vector of the observances present in each view ID in order above.
-            let observances: Vec<Vec<Option<Unit<Vector3<f64>>>>> = opti_landmarks
-                .iter()
-                .copied()
-                .map(|landmark| {
-                    views
+            // Add a camera for each feed and a view for each view ID in the order above.
+            let mut adjuster = BundleAdjuster::new()
+                .loss(HuberLoss(self.settings.bundle_adjust_huber_scale))
+                .gauge(Gauge::FirstViewAndScale)
+                .max_iterations(self.settings.bundle_adjust_iterations)
+                .refine_intrinsics(if self.settings.self_calibration {
+                    FOCAL_PARAMETERS
                         .iter()
+                        .chain(&PRINCIPAL_POINT_PARAMETERS)
+                        .chain(&[K1_PARAMETER])
                         .copied()
-                        .map(|view| {
-                            self.data
-                                .landmark(reconstruction, landmark)
-                                .observations
-                                .get(&view)
-                                .map(|&feature| {
-                                    self.data
-                                        .observation_keypoint(reconstruction, view, feature)
-                                        .bearing()
-                                })
-                        })
                         .collect()
-                })
-                .collect();
+                } else {
+                    vec![]
+                });
+            let mut feed_cameras: HashMap<FeedKey, usize> = HashMap::new();
+            for &view in &views {
+                let feed = self
+                    .data
+                    .frame(self.data.view_frame(reconstruction, view))
+                    .feed;
+                let camera = *feed_cameras
+                    .entry(feed)
+                    .or_insert_with(|| self.add_feed_camera(&mut adjuster, feed));
+                adjuster.add_view(camera, self.data.pose(reconstruction, view));
+            }
 
-            // Retrieve the view poses
-            let poses: Vec<WorldToCamera> = views
+            // Add each landmark that can be triangulated along with its observations in pixel coordinates.
+            let view_indices: HashMap<ViewKey, usize> = views
                 .iter()
                 .copied()
-                .map(|view| self.data.pose(reconstruction, view))
+                .enumerate()
+                .map(|(ix, view)| (view, ix))
                 .collect();
+            let mut adjusted_landmarks = vec![];
+            for &landmark in &opti_landmarks {
+                let point = match self.triangulate_landmark(reconstruction, landmark) {
+                    Some(point) if point.point().is_some() => point,
+                    _ => continue,
+                };
+                let landmark_ix = adjuster.add_landmark(point);
+                adjusted_landmarks.push(landmark);
+                for (view, feature) in self.data.landmark_observations(reconstruction, landmark) {
+                    let keypoint = self.data.observation_pixel(reconstruction, view, feature);
+                    adjuster.add_observation(view_indices[&view], landmark_ix, keypoint);
+                }
+            }
 
             info!(
-                "performing Nelder-Mead optimization on {} poses with {} landmarks",
+                "performing bundle adjustment on {} poses with {} landmarks",
                 views.len(),
                 opti_landmarks.len(),
             );
 
-            let solver = many_view_nelder_mead(poses)
-                .sd_tolerance(self.settings.many_view_std_dev_threshold);
-            let constraint = ManyViewConstraint::new(
-                observances.iter().map(|v| v.iter().copied()),
-                self.triangulator.clone(),
-            )
-            .loss_cutoff(self.settings.loss_cutoff);
-
-            // The initial parameter is empty becasue nelder mead is passed its own initial parameter directly.
-            let opti_state = Executor::new(constraint, solver, Array2::zeros((0, 0)))
-                .add_observer(OptimizationObserver, ObserverMode::Always)
-                .max_iters(self.settings.many_view_patience as u64)
-                .run()
-                .expect("many-view optimization failed")
-                .state;
+            let summary =
+                adjuster.optimize_with(&mut self.optimization_observer("bundle adjustment"));
 
             info!(
-                "extracted poses with mean capped cosine distance of {}",
-                opti_state.best_cost
+                "bundle adjustment of {} observations finished after {} iterations ({:?}) with cost {} (initially {})",
+                summary.observations,
+                summary.iterations,
+                summary.termination,
+                summary.final_cost,
+                summary.initial_cost,
             );
 
-            let poses: Vec<WorldToCamera> = opti_state
-                .best_param
-                .outer_iter()
-                .map(|arr| {
-                    Pose::from_se3(Vector6::from_row_slice(
-                        arr.as_slice().expect("param was not contiguous array"),
-                    ))
-                })
-                .collect();
+            let poses = adjuster.poses().to_vec();
+            let intrinsics = if self.settings.self_calibration {
+                feed_cameras
+                    .iter()
+                    .map(|(&feed, &camera)| {
+                        let intrinsics = adjuster.cameras()[camera];
+                        info!("self-calibrated intrinsics of feed: {:?}", intrinsics);
+                        (feed, intrinsics)
+                    })
+                    .collect()
+            } else {
+                vec![]
+            };
 
             BundleAdjustment {
                 reconstruction,
                 poses: views.iter().copied().zip(poses).collect(),
+                intrinsics,
+                landmarks: adjusted_landmarks
+                    .into_iter()
+                    .zip(adjuster.points())
+                    .collect(),
             }
         } else {
             warn!(
@@ -1209,6 +1670,8 @@ where
             BundleAdjustment {
                 reconstruction,
                 poses: vec![],
+                intrinsics: vec![],
+                landmarks: vec![],
             }
         }
     }
@@ -1457,6 +1920,9 @@ where
 
     /// This checks if a landmark is sufficiently robust by observing its number of robust observations and the largest
     /// observed angle of incidence to see if they are within appropriate thresholds.
+    ///
+    /// If [`VSlamSettings::robust_maximum_relative_std_dev`] is set, the uncertainty of the position of the landmark
+    /// must also be within it.
     pub fn is_landmark_robust(
         &self,
         reconstruction: ReconstructionKey,
@@ -1480,17 +1946,59 @@ where
                     1.0 - bearing_a.dot(&bearing_b)
                         > self.settings.incidence_minimum_cosine_distance
                 })
+            && match self.settings.robust_maximum_relative_std_dev {
+                Some(maximum) => self
+                    .landmark_relative_std_dev(reconstruction, landmark)
+                    .map(|std_dev| std_dev <= maximum)
+                    .unwrap_or(false),
+                None => true,
+            }
+    }
+
+    /// Computes the standard deviation of the position of a landmark triangulated from its robust observations,
+    /// relative to the distance from the landmark to the closest view that observes it.
+    ///
+    /// Returns `None` if the landmark can't be triangulated or isn't constrained in every direction.
+    pub fn landmark_relative_std_dev(
+        &self,
+        reconstruction: ReconstructionKey,
+        landmark: LandmarkKey,
+    ) -> Option<f64> {
+        let observations = self.landmark_robust_observations(reconstruction, landmark);
+        let triangulation =
+            self.triangulate_observations_with_quality(reconstruction, observations.clone())?;
+        let point = triangulation.point.point()?;
+        let distance = observations
+            .map(|(view, _)| {
+                let center = self
+                    .data
+                    .pose(reconstruction, view)
+                    .inverse()
+                    .isometry()
+                    .translation
+                    .vector;
+                (point.coords - center).norm()
+            })
+            .fold(f64::INFINITY, f64::min);
+        Some(triangulation.std_dev()? / distance)
     }
 
+    /// Retrieves the point of a landmark optimized by the last bundle adjust, or triangulates it from its observations
+    /// if the observations changed since.
     pub fn triangulate_landmark(
         &self,
         reconstruction: ReconstructionKey,
         landmark: LandmarkKey,
     ) -> Option<WorldPoint> {
-        self.triangulate_observations(
-            reconstruction,
-            self.data.landmark_observations(reconstruction, landmark),
-        )
+        self.data
+            .landmark(reconstruction, landmark)
+            .point
+            .or_else(|| {
+                self.triangulate_observations(
+                    reconstruction,
+                    self.data.landmark_observations(reconstruction, landmark),
+                )
+            })
     }
 
     /// Return observations that are robust of a landmark.
@@ -1593,6 +2101,26 @@ where
             }))
     }
 
+    /// Triangulates the observations along with the uncertainty of the point and the residual of each observation.
+    ///
+    /// The uncertainty assumes the bearings have an angular standard deviation of [`VSlamSettings::bearing_std_dev`].
+    pub fn triangulate_observations_with_quality(
+        &self,
+        reconstruction: ReconstructionKey,
+        observations: impl Iterator<Item = (ViewKey, usize)> + Clone,
+    ) -> Option<Triangulation<Vec<f64>>> {
+        self.triangulator.triangulate_observations_with_quality(
+            observations.map(|(view, feature)| {
+                (
+                    self.data.pose(reconstruction, view),
+                    self.data
+                        .observation_keypoint(reconstruction, view, feature),
+                )
+            }),
+            self.settings.bearing_std_dev,
+        )
+    }
+
     /// Use this gratuitously to help debug.
     ///
     /// This is useful when the system gets into an inconsistent state due to an internal
@@ -1671,3 +2199,168 @@ fn symmetric_matching<'a>(
             }
         })
 }
+
+/// Stops an argmin optimization that returned an error, which is expected when an observer cancels it.
+fn optimization_cancelled<T>(error: Error) -> Option<T> {
+    if error.is::<Cancelled>() {
+        None
+    } else {
+        panic!("optimization failed: {}", error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrsac::Arrsac;
+    use cv_core::nalgebra::{Point2, Point3, Rotation3, Vector3};
+    use cv_geom::MinSquaresTriangulator;
+    use cv_pinhole::CameraIntrinsics;
+    use lambda_twist::LambdaTwist;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    type TestVSlam = VSlam<Arrsac<Pcg64>, EightPoint, LambdaTwist, MinSquaresTriangulator, Pcg64>;
+
+    fn intrinsics(focal: f64) -> CameraIntrinsicsK1Distortion {
+        CameraIntrinsicsK1Distortion::new(
+            CameraIntrinsics::identity()
+                .focal(focal)
+                .principal_point(Point2::new(320.0, 240.0)),
+            -0.1,
+        )
+    }
+
+    fn points() -> Vec<Point3<f64>> {
+        (0..4)
+            .map(|ix| Point3::new((ix % 2) as f64 - 0.5, (ix / 2) as f64 - 0.5, 5.0))
+            .collect()
+    }
+
+    /// The pose of the second view, which is one unit to the right of the first view.
+    fn true_pose() -> WorldToCamera {
+        WorldToCamera::from_parts(Vector3::new(-1.0, 0.0, 0.0), Rotation3::identity())
+    }
+
+    /// Creates a reconstruction of two views of [`points`], where the second view was initialized twice as far
+    /// from the first view as [`true_pose`], and caches the points of every landmark as a bundle adjust would.
+    ///
+    /// Returns the reconstruction, the second view, and the landmark of each point.
+    fn reconstruction(vslam: &mut TestVSlam) -> (ReconstructionKey, ViewKey, Vec<LandmarkKey>) {
+        let feed = vslam.add_feed(intrinsics(800.0), None);
+        let frames: Vec<FrameKey> = [WorldToCamera::identity(), true_pose()]
+            .iter()
+            .map(|&pose| {
+                let features = points()
+                    .into_iter()
+                    .map(|point| {
+                        let camera_point = pose.transform(WorldPoint::from_point(point));
+                        let pixel = vslam.data.feeds[feed].intrinsics.uncalibrate(
+                            NormalizedKeyPoint::from_camera_point(camera_point).unwrap(),
+                        );
+                        Feature {
+                            keypoint: vslam.data.feeds[feed].intrinsics.calibrate(pixel),
+                            pixel,
+                            descriptor: BitArray::zeros(),
+                            color: [0; 3],
+                        }
+                    })
+                    .collect();
+                let frame = vslam.data.frames.insert(Frame {
+                    feed,
+                    timestamp: None,
+                    features,
+                });
+                vslam.data.feeds[feed].frames.push(frame);
+                frame
+            })
+            .collect();
+        let reconstruction = vslam.data.add_reconstruction(
+            frames[0],
+            frames[1],
+            CameraToCamera::from_parts(Vector3::new(-2.0, 0.0, 0.0), Rotation3::identity()),
+            (0..points().len()).map(|ix| FeatureMatch(ix, ix)).collect(),
+        );
+        let (view_a, view_b) = {
+            let views = &vslam.data.reconstruction(reconstruction).views;
+            let view = |frame| {
+                views
+                    .iter()
+                    .find(|(_, view)| view.frame == frame)
+                    .unwrap()
+                    .0
+            };
+            (view(frames[0]), view(frames[1]))
+        };
+        let landmarks = vslam.data.view(reconstruction, view_a).landmarks.clone();
+        for &landmark in &landmarks {
+            let point = vslam.triangulate_landmark(reconstruction, landmark);
+            vslam.data.reconstructions[reconstruction].landmarks[landmark].point = point;
+        }
+        (reconstruction, view_b, landmarks)
+    }
+
+    fn vslam() -> TestVSlam {
+        VSlam::new(
+            VSlamData::default(),
+            VSlamSettings::default(),
+            Arrsac::new(0.001, Pcg64::from_seed([5; 32])),
+            EightPoint::new(),
+            LambdaTwist::new(),
+            MinSquaresTriangulator::new(),
+            Pcg64::from_seed([5; 32]),
+        )
+    }
+
+    #[test]
+    fn bundle_adjust_clears_points_of_landmarks_it_did_not_optimize() {
+        let mut vslam = vslam();
+        let (reconstruction, view, landmarks) = reconstruction(&mut vslam);
+        let points = points();
+        // Only the first landmark is optimized, but the second view moves to its true pose.
+        vslam.data.apply_bundle_adjust(BundleAdjustment {
+            reconstruction,
+            poses: vec![(view, true_pose())],
+            intrinsics: vec![],
+            landmarks: vec![(landmarks[0], WorldPoint::from_point(points[0]))],
+        });
+        assert_eq!(
+            vslam.data.landmark(reconstruction, landmarks[0]).point,
+            Some(WorldPoint::from_point(points[0]))
+        );
+        for (&landmark, point) in landmarks.iter().zip(&points).skip(1) {
+            assert!(vslam
+                .data
+                .landmark(reconstruction, landmark)
+                .point
+                .is_none());
+            // The landmark is triangulated again with the new pose of the view.
+            let triangulated = vslam
+                .triangulate_landmark(reconstruction, landmark)
+                .and_then(|point| point.point())
+                .unwrap();
+            assert!((triangulated - point).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn set_intrinsics_clears_points_and_calibrates_from_pixels() {
+        let mut vslam = vslam();
+        let (reconstruction, _, landmarks) = reconstruction(&mut vslam);
+        let feed = vslam.data.feeds.keys().next().unwrap();
+        let intrinsics = intrinsics(900.0);
+        vslam.data.set_intrinsics(feed, intrinsics);
+        for &landmark in &landmarks {
+            assert!(vslam
+                .data
+                .landmark(reconstruction, landmark)
+                .point
+                .is_none());
+        }
+        for frame in vslam.data.frames.values() {
+            for feature in &frame.features {
+                assert_eq!(feature.keypoint, intrinsics.calibrate(feature.pixel));
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use cv_core::sample_consensus::Model;
+use cv_core::FeatureMatch;
+use cv_pinhole::{EssentialMatrix, NormalizedKeyPoint};
+use four_point::Homography;
+
+/// What the matches between two views reveal about the motion of the camera and the structure of the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoViewDiagnosis {
+    /// The essential matrix explains the matches best, so there is enough parallax and non-planar structure
+    /// to reconstruct the scene from the essential matrix.
+    General,
+    /// A homography explains the matches best and it is nearly a rotation, so the camera translated too
+    /// little for the translation or the scene to be recovered.
+    PureRotation,
+    /// A homography explains the matches best, but the camera translated, so the matched points lie on a plane
+    /// and the pose should be recovered from the homography.
+    Planar,
+}
+
+/// The model which best explains the matches between two views.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TwoViewModel {
+    /// An essential matrix, which is selected when the diagnosis is [`TwoViewDiagnosis::General`].
+    Essential(EssentialMatrix),
+    /// A homography between normalized image coordinates, which is selected when the diagnosis is
+    /// [`TwoViewDiagnosis::PureRotation`] or [`TwoViewDiagnosis::Planar`].
+    Homography(Homography),
+}
+
+/// The outcome of [`TwoViewModelSelector::select`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoViewModelSelection {
+    pub diagnosis: TwoViewDiagnosis,
+    /// The essential matrix if the diagnosis is [`TwoViewDiagnosis::General`], otherwise the homography.
+    pub model: TwoViewModel,
+    /// The geometric robust information criterion of the essential matrix, where lower is better.
+    pub essential_gric: f64,
+    /// The geometric robust information criterion of the homography, where lower is better.
+    pub homography_gric: f64,
+}
+
+/// Selects between an essential matrix and a homography to explain the matches between two views,
+/// which detects when the views are degenerate for the essential matrix.
+///
+/// The models are compared with the geometric robust information criterion (GRIC) of Torr, which
+/// penalizes the residuals of each model along with its number of parameters and the dimension of its
+/// manifold. A homography has fewer degrees of freedom than an essential matrix, so it is selected when
+/// the matches are explained equally well by both, which happens when the camera only rotated or the
+/// scene is planar. These are then told apart by how close the homography is to a rotation.
+///
+/// ```
+/// use cv_core::nalgebra::{Point3, Rotation3, Vector3};
+/// use cv_core::{CameraPoint, CameraToCamera, FeatureMatch, Pose, Projective};
+/// use cv_pinhole::{EssentialMatrix, NormalizedKeyPoint};
+/// use cv_reconstruction::{TwoViewDiagnosis, TwoViewModelSelector};
+/// use four_point::Homography;
+///
+/// let rotation = Rotation3::new(Vector3::new(0.05, -0.1, 0.02));
+/// let matches: Vec<FeatureMatch<NormalizedKeyPoint>> = (0..100)
+///     .map(|ix| {
+///         let point = CameraPoint::from_point(Point3::new(
+///             (ix % 10) as f64 * 0.1 - 0.5,
+///             (ix / 10) as f64 * 0.1 - 0.5,
+///             2.0 + (ix % 7) as f64,
+///         ));
+///         let pose = CameraToCamera::from_parts(Vector3::zeros(), rotation);
+///         FeatureMatch(
+///             NormalizedKeyPoint::from_camera_point(point).unwrap(),
+///             NormalizedKeyPoint::from_camera_point(pose.transform(point)).unwrap(),
+///         )
+///     })
+///     .collect();
+///
+/// // Any translation is consistent with the matches of a pure rotation.
+/// let essential = EssentialMatrix(Vector3::x().cross_matrix() * rotation.matrix());
+/// let homography = Homography::new(*rotation.matrix());
+/// let selection = TwoViewModelSelector::new().select(essential, homography, matches);
+/// assert_eq!(selection.diagnosis, TwoViewDiagnosis::PureRotation);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoViewModelSelector {
+    std_dev: f64,
+    rotation_threshold: f64,
+}
+
+impl TwoViewModelSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the standard deviation of the error of the keypoints in normalized image coordinates.
+    ///
+    /// Default is `0.001`.
+    pub fn std_dev(self, std_dev: f64) -> Self {
+        Self { std_dev, ..self }
+    }
+
+    /// Set the difference between the largest and smallest singular values of the homography, relative to
+    /// the middle one, below which it is considered a rotation.
+    ///
+    /// This is roughly the ratio of the translation to the distance of the plane.
+    ///
+    /// Default is `0.02`.
+    pub fn rotation_threshold(self, rotation_threshold: f64) -> Self {
+        Self {
+            rotation_threshold,
+            ..self
+        }
+    }
+
+    /// Selects the model which best explains `matches` and diagnoses the degeneracy of the views.
+    ///
+    /// Both models should be estimated from the same matches, but the matches may contain outliers.
+    pub fn select(
+        &self,
+        essential: EssentialMatrix,
+        homography: Homography,
+        matches: impl IntoIterator<Item = FeatureMatch<NormalizedKeyPoint>>,
+    ) -> TwoViewModelSelection {
+        // The residual of a homography is the root mean square of the distances of the transferred points
+        // in both images. Its square is about 2 times the squared geometric error of the reprojection
+        // onto the homography that GRIC expects, like the Sampson error of the essential matrix.
+        let (essential_errors, homography_errors): (Vec<f64>, Vec<f64>) = matches
+            .into_iter()
+            .map(|m| {
+                (
+                    sampson_error(&essential, m),
+                    homography.residual(&m).powi(2) / 2.0,
+                )
+            })
+            .unzip();
+        // An essential matrix has 5 parameters and a 3 dimensional manifold in the 4 dimensional
+        // space of matches, while a homography has 8 parameters and a 2 dimensional manifold.
+        let essential_gric = self.gric(&essential_errors, 3.0, 5.0);
+        let homography_gric = self.gric(&homography_errors, 2.0, 8.0);
+
+        let (diagnosis, model) = if essential_gric <= homography_gric {
+            (
+                TwoViewDiagnosis::General,
+                TwoViewModel::Essential(essential),
+            )
+        } else if rotation_spread(&homography) < self.rotation_threshold {
+            (
+                TwoViewDiagnosis::PureRotation,
+                TwoViewModel::Homography(homography),
+            )
+        } else {
+            (
+                TwoViewDiagnosis::Planar,
+                TwoViewModel::Homography(homography),
+            )
+        };
+        TwoViewModelSelection {
+            diagnosis,
+            model,
+            essential_gric,
+            homography_gric,
+        }
+    }
+
+    /// Computes the GRIC of a model with the dimension `dimension` and `parameters` parameters
+    /// from the squared geometric errors of the matches.
+    fn gric(&self, errors: &[f64], dimension: f64, parameters: f64) -> f64 {
+        // The dimension of the data, which is two keypoints.
+        let data_dimension = 4.0;
+        let n = errors.len() as f64;
+        let variance = self.std_dev * self.std_dev;
+        let outlier_cost = 2.0 * (data_dimension - dimension);
+        let residuals: f64 = errors
+            .iter()
+            .map(|&error| {
+                let error = error / variance;
+                // A NaN error is an outlier, so it must not be kept by `min`.
+                if error < outlier_cost {
+                    error
+                } else {
+                    outlier_cost
+                }
+            })
+            .sum();
+        residuals + data_dimension.ln() * dimension * n + (data_dimension * n).ln() * parameters
+    }
+}
+
+impl Default for TwoViewModelSelector {
+    fn default() -> Self {
+        Self {
+            std_dev: 0.001,
+            rotation_threshold: 0.02,
+        }
+    }
+}
+
+/// Computes the Sampson approximation of the squared geometric error of a match under an essential matrix.
+fn sampson_error(
+    essential: &EssentialMatrix,
+    FeatureMatch(a, b): FeatureMatch<NormalizedKeyPoint>,
+) -> f64 {
+    let (a, b) = (a.to_homogeneous(), b.to_homogeneous());
+    let line_b = essential.0 * a;
+    let line_a = essential.0.transpose() * b;
+    let error = b.dot(&line_b);
+    error * error
+        / (line_b.x * line_b.x + line_b.y * line_b.y + line_a.x * line_a.x + line_a.y * line_a.y)
+}
+
+/// Computes the difference between the largest and smallest singular values of the homography relative to the
+/// middle one, which is zero if it is a rotation.
+fn rotation_spread(homography: &Homography) -> f64 {
+    let mut singular_values = homography.matrix().singular_values();
+    singular_values
+        .as_mut_slice()
+        .sort_unstable_by(|a, b| b.partial_cmp(a).unwrap_or(core::cmp::Ordering::Equal));
+    (singular_values[0] - singular_values[2]) / singular_values[1]
+}
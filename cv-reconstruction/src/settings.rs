@@ -47,7 +47,25 @@ pub struct VSlamSettings {
         serde(default = "default_robust_minimum_observations")
     )]
     pub robust_minimum_observations: usize,
-    /// The cutoff for the loss function
+    /// The standard deviation in radians of the angular error of bearings, used to compute the covariance of landmarks
+    ///
+    /// This is also used as the standard deviation of keypoints in normalized image coordinates to select the model
+    /// of two views, which is the same near the optical axis.
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_bearing_std_dev")
+    )]
+    pub bearing_std_dev: f64,
+    /// The maximum standard deviation of the position of a landmark, relative to its distance from the closest view,
+    /// for it to be considered robust enough for optimization, or `None` to not check the uncertainty of landmarks
+    ///
+    /// Checking this triangulates the landmark every time its robustness is checked.
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_robust_maximum_relative_std_dev")
+    )]
+    pub robust_maximum_relative_std_dev: Option<f64>,
+    /// The cosine distance beyond which single-view and two-view optimization ignore a residual
     #[cfg_attr(feature = "serde-serialize", serde(default = "default_loss_cutoff"))]
     pub loss_cutoff: f64,
     /// The maximum cosine distance permitted in a valid match
@@ -104,6 +122,13 @@ pub struct VSlamSettings {
         serde(default = "default_two_view_filter_loop_iterations")
     )]
     pub two_view_filter_loop_iterations: usize,
+    /// The spread of the singular values of the homography between two views below which they are diagnosed as a pure rotation,
+    /// in which case a reconstruction is not initialized from them
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_two_view_rotation_threshold")
+    )]
+    pub two_view_rotation_threshold: f64,
     /// The maximum number of landmarks to use for pose estimation during tracking.
     #[cfg_attr(
         feature = "serde-serialize",
@@ -111,12 +136,14 @@ pub struct VSlamSettings {
     )]
     pub track_landmarks: usize,
     /// The maximum iterations to optimize many views.
+    #[deprecated(note = "bundle adjust is limited by `bundle_adjust_iterations` instead")]
     #[cfg_attr(
         feature = "serde-serialize",
         serde(default = "default_many_view_patience")
     )]
     pub many_view_patience: usize,
     /// The threshold of mean cosine distance standard deviation that terminates many-view optimization.
+    #[deprecated(note = "bundle adjust terminates when its cost converges instead")]
     #[cfg_attr(
         feature = "serde-serialize",
         serde(default = "default_many_view_std_dev_threshold")
@@ -128,14 +155,70 @@ pub struct VSlamSettings {
         serde(default = "default_many_view_landmarks")
     )]
     pub many_view_landmarks: usize,
+    /// The maximum iterations of Levenberg-Marquardt in bundle adjust.
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_bundle_adjust_iterations")
+    )]
+    pub bundle_adjust_iterations: usize,
+    /// The reprojection error in pixels beyond which the Huber loss of bundle adjust becomes linear.
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_bundle_adjust_huber_scale")
+    )]
+    pub bundle_adjust_huber_scale: f64,
     /// The number of iterations to run bundle adjust, filtering, and merging.
     #[cfg_attr(
         feature = "serde-serialize",
         serde(default = "default_reconstruction_optimization_iterations")
     )]
     pub reconstruction_optimization_iterations: usize,
+    /// Whether to refine the focal lengths, principal point, and K1 distortion of each feed during bundle adjust,
+    /// and to estimate the initial focal length of a feed from the fundamental matrix when a reconstruction is initialized
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration")
+    )]
+    pub self_calibration: bool,
+    /// The standard deviation of the prior on the focal lengths of a feed, relative to the focal lengths it was added with
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration_focal_std_dev")
+    )]
+    pub self_calibration_focal_std_dev: f64,
+    /// The ratio by which the focal lengths of a feed may be larger or smaller than the focal lengths it was added with
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration_focal_ratio")
+    )]
+    pub self_calibration_focal_ratio: f64,
+    /// The standard deviation in pixels of the prior on the principal point of a feed
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration_principal_point_std_dev")
+    )]
+    pub self_calibration_principal_point_std_dev: f64,
+    /// The distance in pixels that the principal point of a feed may move in each axis from where it was added
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration_principal_point_range")
+    )]
+    pub self_calibration_principal_point_range: f64,
+    /// The standard deviation of the prior on the K1 distortion of a feed
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration_k1_std_dev")
+    )]
+    pub self_calibration_k1_std_dev: f64,
+    /// The amount that the K1 distortion of a feed may change from the value it was added with
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(default = "default_self_calibration_k1_range")
+    )]
+    pub self_calibration_k1_range: f64,
 }
 
+#[allow(deprecated)]
 impl Default for VSlamSettings {
     fn default() -> Self {
         Self {
@@ -146,6 +229,8 @@ impl Default for VSlamSettings {
             incidence_minimum_cosine_distance: default_incidence_minimum_cosine_distance(),
             robust_maximum_cosine_distance: default_robust_maximum_cosine_distance(),
             robust_minimum_observations: default_robust_minimum_observations(),
+            bearing_std_dev: default_bearing_std_dev(),
+            robust_maximum_relative_std_dev: default_robust_maximum_relative_std_dev(),
             loss_cutoff: default_loss_cutoff(),
             cosine_distance_threshold: default_cosine_distance_threshold(),
             merge_cosine_distance_threshold: default_merge_cosine_distance_threshold(),
@@ -156,12 +241,24 @@ impl Default for VSlamSettings {
             two_view_patience: default_two_view_patience(),
             two_view_std_dev_threshold: default_two_view_std_dev_threshold(),
             two_view_filter_loop_iterations: default_two_view_filter_loop_iterations(),
+            two_view_rotation_threshold: default_two_view_rotation_threshold(),
             track_landmarks: default_track_landmarks(),
             many_view_patience: default_many_view_patience(),
             many_view_std_dev_threshold: default_many_view_std_dev_threshold(),
             many_view_landmarks: default_many_view_landmarks(),
+            bundle_adjust_iterations: default_bundle_adjust_iterations(),
+            bundle_adjust_huber_scale: default_bundle_adjust_huber_scale(),
             reconstruction_optimization_iterations: default_reconstruction_optimization_iterations(
             ),
+            self_calibration: default_self_calibration(),
+            self_calibration_focal_std_dev: default_self_calibration_focal_std_dev(),
+            self_calibration_focal_ratio: default_self_calibration_focal_ratio(),
+            self_calibration_principal_point_std_dev:
+                default_self_calibration_principal_point_std_dev(),
+            self_calibration_principal_point_range: default_self_calibration_principal_point_range(
+            ),
+            self_calibration_k1_std_dev: default_self_calibration_k1_std_dev(),
+            self_calibration_k1_range: default_self_calibration_k1_range(),
         }
     }
 }
@@ -194,6 +291,14 @@ fn default_robust_minimum_observations() -> usize {
     3
 }
 
+fn default_bearing_std_dev() -> f64 {
+    0.001
+}
+
+fn default_robust_maximum_relative_std_dev() -> Option<f64> {
+    None
+}
+
 fn default_loss_cutoff() -> f64 {
     0.00002
 }
@@ -234,6 +339,10 @@ fn default_two_view_filter_loop_iterations() -> usize {
     3
 }
 
+fn default_two_view_rotation_threshold() -> f64 {
+    0.02
+}
+
 fn default_track_landmarks() -> usize {
     4096
 }
@@ -250,6 +359,42 @@ fn default_many_view_landmarks() -> usize {
     32768
 }
 
+fn default_bundle_adjust_iterations() -> usize {
+    100
+}
+
+fn default_bundle_adjust_huber_scale() -> f64 {
+    2.0
+}
+
 fn default_reconstruction_optimization_iterations() -> usize {
     1
 }
+
+fn default_self_calibration() -> bool {
+    false
+}
+
+fn default_self_calibration_focal_std_dev() -> f64 {
+    0.2
+}
+
+fn default_self_calibration_focal_ratio() -> f64 {
+    2.0
+}
+
+fn default_self_calibration_principal_point_std_dev() -> f64 {
+    20.0
+}
+
+fn default_self_calibration_principal_point_range() -> f64 {
+    100.0
+}
+
+fn default_self_calibration_k1_std_dev() -> f64 {
+    0.1
+}
+
+fn default_self_calibration_k1_range() -> f64 {
+    0.5
+}
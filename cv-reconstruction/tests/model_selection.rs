@@ -0,0 +1,124 @@
+use arrsac::Arrsac;
+use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Vector2, Vector3};
+use cv_core::sample_consensus::Consensus;
+use cv_core::{CameraPoint, CameraToCamera, FeatureMatch, Pose, Projective};
+use cv_pinhole::{EssentialMatrix, NormalizedKeyPoint};
+use cv_reconstruction::{TwoViewDiagnosis, TwoViewModel, TwoViewModelSelector};
+use eight_point::EightPoint;
+use four_point::{FourPoint, Homography};
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+/// The distance from the first camera to the plane of the planar scene.
+const PLANE_DISTANCE: f64 = 3.0;
+/// The consensus threshold shared by the essential matrix and the homography, like in the reconstruction.
+const CONSENSUS_THRESHOLD: f64 = 0.001;
+
+fn pose() -> CameraToCamera {
+    CameraToCamera(IsometryMatrix3::from_parts(
+        Vector3::new(0.5, -0.1, 0.05).into(),
+        Rotation3::new(Vector3::new(0.05, -0.1, 0.02)),
+    ))
+}
+
+fn essential(pose: CameraToCamera) -> EssentialMatrix {
+    let CameraToCamera(isometry) = pose;
+    EssentialMatrix(isometry.translation.vector.cross_matrix() * isometry.rotation.matrix())
+}
+
+/// Matches a grid of points in the first camera at the depths given by `depth`.
+fn matches(
+    pose: CameraToCamera,
+    depth: impl Fn(usize) -> f64,
+) -> Vec<FeatureMatch<NormalizedKeyPoint>> {
+    (0..100)
+        .map(|ix| {
+            let z = depth(ix);
+            let point = CameraPoint::from_point(Point3::new(
+                ((ix % 10) as f64 * 0.1 - 0.5) * z,
+                ((ix / 10) as f64 * 0.1 - 0.5) * z,
+                z,
+            ));
+            FeatureMatch(
+                NormalizedKeyPoint::from_camera_point(point).unwrap(),
+                NormalizedKeyPoint::from_camera_point(pose.transform(point)).unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// Whether the match at `ix` is made an outlier by [`with_outliers`].
+fn is_outlier(ix: usize) -> bool {
+    ix.is_multiple_of(5)
+}
+
+/// Moves every fifth match in the second image by about 10 pixels of a typical camera, which is
+/// small enough to be accepted if the threshold were compared against a squared distance.
+fn with_outliers(
+    mut matches: Vec<FeatureMatch<NormalizedKeyPoint>>,
+) -> Vec<FeatureMatch<NormalizedKeyPoint>> {
+    for (ix, FeatureMatch(_, b)) in matches.iter_mut().enumerate() {
+        if is_outlier(ix) {
+            let sign = if ix.is_multiple_of(2) { 1.0 } else { -1.0 };
+            b.0 += Vector2::new(0.01 * sign, 0.005 + 0.001 * (ix % 3) as f64);
+        }
+    }
+    matches
+}
+
+/// Estimates a homography from `matches` with consensus and checks that none of the outliers are inliers.
+fn consensus_homography(matches: &[FeatureMatch<NormalizedKeyPoint>]) -> Homography {
+    let mut arrsac = Arrsac::new(CONSENSUS_THRESHOLD, Pcg64::from_seed([1; 32]));
+    let (homography, inliers) = arrsac
+        .model_inliers(&FourPoint::new(), matches.iter().copied())
+        .expect("failed to estimate homography");
+    assert!(inliers.iter().all(|&ix| !is_outlier(ix)));
+    homography
+}
+
+#[test]
+fn planar() {
+    let pose = pose();
+    let homography = Homography::from_plane(pose, Vector3::z_axis(), PLANE_DISTANCE);
+    let matches = matches(pose, |_| PLANE_DISTANCE);
+    // Both models explain the matches exactly, so the homography is preferred for having fewer degrees of freedom.
+    let selection = TwoViewModelSelector::new().select(essential(pose), homography, matches);
+    assert_eq!(selection.diagnosis, TwoViewDiagnosis::Planar);
+    assert_eq!(selection.model, TwoViewModel::Homography(homography));
+    assert!(selection.homography_gric < selection.essential_gric);
+}
+
+#[test]
+fn general() {
+    let pose = pose();
+    // The homography of a plane through the scene is only consistent with the points at its depth.
+    let homography = Homography::from_plane(pose, Vector3::z_axis(), PLANE_DISTANCE);
+    let matches = matches(pose, |ix| 2.0 + (ix % 7) as f64);
+    let essential = essential(pose);
+    let selection = TwoViewModelSelector::new().select(essential, homography, matches);
+    assert_eq!(selection.diagnosis, TwoViewDiagnosis::General);
+    assert_eq!(selection.model, TwoViewModel::Essential(essential));
+    assert!(selection.essential_gric < selection.homography_gric);
+}
+
+#[test]
+fn planar_with_outliers() {
+    let pose = pose();
+    let matches = with_outliers(matches(pose, |_| PLANE_DISTANCE));
+    let homography = consensus_homography(&matches);
+    let selection = TwoViewModelSelector::new().select(essential(pose), homography, matches);
+    assert_eq!(selection.diagnosis, TwoViewDiagnosis::Planar);
+}
+
+#[test]
+fn general_with_outliers() {
+    let pose = pose();
+    let matches = with_outliers(matches(pose, |ix| 2.0 + (ix % 7) as f64));
+    let mut arrsac = Arrsac::new(CONSENSUS_THRESHOLD, Pcg64::from_seed([1; 32]));
+    let essential = arrsac
+        .model(&EightPoint::new(), matches.iter().copied())
+        .expect("failed to estimate essential matrix");
+    let homography = consensus_homography(&matches);
+    let selection = TwoViewModelSelector::new().select(essential, homography, matches);
+    assert_eq!(selection.diagnosis, TwoViewDiagnosis::General);
+}
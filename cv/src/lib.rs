@@ -32,6 +32,9 @@ pub use bitarray::BitArray;
 
 /// Camera models
 pub mod camera {
+    /// Omnidirectional camera models (unified and double sphere)
+    #[cfg(feature = "cv-omnidirectional")]
+    pub use cv_omnidirectional as omnidirectional;
     /// The pinhole camera model
     #[cfg(feature = "cv-pinhole")]
     pub use cv_pinhole as pinhole;
@@ -53,8 +56,22 @@ pub mod geom {
 pub mod estimate {
     #[cfg(feature = "eight-point")]
     pub use eight_point::EightPoint;
+    #[cfg(feature = "epnp")]
+    pub use epnp::EPnP;
+    #[cfg(feature = "four-point")]
+    pub use four_point::{FourPoint, Homography};
+    #[cfg(feature = "gp3p")]
+    pub use gp3p::GP3P;
+    #[cfg(feature = "known-vertical")]
+    pub use known_vertical::{VerticalThreePoint, VerticalTwoPoint};
     #[cfg(feature = "lambda-twist")]
     pub use lambda_twist::LambdaTwist;
+    #[cfg(feature = "nister-stewenius")]
+    pub use nister_stewenius::FivePoint;
+    #[cfg(feature = "seven-point")]
+    pub use seven_point::SevenPoint;
+    #[cfg(feature = "seventeen-point")]
+    pub use seventeen_point::SeventeenPoint;
 }
 
 /// Feature detection and description algorithms
@@ -64,6 +81,11 @@ pub mod feature {
     pub mod akaze {
         pub use akaze::*;
     }
+    /// Fiducial marker (AprilTag and ArUco) detection
+    #[cfg(feature = "cv-markers")]
+    pub mod markers {
+        pub use cv_markers::*;
+    }
 }
 
 /// Algorithms for performing k-NN searches
@@ -1,9 +1,9 @@
 #![no_std]
 
-use cv_core::nalgebra::{self, Matrix3, MatrixMN, VectorN, U3, U8, U9};
+use cv_core::nalgebra::{self, Matrix3, MatrixMN, MatrixN, VectorN, U3, U8, U9};
 use cv_core::sample_consensus::Estimator;
-use cv_core::FeatureMatch;
-use cv_pinhole::{EssentialMatrix, NormalizedKeyPoint};
+use cv_core::{normalizing_transform, FeatureMatch, KeyPoint};
+use cv_pinhole::{EssentialMatrix, FundamentalMatrix, NormalizedKeyPoint};
 
 fn encode_epipolar_equation(
     matches: impl Iterator<Item = FeatureMatch<NormalizedKeyPoint>>,
@@ -28,6 +28,10 @@ fn encode_epipolar_equation(
 ///
 /// To recondition the matrix produced by estimation, see
 /// [`cv_core::EssentialMatrix::recondition`].
+///
+/// It can also estimate a [`FundamentalMatrix`] from pixel coordinates ([`KeyPoint`]), in which case
+/// the points are normalized first, all of the matches provided are used in a least-squares fashion,
+/// and the rank 2 constraint is enforced on the result.
 #[derive(Copy, Clone, Debug)]
 pub struct EightPoint {
     pub epsilon: f64,
@@ -71,3 +75,41 @@ impl Estimator<FeatureMatch<NormalizedKeyPoint>> for EightPoint {
         Some(EssentialMatrix(mat))
     }
 }
+
+impl Estimator<FeatureMatch<KeyPoint>> for EightPoint {
+    type Model = FundamentalMatrix;
+    type ModelIter = Option<FundamentalMatrix>;
+    const MIN_SAMPLES: usize = 8;
+
+    fn estimate<I>(&self, data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureMatch<KeyPoint>> + Clone,
+    {
+        let norm_a = normalizing_transform(data.clone().map(|FeatureMatch(a, _)| a.0))?;
+        let norm_b = normalizing_transform(data.clone().map(|FeatureMatch(_, b)| b.0))?;
+
+        // Accumulate the normal equations so any number of matches can be used.
+        let mut ata = MatrixN::<f64, U9>::zeros();
+        for FeatureMatch(a, b) in data {
+            let ap = norm_a * a.0.to_homogeneous();
+            let bp = norm_b * b.0.to_homogeneous();
+            let mut row = VectorN::<f64, U9>::zeros();
+            for j in 0..3 {
+                let v = ap[j] * bp;
+                row.fixed_rows_mut::<U3>(3 * j).copy_from(&v);
+            }
+            ata += row * row.transpose();
+        }
+        let eigens = ata.try_symmetric_eigen(self.epsilon, self.iterations)?;
+        let eigenvector = eigens
+            .eigenvalues
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &n)| float_ord::FloatOrd(n))
+            .map(|(ix, _)| eigens.eigenvectors.column(ix).into_owned())?;
+        let normalized = FundamentalMatrix(Matrix3::from_iterator(eigenvector.iter().copied()))
+            .recondition(self.epsilon, self.iterations)?;
+        let fundamental = norm_b.transpose() * normalized.0 * norm_a;
+        Some(FundamentalMatrix(fundamental / fundamental.norm()))
+    }
+}
@@ -0,0 +1,80 @@
+use cv_core::nalgebra::{IsometryMatrix3, Point2, Rotation3, Vector2, Vector3};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{CameraModel, CameraPoint, CameraToCamera, FeatureMatch, KeyPoint, Pose};
+use cv_pinhole::{CameraIntrinsics, EssentialMatrix, NormalizedKeyPoint};
+use eight_point::EightPoint;
+
+const SAMPLE_POINTS: usize = 16;
+const RESIDUAL_THRESHOLD: f64 = 1e-3;
+const ESSENTIAL_THRESHOLD: f64 = 1e-4;
+
+const ROT_MAGNITUDE: f64 = 0.2;
+const POINT_BOX_SIZE: f64 = 2.0;
+const POINT_DISTANCE: f64 = 3.0;
+
+#[test]
+fn randomized() {
+    let successes = (0..1000).filter(|_| run_round()).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+fn run_round() -> bool {
+    let (real_pose, a_intrinsics, b_intrinsics, matches) = some_test_data();
+    let fundamental = match EightPoint::new().estimate(matches.iter().copied()) {
+        Some(fundamental) => fundamental,
+        None => return false,
+    };
+    // The rank 2 constraint must be enforced.
+    let rank_ok = fundamental.determinant().abs() < 1e-12;
+    let real_essential = EssentialMatrix::from(real_pose).0.normalize();
+    let residuals_ok = matches
+        .iter()
+        .all(|m| fundamental.residual(m) < RESIDUAL_THRESHOLD);
+    let essential = fundamental
+        .essential(&a_intrinsics, &b_intrinsics)
+        .0
+        .normalize();
+    let essential_residual = (essential - real_essential)
+        .norm()
+        .min((essential + real_essential).norm());
+    rank_ok && residuals_ok && essential_residual < ESSENTIAL_THRESHOLD
+}
+
+/// Gets a random relative pose, the intrinsics of cameras A and B, and the matches in pixels.
+fn some_test_data() -> (
+    CameraToCamera,
+    CameraIntrinsics,
+    CameraIntrinsics,
+    Vec<FeatureMatch<KeyPoint>>,
+) {
+    let relative_pose = CameraToCamera(IsometryMatrix3::from_parts(
+        Vector3::new_random().into(),
+        Rotation3::new(Vector3::new_random() * std::f64::consts::PI * 2.0 * ROT_MAGNITUDE),
+    ));
+    let intrinsics = || {
+        CameraIntrinsics::identity()
+            .focals(Vector2::new_random() * 400.0 + Vector2::new(400.0, 400.0))
+            .principal_point(Point2::from(
+                Vector2::new_random() * 100.0 + Vector2::new(270.0, 190.0),
+            ))
+    };
+    let (a_intrinsics, b_intrinsics) = (intrinsics(), intrinsics());
+
+    let matches = (0..SAMPLE_POINTS)
+        .map(|_| {
+            let mut a = Vector3::new_random() * POINT_BOX_SIZE;
+            a.x -= 0.5 * POINT_BOX_SIZE;
+            a.y -= 0.5 * POINT_BOX_SIZE;
+            a.z += POINT_DISTANCE;
+            let a = CameraPoint(a.push(1.0));
+            let b = relative_pose.transform(a);
+            FeatureMatch(
+                a_intrinsics.uncalibrate(NormalizedKeyPoint::from_camera_point(a).unwrap()),
+                b_intrinsics.uncalibrate(NormalizedKeyPoint::from_camera_point(b).unwrap()),
+            )
+        })
+        .collect();
+
+    (relative_pose, a_intrinsics, b_intrinsics, matches)
+}
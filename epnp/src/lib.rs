@@ -0,0 +1,361 @@
+//! This crate implements the EPnP algorithm by Vincent Lepetit, Francesc Moreno-Noguer, and Pascal Fua,
+//! as described in "EPnP: An Accurate O(n) Solution to the PnP Problem".
+//!
+//! EPnP expresses every world point as a weighted sum of four virtual control points. The projection
+//! constraints are linear in the coordinates of the control points in the camera frame, so the
+//! solution lies in the small null space of a 12x12 matrix, which is accumulated in a single pass
+//! over the data. The right combination of null space vectors is found from the distances between
+//! the control points, which are preserved by the pose. Planar scenes are supported by using three
+//! control points on the plane instead of four.
+//!
+//! Unlike a minimal P3P solver like `lambda-twist`, EPnP uses every point it is given, so it can be used to
+//! refit a pose from all of the inliers found by consensus.
+
+#![no_std]
+
+use cv_core::nalgebra::{
+    IsometryMatrix3, Matrix3, Matrix6, MatrixN, Point3, Rotation3, Unit, Vector3, Vector6, U12, U3,
+};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{Bearing, FeatureWorldMatch, Projective, WorldPoint, WorldToCamera};
+use num_traits::Float;
+
+/// The ratio between the smallest and largest variance of the world points below which
+/// they are considered to be on a plane.
+const PLANAR_THRESHOLD: f64 = 1e-10;
+
+/// Control points, where the unused control point of planar scenes is left at zero.
+type ControlPoints = [Vector3<f64>; 4];
+
+/// Performs the EPnP algorithm to estimate the pose of a camera from bearings to known world points.
+///
+/// All of the matches provided are used, and at least six matches are needed in general.
+/// Four matches are enough if all of the world points are on a plane. World points at infinity are ignored.
+///
+/// ```
+/// use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Vector3};
+/// use cv_core::sample_consensus::Estimator;
+/// use cv_core::{FeatureWorldMatch, Pose, Projective, WorldPoint, WorldToCamera};
+/// use epnp::EPnP;
+/// let pose = WorldToCamera(IsometryMatrix3::from_parts(
+///     Vector3::new(0.1, -0.2, 3.0).into(),
+///     Rotation3::from_euler_angles(0.1, 0.2, 0.3),
+/// ));
+/// let world_points = [
+///     [-1.0, -1.0, 0.5],
+///     [1.0, -0.5, -0.5],
+///     [0.5, 1.0, 0.0],
+///     [-0.5, 0.5, 1.0],
+///     [0.0, 0.0, -1.0],
+///     [1.0, 1.0, 1.0],
+///     [-1.0, 0.2, -0.7],
+/// ];
+/// let matches = world_points.iter().map(|&[x, y, z]| {
+///     let world = WorldPoint::from_point(Point3::new(x, y, z));
+///     FeatureWorldMatch(pose.transform(world).bearing(), world)
+/// });
+/// let estimate = EPnP::new().estimate(matches).unwrap();
+/// assert!((estimate.0.to_homogeneous() - pose.0.to_homogeneous()).norm() < 1e-9);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct EPnP {
+    pub epsilon: f64,
+    pub iterations: usize,
+    /// The number of Gauss-Newton iterations used to refine the weights of the null space vectors.
+    pub refinement_iterations: usize,
+}
+
+impl EPnP {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Default for EPnP {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-12,
+            iterations: 1000,
+            refinement_iterations: 5,
+        }
+    }
+}
+
+impl<P> Estimator<FeatureWorldMatch<P>> for EPnP
+where
+    P: Bearing,
+{
+    type Model = WorldToCamera;
+    type ModelIter = Option<WorldToCamera>;
+    const MIN_SAMPLES: usize = 6;
+
+    fn estimate<I>(&self, data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureWorldMatch<P>> + Clone,
+    {
+        let points = data.filter_map(|FeatureWorldMatch(feature, world)| {
+            Some((feature.bearing(), world.point()?))
+        });
+
+        // Place the control points at the centroid and along the principal axes of the world points.
+        let (sum, count) = points
+            .clone()
+            .fold((Vector3::zeros(), 0), |(sum, count), (_, p)| {
+                (sum + p.coords, count + 1)
+            });
+        if count < 4 {
+            return None;
+        }
+        let centroid = Point3::from(sum / count as f64);
+        let covariance = points.clone().fold(Matrix3::zeros(), |covariance, (_, p)| {
+            let offset = p - centroid;
+            covariance + offset * offset.transpose()
+        }) / count as f64;
+        let eigens = covariance.try_symmetric_eigen(self.epsilon, self.iterations)?;
+        let mut order = [0, 1, 2];
+        order.sort_unstable_by_key(|&ix| float_ord::FloatOrd(-eigens.eigenvalues[ix]));
+        let variance = |ix: usize| eigens.eigenvalues[order[ix]];
+        if variance(1) <= PLANAR_THRESHOLD * variance(0) {
+            // The points are on a line (or a single point), so the pose can't be determined.
+            return None;
+        }
+        let planar = variance(2) <= PLANAR_THRESHOLD * variance(0);
+        let axes = if planar { 2 } else { 3 };
+        let mut axis = [Vector3::zeros(); 3];
+        let mut world_controls = [centroid.coords; 4];
+        for i in 0..axes {
+            axis[i] = eigens.eigenvectors.column(order[i]) * Float::sqrt(variance(i));
+            world_controls[i + 1] += axis[i];
+        }
+        let alphas = |p: Point3<f64>| {
+            let offset = p - centroid;
+            let mut alphas = [0.0; 4];
+            for i in 0..axes {
+                alphas[i + 1] = offset.dot(&axis[i]) / axis[i].norm_squared();
+            }
+            alphas[0] = 1.0 - alphas[1] - alphas[2] - alphas[3];
+            alphas
+        };
+
+        // Each bearing `b` requires the camera point `sum(alpha_j * c_j)` to be parallel to it,
+        // which means its projection onto the plane perpendicular to `b` must be zero.
+        let mut mtm = MatrixN::<f64, U12>::zeros();
+        for (bearing, p) in points.clone() {
+            let alphas = alphas(p);
+            let bearing = bearing.into_inner();
+            let perpendicular = Matrix3::identity() - bearing * bearing.transpose();
+            for j in 0..4 {
+                for k in 0..4 {
+                    let mut block = mtm.fixed_slice_mut::<U3, U3>(3 * j, 3 * k);
+                    block += alphas[j] * alphas[k] * perpendicular;
+                }
+            }
+        }
+        if planar {
+            // The unused control point is decoupled from the rest and its eigenvalues are
+            // pushed above all of the others so it isn't part of the null space.
+            let trace = mtm.trace();
+            mtm.fixed_slice_mut::<U3, U3>(9, 9)
+                .copy_from(&(Matrix3::identity() * trace));
+        }
+
+        let eigens = mtm.try_symmetric_eigen(self.epsilon, self.iterations)?;
+        let mut order = [0; 12];
+        for (ix, o) in order.iter_mut().enumerate() {
+            *o = ix;
+        }
+        order.sort_unstable_by_key(|&ix| float_ord::FloatOrd(eigens.eigenvalues[ix]));
+        let mut kernel = [[Vector3::zeros(); 4]; 3];
+        for (vectors, &ix) in kernel.iter_mut().zip(&order) {
+            let eigenvector = eigens.eigenvectors.column(ix);
+            for (j, control) in vectors.iter_mut().enumerate() {
+                *control = eigenvector.fixed_rows::<U3>(3 * j).into_owned();
+            }
+        }
+
+        // Try combinations of one to three null space vectors and keep the best pose.
+        let controls = axes + 1;
+        let max_dimensions = if planar { 2 } else { 3 };
+        let error = |pose: &WorldToCamera| {
+            points
+                .clone()
+                .map(|(bearing, p)| {
+                    pose.residual(&FeatureWorldMatch(bearing, WorldPoint::from_point(p)))
+                })
+                .sum::<f64>()
+        };
+        (1..=max_dimensions)
+            .filter_map(|dimensions| {
+                let betas = initial_betas(dimensions, &kernel, &world_controls, controls)?;
+                let betas =
+                    self.refine_betas(dimensions, betas, &kernel, &world_controls, controls);
+                let mut camera_controls = [Vector3::zeros(); 4];
+                for (k, vectors) in kernel.iter().enumerate().take(dimensions) {
+                    for (camera_control, vector) in camera_controls.iter_mut().zip(vectors) {
+                        *camera_control += betas[k] * vector;
+                    }
+                }
+                self.pose(&camera_controls, centroid, points.clone(), alphas)
+            })
+            .min_by_key(|pose| float_ord::FloatOrd(error(pose)))
+    }
+}
+
+impl EPnP {
+    /// Refines the weights of the null space vectors with Gauss-Newton so that the distances
+    /// between the control points in the camera frame match the distances in the world frame.
+    fn refine_betas(
+        &self,
+        dimensions: usize,
+        mut betas: Vector3<f64>,
+        kernel: &[ControlPoints; 3],
+        world_controls: &ControlPoints,
+        controls: usize,
+    ) -> Vector3<f64> {
+        for _ in 0..self.refinement_iterations {
+            let mut jtj = Matrix3::zeros();
+            let mut jtr = Vector3::zeros();
+            for (i, j) in pairs(controls) {
+                let distance_squared = (world_controls[i] - world_controls[j]).norm_squared();
+                let mut deltas = [Vector3::zeros(); 3];
+                let mut delta = Vector3::zeros();
+                for k in 0..dimensions {
+                    deltas[k] = kernel[k][i] - kernel[k][j];
+                    delta += betas[k] * deltas[k];
+                }
+                let residual = delta.norm_squared() - distance_squared;
+                let mut jacobian = Vector3::zeros();
+                for k in 0..dimensions {
+                    jacobian[k] = 2.0 * delta.dot(&deltas[k]);
+                }
+                jtj += jacobian * jacobian.transpose();
+                jtr += jacobian * residual;
+            }
+            // Unused dimensions are held in place.
+            for k in dimensions..3 {
+                jtj[(k, k)] = 1.0;
+            }
+            match jtj.try_inverse() {
+                Some(inverse) => betas -= inverse * jtr,
+                None => break,
+            }
+        }
+        betas
+    }
+
+    /// Computes the pose that moves the world points onto the camera points given by the control points.
+    fn pose(
+        &self,
+        camera_controls: &ControlPoints,
+        world_centroid: Point3<f64>,
+        points: impl Iterator<Item = (Unit<Vector3<f64>>, Point3<f64>)> + Clone,
+        alphas: impl Fn(Point3<f64>) -> [f64; 4],
+    ) -> Option<WorldToCamera> {
+        let camera_point = |p: Point3<f64>| {
+            alphas(p)
+                .iter()
+                .zip(camera_controls)
+                .map(|(&alpha, control)| alpha * control)
+                .sum::<Vector3<f64>>()
+        };
+        // The null space vectors have an arbitrary sign, so flip the points if they are behind the camera.
+        let depth = points
+            .clone()
+            .map(|(bearing, p)| bearing.dot(&camera_point(p)))
+            .sum::<f64>();
+        let sign = if depth < 0.0 { -1.0 } else { 1.0 };
+
+        let (sum, count) = points
+            .clone()
+            .fold((Vector3::zeros(), 0), |(sum, count), (_, p)| {
+                (sum + sign * camera_point(p), count + 1)
+            });
+        let camera_centroid = sum / count as f64;
+        let cross_covariance = points.fold(Matrix3::zeros(), |covariance, (_, p)| {
+            covariance
+                + (sign * camera_point(p) - camera_centroid) * (p - world_centroid).transpose()
+        });
+        let svd = cross_covariance.try_svd(true, true, self.epsilon, self.iterations)?;
+        let (u, v_t) = (svd.u?, svd.v_t?);
+        let correction =
+            Matrix3::from_diagonal(&Vector3::new(1.0, 1.0, (u * v_t).determinant().signum()));
+        let rotation = Rotation3::from_matrix_unchecked(u * correction * v_t);
+        let translation = camera_centroid - rotation * world_centroid.coords;
+        Some(WorldToCamera(IsometryMatrix3::from_parts(
+            translation.into(),
+            rotation,
+        )))
+    }
+}
+
+/// Iterates over all of the pairs of the first `controls` control points.
+fn pairs(controls: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..controls).flat_map(move |i| (i + 1..controls).map(move |j| (i, j)))
+}
+
+/// Computes the initial weights of the null space vectors by linearizing the distance constraints.
+fn initial_betas(
+    dimensions: usize,
+    kernel: &[ControlPoints; 3],
+    world_controls: &ControlPoints,
+    controls: usize,
+) -> Option<Vector3<f64>> {
+    let constraints = pairs(controls).map(|(i, j)| {
+        let distance_squared = (world_controls[i] - world_controls[j]).norm_squared();
+        let delta = |k: usize| kernel[k][i] - kernel[k][j];
+        (distance_squared, [delta(0), delta(1), delta(2)])
+    });
+    match dimensions {
+        1 => {
+            let (numerator, denominator) =
+                constraints.fold((0.0, 0.0), |(numerator, denominator), (d, deltas)| {
+                    let norm = deltas[0].norm();
+                    (numerator + norm * Float::sqrt(d), denominator + norm * norm)
+                });
+            Some(Vector3::new(numerator / denominator, 0.0, 0.0))
+        }
+        2 => {
+            // Solve for `[b00, b01, b11]` where `bij = beta_i * beta_j`.
+            let (ltl, ltd) = constraints.fold(
+                (Matrix3::zeros(), Vector3::zeros()),
+                |(ltl, ltd), (d, deltas)| {
+                    let l = Vector3::new(
+                        deltas[0].norm_squared(),
+                        2.0 * deltas[0].dot(&deltas[1]),
+                        deltas[1].norm_squared(),
+                    );
+                    (ltl + l * l.transpose(), ltd + l * d)
+                },
+            );
+            let b = ltl.try_inverse()? * ltd;
+            Some(Vector3::new(
+                Float::sqrt(b[0].abs()),
+                Float::sqrt(b[2].abs()) * b[1].signum(),
+                0.0,
+            ))
+        }
+        _ => {
+            // Solve for `[b00, b01, b02, b11, b12, b22]` where `bij = beta_i * beta_j`.
+            let (ltl, ltd) = constraints.fold(
+                (Matrix6::zeros(), Vector6::zeros()),
+                |(ltl, ltd), (d, deltas)| {
+                    let l = Vector6::new(
+                        deltas[0].norm_squared(),
+                        2.0 * deltas[0].dot(&deltas[1]),
+                        2.0 * deltas[0].dot(&deltas[2]),
+                        deltas[1].norm_squared(),
+                        2.0 * deltas[1].dot(&deltas[2]),
+                        deltas[2].norm_squared(),
+                    );
+                    (ltl + l * l.transpose(), ltd + l * d)
+                },
+            );
+            let b = ltl.lu().solve(&ltd)?;
+            Some(Vector3::new(
+                Float::sqrt(b[0].abs()),
+                Float::sqrt(b[3].abs()) * b[1].signum(),
+                Float::sqrt(b[5].abs()) * b[2].signum(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Unit, UnitQuaternion, Vector3};
+use cv_core::sample_consensus::Estimator;
+use cv_core::{FeatureWorldMatch, Pose, Projective, WorldPoint, WorldToCamera};
+use epnp::EPnP;
+
+const SAMPLE_POINTS: usize = 50;
+const POSE_THRESHOLD: f64 = 1e-6;
+const NOISY_POSE_THRESHOLD: f64 = 1e-2;
+
+const ROT_MAGNITUDE: f64 = 0.2;
+const POINT_BOX_SIZE: f64 = 2.0;
+const POINT_DISTANCE: f64 = 4.0;
+
+#[test]
+fn randomized() {
+    let successes = (0..1000)
+        .filter(|_| run_round(SAMPLE_POINTS, false, 0.0, POSE_THRESHOLD))
+        .count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 990);
+}
+
+#[test]
+fn randomized_few_points() {
+    let successes = (0..1000)
+        .filter(|_| run_round(6, false, 0.0, POSE_THRESHOLD))
+        .count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+#[test]
+fn randomized_planar() {
+    let successes = (0..1000)
+        .filter(|_| run_round(SAMPLE_POINTS, true, 0.0, POSE_THRESHOLD))
+        .count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 990);
+}
+
+#[test]
+fn randomized_noisy() {
+    let successes = (0..1000)
+        .filter(|_| run_round(SAMPLE_POINTS, false, 1e-3, NOISY_POSE_THRESHOLD))
+        .count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+fn run_round(points: usize, planar: bool, noise: f64, threshold: f64) -> bool {
+    let (real_pose, matches) = some_test_data(points, planar, noise);
+    let pose = match EPnP::new().estimate(matches.iter().copied()) {
+        Some(pose) => pose,
+        None => return false,
+    };
+    let rotation_residual = pose.0.rotation.rotation_to(&real_pose.0.rotation);
+    let rotation_residual = UnitQuaternion::from(rotation_residual).angle();
+    let translation_residual =
+        (pose.0.translation.vector - real_pose.0.translation.vector).norm() / POINT_DISTANCE;
+    let success = rotation_residual < threshold && translation_residual < threshold;
+    if !success {
+        eprintln!(
+            "rotation residual {} translation residual {}",
+            rotation_residual, translation_residual
+        );
+    }
+    success
+}
+
+/// Gets a random world to camera pose and the bearings of random world points.
+fn some_test_data(
+    points: usize,
+    planar: bool,
+    noise: f64,
+) -> (WorldToCamera, Vec<FeatureWorldMatch<Unit<Vector3<f64>>>>) {
+    let pose = WorldToCamera(IsometryMatrix3::from_parts(
+        (Vector3::new_random() + Vector3::new(0.0, 0.0, POINT_DISTANCE)).into(),
+        Rotation3::new(Vector3::new_random() * std::f64::consts::PI * 2.0 * ROT_MAGNITUDE),
+    ));
+    // The world points are near the origin, which is in front of the camera.
+    let matches = (0..points)
+        .map(|_| {
+            let mut p = (Vector3::new_random() - Vector3::repeat(0.5)) * POINT_BOX_SIZE;
+            if planar {
+                p.z = 0.3 * p.x - 0.2 * p.y;
+            }
+            let world = WorldPoint::from_point(Point3::from(p));
+            let bearing = pose.transform(world).bearing().into_inner()
+                + (Vector3::new_random() - Vector3::repeat(0.5)) * noise;
+            FeatureWorldMatch(Unit::new_normalize(bearing), world)
+        })
+        .collect();
+    (pose, matches)
+}
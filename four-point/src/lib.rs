@@ -0,0 +1,282 @@
+#![no_std]
+
+use arrayvec::ArrayVec;
+use cv_core::nalgebra::{Matrix3, MatrixN, Point2, Rotation3, Unit, Vector3, VectorN, U9};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{normalizing_transform, CameraToCamera, FeatureMatch, Pose};
+use derive_more::{AsRef, Deref};
+use num_traits::Float;
+
+/// Relative poses paired with the normal of the plane in the first camera.
+type PlanarPoses = ArrayVec<[(CameraToCamera, Unit<Vector3<f64>>); 4]>;
+
+/// This stores a homography, which maps points from the first image onto the second image:
+///
+/// x' = H * x
+///
+/// Where `x'` and `x` are homogeneous image coordinates and the equality is only up to scale.
+///
+/// Two images of points on a plane are related by a homography, as are any two images taken
+/// by cameras that only rotate relative to one another. Unlike the essential matrix, a homography
+/// maps every point to a single point rather than to an epipolar line, so it remains well-defined when
+/// all of the points lie on a plane, which is exactly the case where the essential matrix can't be estimated.
+///
+/// A homography can be estimated between any kind of image coordinates, such as pixel coordinates
+/// ([`cv_core::KeyPoint`]) or normalized image coordinates. Only a homography between normalized image
+/// coordinates can be decomposed into relative poses with [`Homography::possible_unscaled_poses`].
+/// A pixel homography `H` between cameras with intrinsic matrices `K` and `K'` can be turned into
+/// one between normalized image coordinates with `inverse(K') * H * K`.
+///
+/// The inverse is computed once when the homography is created, since the residual of every match
+/// transfers points in both directions.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, AsRef, Deref)]
+pub struct Homography {
+    #[as_ref]
+    #[deref]
+    matrix: Matrix3<f64>,
+    inverse: Option<Matrix3<f64>>,
+}
+
+impl Homography {
+    /// Creates a homography from its matrix.
+    pub fn new(matrix: Matrix3<f64>) -> Self {
+        Self {
+            matrix,
+            inverse: matrix.try_inverse(),
+        }
+    }
+
+    /// Retrieves the matrix of the homography.
+    pub fn matrix(&self) -> Matrix3<f64> {
+        self.matrix
+    }
+
+    /// Creates the homography between normalized image coordinates that is induced by a plane.
+    ///
+    /// The plane is specified in the coordinates of the first camera as the points `X` where
+    /// `dot(normal, X) = distance`, and `pose` transforms points from the first camera to the second.
+    pub fn from_plane(pose: CameraToCamera, normal: Unit<Vector3<f64>>, distance: f64) -> Self {
+        let CameraToCamera(pose) = pose;
+        Self::new(pose.rotation.matrix() + pose.translation.vector * normal.transpose() / distance)
+    }
+
+    /// Maps a point in the first image to the second image.
+    ///
+    /// Returns `None` if the point is mapped to infinity.
+    pub fn transfer(&self, point: Point2<f64>) -> Option<Point2<f64>> {
+        Point2::from_homogeneous(self.matrix * point.to_homogeneous())
+    }
+
+    /// Computes the homography that maps points from the second image to the first image.
+    pub fn inverse(&self) -> Option<Self> {
+        self.inverse.map(|inverse| Self {
+            matrix: inverse,
+            inverse: Some(self.matrix),
+        })
+    }
+
+    /// Decomposes a homography between normalized image coordinates into the poses it could have come from.
+    ///
+    /// This uses the method by Olivier Faugeras as presented in "An Invitation to 3-D Vision"
+    /// by Yi Ma, Stefano Soatto, Jana Košecká, and Shankar Sastry. Each candidate is returned with
+    /// the normal of the plane in the coordinates of the first camera. The translation of each pose
+    /// is divided by the distance from the first camera to the plane, so it only has the correct
+    /// direction and a scale relative to the plane distance.
+    ///
+    /// There are four candidates in general. Only two of them have all of the points in front of the
+    /// first camera, which are the ones where `dot(normal, x)` is positive for the homogeneous normalized image
+    /// coordinates `x` of the points in the first image. Picking between those two requires another view or
+    /// knowledge about the plane. If the cameras only rotate relative to one another, the plane can't be observed
+    /// and a single candidate without translation is returned along with an arbitrary normal.
+    ///
+    /// Returns `None` if the homography is degenerate or the eigen decomposition fails to converge.
+    ///
+    /// ```
+    /// use cv_core::nalgebra::{IsometryMatrix3, Rotation3, Unit, Vector3};
+    /// use cv_core::CameraToCamera;
+    /// use four_point::Homography;
+    /// let pose = CameraToCamera(IsometryMatrix3::from_parts(
+    ///     Vector3::new(-0.8, 0.4, 0.5).into(),
+    ///     Rotation3::from_euler_angles(0.2, 0.3, 0.4),
+    /// ));
+    /// let normal = Unit::new_normalize(Vector3::new(0.1, -0.2, 1.0));
+    /// let homography = Homography::from_plane(pose, normal, 2.0);
+    /// let candidates = homography.possible_unscaled_poses(1e-12, 1000).unwrap();
+    /// assert_eq!(candidates.len(), 4);
+    /// let one_correct = candidates.iter().any(|&(candidate, candidate_normal)| {
+    ///     let rotation_residual =
+    ///         (candidate.0.rotation.matrix() - pose.0.rotation.matrix()).norm();
+    ///     let translation_residual =
+    ///         (candidate.0.translation.vector - pose.0.translation.vector / 2.0).norm();
+    ///     let normal_residual = 1.0 - candidate_normal.dot(&normal);
+    ///     rotation_residual < 1e-6 && translation_residual < 1e-6 && normal_residual < 1e-6
+    /// });
+    /// assert!(one_correct);
+    /// ```
+    pub fn possible_unscaled_poses(
+        &self,
+        epsilon: f64,
+        max_iterations: usize,
+    ) -> Option<PlanarPoses> {
+        let eigens =
+            (self.matrix.transpose() * self.matrix).try_symmetric_eigen(epsilon, max_iterations)?;
+        // Sort the squared singular values in descending order.
+        let mut order = [0, 1, 2];
+        order.sort_unstable_by_key(|&ix| float_ord::FloatOrd(-eigens.eigenvalues[ix]));
+        let eigenvalue = |ix: usize| eigens.eigenvalues[order[ix]];
+        let eigenvector = |ix: usize| eigens.eigenvectors.column(order[ix]).into_owned();
+
+        // Scale the homography so that its middle singular value is one, which makes it
+        // equal to `R + t * transpose(n)`. The sign is chosen so that the first camera is
+        // on the same side of the plane as the second camera.
+        if eigenvalue(1) <= 0.0 {
+            return None;
+        }
+        let mut homography = self.matrix / Float::sqrt(eigenvalue(1));
+        if homography.determinant() < 0.0 {
+            homography = -homography;
+        }
+        let s1 = (eigenvalue(0) / eigenvalue(1)).max(1.0);
+        let s3 = (eigenvalue(2) / eigenvalue(1)).min(1.0);
+
+        let to_rotation = |mat: Matrix3<f64>| {
+            Rotation3::from_matrix_eps(&mat, epsilon, max_iterations, Rotation3::identity())
+        };
+        let mut poses = ArrayVec::new();
+        if s1 - s3 < epsilon.sqrt() {
+            poses.push((
+                CameraToCamera::from_parts(Vector3::zeros(), to_rotation(homography)),
+                Vector3::z_axis(),
+            ));
+            return Some(poses);
+        }
+
+        let (v1, v2, v3) = (eigenvector(0), eigenvector(1), eigenvector(2));
+        let scale = Float::sqrt(s1 - s3);
+        let a = Float::sqrt(1.0 - s3) / scale;
+        let b = Float::sqrt(s1 - 1.0) / scale;
+        for &u in &[a * v1 + b * v3, a * v1 - b * v3] {
+            // The homography preserves the length of and angle between `v2` and `u`,
+            // so the rotation is the one which maps them to their images.
+            let normal = v2.cross(&u);
+            let (hv2, hu) = (homography * v2, homography * u);
+            let from = Matrix3::from_columns(&[v2, u, normal]);
+            let to = Matrix3::from_columns(&[hv2, hu, hv2.cross(&hu)]);
+            let rotation = to_rotation(to * from.transpose());
+            let translation = (homography - rotation.matrix()) * normal;
+            let normal = Unit::new_normalize(normal);
+            poses.push((CameraToCamera::from_parts(translation, rotation), normal));
+            poses.push((CameraToCamera::from_parts(-translation, rotation), -normal));
+        }
+        Some(poses)
+    }
+}
+
+impl From<Matrix3<f64>> for Homography {
+    fn from(matrix: Matrix3<f64>) -> Self {
+        Self::new(matrix)
+    }
+}
+
+impl From<Homography> for Matrix3<f64> {
+    fn from(homography: Homography) -> Self {
+        homography.matrix
+    }
+}
+
+impl<P> Model<FeatureMatch<P>> for Homography
+where
+    P: Copy + Into<Point2<f64>>,
+{
+    /// Computes the root mean square of the symmetric transfer error, which is the distance
+    /// between each point and the other point transferred into its image.
+    ///
+    /// This is in the units of the image coordinates, like the residuals of the other models.
+    fn residual(&self, data: &FeatureMatch<P>) -> f64 {
+        let FeatureMatch(a, b) = *data;
+        let (a, b) = (a.into(), b.into());
+        let forward = self.transfer(a);
+        let backward = self
+            .inverse
+            .and_then(|inverse| Point2::from_homogeneous(inverse * b.to_homogeneous()));
+        match (forward, backward) {
+            (Some(forward), Some(backward)) => {
+                Float::sqrt(((forward - b).norm_squared() + (backward - a).norm_squared()) / 2.0)
+            }
+            _ => f64::INFINITY,
+        }
+    }
+}
+
+/// Performs the normalized four-point direct linear transform (DLT) by Richard Hartley and Andrew Zisserman.
+///
+/// The points of each image are normalized before solving for the homography to improve the conditioning
+/// of the problem. Four matches are required, but all of the matches provided are used in a least-squares
+/// fashion, so this can also be used to refine a homography from its inliers.
+///
+/// It can estimate a homography from matches of any point type that can be converted into a [`Point2`],
+/// including [`cv_core::KeyPoint`] and `NormalizedKeyPoint` from `cv-pinhole`.
+#[derive(Copy, Clone, Debug)]
+pub struct FourPoint {
+    pub epsilon: f64,
+    pub iterations: usize,
+}
+
+impl FourPoint {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Default for FourPoint {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-12,
+            iterations: 1000,
+        }
+    }
+}
+
+impl<P> Estimator<FeatureMatch<P>> for FourPoint
+where
+    P: Copy + Into<Point2<f64>>,
+{
+    type Model = Homography;
+    type ModelIter = Option<Homography>;
+    const MIN_SAMPLES: usize = 4;
+
+    fn estimate<I>(&self, data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureMatch<P>> + Clone,
+    {
+        let norm_a = normalizing_transform(data.clone().map(|FeatureMatch(a, _)| a.into()))?;
+        let norm_b = normalizing_transform(data.clone().map(|FeatureMatch(_, b)| b.into()))?;
+
+        // Accumulate the normal equations of the DLT so any number of matches can be used.
+        let mut ata = MatrixN::<f64, U9>::zeros();
+        for FeatureMatch(a, b) in data {
+            let a = norm_a * a.into().to_homogeneous();
+            let b = norm_b * b.into().to_homogeneous();
+            let mut row_x = VectorN::<f64, U9>::zeros();
+            let mut row_y = VectorN::<f64, U9>::zeros();
+            for j in 0..3 {
+                row_x[j] = -a[j];
+                row_x[6 + j] = b.x * a[j];
+                row_y[3 + j] = -a[j];
+                row_y[6 + j] = b.y * a[j];
+            }
+            ata += row_x * row_x.transpose() + row_y * row_y.transpose();
+        }
+
+        let eigens = ata.try_symmetric_eigen(self.epsilon, self.iterations)?;
+        let eigenvector = eigens
+            .eigenvalues
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &n)| float_ord::FloatOrd(n))
+            .map(|(ix, _)| eigens.eigenvectors.column(ix).into_owned())?;
+        let normalized = Matrix3::from_row_slice(eigenvector.as_slice());
+        let homography = norm_b.try_inverse()? * normalized * norm_a;
+        Some(Homography::new(homography / homography.norm()))
+    }
+}
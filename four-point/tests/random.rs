@@ -0,0 +1,137 @@
+use cv_core::nalgebra::{IsometryMatrix3, Point2, Rotation3, Unit, Vector2, Vector3};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{CameraModel, CameraPoint, CameraToCamera, FeatureMatch, KeyPoint, Pose};
+use cv_pinhole::{CameraIntrinsics, NormalizedKeyPoint};
+use four_point::FourPoint;
+
+const SAMPLE_POINTS: usize = 16;
+const RESIDUAL_THRESHOLD: f64 = 1e-4;
+const POSE_THRESHOLD: f64 = 1e-4;
+
+const ROT_MAGNITUDE: f64 = 0.2;
+const PLANE_SIZE: f64 = 2.0;
+const PLANE_DISTANCE: f64 = 3.0;
+
+#[test]
+fn randomized() {
+    let successes = (0..1000).filter(|_| run_round(SAMPLE_POINTS)).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+#[test]
+fn randomized_minimal() {
+    let successes = (0..1000).filter(|_| run_round(4)).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+#[test]
+fn pixel_coordinates() {
+    let intrinsics = CameraIntrinsics::identity()
+        .focals(Vector2::new(800.0, 810.0))
+        .principal_point(Point2::new(320.0, 240.0))
+        .skew(0.5);
+    let (_, _, aps, bps) = some_test_data(SAMPLE_POINTS);
+    let matches = aps
+        .iter()
+        .zip(&bps)
+        .map(|(&a, &b)| FeatureMatch(intrinsics.uncalibrate(a), intrinsics.uncalibrate(b)))
+        .collect::<Vec<FeatureMatch<KeyPoint>>>();
+    let homography = FourPoint::new()
+        .estimate(matches.iter().copied())
+        .expect("didn't get a homography");
+    for m in &matches {
+        // The residual is in pixels.
+        assert!(homography.residual(m) < 1e-3);
+    }
+}
+
+fn run_round(points: usize) -> bool {
+    let mut success = true;
+    let (real_pose, real_normal, aps, bps) = some_test_data(points);
+    let matches = aps.iter().zip(&bps).map(|(&a, &b)| FeatureMatch(a, b));
+    let homography = FourPoint::new()
+        .estimate(matches.clone())
+        .expect("didn't get a homography");
+    for m in matches {
+        if homography.residual(&m) > RESIDUAL_THRESHOLD {
+            success = false;
+            eprintln!("failed residual check: {}", homography.residual(&m));
+        }
+    }
+
+    let candidates = match homography.possible_unscaled_poses(1e-12, 1000) {
+        Some(candidates) => candidates,
+        None => return false,
+    };
+    success &= candidates.iter().any(|&(pose, normal)| {
+        // The real pose must have all of the points in front of the first camera.
+        let visible = aps.iter().all(|a| normal.dot(&a.0.coords.push(1.0)) > 0.0);
+        let rot_residual = (pose.0.rotation.matrix() - real_pose.0.rotation.matrix()).norm();
+        let translation_residual = 1.0
+            - real_pose
+                .0
+                .translation
+                .vector
+                .normalize()
+                .dot(&pose.0.translation.vector.normalize());
+        let normal_residual = 1.0 - normal.dot(&real_normal);
+        visible
+            && rot_residual < POSE_THRESHOLD
+            && translation_residual < POSE_THRESHOLD
+            && normal_residual < POSE_THRESHOLD
+    });
+    if !success {
+        eprintln!("real pose: {:?}", real_pose);
+        eprintln!("candidates: {:?}", candidates);
+    }
+    success
+}
+
+/// Gets a random relative pose, the plane normal, input points A, and input points B.
+fn some_test_data(
+    points: usize,
+) -> (
+    CameraToCamera,
+    Unit<Vector3<f64>>,
+    Vec<NormalizedKeyPoint>,
+    Vec<NormalizedKeyPoint>,
+) {
+    let relative_pose = CameraToCamera(IsometryMatrix3::from_parts(
+        Vector3::new_random().into(),
+        Rotation3::new(Vector3::new_random() * std::f64::consts::PI * 2.0 * ROT_MAGNITUDE),
+    ));
+
+    // Generate a plane in front of camera A which is tilted by up to 45 degrees.
+    let tilt = Rotation3::new(Vector3::new(
+        rand_centered() * std::f64::consts::FRAC_PI_4,
+        rand_centered() * std::f64::consts::FRAC_PI_4,
+        0.0,
+    ));
+    let center = Vector3::new(0.0, 0.0, PLANE_DISTANCE);
+    let normal = Unit::new_normalize(tilt * Vector3::z());
+
+    // Generate A's camera points on the plane.
+    let cams_a = (0..points)
+        .map(|_| {
+            let offset = tilt * Vector3::new(rand_centered(), rand_centered(), 0.0) * PLANE_SIZE;
+            CameraPoint((center + offset).push(1.0))
+        })
+        .collect::<Vec<_>>();
+
+    let kps_a = cams_a
+        .iter()
+        .map(|&a| NormalizedKeyPoint::from_camera_point(a).unwrap())
+        .collect();
+    let kps_b = cams_a
+        .iter()
+        .map(|&a| NormalizedKeyPoint::from_camera_point(relative_pose.transform(a)).unwrap())
+        .collect();
+
+    (relative_pose, normal, kps_a, kps_b)
+}
+
+fn rand_centered() -> f64 {
+    Vector2::<f64>::new_random().x - 0.5
+}
@@ -0,0 +1,227 @@
+//! This crate implements a minimal solver for the generalized perspective-three-point (gP3P) problem.
+//!
+//! A generalized camera, such as a [`cv_core::CameraRig`], observes [`PluckerRay`]s which don't share an optical
+//! center. Given three rays and the world points they observe, the distances between the points on the rays
+//! must equal the distances between the world points. Each distance is a quadratic constraint on the depths along
+//! two of the rays, and eliminating the depths along the second and third ray leaves a polynomial of degree eight in
+//! the depth along the first ray. The pose is then the rigid transform between the world points and the points on
+//! the rays.
+//!
+//! There are up to eight solutions, and a fourth match is needed to pick between them.
+
+#![no_std]
+
+use arrayvec::ArrayVec;
+use cv_core::nalgebra::{Matrix3, Rotation3, Vector3};
+use cv_core::sample_consensus::Estimator;
+use cv_core::{
+    add_polynomial_product, evaluate_polynomial, real_polynomial_roots, FeatureWorldMatch,
+    PluckerRay, Pose, Projective, WorldToCamera,
+};
+
+/// A polynomial in the depth along the first ray, starting from the constant term.
+type Polynomial = [f64; 9];
+/// A polynomial in the depths along the first and third ray, indexed by the power of the depth along the third ray.
+type Bivariate = [Polynomial; 5];
+
+/// A ray given by the point closest to the origin and its direction.
+#[derive(Copy, Clone)]
+struct Line {
+    origin: Vector3<f64>,
+    direction: Vector3<f64>,
+}
+
+impl Line {
+    fn new(ray: PluckerRay) -> Self {
+        Self {
+            origin: ray.closest_point_to_origin().coords,
+            direction: ray.direction.into_inner(),
+        }
+    }
+
+    fn at(&self, depth: f64) -> Vector3<f64> {
+        self.origin + depth * self.direction
+    }
+
+    /// Computes the constraint that the point at depth `x` along this line and the point at depth `y` along
+    /// `other` are at the given squared distance.
+    ///
+    /// The constraint is `y^2 + linear * y + constant`, where `linear` and `constant` are polynomials in `x`.
+    fn distance_constraint(&self, other: &Line, distance_squared: f64) -> ([f64; 3], [f64; 3]) {
+        let delta = self.origin - other.origin;
+        let linear = [
+            -2.0 * other.direction.dot(&delta),
+            -2.0 * self.direction.dot(&other.direction),
+            0.0,
+        ];
+        let constant = [
+            delta.norm_squared() - distance_squared,
+            2.0 * self.direction.dot(&delta),
+            1.0,
+        ];
+        (linear, constant)
+    }
+}
+
+/// Estimates the pose of a generalized camera from three matches between rays and world points.
+///
+/// The rays must be in the frame of the generalized camera, such as from [`cv_core::CameraRig::ray`],
+/// and the resulting [`WorldToCamera`] transforms world points into that frame. The depths along the first ray
+/// are the roots of a polynomial of degree eight, which are found as the eigenvalues of its companion matrix.
+/// Only the first three matches provided are used.
+#[derive(Copy, Clone, Debug)]
+pub struct GP3P {
+    pub epsilon: f64,
+    pub iterations: usize,
+}
+
+impl GP3P {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Computes the rigid transform that maps the world points onto the points on the rays.
+    fn align(&self, world: &[Vector3<f64>; 3], rig: &[Vector3<f64>; 3]) -> Option<WorldToCamera> {
+        let world_centroid = (world[0] + world[1] + world[2]) / 3.0;
+        let rig_centroid = (rig[0] + rig[1] + rig[2]) / 3.0;
+        let covariance = world
+            .iter()
+            .zip(rig)
+            .map(|(w, r)| (r - rig_centroid) * (w - world_centroid).transpose())
+            .sum::<Matrix3<f64>>();
+        let svd = covariance.try_svd(true, true, self.epsilon, self.iterations)?;
+        let (u, v_t) = (svd.u?, svd.v_t?);
+        // Flip the direction of the smallest singular value (zero for three points) to avoid a reflection.
+        let mut correction = Matrix3::identity();
+        let smallest = svd.singular_values.imin();
+        correction[(smallest, smallest)] = (u * v_t).determinant().signum();
+        let rotation = Rotation3::from_matrix_unchecked(u * correction * v_t);
+        let translation = rig_centroid - rotation * world_centroid;
+        Some(WorldToCamera::from_parts(translation, rotation))
+    }
+}
+
+impl Default for GP3P {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-12,
+            iterations: 1000,
+        }
+    }
+}
+
+impl Estimator<FeatureWorldMatch<PluckerRay>> for GP3P {
+    type Model = WorldToCamera;
+    type ModelIter = ArrayVec<[WorldToCamera; 8]>;
+    const MIN_SAMPLES: usize = 3;
+
+    fn estimate<I>(&self, mut data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureWorldMatch<PluckerRay>> + Clone,
+    {
+        let mut lines = [Line::new(PluckerRay::from_bearing(Vector3::z_axis())); 3];
+        let mut world = [Vector3::zeros(); 3];
+        let mut poses = ArrayVec::new();
+        for (line, world) in lines.iter_mut().zip(world.iter_mut()) {
+            let FeatureWorldMatch(ray, point) = data
+                .next()
+                .expect("must provide 3 samples at minimum to GP3P");
+            *line = Line::new(ray);
+            *world = match point.point() {
+                Some(point) => point.coords,
+                None => return poses,
+            };
+        }
+        let [l1, l2, l3] = lines;
+        let d12 = (world[0] - world[1]).norm_squared();
+        let d13 = (world[0] - world[2]).norm_squared();
+        let d23 = (world[1] - world[2]).norm_squared();
+
+        // With the depths `x`, `y` and `z` along the three rays, the constraints are
+        // `y^2 + a1 * y + a0`, `y^2 + b1 * y + b0` and `z^2 + e1 * z + e0`, where `a` depends on `x`,
+        // `b` depends on `z` and `e` depends on `x`.
+        let (a1, a0) = l1.distance_constraint(&l2, d12);
+        let (b1, b0) = l3.distance_constraint(&l2, d23);
+        let (e1, e0) = l1.distance_constraint(&l3, d13);
+
+        // Eliminate `y` with the resultant of the first two constraints, which is
+        // `(b0 - a0)^2 - (b1 - a1) * (a1 * b0 - a0 * b1)`.
+        let in_x = |p: [f64; 3]| -> Bivariate {
+            let mut bivariate = Bivariate::default();
+            bivariate[0][..3].copy_from_slice(&p);
+            bivariate
+        };
+        let in_z = |p: [f64; 3]| -> Bivariate {
+            let mut bivariate = Bivariate::default();
+            for (coefficient, &c) in bivariate.iter_mut().zip(&p) {
+                coefficient[0] = c;
+            }
+            bivariate
+        };
+        let (a1_xz, a0_xz, b1_xz, b0_xz) = (in_x(a1), in_x(a0), in_z(b1), in_z(b0));
+        let mut difference0 = Bivariate::default();
+        add_bivariate_product(&mut difference0, 1.0, &b0_xz, &one());
+        add_bivariate_product(&mut difference0, -1.0, &a0_xz, &one());
+        let mut difference1 = Bivariate::default();
+        add_bivariate_product(&mut difference1, 1.0, &b1_xz, &one());
+        add_bivariate_product(&mut difference1, -1.0, &a1_xz, &one());
+        let mut cross = Bivariate::default();
+        add_bivariate_product(&mut cross, 1.0, &a1_xz, &b0_xz);
+        add_bivariate_product(&mut cross, -1.0, &a0_xz, &b1_xz);
+        let mut resultant = Bivariate::default();
+        add_bivariate_product(&mut resultant, 1.0, &difference0, &difference0);
+        add_bivariate_product(&mut resultant, -1.0, &difference1, &cross);
+
+        // Reduce the resultant with `z^2 = -e1 * z - e0` to `p * z + q`.
+        for power in (2..resultant.len()).rev() {
+            let coefficient = core::mem::take(&mut resultant[power]);
+            add_polynomial_product(&mut resultant[power - 1], -1.0, &e1, &coefficient);
+            add_polynomial_product(&mut resultant[power - 2], -1.0, &e0, &coefficient);
+        }
+        let [q, p, ..] = resultant;
+
+        // Substituting `z = -q / p` into the last constraint gives `q^2 - e1 * p * q + e0 * p^2`.
+        let mut e1_p = Polynomial::default();
+        add_polynomial_product(&mut e1_p, 1.0, &e1, &p);
+        let mut e0_p = Polynomial::default();
+        add_polynomial_product(&mut e0_p, 1.0, &e0, &p);
+        let mut polynomial = Polynomial::default();
+        add_polynomial_product(&mut polynomial, 1.0, &q, &q);
+        add_polynomial_product(&mut polynomial, -1.0, &e1_p, &q);
+        add_polynomial_product(&mut polynomial, 1.0, &e0_p, &p);
+
+        for x in real_polynomial_roots(&polynomial) {
+            let z = -evaluate_polynomial(&q, x) / evaluate_polynomial(&p, x);
+            // The difference of the first two constraints is linear in `y`.
+            let y = -(evaluate_polynomial(&a0, x) - evaluate_polynomial(&b0, z))
+                / (evaluate_polynomial(&a1, x) - evaluate_polynomial(&b1, z));
+            if !(y.is_finite() && z.is_finite()) {
+                continue;
+            }
+            if let Some(pose) = self.align(&world, &[l1.at(x), l2.at(y), l3.at(z)]) {
+                if poses.len() < poses.capacity() {
+                    poses.push(pose);
+                }
+            }
+        }
+        poses
+    }
+}
+
+/// The bivariate polynomial `1`.
+fn one() -> Bivariate {
+    let mut one = Bivariate::default();
+    one[0][0] = 1.0;
+    one
+}
+
+/// Adds `factor` times the product of the bivariate polynomials `a` and `b` to `output`.
+fn add_bivariate_product(output: &mut Bivariate, factor: f64, a: &Bivariate, b: &Bivariate) {
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            if let Some(output) = output.get_mut(i + j) {
+                add_polynomial_product(output, factor, a, b);
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Unit, Vector3};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{
+    CameraRig, CameraToCamera, FeatureWorldMatch, PluckerRay, Pose, Projective, WorldPoint,
+    WorldToCamera,
+};
+use gp3p::GP3P;
+
+const POSE_THRESHOLD: f64 = 1e-6;
+const RESIDUAL_THRESHOLD: f64 = 1e-9;
+
+const ROT_MAGNITUDE: f64 = 0.2;
+const POINT_BOX_SIZE: f64 = 2.0;
+const POINT_DISTANCE: f64 = 3.0;
+const RIG_CAMERAS: usize = 4;
+
+#[test]
+fn randomized() {
+    let successes = (0..1000).filter(|_| run_round()).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 990);
+}
+
+/// Creates a rig of cameras facing outwards in a ring, like the cameras on a vehicle.
+fn rig() -> CameraRig<[CameraToCamera; RIG_CAMERAS]> {
+    let mut cameras = [CameraToCamera::identity(); RIG_CAMERAS];
+    for (ix, camera) in cameras.iter_mut().enumerate() {
+        let rotation = Rotation3::from_axis_angle(
+            &Vector3::y_axis(),
+            ix as f64 * 2.0 * std::f64::consts::PI / RIG_CAMERAS as f64,
+        );
+        *camera = CameraToCamera::from_parts(rotation * Vector3::new(0.0, 0.0, 1.0), rotation);
+    }
+    CameraRig(cameras)
+}
+
+/// Creates a point in front of the given camera of the rig, in the frame of the rig.
+fn random_point(rig: &CameraRig<[CameraToCamera; RIG_CAMERAS]>, camera: usize) -> Point3<f64> {
+    let mut p = Vector3::new_random() * POINT_BOX_SIZE;
+    p.x -= 0.5 * POINT_BOX_SIZE;
+    p.y -= 0.5 * POINT_BOX_SIZE;
+    p.z += POINT_DISTANCE;
+    rig.cameras()[camera].isometry() * Point3::from(p)
+}
+
+fn run_round() -> bool {
+    let rig = rig();
+    let pose = WorldToCamera(IsometryMatrix3::from_parts(
+        Vector3::new_random().into(),
+        Rotation3::new(Vector3::new_random() * std::f64::consts::PI * 2.0 * ROT_MAGNITUDE),
+    ));
+    let matches: Vec<_> = (0..4)
+        .map(|camera| {
+            let point = random_point(&rig, camera);
+            let center = Point3::from(rig.cameras()[camera].isometry().translation.vector);
+            let ray = PluckerRay::new(center, Unit::new_normalize(point - center));
+            let world = WorldPoint::from_point(pose.inverse().isometry() * point);
+            FeatureWorldMatch(ray, world)
+        })
+        .collect();
+
+    let candidates = GP3P::new().estimate(matches.iter().copied());
+    // The fourth match picks the correct solution.
+    let best = candidates
+        .iter()
+        .min_by(|a, b| {
+            a.residual(&matches[3])
+                .partial_cmp(&b.residual(&matches[3]))
+                .unwrap()
+        })
+        .copied();
+    match best {
+        Some(best) => {
+            matches
+                .iter()
+                .all(|m| best.residual(m) < RESIDUAL_THRESHOLD)
+                && (best.0.to_homogeneous() - pose.0.to_homogeneous()).norm() < POSE_THRESHOLD
+        }
+        None => false,
+    }
+}
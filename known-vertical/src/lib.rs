@@ -0,0 +1,374 @@
+//! This crate implements minimal pose solvers for cameras with a known vertical direction.
+//!
+//! When the direction of gravity is known in the frame of each camera, such as from an IMU, only the rotation
+//! around the vertical axis is unknown. This reduces the relative pose between two cameras to three degrees of freedom
+//! (the angle and the direction of translation) and the absolute pose of a camera to four degrees of freedom
+//! (the angle and the translation). Both solvers rotate the bearings so that the vertical direction becomes the
+//! `y` axis of every frame, solve for the remaining degrees of freedom, and then rotate the solutions back.
+//!
+//! * [`VerticalThreePoint`] estimates a [`CameraToCamera`] from three [`FeatureMatch`]es, as in
+//!   "A Minimal Case Solution to the Calibrated Relative Pose Problem for the Case of Two Known Orientation Angles"
+//!   by Friedrich Fraundorfer, Petri Tanskanen, and Marc Pollefeys
+//! * [`VerticalTwoPoint`] estimates a [`WorldToCamera`] from two [`FeatureWorldMatch`]es, as in
+//!   "Closed-form solutions to minimal absolute pose problems with known vertical direction"
+//!   by Zuzana Kukelova, Martin Bujnak, and Tomas Pajdla
+//!
+//! Fewer samples per hypothesis means far fewer iterations of consensus are needed to find an uncontaminated sample.
+
+#![no_std]
+
+use arrayvec::ArrayVec;
+use core::f64::consts::PI;
+use cv_core::nalgebra::{
+    IsometryMatrix3, Matrix3, MatrixMN, Rotation3, Unit, Vector3, VectorN, U3, U5, U6,
+};
+use cv_core::sample_consensus::Estimator;
+use cv_core::{
+    add_polynomial_product, real_polynomial_roots, Bearing, CameraToCamera, FeatureMatch,
+    FeatureWorldMatch, Pose, Projective, WorldToCamera,
+};
+use num_traits::Float;
+
+/// A polynomial in the tangent of half of the rotation angle, starting from the constant term.
+type Polynomial = [f64; 7];
+
+/// The leading coefficient of the relative pose polynomial relative to its largest coefficient,
+/// below which a rotation by half a turn is considered a root.
+const NEGLIGIBLE_LEADING_COEFFICIENT: f64 = 1e-14;
+
+/// Computes the rotation that maps the vertical direction onto the `y` axis.
+fn align_vertical(vertical: Unit<Vector3<f64>>) -> Rotation3<f64> {
+    Rotation3::rotation_between(&vertical, &Vector3::y())
+        .unwrap_or_else(|| Rotation3::from_axis_angle(&Vector3::x_axis(), PI))
+}
+
+/// Computes the rotation around the `y` axis by the angle with the given cosine and sine.
+#[rustfmt::skip]
+fn rotation_y(cos: f64, sin: f64) -> Rotation3<f64> {
+    Rotation3::from_matrix_unchecked(Matrix3::new(
+        cos,  0.0, sin,
+        0.0,  1.0, 0.0,
+        -sin, 0.0, cos,
+    ))
+}
+
+/// Estimates the relative pose between two cameras from three matches and the vertical direction in both cameras.
+///
+/// The relative pose constraint is a trigonometric polynomial in the rotation angle, which has up to six roots.
+/// Substituting the tangent of half of the angle turns it into a polynomial of degree six, whose roots are found
+/// as the eigenvalues of its companion matrix. For each angle, the
+/// direction of translation is the one that puts the most of the three points in front of both cameras,
+/// since the residual of a [`CameraToCamera`] can't distinguish a translation from its opposite.
+///
+/// ```
+/// use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Unit, Vector3};
+/// use cv_core::sample_consensus::Estimator;
+/// use cv_core::{CameraPoint, CameraToCamera, FeatureMatch, Pose, Projective};
+/// use known_vertical::VerticalThreePoint;
+/// let pose = CameraToCamera(IsometryMatrix3::from_parts(
+///     Vector3::new(0.3, -0.1, 0.2).into(),
+///     Rotation3::from_euler_angles(0.1, 0.2, 0.3),
+/// ));
+/// let gravity_a = Unit::new_normalize(Vector3::new(0.1, 1.0, 0.2));
+/// let gravity_b = pose.0.rotation * gravity_a;
+/// let points = [[-1.0, 0.5, 4.0], [1.0, -0.5, 3.0], [0.5, 1.0, 5.0]];
+/// let matches = points.iter().map(|&[x, y, z]| {
+///     let a = CameraPoint::from_point(Point3::new(x, y, z));
+///     FeatureMatch(a.bearing(), pose.transform(a).bearing())
+/// });
+/// let candidates = VerticalThreePoint::new(gravity_a, gravity_b).estimate(matches);
+/// // The translation is estimated with unit length.
+/// let translation = pose.0.translation.vector.normalize();
+/// assert!(candidates.iter().any(|candidate| {
+///     (candidate.0.rotation.matrix() - pose.0.rotation.matrix()).norm() < 1e-9
+///         && (candidate.0.translation.vector - translation).norm() < 1e-9
+/// }));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct VerticalThreePoint {
+    /// The vertical direction in the frame of the first camera of every match.
+    pub vertical_a: Unit<Vector3<f64>>,
+    /// The vertical direction in the frame of the second camera of every match.
+    pub vertical_b: Unit<Vector3<f64>>,
+}
+
+impl VerticalThreePoint {
+    /// Creates the solver from the vertical direction (such as gravity) measured in each camera.
+    pub fn new(vertical_a: Unit<Vector3<f64>>, vertical_b: Unit<Vector3<f64>>) -> Self {
+        Self {
+            vertical_a,
+            vertical_b,
+        }
+    }
+}
+
+impl<P> Estimator<FeatureMatch<P>> for VerticalThreePoint
+where
+    P: Bearing,
+{
+    type Model = CameraToCamera;
+    type ModelIter = ArrayVec<[CameraToCamera; 6]>;
+    const MIN_SAMPLES: usize = 3;
+
+    fn estimate<I>(&self, mut data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureMatch<P>> + Clone,
+    {
+        let align_a = align_vertical(self.vertical_a);
+        let align_b = align_vertical(self.vertical_b);
+        let mut a = [Vector3::zeros(); 3];
+        let mut b = [Vector3::zeros(); 3];
+        for (a, b) in a.iter_mut().zip(b.iter_mut()) {
+            let FeatureMatch(a_bearing, b_bearing) = data
+                .next()
+                .expect("must provide 3 samples at minimum to VerticalThreePoint");
+            *a = align_a * a_bearing.bearing().into_inner();
+            *b = align_b * b_bearing.bearing().into_inner();
+        }
+
+        // The translation must be perpendicular to the normal of every epipolar plane,
+        // so the normals must be coplanar.
+        let normals = |angle: f64| {
+            let rotation = rotation_y(Float::cos(angle), Float::sin(angle));
+            let mut normals = [Vector3::zeros(); 3];
+            for ((normal, a), b) in normals.iter_mut().zip(&a).zip(&b) {
+                *normal = (rotation * a).cross(b);
+            }
+            normals
+        };
+
+        // With `t = tan(angle / 2)`, `(1 + t^2) * rotation * a` is quadratic in `t`, so scaling each normal by
+        // `1 + t^2` makes their triple product a polynomial of degree six.
+        let mut polynomial_normals = [[Polynomial::default(); 3]; 3];
+        for ((normal, a), b) in polynomial_normals.iter_mut().zip(&a).zip(&b) {
+            let rotated = [
+                [a.x, 2.0 * a.z, -a.x],
+                [a.y, 0.0, a.y],
+                [a.z, -2.0 * a.x, -a.z],
+            ];
+            for (k, normal) in normal.iter_mut().enumerate() {
+                let (i, j) = ((k + 1) % 3, (k + 2) % 3);
+                add_polynomial_product(normal, b[j], &rotated[i], &[1.0]);
+                add_polynomial_product(normal, -b[i], &rotated[j], &[1.0]);
+            }
+        }
+        let [n0, n1, n2] = polynomial_normals;
+        let mut constraint = Polynomial::default();
+        for (k, n0) in n0.iter().enumerate() {
+            let (i, j) = ((k + 1) % 3, (k + 2) % 3);
+            let mut cross = Polynomial::default();
+            add_polynomial_product(&mut cross, 1.0, &n1[i], &n2[j]);
+            add_polynomial_product(&mut cross, -1.0, &n1[j], &n2[i]);
+            add_polynomial_product(&mut constraint, 1.0, n0, &cross);
+        }
+
+        let largest = constraint
+            .iter()
+            .fold(0.0, |largest: f64, &c| largest.max(Float::abs(c)));
+        let mut angles: ArrayVec<[f64; 6]> = real_polynomial_roots(&constraint)
+            .into_iter()
+            .map(|t| 2.0 * Float::atan(t))
+            .collect();
+        // A rotation by half a turn is at infinity, where the degree of the polynomial drops.
+        if Float::abs(constraint[6]) <= NEGLIGIBLE_LEADING_COEFFICIENT * largest
+            && angles.len() < angles.capacity()
+        {
+            angles.push(PI);
+        }
+
+        let mut poses = ArrayVec::new();
+        for angle in angles {
+            if let Some(pose) = relative_pose(angle, &normals(angle), &a, &b) {
+                // Undo the alignment of both cameras.
+                let CameraToCamera(iso) = pose;
+                let rotation = align_b.inverse() * iso.rotation * align_a;
+                let translation = align_b.inverse() * iso.translation.vector;
+                poses.push(CameraToCamera::from_parts(translation, rotation));
+            }
+        }
+        poses
+    }
+}
+
+/// Computes the aligned relative pose for a root of the constraint, picking the direction of
+/// translation which puts the most points in front of both cameras.
+fn relative_pose(
+    angle: f64,
+    normals: &[Vector3<f64>; 3],
+    a: &[Vector3<f64>; 3],
+    b: &[Vector3<f64>; 3],
+) -> Option<CameraToCamera> {
+    // The translation is perpendicular to all of the normals, so use the most stable cross product.
+    let translation = [
+        normals[0].cross(&normals[1]),
+        normals[0].cross(&normals[2]),
+        normals[1].cross(&normals[2]),
+    ]
+    .iter()
+    .copied()
+    .max_by(|x, y| x.norm_squared().partial_cmp(&y.norm_squared()).unwrap())?
+    .try_normalize(0.0)?;
+    let rotation = rotation_y(Float::cos(angle), Float::sin(angle));
+
+    // Triangulate the depths of each point along both bearings.
+    let in_front = |translation: Vector3<f64>| {
+        a.iter()
+            .zip(b)
+            .filter(|&(a, b)| {
+                let r = rotation * a;
+                let rb = r.dot(b);
+                let det = 1.0 - rb * rb;
+                let depth_a = (rb * b.dot(&translation) - r.dot(&translation)) / det;
+                let depth_b = (b.dot(&translation) - rb * r.dot(&translation)) / det;
+                depth_a > 0.0 && depth_b > 0.0
+            })
+            .count()
+    };
+    let translation = if in_front(-translation) > in_front(translation) {
+        -translation
+    } else {
+        translation
+    };
+    Some(CameraToCamera::from_parts(translation, rotation))
+}
+
+/// Estimates the absolute pose of a camera from two matches and the vertical direction in the camera and world.
+///
+/// The projection constraints are linear in the cosine and sine of the rotation angle and the translation.
+/// Two matches leave a one dimensional family of solutions, and requiring the cosine and sine to be on the unit
+/// circle leaves up to two poses. Poses which put either point behind the camera are discarded.
+///
+/// ```
+/// use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Unit, Vector3};
+/// use cv_core::sample_consensus::Estimator;
+/// use cv_core::{FeatureWorldMatch, Pose, Projective, WorldPoint, WorldToCamera};
+/// use known_vertical::VerticalTwoPoint;
+/// let pose = WorldToCamera(IsometryMatrix3::from_parts(
+///     Vector3::new(0.3, -0.1, 3.0).into(),
+///     Rotation3::from_euler_angles(0.1, 0.2, 0.3),
+/// ));
+/// let world_vertical = Vector3::z_axis();
+/// let camera_vertical = pose.0.rotation * world_vertical;
+/// let points = [[-1.0, 0.5, 0.5], [1.0, -0.5, -0.2]];
+/// let matches = points.iter().map(|&[x, y, z]| {
+///     let world = WorldPoint::from_point(Point3::new(x, y, z));
+///     FeatureWorldMatch(pose.transform(world).bearing(), world)
+/// });
+/// let candidates = VerticalTwoPoint::new(camera_vertical, world_vertical).estimate(matches);
+/// assert!(candidates
+///     .iter()
+///     .any(|candidate| (candidate.0.to_homogeneous() - pose.0.to_homogeneous()).norm() < 1e-9));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct VerticalTwoPoint {
+    /// The vertical direction in the frame of the camera.
+    pub camera_vertical: Unit<Vector3<f64>>,
+    /// The vertical direction in the frame of the world.
+    pub world_vertical: Unit<Vector3<f64>>,
+    pub epsilon: f64,
+    pub iterations: usize,
+}
+
+impl VerticalTwoPoint {
+    /// Creates the solver from the vertical direction (such as gravity) measured in the camera and known in the world.
+    pub fn new(camera_vertical: Unit<Vector3<f64>>, world_vertical: Unit<Vector3<f64>>) -> Self {
+        Self {
+            camera_vertical,
+            world_vertical,
+            epsilon: 1e-12,
+            iterations: 1000,
+        }
+    }
+}
+
+impl<P> Estimator<FeatureWorldMatch<P>> for VerticalTwoPoint
+where
+    P: Bearing,
+{
+    type Model = WorldToCamera;
+    type ModelIter = ArrayVec<[WorldToCamera; 2]>;
+    const MIN_SAMPLES: usize = 2;
+
+    fn estimate<I>(&self, mut data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureWorldMatch<P>> + Clone,
+    {
+        let align_camera = align_vertical(self.camera_vertical);
+        let align_world = align_vertical(self.world_vertical);
+        let mut poses = ArrayVec::new();
+        let mut bearings = [Vector3::zeros(); 2];
+        let mut points = [Vector3::zeros(); 2];
+        for (bearing, point) in bearings.iter_mut().zip(points.iter_mut()) {
+            let FeatureWorldMatch(feature, world) = data
+                .next()
+                .expect("must provide 2 samples at minimum to VerticalTwoPoint");
+            *bearing = align_camera * feature.bearing().into_inner();
+            *point = match world.point() {
+                Some(point) => align_world * point.coords,
+                None => return poses,
+            };
+        }
+
+        // Each bearing `b` must be parallel to `R * X + t`, where `R * X = cos * u + sin * v + w`.
+        // This gives `cross(b, cos * u + sin * v + t) = -cross(b, w)`, which is linear in `[cos, sin, t]`.
+        let mut system = MatrixMN::<f64, U6, U5>::zeros();
+        let mut rhs = VectorN::<f64, U6>::zeros();
+        for (i, (bearing, point)) in bearings.iter().zip(&points).enumerate() {
+            let skew = bearing.cross_matrix();
+            let u = Vector3::new(point.x, 0.0, point.z);
+            let v = Vector3::new(point.z, 0.0, -point.x);
+            let w = Vector3::new(0.0, point.y, 0.0);
+            let mut rows = system.fixed_rows_mut::<U3>(3 * i);
+            rows.column_mut(0).copy_from(&(skew * u));
+            rows.column_mut(1).copy_from(&(skew * v));
+            rows.fixed_columns_mut::<U3>(2).copy_from(&skew);
+            rhs.fixed_rows_mut::<U3>(3 * i).copy_from(&-(skew * w));
+        }
+
+        // Find the one dimensional family of solutions `particular + scale * null`.
+        let svd = match system.try_svd(true, true, self.epsilon, self.iterations) {
+            Some(svd) => svd,
+            None => return poses,
+        };
+        let null_ix = svd.singular_values.imin();
+        let null = match svd.v_t {
+            Some(v_t) => v_t.row(null_ix).transpose(),
+            None => return poses,
+        };
+        let cutoff = svd.singular_values.max() * 1e-9;
+        let particular = match svd.solve(&rhs, cutoff) {
+            Ok(particular) => particular,
+            Err(_) => return poses,
+        };
+
+        // Solve `cos^2 + sin^2 = 1` for the scale.
+        let qa = null[0] * null[0] + null[1] * null[1];
+        let qb = 2.0 * (particular[0] * null[0] + particular[1] * null[1]);
+        let qc = particular[0] * particular[0] + particular[1] * particular[1] - 1.0;
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if qa == 0.0 || discriminant < 0.0 {
+            return poses;
+        }
+        let sqrt = Float::sqrt(discriminant);
+        for &scale in &[(-qb + sqrt) / (2.0 * qa), (-qb - sqrt) / (2.0 * qa)] {
+            let solution = particular + scale * null;
+            let norm = Float::sqrt(solution[0] * solution[0] + solution[1] * solution[1]);
+            let rotation = rotation_y(solution[0] / norm, solution[1] / norm);
+            let translation = Vector3::new(solution[2], solution[3], solution[4]);
+            let in_front = bearings
+                .iter()
+                .zip(&points)
+                .all(|(bearing, point)| bearing.dot(&(rotation * point + translation)) > 0.0);
+            if in_front {
+                // Undo the alignment of the camera and world.
+                let rotation = align_camera.inverse() * rotation * align_world;
+                let translation = align_camera.inverse() * translation;
+                poses.push(WorldToCamera(IsometryMatrix3::from_parts(
+                    translation.into(),
+                    rotation,
+                )));
+            }
+        }
+        poses
+    }
+}
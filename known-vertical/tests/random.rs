@@ -0,0 +1,92 @@
+use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Unit, Vector3};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{
+    CameraPoint, CameraToCamera, FeatureMatch, FeatureWorldMatch, Pose, Projective, WorldToCamera,
+};
+use known_vertical::{VerticalThreePoint, VerticalTwoPoint};
+
+const POSE_THRESHOLD: f64 = 1e-6;
+const RESIDUAL_THRESHOLD: f64 = 1e-9;
+
+const ROT_MAGNITUDE: f64 = 0.2;
+const POINT_BOX_SIZE: f64 = 2.0;
+const POINT_DISTANCE: f64 = 3.0;
+
+#[test]
+fn randomized_relative() {
+    let successes = (0..1000).filter(|_| run_relative_round()).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+#[test]
+fn randomized_absolute() {
+    let successes = (0..1000).filter(|_| run_absolute_round()).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 990);
+}
+
+fn random_rotation() -> Rotation3<f64> {
+    Rotation3::new(Vector3::new_random() * std::f64::consts::PI * 2.0 * ROT_MAGNITUDE)
+}
+
+fn random_vertical() -> Unit<Vector3<f64>> {
+    Unit::new_normalize(Vector3::new_random() - Vector3::repeat(0.5))
+}
+
+fn random_point() -> Point3<f64> {
+    let mut p = Vector3::new_random() * POINT_BOX_SIZE;
+    p.x -= 0.5 * POINT_BOX_SIZE;
+    p.y -= 0.5 * POINT_BOX_SIZE;
+    p.z += POINT_DISTANCE;
+    Point3::from(p)
+}
+
+fn run_relative_round() -> bool {
+    let pose = CameraToCamera(IsometryMatrix3::from_parts(
+        Vector3::new_random().into(),
+        random_rotation(),
+    ));
+    let vertical_a = random_vertical();
+    let vertical_b = pose.0.rotation * vertical_a;
+    let matches: Vec<_> = (0..3)
+        .map(|_| {
+            let a = CameraPoint::from_point(random_point());
+            FeatureMatch(a.bearing(), pose.transform(a).bearing())
+        })
+        .collect();
+
+    let candidates =
+        VerticalThreePoint::new(vertical_a, vertical_b).estimate(matches.iter().copied());
+    let translation = pose.0.translation.vector.normalize();
+    candidates.iter().any(|candidate| {
+        let residuals_ok = matches
+            .iter()
+            .all(|m| candidate.residual(m) < RESIDUAL_THRESHOLD);
+        residuals_ok
+            && (candidate.0.rotation.matrix() - pose.0.rotation.matrix()).norm() < POSE_THRESHOLD
+            && (candidate.0.translation.vector - translation).norm() < POSE_THRESHOLD
+    })
+}
+
+fn run_absolute_round() -> bool {
+    let pose = WorldToCamera(IsometryMatrix3::from_parts(
+        Vector3::new_random().into(),
+        random_rotation(),
+    ));
+    let world_vertical = random_vertical();
+    let camera_vertical = pose.0.rotation * world_vertical;
+    let camera_to_world = pose.inverse();
+    let matches: Vec<_> = (0..2)
+        .map(|_| {
+            let camera = CameraPoint::from_point(random_point());
+            FeatureWorldMatch(camera.bearing(), camera_to_world.transform(camera))
+        })
+        .collect::<Vec<FeatureWorldMatch<_>>>();
+
+    let candidates =
+        VerticalTwoPoint::new(camera_vertical, world_vertical).estimate(matches.iter().copied());
+    candidates.iter().any(|candidate| {
+        (candidate.0.to_homogeneous() - pose.0.to_homogeneous()).norm() < POSE_THRESHOLD
+    })
+}
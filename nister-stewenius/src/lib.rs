@@ -21,11 +21,14 @@ const BASIS_Y: usize = 17;
 const BASIS_Z: usize = 18;
 const BASIS_1: usize = 19;
 
+use arrayvec::ArrayVec;
 use cv_core::nalgebra::{
     self,
     dimension::{U10, U20, U3, U4, U5, U9},
     DimName, Matrix3, MatrixMN, MatrixN, Vector4, VectorN,
 };
+use cv_core::sample_consensus::Estimator;
+use cv_core::FeatureMatch;
 use cv_pinhole::{EssentialMatrix, NormalizedKeyPoint};
 
 const EIGEN_CONVERGENCE: f64 = 1e-6;
@@ -229,7 +232,7 @@ fn essentials_from_action_ebasis(
             if e.im == 0.0 {
                 let e = e.re;
                 // Solve for the eigen vector.
-                compute_eigenvector(&at, e).map(|v| v.fixed_rows::<U4>(5).into_owned())
+                compute_eigenvector(&at, e).map(|v| v.fixed_rows::<U4>(6).into_owned())
             } else {
                 None
             }
@@ -244,24 +247,27 @@ pub fn five_points_relative_pose(
     a: &[NormalizedKeyPoint; 5],
     b: &[NormalizedKeyPoint; 5],
 ) -> impl Iterator<Item = EssentialMatrix> {
+    five_points_action_ebasis(a, b)
+        .into_iter()
+        .flat_map(|(at, e_basis)| essentials_from_action_ebasis(at, e_basis))
+}
+
+/// Computes the action matrix and the basis of the essential matrix.
+///
+/// Returns `None` if the points are degenerate.
+fn five_points_action_ebasis(
+    a: &[NormalizedKeyPoint; 5],
+    b: &[NormalizedKeyPoint; 5],
+) -> Option<(Square10, NullspaceMat)> {
     // Step 1: Nullspace Extraction.
-    let e_basis = if let Some(m) = five_points_nullspace_basis(a, b) {
-        m
-    } else {
-        return essentials_from_action_ebasis(Square10::zeros(), NullspaceMat::zeros());
-    };
+    let e_basis = five_points_nullspace_basis(a, b)?;
 
     // Step 2: Constraint Expansion.
     let e_constraints = five_points_polynomial_constraints(&e_basis);
 
     // Step 3: Gauss-Jordan Elimination (done thanks to a LU decomposition).
     let c_lu = e_constraints.fixed_slice::<U10, U10>(0, 0).full_piv_lu();
-    let m = if let Some(m) = c_lu.solve(&e_constraints.fixed_slice::<U10, U10>(0, 10).into_owned())
-    {
-        m
-    } else {
-        return essentials_from_action_ebasis(Square10::zeros(), NullspaceMat::zeros());
-    };
+    let m = c_lu.solve(&e_constraints.fixed_slice::<U10, U10>(0, 10).into_owned())?;
 
     // For next steps we follow the matlab code given in Stewenius et al [1].
 
@@ -279,7 +285,45 @@ pub fn five_points_relative_pose(
     at[(8, 3)] = -1.0;
     at[(9, 6)] = -1.0;
 
-    essentials_from_action_ebasis(at, e_basis)
+    Some((at, e_basis))
+}
+
+/// Performs the five-point algorithm by David Nistér, as formulated by Henrik Stewénius et al.
+///
+/// Up to 10 essential matrices are consistent with 5 matches, so all of the candidates are
+/// returned and the consensus algorithm picks the one which agrees with the most matches.
+///
+/// To recondition the matrices produced by estimation, see
+/// [`cv_pinhole::EssentialMatrix::recondition`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FivePoint;
+
+impl FivePoint {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Estimator<FeatureMatch<NormalizedKeyPoint>> for FivePoint {
+    type Model = EssentialMatrix;
+    type ModelIter = ArrayVec<[EssentialMatrix; 10]>;
+    const MIN_SAMPLES: usize = 5;
+
+    fn estimate<I>(&self, mut data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureMatch<NormalizedKeyPoint>> + Clone,
+    {
+        let mut a = [NormalizedKeyPoint(nalgebra::Point2::origin()); 5];
+        let mut b = a;
+        for (a, b) in a.iter_mut().zip(b.iter_mut()) {
+            let FeatureMatch(new_a, new_b) = data
+                .next()
+                .expect("must provide 5 samples at minimum to FivePoint");
+            *a = new_a;
+            *b = new_b;
+        }
+        five_points_relative_pose(&a, &b).collect()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,167 @@
+#![no_std]
+
+use arrayvec::ArrayVec;
+use cv_core::nalgebra::{Matrix3, MatrixN, Point2, Vector3, VectorN, U3, U9};
+use cv_core::sample_consensus::Estimator;
+use cv_core::{normalizing_transform, FeatureMatch, KeyPoint};
+use cv_pinhole::FundamentalMatrix;
+use num_traits::Float;
+
+/// Finds the real roots of `c3 * x^3 + c2 * x^2 + c1 * x + c0`.
+fn cubic_roots(c3: f64, c2: f64, c1: f64, c0: f64) -> ArrayVec<[f64; 3]> {
+    let mut roots = ArrayVec::new();
+    let scale = c3.abs().max(c2.abs()).max(c1.abs()).max(c0.abs());
+    if scale == 0.0 {
+        return roots;
+    }
+    if c3.abs() < 1e-12 * scale {
+        // The polynomial is quadratic.
+        if c2.abs() < 1e-12 * scale {
+            if c1 != 0.0 {
+                roots.push(-c0 / c1);
+            }
+            return roots;
+        }
+        let discriminant = c1 * c1 - 4.0 * c2 * c0;
+        if discriminant >= 0.0 {
+            let sqrt = Float::sqrt(discriminant);
+            roots.push((-c1 + sqrt) / (2.0 * c2));
+            roots.push((-c1 - sqrt) / (2.0 * c2));
+        }
+        return roots;
+    }
+
+    // Solve the depressed cubic `t^3 + p * t + q` where `x = t - a / 3`.
+    let (a, b, c) = (c2 / c3, c1 / c3, c0 / c3);
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    if discriminant > 0.0 {
+        let sqrt = Float::sqrt(discriminant);
+        roots.push(Float::cbrt(-q / 2.0 + sqrt) + Float::cbrt(-q / 2.0 - sqrt) - a / 3.0);
+    } else {
+        let r = Float::sqrt(-p / 3.0);
+        let cos = if r == 0.0 {
+            1.0
+        } else {
+            (-q / (2.0 * r * r * r)).clamp(-1.0, 1.0)
+        };
+        let phi = Float::acos(cos);
+        for k in 0..3 {
+            let angle = (phi - 2.0 * core::f64::consts::PI * k as f64) / 3.0;
+            roots.push(2.0 * r * Float::cos(angle) - a / 3.0);
+        }
+    }
+
+    // Polish the roots with a few Newton iterations to remove the error from the closed form.
+    for root in &mut roots {
+        for _ in 0..2 {
+            let value = ((c3 * *root + c2) * *root + c1) * *root + c0;
+            let derivative = (3.0 * c3 * *root + 2.0 * c2) * *root + c1;
+            if derivative != 0.0 {
+                *root -= value / derivative;
+            }
+        }
+    }
+    roots
+}
+
+/// Performs the seven-point algorithm to estimate a [`FundamentalMatrix`] from pixel coordinates.
+///
+/// The fundamental matrix has seven degrees of freedom, so the seven matches constrain it to a one-parameter
+/// family of matrices, from which the rank 2 constraint selects up to three solutions. The points are normalized
+/// before solving, as with the normalized eight-point algorithm by Richard Hartley and Andrew Zisserman.
+/// Only the first seven matches provided are used.
+#[derive(Copy, Clone, Debug)]
+pub struct SevenPoint {
+    pub epsilon: f64,
+    pub iterations: usize,
+}
+
+impl SevenPoint {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn estimate_normalized(
+        &self,
+        a: &[Point2<f64>; 7],
+        b: &[Point2<f64>; 7],
+    ) -> Option<ArrayVec<[FundamentalMatrix; 3]>> {
+        let norm_a = normalizing_transform(a.iter().copied())?;
+        let norm_b = normalizing_transform(b.iter().copied())?;
+
+        let mut ata = MatrixN::<f64, U9>::zeros();
+        for (a, b) in a.iter().zip(b) {
+            let ap: Vector3<f64> = norm_a * a.to_homogeneous();
+            let bp: Vector3<f64> = norm_b * b.to_homogeneous();
+            let mut row = VectorN::<f64, U9>::zeros();
+            for j in 0..3 {
+                let v = ap[j] * bp;
+                row.fixed_rows_mut::<U3>(3 * j).copy_from(&v);
+            }
+            ata += row * row.transpose();
+        }
+
+        // The two eigenvectors with the smallest eigenvalues span the null space.
+        let eigens = ata.try_symmetric_eigen(self.epsilon, self.iterations)?;
+        let mut order = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+        order.sort_unstable_by_key(|&ix| float_ord::FloatOrd(eigens.eigenvalues[ix]));
+        let basis = |ix: usize| {
+            Matrix3::from_iterator(eigens.eigenvectors.column(order[ix]).iter().copied())
+        };
+        let (f1, f2) = (basis(0), basis(1));
+
+        // Find the `alpha` where `det(alpha * f1 + (1 - alpha) * f2) = 0` by interpolating
+        // the cubic from its values at four points.
+        let det = |alpha: f64| (alpha * f1 + (1.0 - alpha) * f2).determinant();
+        let (d0, d1, dm1, d2) = (det(0.0), det(1.0), det(-1.0), det(2.0));
+        let c0 = d0;
+        let c2 = (d1 + dm1) / 2.0 - c0;
+        let odd = (d1 - dm1) / 2.0;
+        let c3 = (d2 - c0 - 4.0 * c2 - 2.0 * odd) / 6.0;
+        let c1 = odd - c3;
+
+        Some(
+            cubic_roots(c3, c2, c1, c0)
+                .into_iter()
+                .map(|alpha| {
+                    let fundamental =
+                        norm_b.transpose() * (alpha * f1 + (1.0 - alpha) * f2) * norm_a;
+                    FundamentalMatrix(fundamental / fundamental.norm())
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Default for SevenPoint {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-12,
+            iterations: 1000,
+        }
+    }
+}
+
+impl Estimator<FeatureMatch<KeyPoint>> for SevenPoint {
+    type Model = FundamentalMatrix;
+    type ModelIter = ArrayVec<[FundamentalMatrix; 3]>;
+    const MIN_SAMPLES: usize = 7;
+
+    fn estimate<I>(&self, mut data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureMatch<KeyPoint>> + Clone,
+    {
+        let mut a = [Point2::origin(); 7];
+        let mut b = [Point2::origin(); 7];
+        for (a, b) in a.iter_mut().zip(b.iter_mut()) {
+            let FeatureMatch(ap, bp) = data
+                .next()
+                .expect("must provide 7 samples at minimum to SevenPoint");
+            *a = ap.0;
+            *b = bp.0;
+        }
+        self.estimate_normalized(&a, &b).unwrap_or_default()
+    }
+}
@@ -0,0 +1,156 @@
+#![no_std]
+
+use cv_core::nalgebra::{Matrix3, MatrixN, Rotation3, Vector3, VectorN, U18};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{CameraToCamera, FeatureMatch, PluckerRay, Pose};
+
+type Unknowns = VectorN<f64, U18>;
+
+/// Retrieves the row-major matrix stored at `offset` in the unknowns.
+fn block(x: &Unknowns, offset: usize) -> Matrix3<f64> {
+    Matrix3::from_row_slice(&x.as_slice()[offset..offset + 9])
+}
+
+/// Performs the linear seventeen-point algorithm to estimate the relative pose of a generalized camera.
+///
+/// This is the algorithm by Robert Pless from "Using Many Cameras as One", which solves the generalized
+/// epipolar constraint for the essential matrix `E` and the rotation `R` as eighteen independent unknowns.
+/// The rotations are then extracted from `E`, and the translation (including its scale) is solved linearly for
+/// each rotation, keeping the pose with the lowest residual.
+///
+/// When every ray is matched to a ray from the same camera of the rig (such as when the cameras of a rig don't
+/// overlap), the linear system has a second null vector, as shown by Hongdong Li, Richard Hartley, and Jae-hak Kim
+/// in "A Linear Approach to Motion Estimation using Generalized Camera Models". This is handled by also solving
+/// with the null vector that doesn't contain the spurious solution.
+///
+/// At least seventeen matches are required, and all of the matches provided are used in a least-squares fashion.
+/// The matches must not all come from a single central camera, since then the scale of the translation
+/// and the rotation block are unobservable.
+#[derive(Copy, Clone, Debug)]
+pub struct SeventeenPoint {
+    pub epsilon: f64,
+    pub iterations: usize,
+}
+
+impl SeventeenPoint {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Computes the pose which has the given rotation and best satisfies the matches.
+    fn pose_from_rotation<I>(&self, rotation: Rotation3<f64>, data: I) -> Option<CameraToCamera>
+    where
+        I: Iterator<Item = FeatureMatch<PluckerRay>>,
+    {
+        // The constraint is linear in the translation: `dot(t, cross(R * da, db)) = -(db * R * ma + mb * R * da)`.
+        let mut ata = Matrix3::zeros();
+        let mut atb = Vector3::zeros();
+        for FeatureMatch(a, b) in data {
+            let direction = rotation * a.direction;
+            let row = direction.cross(&b.direction);
+            let rhs = -(b.direction.dot(&(rotation * a.moment)) + b.moment.dot(&direction));
+            ata += row * row.transpose();
+            atb += row * rhs;
+        }
+        let translation = ata.try_inverse()? * atb;
+        Some(CameraToCamera::from_parts(translation, rotation))
+    }
+
+    /// Computes the two rotations which can be extracted from an essential matrix.
+    fn rotations(&self, essential: Matrix3<f64>) -> Option<[Rotation3<f64>; 2]> {
+        let svd = essential.try_svd(true, true, self.epsilon, self.iterations)?;
+        let (u, v_t) = (svd.u?, svd.v_t?);
+        // Sort the singular vectors so that the singular value which should be zero is last.
+        let mut order = [0, 1, 2];
+        order.sort_unstable_by_key(|&ix| float_ord::FloatOrd(-svd.singular_values[ix]));
+        let mut u =
+            Matrix3::from_columns(&[u.column(order[0]), u.column(order[1]), u.column(order[2])]);
+        let mut v_t =
+            Matrix3::from_rows(&[v_t.row(order[0]), v_t.row(order[1]), v_t.row(order[2])]);
+        if u.determinant() < 0.0 {
+            u = -u;
+        }
+        if v_t.determinant() < 0.0 {
+            v_t = -v_t;
+        }
+        let w = Matrix3::new(0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        Some([
+            Rotation3::from_matrix_unchecked(u * w * v_t),
+            Rotation3::from_matrix_unchecked(u * w.transpose() * v_t),
+        ])
+    }
+}
+
+impl Default for SeventeenPoint {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-12,
+            iterations: 1000,
+        }
+    }
+}
+
+impl Estimator<FeatureMatch<PluckerRay>> for SeventeenPoint {
+    type Model = CameraToCamera;
+    type ModelIter = Option<CameraToCamera>;
+    const MIN_SAMPLES: usize = 17;
+
+    fn estimate<I>(&self, data: I) -> Self::ModelIter
+    where
+        I: Iterator<Item = FeatureMatch<PluckerRay>> + Clone,
+    {
+        // Every match gives the constraint `db * E * da + db * R * ma + mb * R * da = 0`,
+        // with the unknowns `E` and `R` stored row-major.
+        let mut ata = MatrixN::<f64, U18>::zeros();
+        for FeatureMatch(a, b) in data.clone() {
+            let mut row = Unknowns::zeros();
+            for i in 0..3 {
+                for j in 0..3 {
+                    row[3 * i + j] = b.direction[i] * a.direction[j];
+                    row[9 + 3 * i + j] =
+                        b.direction[i] * a.moment[j] + b.moment[i] * a.direction[j];
+                }
+            }
+            ata += row * row.transpose();
+        }
+
+        let eigens = ata.try_symmetric_eigen(self.epsilon, self.iterations)?;
+        let mut order = [0; 18];
+        for (ix, o) in order.iter_mut().enumerate() {
+            *o = ix;
+        }
+        order.sort_unstable_by_key(|&ix| float_ord::FloatOrd(eigens.eigenvalues[ix]));
+        let null = |ix: usize| eigens.eigenvectors.column(order[ix]).into_owned();
+
+        // The vector with `E = 0` and `R = I` satisfies the constraint for every match between rays of the same camera.
+        // Removing it from the two smallest eigenvectors recovers the solution when it is a null vector.
+        let mut spurious = Unknowns::zeros();
+        spurious[9] = 1.0;
+        spurious[13] = 1.0;
+        spurious[17] = 1.0;
+        let (first, second) = (null(0), null(1));
+        let without_spurious = second.dot(&spurious) * first - first.dot(&spurious) * second;
+
+        let mut best: Option<(f64, CameraToCamera)> = None;
+        for x in [first, without_spurious].iter() {
+            for &rotation in self.rotations(block(x, 0)).iter().flatten() {
+                if let Some(pose) = self.pose_from_rotation(rotation, data.clone()) {
+                    let score: f64 = data
+                        .clone()
+                        .map(|m| {
+                            let residual = pose.residual(&m);
+                            residual * residual
+                        })
+                        .sum();
+                    if best
+                        .map(|(best_score, _)| score < best_score)
+                        .unwrap_or(true)
+                    {
+                        best = Some((score, pose));
+                    }
+                }
+            }
+        }
+        best.map(|(_, pose)| pose)
+    }
+}
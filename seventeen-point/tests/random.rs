@@ -0,0 +1,87 @@
+use cv_core::nalgebra::{IsometryMatrix3, Point3, Rotation3, Unit, Vector3};
+use cv_core::sample_consensus::{Estimator, Model};
+use cv_core::{CameraRig, CameraToCamera, FeatureMatch, PluckerRay, Pose};
+use seventeen_point::SeventeenPoint;
+
+const SAMPLE_POINTS: usize = 50;
+const POSE_THRESHOLD: f64 = 1e-6;
+const RESIDUAL_THRESHOLD: f64 = 1e-9;
+
+const ROT_MAGNITUDE: f64 = 0.2;
+const POINT_BOX_SIZE: f64 = 8.0;
+const RIG_CAMERAS: usize = 4;
+
+#[test]
+fn randomized_same_camera() {
+    let successes = (0..1000)
+        .filter(|_| run_round(SAMPLE_POINTS, false))
+        .count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 990);
+}
+
+#[test]
+fn randomized_across_cameras() {
+    let successes = (0..1000).filter(|_| run_round(SAMPLE_POINTS, true)).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 990);
+}
+
+#[test]
+fn randomized_minimal() {
+    let successes = (0..1000).filter(|_| run_round(17, true)).count();
+    eprintln!("successes: {}", successes);
+    assert!(successes > 950);
+}
+
+/// Creates a rig of cameras facing outwards in a ring, like the cameras on a vehicle.
+fn rig() -> CameraRig<[CameraToCamera; RIG_CAMERAS]> {
+    let mut cameras = [CameraToCamera::identity(); RIG_CAMERAS];
+    for (ix, camera) in cameras.iter_mut().enumerate() {
+        let rotation = Rotation3::from_axis_angle(
+            &Vector3::y_axis(),
+            ix as f64 * 2.0 * std::f64::consts::PI / RIG_CAMERAS as f64,
+        );
+        *camera = CameraToCamera::from_parts(rotation * Vector3::new(0.0, 0.0, 1.0), rotation);
+    }
+    CameraRig(cameras)
+}
+
+fn random_point() -> Point3<f64> {
+    Point3::from((Vector3::new_random() - Vector3::repeat(0.5)) * POINT_BOX_SIZE)
+}
+
+fn run_round(num_points: usize, across: bool) -> bool {
+    let rig = rig();
+    let pose = CameraToCamera(IsometryMatrix3::from_parts(
+        Vector3::new_random().into(),
+        Rotation3::new(Vector3::new_random() * std::f64::consts::PI * 2.0 * ROT_MAGNITUDE),
+    ));
+    let center = |camera: usize| Point3::from(rig.cameras()[camera].isometry().translation.vector);
+    let ray = |camera: usize, point: Point3<f64>| {
+        PluckerRay::new(center(camera), Unit::new_normalize(point - center(camera)))
+    };
+
+    let matches: Vec<_> = (0..num_points)
+        .map(|ix| {
+            let a = random_point();
+            let b = pose.isometry() * a;
+            let camera_a = ix % RIG_CAMERAS;
+            let camera_b = if across {
+                (ix / RIG_CAMERAS) % RIG_CAMERAS
+            } else {
+                camera_a
+            };
+            FeatureMatch(ray(camera_a, a), ray(camera_b, b))
+        })
+        .collect();
+
+    let estimate = match SeventeenPoint::new().estimate(matches.iter().copied()) {
+        Some(estimate) => estimate,
+        None => return false,
+    };
+    matches
+        .iter()
+        .all(|m| estimate.residual(m) < RESIDUAL_THRESHOLD)
+        && (estimate.0.to_homogeneous() - pose.0.to_homogeneous()).norm() < POSE_THRESHOLD
+}
@@ -5,13 +5,36 @@ use cv::{
     estimate::{EightPoint, LambdaTwist},
     geom::MinSquaresTriangulator,
 };
+use cv_camera_files::{kalibr, opencv, ros};
 use cv_reconstruction::{VSlam, VSlamSettings};
 use log::*;
 use rand::SeedableRng;
 use rand_pcg::Pcg64;
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::StructOpt;
 
+/// The format of a camera calibration file.
+#[derive(Copy, Clone, Debug)]
+enum CalibrationFormat {
+    OpenCv,
+    Ros,
+    Kalibr,
+}
+
+impl FromStr for CalibrationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opencv" => Ok(Self::OpenCv),
+            "ros" => Ok(Self::Ros),
+            "kalibr" => Ok(Self::Kalibr),
+            _ => Err(format!("unknown calibration format: {}", s)),
+        }
+    }
+}
+
 #[derive(StructOpt, Clone)]
 #[structopt(name = "vslam-sandbox", about = "A tool for testing vslam algorithms")]
 struct Opt {
@@ -34,6 +57,17 @@ struct Opt {
     /// Export required observations
     #[structopt(long, default_value = "3")]
     export_robust_minimum_observations: usize,
+    /// A camera calibration file to load the intrinsics from.
+    ///
+    /// If this is specified, the intrinsics flags below are ignored.
+    #[structopt(long)]
+    calibration: Option<PathBuf>,
+    /// The format of the calibration file (opencv, ros, or kalibr).
+    #[structopt(long, default_value = "opencv", possible_values = &["opencv", "ros", "kalibr"])]
+    calibration_format: CalibrationFormat,
+    /// The index of the camera to use from a Kalibr camchain.
+    #[structopt(long, default_value = "0")]
+    calibration_camera: usize,
     /// The x focal length
     #[structopt(long, default_value = "984.2439")]
     x_focal: f64,
@@ -66,15 +100,34 @@ fn main() {
     pretty_env_logger::init_timed();
     let opt = Opt::from_args();
 
-    // Fill intrinsics from args.
-    let intrinsics = CameraIntrinsicsK1Distortion::new(
-        CameraIntrinsics {
-            focals: Vector2::new(opt.x_focal, opt.y_focal),
-            principal_point: Point2::new(opt.x_center, opt.y_center),
-            skew: opt.skew,
-        },
-        opt.radial_distortion,
-    );
+    // Fill intrinsics from the calibration file or from args.
+    let intrinsics = if let Some(path) = &opt.calibration {
+        let file = std::fs::read_to_string(path).expect("failed to read calibration file");
+        let calibration = match opt.calibration_format {
+            CalibrationFormat::OpenCv => opencv::read(&file),
+            CalibrationFormat::Ros => ros::read(&file),
+            CalibrationFormat::Kalibr => kalibr::read(&file).map(|mut calibrations| {
+                if opt.calibration_camera >= calibrations.len() {
+                    panic!("camera {} not found in camchain", opt.calibration_camera);
+                }
+                calibrations.swap_remove(opt.calibration_camera)
+            }),
+        }
+        .expect("failed to parse calibration file");
+        info!("loaded calibration {:?}", calibration);
+        calibration
+            .k1_distortion()
+            .expect("calibration must be a pinhole camera without tangential distortion")
+    } else {
+        CameraIntrinsicsK1Distortion::new(
+            CameraIntrinsics {
+                focals: Vector2::new(opt.x_focal, opt.y_focal),
+                principal_point: Point2::new(opt.x_center, opt.y_center),
+                skew: opt.skew,
+            },
+            opt.radial_distortion,
+        )
+    };
 
     info!("trying to load existing reconstruction data");
     let data = std::fs::File::open(&opt.data)